@@ -252,8 +252,7 @@ impl<T: Read> ArithmeticDecoder<T> {
         }
     }
 
-    #[allow(dead_code)]
-    fn read_byte(&mut self) -> std::io::Result<u8> {
+    pub fn read_byte(&mut self) -> std::io::Result<u8> {
         // decode symbol, change length
         self.length >>= 8;
         let sym = self.value / self.length;
@@ -302,6 +301,20 @@ impl<T: Read> ArithmeticDecoder<T> {
         Ok(())
     }
 
+    /// Returns the `(value, length)` pair describing the coder's current interval
+    pub fn state(&self) -> (u32, u32) {
+        (self.value, self.length)
+    }
+
+    /// Restores an interval previously obtained with [`Self::state`]
+    ///
+    /// The input stream must be positioned where it was
+    /// when the state was taken.
+    pub fn set_state(&mut self, (value, length): (u32, u32)) {
+        self.value = value;
+        self.length = length;
+    }
+
     pub fn get_mut(&mut self) -> &mut T {
         &mut self.in_stream
     }
@@ -113,6 +113,28 @@ impl IntegerDecompressor {
         self.k
     }
 
+    /// Adds the stats of the models that were used, prefixed by `name`
+    #[cfg(feature = "model-stats")]
+    pub(crate) fn add_model_stats(
+        &self,
+        name: &str,
+        stats: &mut Vec<crate::model_stats::ModelStats>,
+    ) {
+        use crate::model_stats::{add_models_stats, ModelStats};
+        add_models_stats(stats, &format!("{}.bits", name), &self.m_bits);
+        stats.extend(ModelStats::of_bit_model(
+            format!("{}.corrector0", name),
+            &self.m_corrector0,
+        ));
+        // The corrector models are for 1 bit and more
+        for (i, model) in self.m_corrector.iter().enumerate() {
+            stats.extend(ModelStats::of_model(
+                format!("{}.corrector[{}]", name, i + 1),
+                model,
+            ));
+        }
+    }
+
     pub fn init(&mut self) {
         if self.m_bits.is_empty() {
             for _i in 0..self.contexts {
@@ -127,7 +127,7 @@ impl<T: Write> ArithmeticEncoder<T> {
     pub fn reset(&mut self) {
         self.base = 0;
         self.length = decoders::AC_MAX_LENGTH;
-        self.out_buffer = vec![0u8; 2 * AC_BUFFER_SIZE];
+        self.out_buffer.fill(0);
         self.out_byte = self.out_buffer.as_mut_ptr();
         self.end_byte = unsafe { self.out_buffer.as_ptr().add(2 * AC_BUFFER_SIZE) };
     }
@@ -23,10 +23,152 @@ pub enum LasZipError {
     /// The chunk table could not be found in the file
     /// and it is required for the operation.
     MissingChunkTable,
+    /// The length of a buffer of points is not a multiple of the point size
+    BufferLenNotMultipleOfPointSize {
+        buffer_len: usize,
+        point_size: usize,
+    },
+    /// The size of the points of a buffer is not the one expected
+    PointSizeMismatch { expected: usize, found: usize },
+    /// The index of a chunk is not lower than the number of chunks
+    ChunkIndexOutOfRange { index: usize, num_chunks: usize },
+    /// The chunks of the chunk table do not have the size of the compressed data
+    ChunkTableMismatch {
+        /// Sum of the byte counts of the chunk table
+        table_bytes: u64,
+        /// Number of bytes of chunks in the stream
+        stream_bytes: u64,
+    },
+    /// The buffer given to hold the compressed data is too small
+    OutputBufferTooSmall {
+        /// Size the buffer needs to have
+        required: usize,
+    },
+    /// An internal allocation would have made the memory used by
+    /// a (de)compressor go past the limit it was given
+    MemoryLimitExceeded {
+        /// The memory limit, in bytes
+        limit: usize,
+        /// Number of bytes the allocation would have needed
+        required: usize,
+    },
+    /// The entropy coder of the LASzip vlr is not known,
+    /// only the arithmetic coder (0) is defined
+    UnknownCoder(u16),
+    /// An option of [`LazOpenOptions`](crate::laszip::LazOpenOptions) is not supported
+    /// by the decompressor it was given to, the name of the option is given
+    UnsupportedOption(&'static str),
+    /// A non-fatal issue, that is an error in strict mode,
+    /// see [`LazOpenOptions::with_strict`](crate::laszip::LazOpenOptions::with_strict)
+    StrictModeWarning(Box<LasZipWarning>),
+    /// The chunk table has more chunks than the limit given,
+    /// see [`LazOpenOptions::with_max_num_chunks`](crate::laszip::LazOpenOptions::with_max_num_chunks)
+    ChunkLimitExceeded { limit: usize, num_chunks: usize },
+}
+
+/// Stable numeric codes of the [`LasZipError`] variants, for FFI and bindings
+///
+/// The value of a code never changes, new variants get new values,
+/// 0 is not used so that it can mean success.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[repr(u32)]
+pub enum ErrorCode {
+    UnknownLazItem = 1,
+    UnsupportedLazItemVersion = 2,
+    UnknownCompressorType = 3,
+    UnsupportedCompressorType = 4,
+    UnsupportedPointFormat = 5,
+    IoError = 6,
+    MissingChunkTable = 7,
+    BufferLenNotMultipleOfPointSize = 8,
+    PointSizeMismatch = 9,
+    ChunkIndexOutOfRange = 10,
+    ChunkTableMismatch = 11,
+    OutputBufferTooSmall = 12,
+    MemoryLimitExceeded = 13,
+    UnknownCoder = 14,
+    UnsupportedOption = 15,
+    StrictModeWarning = 16,
+    ChunkLimitExceeded = 17,
+}
+
+impl ErrorCode {
+    const ALL: [ErrorCode; 17] = [
+        ErrorCode::UnknownLazItem,
+        ErrorCode::UnsupportedLazItemVersion,
+        ErrorCode::UnknownCompressorType,
+        ErrorCode::UnsupportedCompressorType,
+        ErrorCode::UnsupportedPointFormat,
+        ErrorCode::IoError,
+        ErrorCode::MissingChunkTable,
+        ErrorCode::BufferLenNotMultipleOfPointSize,
+        ErrorCode::PointSizeMismatch,
+        ErrorCode::ChunkIndexOutOfRange,
+        ErrorCode::ChunkTableMismatch,
+        ErrorCode::OutputBufferTooSmall,
+        ErrorCode::MemoryLimitExceeded,
+        ErrorCode::UnknownCoder,
+        ErrorCode::UnsupportedOption,
+        ErrorCode::StrictModeWarning,
+        ErrorCode::ChunkLimitExceeded,
+    ];
+
+    /// Returns the code that has the numeric `value`, if any
+    pub fn from_u32(value: u32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|&code| code as u32 == value)
+    }
+
+    pub fn as_u32(self) -> u32 {
+        self as u32
+    }
+}
+
+impl LasZipError {
+    /// Returns the stable code of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            LasZipError::UnknownLazItem(_) => ErrorCode::UnknownLazItem,
+            LasZipError::UnsupportedLazItemVersion(_, _) => ErrorCode::UnsupportedLazItemVersion,
+            LasZipError::UnknownCompressorType(_) => ErrorCode::UnknownCompressorType,
+            LasZipError::UnsupportedCompressorType(_) => ErrorCode::UnsupportedCompressorType,
+            LasZipError::UnsupportedPointFormat(_) => ErrorCode::UnsupportedPointFormat,
+            LasZipError::IoError(_) => ErrorCode::IoError,
+            LasZipError::MissingChunkTable => ErrorCode::MissingChunkTable,
+            LasZipError::BufferLenNotMultipleOfPointSize { .. } => {
+                ErrorCode::BufferLenNotMultipleOfPointSize
+            }
+            LasZipError::PointSizeMismatch { .. } => ErrorCode::PointSizeMismatch,
+            LasZipError::ChunkIndexOutOfRange { .. } => ErrorCode::ChunkIndexOutOfRange,
+            LasZipError::ChunkTableMismatch { .. } => ErrorCode::ChunkTableMismatch,
+            LasZipError::OutputBufferTooSmall { .. } => ErrorCode::OutputBufferTooSmall,
+            LasZipError::MemoryLimitExceeded { .. } => ErrorCode::MemoryLimitExceeded,
+            LasZipError::UnknownCoder(_) => ErrorCode::UnknownCoder,
+            LasZipError::UnsupportedOption(_) => ErrorCode::UnsupportedOption,
+            LasZipError::StrictModeWarning(_) => ErrorCode::StrictModeWarning,
+            LasZipError::ChunkLimitExceeded { .. } => ErrorCode::ChunkLimitExceeded,
+        }
+    }
+
+    /// Wraps the error in an [`std::io::Error`], for the functions that return one,
+    /// converting it back to a [`LasZipError`] gives the original error.
+    pub(crate) fn into_io_error(self) -> std::io::Error {
+        let kind = match self {
+            LasZipError::IoError(e) => return e,
+            LasZipError::MemoryLimitExceeded { .. } => std::io::ErrorKind::OutOfMemory,
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, self)
+    }
 }
 
 impl From<std::io::Error> for LasZipError {
     fn from(e: std::io::Error) -> Self {
+        if e.get_ref().is_some_and(|inner| inner.is::<LasZipError>()) {
+            // Made by `into_io_error`
+            let inner = e.into_inner().unwrap();
+            return *inner.downcast::<LasZipError>().unwrap();
+        }
         LasZipError::IoError(e)
     }
 }
@@ -52,8 +194,146 @@ impl fmt::Display for LasZipError {
                 write!(f, "Point format {} is not supported", id)
             }
             LasZipError::MissingChunkTable => write!(f, "The chunk table could not be found"),
+            LasZipError::BufferLenNotMultipleOfPointSize {
+                buffer_len,
+                point_size,
+            } => write!(
+                f,
+                "The length of the buffer ({}) is not a multiple of the point size ({})",
+                buffer_len, point_size
+            ),
+            LasZipError::PointSizeMismatch { expected, found } => write!(
+                f,
+                "Expected points of {} bytes, got points of {} bytes",
+                expected, found
+            ),
+            LasZipError::ChunkIndexOutOfRange { index, num_chunks } => write!(
+                f,
+                "Chunk {} does not exist, there are {} chunks",
+                index, num_chunks
+            ),
+            LasZipError::ChunkTableMismatch {
+                table_bytes,
+                stream_bytes,
+            } => write!(
+                f,
+                "The chunk table describes {} bytes of chunks, the stream has {}",
+                table_bytes, stream_bytes
+            ),
+            LasZipError::OutputBufferTooSmall { required } => write!(
+                f,
+                "The output buffer is too small, {} bytes are required",
+                required
+            ),
+            LasZipError::MemoryLimitExceeded { limit, required } => write!(
+                f,
+                "{} bytes are required, which is more than the memory limit of {} bytes",
+                required, limit
+            ),
+            LasZipError::UnknownCoder(coder) => write!(f, "Coder {} is not valid", coder),
+            LasZipError::UnsupportedOption(option) => {
+                write!(
+                    f,
+                    "The option {} is not supported by this decompressor",
+                    option
+                )
+            }
+            LasZipError::StrictModeWarning(warning) => write!(f, "{} (strict mode)", warning),
+            LasZipError::ChunkLimitExceeded { limit, num_chunks } => write!(
+                f,
+                "The chunk table has {} chunks, which is more than the limit of {}",
+                num_chunks, limit
+            ),
         }
     }
 }
 
 impl std::error::Error for LasZipError {}
+
+/// Returns [`LasZipError::MemoryLimitExceeded`] if `required` bytes
+/// are more than the `limit`, if any.
+pub(crate) fn check_memory_limit(limit: Option<usize>, required: usize) -> crate::Result<()> {
+    match limit {
+        Some(limit) if required > limit => {
+            Err(LasZipError::MemoryLimitExceeded { limit, required })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Non-fatal issues found while reading LAZ data
+///
+/// They are reported to the callback given to functions such as
+/// [`LasZipDecompressor::new_with_warnings`](crate::LasZipDecompressor::new_with_warnings).
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LasZipWarning {
+    /// The chunk table could not be read, so seeking is not possible.
+    ChunkTableUnreadable(LasZipError),
+    /// The offset to the chunk table, that should be written before the points,
+    /// was not updated, it was found after the chunk table instead.
+    ChunkTableOffsetAtEnd,
+    /// The item is compressed using an old version of the compression.
+    DeprecatedItemVersion(LazItemType, u16),
+    /// Seeking to a point index that is greater than the number of points.
+    SeekPastEnd(u64),
+}
+
+impl fmt::Display for LasZipWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            LasZipWarning::ChunkTableUnreadable(e) => {
+                write!(f, "The chunk table could not be read: {}", e)
+            }
+            LasZipWarning::ChunkTableOffsetAtEnd => {
+                write!(
+                    f,
+                    "The offset to the chunk table was found at the end of the data"
+                )
+            }
+            LasZipWarning::DeprecatedItemVersion(item_type, version) => write!(
+                f,
+                "Item {:?} uses the deprecated compression version: {}",
+                item_type, version
+            ),
+            LasZipWarning::SeekPastEnd(index) => {
+                write!(f, "Point {} is past the end of the points", index)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        for (i, code) in ErrorCode::ALL.iter().enumerate() {
+            assert_eq!(code.as_u32(), i as u32 + 1);
+            assert_eq!(ErrorCode::from_u32(code.as_u32()), Some(*code));
+        }
+        assert_eq!(ErrorCode::from_u32(0), None);
+        assert_eq!(
+            LasZipError::MissingChunkTable.code(),
+            ErrorCode::MissingChunkTable
+        );
+    }
+
+    #[test]
+    fn test_io_error_round_trip() {
+        let e = LasZipError::MemoryLimitExceeded {
+            limit: 10,
+            required: 20,
+        }
+        .into_io_error();
+        assert_eq!(e.kind(), std::io::ErrorKind::OutOfMemory);
+        assert!(matches!(
+            LasZipError::from(e),
+            LasZipError::MemoryLimitExceeded {
+                limit: 10,
+                required: 20
+            }
+        ));
+    }
+}
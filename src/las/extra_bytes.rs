@@ -27,6 +27,7 @@
 //! Defines the different version of compressors and decompressors for ExtraBytes contained in points
 
 /// Just re-export v1 as v2 as they are both the same implementation
+#[cfg(all(feature = "format-legacy", feature = "extra-bytes"))]
 pub use v1 as v2;
 
 pub trait LasExtraBytes {
@@ -58,6 +59,7 @@ impl LasExtraBytes for ExtraBytes {
     }
 }
 
+#[cfg(all(feature = "format-legacy", feature = "extra-bytes"))]
 pub mod v1 {
     //! The Algorithm is simple:
     //! encode the difference between byte for each extra bytes
@@ -116,6 +118,7 @@ pub mod v1 {
         }
     }
 
+    #[derive(Clone)]
     pub struct LasExtraByteDecompressor {
         last_bytes: Vec<u8>,
         count: usize,
@@ -137,6 +140,15 @@ pub mod v1 {
     }
 
     impl<R: Read> FieldDecompressor<R> for LasExtraByteDecompressor {
+        fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+            Some(Box::new(self.clone()))
+        }
+
+        #[cfg(feature = "model-stats")]
+        fn add_model_stats(&self, stats: &mut Vec<crate::model_stats::ModelStats>) {
+            crate::model_stats::add_models_stats(stats, "bytes", &self.models);
+        }
+
         fn size_of_field(&self) -> usize {
             self.count
         }
@@ -163,6 +175,7 @@ pub mod v1 {
     }
 }
 
+#[cfg(all(feature = "format-14", feature = "extra-bytes"))]
 pub mod v3 {
     //! The algorithm is similar to v1 (& v2), the changes are
     //! that compressor / decompressor uses contexts (4)
@@ -176,6 +189,7 @@ pub mod v3 {
     use crate::encoders::ArithmeticEncoder;
     use crate::las::extra_bytes::ExtraBytes;
     use crate::las::utils::{copy_bytes_into_decoder, copy_encoder_content_to};
+    use crate::laszip::DecompressionSelection;
     use crate::models::{ArithmeticModel, ArithmeticModelBuilder};
     use crate::record::{LayeredFieldCompressor, LayeredFieldDecompressor};
 
@@ -202,6 +216,7 @@ pub mod v3 {
         decoders: Vec<ArithmeticDecoder<Cursor<Vec<u8>>>>,
         num_bytes_per_layer: Vec<u32>,
         has_byte_changed: Vec<bool>,
+        is_byte_requested: Vec<bool>,
         contexts: Vec<ExtraBytesContext>,
         num_extra_bytes: usize,
         last_context_used: usize,
@@ -209,12 +224,21 @@ pub mod v3 {
 
     impl LasExtraByteDecompressor {
         pub fn new(count: usize) -> Self {
+            Self::selective(count, DecompressionSelection::all())
+        }
+
+        /// Creates a decompressor that skips the layers of the extra bytes
+        /// that are not in the `selection`
+        pub fn selective(count: usize, selection: DecompressionSelection) -> Self {
             Self {
                 decoders: (0..count)
                     .map(|_i| ArithmeticDecoder::new(Cursor::new(Vec::<u8>::new())))
                     .collect(),
                 num_bytes_per_layer: vec![0; count],
                 has_byte_changed: vec![false; count],
+                is_byte_requested: (0..count)
+                    .map(|i| selection.extra_byte_requested(i))
+                    .collect(),
                 contexts: (0..4).map(|_i| ExtraBytesContext::new(count)).collect(),
                 num_extra_bytes: count,
                 last_context_used: 0,
@@ -227,6 +251,16 @@ pub mod v3 {
             self.num_extra_bytes
         }
 
+        #[cfg(feature = "model-stats")]
+        fn add_model_stats(&self, stats: &mut Vec<crate::model_stats::ModelStats>) {
+            for (i, eb_context) in self.contexts.iter().enumerate() {
+                if !eb_context.unused {
+                    let name = format!("contexts[{}].bytes", i);
+                    crate::model_stats::add_models_stats(stats, &name, &eb_context.models);
+                }
+            }
+        }
+
         fn init_first_point(
             &mut self,
             src: &mut R,
@@ -283,10 +317,17 @@ pub mod v3 {
             Ok(())
         }
 
+        fn layers_size(&self) -> usize {
+            self.num_bytes_per_layer
+                .iter()
+                .map(|&size| size as usize)
+                .sum()
+        }
+
         fn read_layers(&mut self, src: &mut R) -> std::io::Result<()> {
             for i in 0..self.num_extra_bytes {
                 self.has_byte_changed[i] = copy_bytes_into_decoder(
-                    true, // TODO requested bytes
+                    self.is_byte_requested[i],
                     self.num_bytes_per_layer[i] as usize,
                     &mut self.decoders[i],
                     src,
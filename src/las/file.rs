@@ -106,14 +106,24 @@ impl Vlr {
     }
 }
 
+impl Vlr {
+    /// Returns whether this is the vlr of LASzip
+    pub(crate) fn is_laszip_vlr(&self) -> bool {
+        self.record_id == 22204
+            && String::from_utf8_lossy(&self.user_id).trim_end_matches(|c| c as u8 == 0)
+                == "laszip encoded"
+    }
+
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
 pub fn read_vlrs_and_get_laszip_vlr<R: Read>(src: &mut R, header: &QuickHeader) -> Option<LazVlr> {
     let mut laszip_vlr = None;
     for _i in 0..header.num_vlrs {
         let vlr = Vlr::read_from(src).unwrap();
-        if vlr.record_id == 22204
-            && String::from_utf8_lossy(&vlr.user_id).trim_end_matches(|c| c as u8 == 0)
-                == "laszip encoded"
-        {
+        if vlr.is_laszip_vlr() {
             laszip_vlr = Some(LazVlr::read_from(vlr.data.as_slice()).unwrap());
         }
     }
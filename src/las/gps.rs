@@ -149,11 +149,8 @@ impl Packable for GpsTime {
             input.len() >= 8,
             "GpsTime::unpack_from expected a buffer of 8 bytes"
         );
-        let lower = u32::unpack_from_unchecked(input.get_unchecked(0..4));
-        let upper = u32::unpack_from_unchecked(input.get_unchecked(4..8));
-
         GpsTime {
-            value: i64::from(upper) << 32 | i64::from(lower),
+            value: u64::unpack_from_unchecked(input) as i64,
         }
     }
 
@@ -162,14 +159,11 @@ impl Packable for GpsTime {
             output.len() >= 8,
             "GpsTime::pack_into expected a buffer of 8 bytes"
         );
-        u32::pack_into_unchecked(
-            &((self.value & 0xFFFF_FFFF) as u32),
-            output.get_unchecked_mut(0..4),
-        );
-        u32::pack_into_unchecked(&((self.value >> 32) as u32), output.get_unchecked_mut(4..8));
+        u64::pack_into_unchecked(&(self.value as u64), output);
     }
 }
 
+#[cfg(feature = "format-legacy")]
 pub mod v1 {
     use std::io::{Read, Write};
 
@@ -191,6 +185,7 @@ pub mod v1 {
 
     const LASZIP_GPS_TIME_MULTI_MAX: u32 = 512;
 
+    #[derive(Clone)]
     pub struct LasGpsTimeDecompressor {
         last_gps: i64,
         gps_time_multi_model: ArithmeticModel,
@@ -375,6 +370,10 @@ pub mod v1 {
     }
 
     impl<R: Read> FieldDecompressor<R> for LasGpsTimeDecompressor {
+        fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+            Some(Box::new(self.clone()))
+        }
+
         fn size_of_field(&self) -> usize {
             std::mem::size_of::<f64>()
         }
@@ -459,6 +458,7 @@ pub mod v1 {
     }
 }
 
+#[cfg(feature = "format-legacy")]
 pub mod v2 {
     use std::io::{Read, Write};
 
@@ -477,6 +477,7 @@ pub mod v2 {
     };
 
     // Common parts for both a compressor and decompressor go here
+    #[derive(Clone)]
     struct Common {
         gps_time_multi: ArithmeticModel,
         gps_time_0_diff: ArithmeticModel,
@@ -836,6 +837,7 @@ pub mod v2 {
         }
     }
 
+    #[derive(Clone)]
     pub struct GpsTimeDecompressor {
         common: Common,
         ic_gps_time: IntegerDecompressor,
@@ -854,6 +856,18 @@ pub mod v2 {
     }
 
     impl<R: Read> FieldDecompressor<R> for GpsTimeDecompressor {
+        fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+            Some(Box::new(self.clone()))
+        }
+
+        #[cfg(feature = "model-stats")]
+        fn add_model_stats(&self, stats: &mut Vec<crate::model_stats::ModelStats>) {
+            use crate::model_stats::add_model_stats;
+            add_model_stats(stats, "gps_time_multi", &self.common.gps_time_multi);
+            add_model_stats(stats, "gps_time_0_diff", &self.common.gps_time_0_diff);
+            self.ic_gps_time.add_model_stats("ic_gps_time", stats);
+        }
+
         fn size_of_field(&self) -> usize {
             std::mem::size_of::<i64>()
         }
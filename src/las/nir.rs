@@ -5,7 +5,9 @@ pub trait LasNIR {
     fn set_nir(&mut self, new_val: u16);
 }
 
+/// Its layout is the one of the LAS format (on little-endian hosts).
 #[derive(Default, Copy, Clone, PartialOrd, PartialEq, Debug)]
+#[repr(C)]
 pub struct Nir(u16);
 
 impl LasNIR for Nir {
@@ -22,6 +24,7 @@ impl Nir {
     pub const SIZE: usize = 2;
 }
 
+#[cfg(feature = "format-14")]
 pub mod v3 {
     use std::io::{Cursor, Read, Seek};
 
@@ -34,6 +37,7 @@ pub mod v3 {
         copy_encoder_content_to, lower_byte, lower_byte_changed, read_and_unpack, upper_byte,
         upper_byte_changed,
     };
+    use crate::laszip::DecompressionSelection;
     use crate::models::{ArithmeticModel, ArithmeticModelBuilder};
     use crate::packers::Packable;
     use crate::record::{LayeredFieldCompressor, LayeredFieldDecompressor};
@@ -56,10 +60,10 @@ pub mod v3 {
         }
     }
 
-    //TODO Selective
     pub struct LasNIRDecompressor {
         decoder: ArithmeticDecoder<Cursor<Vec<u8>>>,
         changed_nir: bool,
+        requested_nir: bool,
         layer_size: u32,
         last_context_used: usize,
         // Last & contexts are separated for the same reasons as in v3::RGB
@@ -69,8 +73,17 @@ pub mod v3 {
 
     impl Default for LasNIRDecompressor {
         fn default() -> Self {
+            Self::selective(DecompressionSelection::all())
+        }
+    }
+
+    impl LasNIRDecompressor {
+        /// Creates a decompressor that skips the layer of the near infrared
+        /// if it is not in the `selection`
+        pub fn selective(selection: DecompressionSelection) -> Self {
             Self {
                 decoder: ArithmeticDecoder::new(Cursor::new(Vec::<u8>::new())),
+                requested_nir: selection.contains(DecompressionSelection::NIR),
                 contexts: [
                     NirContext::default(),
                     NirContext::default(),
@@ -90,6 +103,24 @@ pub mod v3 {
             std::mem::size_of::<u16>()
         }
 
+        #[cfg(feature = "model-stats")]
+        fn add_model_stats(&self, stats: &mut Vec<crate::model_stats::ModelStats>) {
+            use crate::model_stats::add_model_stats;
+            for (i, nir_context) in self.contexts.iter().enumerate() {
+                if !nir_context.unused {
+                    let prefix = format!("contexts[{}].", i);
+                    let models = [
+                        ("bytes_used", &nir_context.bytes_used_model),
+                        ("lower_byte_diff", &nir_context.lower_byte_diff_model),
+                        ("upper_byte_diff", &nir_context.upper_byte_diff_model),
+                    ];
+                    for (name, model) in models.iter() {
+                        add_model_stats(stats, &format!("{}{}", prefix, name), model);
+                    }
+                }
+            }
+        }
+
         fn init_first_point(
             &mut self,
             src: &mut R,
@@ -159,9 +190,13 @@ pub mod v3 {
             Ok(())
         }
 
+        fn layers_size(&self) -> usize {
+            self.layer_size as usize
+        }
+
         fn read_layers(&mut self, src: &mut R) -> std::io::Result<()> {
             self.changed_nir = copy_bytes_into_decoder(
-                true, //TODO
+                self.requested_nir,
                 self.layer_size as usize,
                 &mut self.decoder,
                 src,
@@ -258,11 +293,14 @@ pub mod v3 {
         }
 
         fn write_layers_sizes(&mut self, dst: &mut R) -> std::io::Result<()> {
-            if self.has_nir_changed {
+            // Like LASzip, the size is written even if nothing changed
+            let layer_size = if self.has_nir_changed {
                 self.encoder.done()?;
-                dst.write_u32::<LittleEndian>(self.encoder.get_mut().get_ref().len() as u32)?;
-            }
-            Ok(())
+                self.encoder.get_mut().get_ref().len() as u32
+            } else {
+                0
+            };
+            dst.write_u32::<LittleEndian>(layer_size)
         }
 
         fn write_layers(&mut self, dst: &mut R) -> std::io::Result<()> {
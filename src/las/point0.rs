@@ -254,6 +254,7 @@ impl Packable for Point0 {
     }
 }
 
+#[cfg(feature = "format-legacy")]
 pub mod v1 {
     use std::io::{Read, Write};
 
@@ -293,6 +294,7 @@ pub mod v1 {
         }
     }
 
+    #[derive(Clone)]
     pub struct LasPoint0Decompressor {
         last_point: Point0,
         last_x_diffs: [i32; 3],
@@ -523,6 +525,10 @@ pub mod v1 {
     }
 
     impl<R: Read> FieldDecompressor<R> for LasPoint0Decompressor {
+        fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+            Some(Box::new(self.clone()))
+        }
+
         fn size_of_field(&self) -> usize {
             20
         }
@@ -656,6 +662,7 @@ pub mod v1 {
     }
 }
 
+#[cfg(feature = "format-legacy")]
 pub mod v2 {
     use std::io::{Read, Write};
 
@@ -734,6 +741,7 @@ pub mod v2 {
         }
     }
 
+    #[derive(Clone)]
     struct Common {
         last_intensity: [u16; 16],
 
@@ -967,6 +975,7 @@ pub mod v2 {
         }
     }
 
+    #[derive(Clone)]
     pub struct LasPoint0Decompressor {
         last_point: Point0,
         ic_intensity: IntegerDecompressor,
@@ -1007,6 +1016,26 @@ pub mod v2 {
     }
 
     impl<R: Read> FieldDecompressor<R> for LasPoint0Decompressor {
+        fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+            Some(Box::new(self.clone()))
+        }
+
+        #[cfg(feature = "model-stats")]
+        fn add_model_stats(&self, stats: &mut Vec<crate::model_stats::ModelStats>) {
+            use crate::model_stats::{add_model_stats, add_models_stats};
+            add_model_stats(stats, "changed_values", &self.common.changed_values);
+            add_models_stats(stats, "scan_angle_rank", &self.common.scan_angle_rank);
+            add_models_stats(stats, "bit_byte", &self.common.bit_byte);
+            add_models_stats(stats, "classification", &self.common.classification);
+            add_models_stats(stats, "user_data", &self.common.user_data);
+            self.ic_intensity.add_model_stats("ic_intensity", stats);
+            self.ic_point_source_id
+                .add_model_stats("ic_point_source_id", stats);
+            self.ic_dx.add_model_stats("ic_dx", stats);
+            self.ic_dy.add_model_stats("ic_dy", stats);
+            self.ic_z.add_model_stats("ic_z", stats);
+        }
+
         fn size_of_field(&self) -> usize {
             20
         }
@@ -7,54 +7,6 @@ fn u32_zero_bit_0(n: u32) -> u32 {
     n & 0xFFFF_FFFE
 }
 
-pub struct DecompressionSelector(u32);
-
-impl DecompressionSelector {
-    pub fn decompress_all() -> Self {
-        Self { 0: 0xFFFF_FFFF }
-    }
-
-    pub fn channel_returns_xy_requested(&self) -> bool {
-        self.is_set(0x0000_0000)
-    }
-
-    pub fn z_requested(&self) -> bool {
-        self.is_set(0x0000_0001)
-    }
-
-    pub fn classification_requested(&self) -> bool {
-        self.is_set(0x0000_0002)
-    }
-
-    pub fn flags_requested(&self) -> bool {
-        self.is_set(0x0000_0004)
-    }
-
-    pub fn intensity_requested(&self) -> bool {
-        self.is_set(0x0000_0008)
-    }
-
-    pub fn scan_angle_requested(&self) -> bool {
-        self.is_set(0x0000_0010)
-    }
-
-    pub fn user_data_requested(&self) -> bool {
-        self.is_set(0x0000_0020)
-    }
-
-    pub fn point_source_requested(&self) -> bool {
-        self.is_set(0x0000_0040)
-    }
-
-    pub fn gps_time_requested(&self) -> bool {
-        self.is_set(0x0000_0080)
-    }
-
-    fn is_set(&self, mask: u32) -> bool {
-        self.0 & mask != 0
-    }
-}
-
 //TODO cleanup
 pub trait LasPoint6 {
     // Non mutable accessors
@@ -303,17 +255,17 @@ impl Packable for Point6 {
             "Point6::unpack_from expected buffer of 30 bytes"
         );
         Point6 {
-            x: i32::unpack_from_unchecked(&input[..4]),
-            y: i32::unpack_from_unchecked(&input[4..8]),
-            z: i32::unpack_from_unchecked(&input[8..12]),
-            bit_fields: u8::unpack_from_unchecked(&input[14..15]),
-            flags: u8::unpack_from_unchecked(&input[15..16]),
-            intensity: u16::unpack_from_unchecked(&input[12..14]),
-            classification: u8::unpack_from_unchecked(&input[16..17]),
-            scan_angle_rank: i16::unpack_from_unchecked(&input[18..20]),
-            user_data: u8::unpack_from_unchecked(&input[17..18]),
-            point_source_id: u16::unpack_from_unchecked(&input[20..22]),
-            gps_time: f64::from(GpsTime::unpack_from_unchecked(&input[22..30])),
+            x: i32::unpack_from_unchecked(input.get_unchecked(..4)),
+            y: i32::unpack_from_unchecked(input.get_unchecked(4..8)),
+            z: i32::unpack_from_unchecked(input.get_unchecked(8..12)),
+            bit_fields: u8::unpack_from_unchecked(input.get_unchecked(14..15)),
+            flags: u8::unpack_from_unchecked(input.get_unchecked(15..16)),
+            intensity: u16::unpack_from_unchecked(input.get_unchecked(12..14)),
+            classification: u8::unpack_from_unchecked(input.get_unchecked(16..17)),
+            scan_angle_rank: i16::unpack_from_unchecked(input.get_unchecked(18..20)),
+            user_data: u8::unpack_from_unchecked(input.get_unchecked(17..18)),
+            point_source_id: u16::unpack_from_unchecked(input.get_unchecked(20..22)),
+            gps_time: f64::from(GpsTime::unpack_from_unchecked(input.get_unchecked(22..30))),
             gps_time_change: false,
         }
     }
@@ -323,19 +275,24 @@ impl Packable for Point6 {
             output.len() >= Self::SIZE,
             "Point6::pack_into expected buffer of 30 bytes"
         );
-        self.x.pack_into_unchecked(&mut output[..4]);
-        self.y.pack_into_unchecked(&mut output[4..8]);
-        self.z.pack_into_unchecked(&mut output[8..12]);
-        self.intensity.pack_into_unchecked(&mut output[12..14]);
-        self.bit_fields.pack_into_unchecked(&mut output[14..15]);
-        self.flags.pack_into_unchecked(&mut output[15..16]);
-        self.classification.pack_into_unchecked(&mut output[16..17]);
-        self.user_data.pack_into_unchecked(&mut output[17..18]);
+        self.x.pack_into_unchecked(output.get_unchecked_mut(..4));
+        self.y.pack_into_unchecked(output.get_unchecked_mut(4..8));
+        self.z.pack_into_unchecked(output.get_unchecked_mut(8..12));
+        self.intensity
+            .pack_into_unchecked(output.get_unchecked_mut(12..14));
+        self.bit_fields
+            .pack_into_unchecked(output.get_unchecked_mut(14..15));
+        self.flags
+            .pack_into_unchecked(output.get_unchecked_mut(15..16));
+        self.classification
+            .pack_into_unchecked(output.get_unchecked_mut(16..17));
+        self.user_data
+            .pack_into_unchecked(output.get_unchecked_mut(17..18));
         self.scan_angle_rank
-            .pack_into_unchecked(&mut output[18..20]);
+            .pack_into_unchecked(output.get_unchecked_mut(18..20));
         self.point_source_id
-            .pack_into_unchecked(&mut output[20..22]);
-        GpsTime::from(self.gps_time).pack_into_unchecked(&mut output[22..30]);
+            .pack_into_unchecked(output.get_unchecked_mut(20..22));
+        GpsTime::from(self.gps_time).pack_into_unchecked(output.get_unchecked_mut(22..30));
     }
 }
 
@@ -357,6 +314,7 @@ mod test {
     }
 }
 
+#[cfg(feature = "format-14")]
 pub mod v3 {
     use std::io::{Cursor, Read, Seek, Write};
 
@@ -371,11 +329,12 @@ pub mod v3 {
     };
     use crate::encoders::ArithmeticEncoder;
     use crate::las::gps::{GpsTime, LasGpsTime};
-    use crate::las::point6::{u32_zero_bit_0, DecompressionSelector, LasPoint6, Point6};
+    use crate::las::point6::{u32_zero_bit_0, LasPoint6, Point6};
     use crate::las::utils::{
         copy_bytes_into_decoder, copy_encoder_content_to, i32_quantize, read_and_unpack,
         StreamingMedian, NUMBER_RETURN_LEVEL_8CT, NUMBER_RETURN_MAP_6CTX,
     };
+    use crate::laszip::DecompressionSelection;
     use crate::models::{ArithmeticModel, ArithmeticModelBuilder};
     use crate::packers::Packable;
     use crate::record::{LayeredFieldCompressor, LayeredFieldDecompressor};
@@ -667,6 +626,23 @@ pub mod v3 {
             dst.write_u32::<LittleEndian>(self.gps_time as u32)?;
             Ok(())
         }
+
+        /// Returns the number of bytes of all the layers
+        fn total(&self) -> usize {
+            [
+                self.channel_returns_xy,
+                self.z,
+                self.classification,
+                self.flags,
+                self.intensity,
+                self.scan_angle,
+                self.user_data,
+                self.point_source,
+                self.gps_time,
+            ]
+            .iter()
+            .fold(0usize, |total, &size| total.saturating_add(size))
+        }
     }
 
     pub struct LasPoint6Decompressor {
@@ -674,7 +650,7 @@ pub mod v3 {
 
         layers_sizes: LayerSizes,
         should_decompress: Point6FieldFlags,
-        decompression_selector: DecompressionSelector,
+        selection: DecompressionSelection,
 
         current_context: usize,
         contexts: [Point6DecompressionContext; 4],
@@ -682,18 +658,19 @@ pub mod v3 {
 
     impl Default for LasPoint6Decompressor {
         fn default() -> Self {
-            Self::selective(DecompressionSelector::decompress_all())
+            Self::selective(DecompressionSelection::all())
         }
     }
 
     impl LasPoint6Decompressor {
-        pub fn selective(selector: DecompressionSelector) -> Self {
+        /// Creates a decompressor that only decodes the layers of the fields in the `selection`
+        pub fn selective(selection: DecompressionSelection) -> Self {
             let p = Point6::default();
             Self {
                 decoders: Point6Decoders::default(),
                 layers_sizes: Default::default(),
                 should_decompress: Point6FieldFlags::default(),
-                decompression_selector: selector,
+                selection,
                 current_context: 0,
                 contexts: [
                     Point6DecompressionContext::from_last_point(&p),
@@ -1159,6 +1136,10 @@ pub mod v3 {
             Ok(())
         }
 
+        fn layers_size(&self) -> usize {
+            self.layers_sizes.total()
+        }
+
         fn read_layers(&mut self, src: &mut R) -> std::io::Result<()> {
             let num_bytes = &self.layers_sizes;
 
@@ -1170,55 +1151,57 @@ pub mod v3 {
             )?;
 
             self.should_decompress.z = copy_bytes_into_decoder(
-                self.decompression_selector.z_requested(),
+                self.selection.contains(DecompressionSelection::Z),
                 num_bytes.z,
                 &mut self.decoders.z,
                 src,
             )?;
 
             self.should_decompress.classification = copy_bytes_into_decoder(
-                self.decompression_selector.classification_requested(),
+                self.selection
+                    .contains(DecompressionSelection::CLASSIFICATION),
                 num_bytes.classification,
                 &mut self.decoders.classification,
                 src,
             )?;
 
             self.should_decompress.flags = copy_bytes_into_decoder(
-                self.decompression_selector.flags_requested(),
+                self.selection.contains(DecompressionSelection::FLAGS),
                 num_bytes.flags,
                 &mut self.decoders.flags,
                 src,
             )?;
 
             self.should_decompress.intensity = copy_bytes_into_decoder(
-                self.decompression_selector.intensity_requested(),
+                self.selection.contains(DecompressionSelection::INTENSITY),
                 num_bytes.intensity,
                 &mut self.decoders.intensity,
                 src,
             )?;
 
             self.should_decompress.scan_angle = copy_bytes_into_decoder(
-                self.decompression_selector.scan_angle_requested(),
+                self.selection.contains(DecompressionSelection::SCAN_ANGLE),
                 num_bytes.scan_angle,
                 &mut self.decoders.scan_angle,
                 src,
             )?;
 
             self.should_decompress.user_data = copy_bytes_into_decoder(
-                self.decompression_selector.user_data_requested(),
+                self.selection.contains(DecompressionSelection::USER_DATA),
                 num_bytes.user_data,
                 &mut self.decoders.user_data,
                 src,
             )?;
 
             self.should_decompress.point_source = copy_bytes_into_decoder(
-                self.decompression_selector.point_source_requested(),
+                self.selection
+                    .contains(DecompressionSelection::POINT_SOURCE_ID),
                 num_bytes.point_source,
                 &mut self.decoders.point_source,
                 src,
             )?;
             self.should_decompress.gps_time = copy_bytes_into_decoder(
-                self.decompression_selector.gps_time_requested(),
+                self.selection.contains(DecompressionSelection::GPS_TIME),
                 num_bytes.gps_time,
                 &mut self.decoders.gps_time,
                 src,
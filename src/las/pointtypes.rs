@@ -3,6 +3,7 @@ use crate::las::nir::Nir;
 pub use crate::las::point0::{LasPoint0, Point0};
 pub use crate::las::point6::{LasPoint6, Point6};
 pub use crate::las::rgb::{LasRGB, RGB};
+pub use crate::las::wavepacket::LasWavepacket;
 use crate::laszip::{DefaultVersion, LazItem, LazItemType, Version1, Version2, Version3};
 
 pub trait Point0Based {
@@ -302,6 +303,95 @@ impl DefaultVersion for Point3 {
     }
 }
 
+/***************************************************************************************************
+                    Point Format 4
+***************************************************************************************************/
+
+#[derive(Default, Debug, PartialEq, Copy, Clone)]
+pub struct Point4 {
+    base: Point0,
+    gps_time: f64,
+    wavepacket: LasWavepacket,
+}
+
+impl Version2 for Point4 {
+    fn version_2(num_extra_bytes: u16) -> Vec<LazItem> {
+        vec_of_laz_items!(
+            vec_capacity: 4,
+            version: 2,
+            extra_bytes_type: LazItemType::Byte(num_extra_bytes),
+            LazItemType::Point10,
+            LazItemType::GpsTime,
+            LazItemType::WavePacket13
+        )
+    }
+}
+
+impl Version1 for Point4 {
+    fn version_1(num_extra_bytes: u16) -> Vec<LazItem> {
+        vec_of_laz_items!(
+            vec_capacity: 4,
+            version: 1,
+            extra_bytes_type: LazItemType::Byte(num_extra_bytes),
+            LazItemType::Point10,
+            LazItemType::GpsTime,
+            LazItemType::WavePacket13
+        )
+    }
+}
+
+impl DefaultVersion for Point4 {
+    fn default_version(num_extra_bytes: u16) -> Vec<LazItem> {
+        <Self as Version2>::version_2(num_extra_bytes)
+    }
+}
+
+/***************************************************************************************************
+                    Point Format 5
+***************************************************************************************************/
+
+#[derive(Default, Debug, PartialEq, Copy, Clone)]
+pub struct Point5 {
+    base: Point0,
+    gps_time: f64,
+    rgb: RGB,
+    wavepacket: LasWavepacket,
+}
+
+impl Version2 for Point5 {
+    fn version_2(num_extra_bytes: u16) -> Vec<LazItem> {
+        vec_of_laz_items!(
+            vec_capacity: 5,
+            version: 2,
+            extra_bytes_type: LazItemType::Byte(num_extra_bytes),
+            LazItemType::Point10,
+            LazItemType::GpsTime,
+            LazItemType::RGB12,
+            LazItemType::WavePacket13
+        )
+    }
+}
+
+impl Version1 for Point5 {
+    fn version_1(num_extra_bytes: u16) -> Vec<LazItem> {
+        vec_of_laz_items!(
+            vec_capacity: 5,
+            version: 1,
+            extra_bytes_type: LazItemType::Byte(num_extra_bytes),
+            LazItemType::Point10,
+            LazItemType::GpsTime,
+            LazItemType::RGB12,
+            LazItemType::WavePacket13
+        )
+    }
+}
+
+impl DefaultVersion for Point5 {
+    fn default_version(num_extra_bytes: u16) -> Vec<LazItem> {
+        <Self as Version2>::version_2(num_extra_bytes)
+    }
+}
+
 /***************************************************************************************************
                     Point Format 6
 ***************************************************************************************************/
@@ -415,6 +505,65 @@ impl DefaultVersion for Point8 {
     }
 }
 
+/***************************************************************************************************
+                    Point Format 9
+***************************************************************************************************/
+
+#[derive(Default, Debug, PartialEq, Copy, Clone)]
+pub struct Point9 {
+    base: Point6,
+    wavepacket: LasWavepacket,
+}
+
+impl Version3 for Point9 {
+    fn version_3(num_extra_bytes: u16) -> Vec<LazItem> {
+        vec_of_laz_items![
+            vec_capacity: 3,
+            version: 3,
+            extra_bytes_type: LazItemType::Byte14(num_extra_bytes),
+            LazItemType::Point14,
+            LazItemType::WavePacket14
+        ]
+    }
+}
+
+impl DefaultVersion for Point9 {
+    fn default_version(num_extra_bytes: u16) -> Vec<LazItem> {
+        Self::version_3(num_extra_bytes)
+    }
+}
+
+/***************************************************************************************************
+                    Point Format 10
+***************************************************************************************************/
+
+#[derive(Default, Debug, PartialEq, Copy, Clone)]
+pub struct Point10 {
+    base: Point6,
+    rgb: RGB,
+    nir: Nir,
+    wavepacket: LasWavepacket,
+}
+
+impl Version3 for Point10 {
+    fn version_3(num_extra_bytes: u16) -> Vec<LazItem> {
+        vec_of_laz_items![
+            vec_capacity: 4,
+            version: 3,
+            extra_bytes_type: LazItemType::Byte14(num_extra_bytes),
+            LazItemType::Point14,
+            LazItemType::RGBNIR14,
+            LazItemType::WavePacket14
+        ]
+    }
+}
+
+impl DefaultVersion for Point10 {
+    fn default_version(num_extra_bytes: u16) -> Vec<LazItem> {
+        Self::version_3(num_extra_bytes)
+    }
+}
+
 /***************************************************************************************************
                     Auto implementation of some traits
 ***************************************************************************************************/
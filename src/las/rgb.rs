@@ -42,7 +42,10 @@ pub trait LasRGB {
 
 /// Struct representing a RGB component of a point, in compliance with
 /// the LAS spec
+///
+/// Its layout is the one of the LAS format (on little-endian hosts).
 #[derive(Default, Copy, Clone, Debug, PartialEq)]
+#[repr(C)]
 pub struct RGB {
     pub red: u16,
     pub green: u16,
@@ -155,6 +158,7 @@ impl Packable for RGB {
     }
 }
 
+#[cfg(feature = "format-legacy")]
 pub mod v1 {
     //! Contains the implementation for the Version 1 of the RGB Compression / Decompression
     //!
@@ -185,6 +189,7 @@ pub mod v1 {
     const LOWER_BLUE_BYTE_CONTEXT: u32 = 4;
     const UPPER_BLUE_BYTE_CONTEXT: u32 = 5;
 
+    #[derive(Clone)]
     pub struct LasRGBDecompressor {
         last: RGB,
         byte_used_model: ArithmeticModel,
@@ -236,6 +241,10 @@ pub mod v1 {
     }
 
     impl<R: Read> FieldDecompressor<R> for LasRGBDecompressor {
+        fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+            Some(Box::new(self.clone()))
+        }
+
         fn size_of_field(&self) -> usize {
             3 * size_of::<u16>()
         }
@@ -263,12 +272,12 @@ pub mod v1 {
             }
 
             if color_diff.upper_red_byte_changed() {
-                self.last.red |= (self.decompress_byte(
+                let new_upper_red = self.decompress_byte(
                     decoder,
                     UPPER_RED_BYTE_CONTEXT,
                     upper_byte(self.last.red),
-                )? as u16)
-                    << 8;
+                )?;
+                self.last.red = (new_upper_red as u16) << 8 | self.last.red & 0x00FF;
             }
 
             if color_diff.lower_green_byte_changed() {
@@ -281,12 +290,12 @@ pub mod v1 {
             }
 
             if color_diff.upper_green_byte_changed() {
-                self.last.green |= (self.decompress_byte(
+                let new_upper_green = self.decompress_byte(
                     decoder,
                     UPPER_GREEN_BYTE_CONTEXT,
                     upper_byte(self.last.green),
-                )? as u16)
-                    << 8;
+                )?;
+                self.last.green = (new_upper_green as u16) << 8 | self.last.green & 0x00FF;
             }
 
             if color_diff.lower_blue_byte_changed() {
@@ -299,12 +308,12 @@ pub mod v1 {
             }
 
             if color_diff.upper_blue_byte_changed() {
-                self.last.blue |= (self.decompress_byte(
+                let new_upper_blue = self.decompress_byte(
                     decoder,
                     UPPER_BLUE_BYTE_CONTEXT,
                     upper_byte(self.last.blue),
-                )? as u16)
-                    << 8;
+                )?;
+                self.last.blue = (new_upper_blue as u16) << 8 | self.last.blue & 0x00FF;
             }
             self.last.pack_into(buf);
             Ok(())
@@ -387,7 +396,7 @@ pub mod v1 {
                     &mut encoder,
                     i32::from(upper_byte(self.last.blue)),
                     i32::from(upper_byte(current_point.blue)),
-                    UPPER_GREEN_BYTE_CONTEXT,
+                    UPPER_BLUE_BYTE_CONTEXT,
                 )?;
             }
             self.last = current_point;
@@ -396,6 +405,8 @@ pub mod v1 {
     }
 }
 
+// The models of v2 are also used by v3
+#[cfg(any(feature = "format-legacy", feature = "format-14"))]
 pub mod v2 {
     //! Contains the implementation for the Version 2 of the RGB Compression / Decompression
     use std::io::{Read, Write};
@@ -410,6 +421,7 @@ pub mod v2 {
 
     use super::{ColorDiff, RGB};
 
+    #[derive(Clone)]
     pub(crate) struct RGBModels {
         byte_used: ArithmeticModel,
         lower_red_byte: ArithmeticModel,
@@ -420,6 +432,29 @@ pub mod v2 {
         upper_blue_byte: ArithmeticModel,
     }
 
+    impl RGBModels {
+        #[cfg(feature = "model-stats")]
+        pub(crate) fn add_model_stats(
+            &self,
+            prefix: &str,
+            stats: &mut Vec<crate::model_stats::ModelStats>,
+        ) {
+            use crate::model_stats::add_model_stats;
+            let models = [
+                ("byte_used", &self.byte_used),
+                ("lower_red_byte", &self.lower_red_byte),
+                ("upper_red_byte", &self.upper_red_byte),
+                ("lower_green_byte", &self.lower_green_byte),
+                ("upper_green_byte", &self.upper_green_byte),
+                ("lower_blue_byte", &self.lower_blue_byte),
+                ("upper_blue_byte", &self.upper_blue_byte),
+            ];
+            for (name, model) in models.iter() {
+                add_model_stats(stats, &format!("{}{}", prefix, name), model);
+            }
+        }
+    }
+
     impl Default for RGBModels {
         fn default() -> Self {
             Self {
@@ -527,6 +562,7 @@ pub mod v2 {
         }
     }
 
+    #[derive(Clone)]
     pub struct LasRGBDecompressor {
         last: RGB,
         models: RGBModels,
@@ -620,6 +656,15 @@ pub mod v2 {
     }
 
     impl<R: Read> FieldDecompressor<R> for LasRGBDecompressor {
+        fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+            Some(Box::new(self.clone()))
+        }
+
+        #[cfg(feature = "model-stats")]
+        fn add_model_stats(&self, stats: &mut Vec<crate::model_stats::ModelStats>) {
+            self.models.add_model_stats("", stats);
+        }
+
         fn size_of_field(&self) -> usize {
             6
         }
@@ -642,6 +687,7 @@ pub mod v2 {
     }
 }
 
+#[cfg(feature = "format-14")]
 pub mod v3 {
     //! Contains the implementation for the Version 3 of the RGB Compression / Decompression
     //!
@@ -660,6 +706,7 @@ pub mod v3 {
     use crate::las::utils::{
         copy_bytes_into_decoder, copy_encoder_content_to, inner_buffer_len_of, read_and_unpack,
     };
+    use crate::laszip::DecompressionSelection;
     use crate::packers::Packable;
     use crate::record::{LayeredFieldCompressor, LayeredFieldDecompressor};
 
@@ -696,10 +743,18 @@ pub mod v3 {
 
     impl Default for LasRGBDecompressor {
         fn default() -> Self {
+            Self::selective(DecompressionSelection::all())
+        }
+    }
+
+    impl LasRGBDecompressor {
+        /// Creates a decompressor that skips the layer of the colors
+        /// if they are not in the `selection`
+        pub fn selective(selection: DecompressionSelection) -> Self {
             Self {
                 decoder: ArithmeticDecoder::new(Cursor::new(Vec::<u8>::new())),
                 changed_rgb: false,
-                requested_rgb: true,
+                requested_rgb: selection.contains(DecompressionSelection::RGB),
                 layer_size: 0,
                 contexts: [
                     LasDecompressionContextRGB::default(),
@@ -718,6 +773,16 @@ pub mod v3 {
             std::mem::size_of::<u16>() * 3
         }
 
+        #[cfg(feature = "model-stats")]
+        fn add_model_stats(&self, stats: &mut Vec<crate::model_stats::ModelStats>) {
+            for (i, rgb_context) in self.contexts.iter().enumerate() {
+                if !rgb_context.unused {
+                    let prefix = format!("contexts[{}].", i);
+                    rgb_context.models.add_model_stats(&prefix, stats);
+                }
+            }
+        }
+
         fn init_first_point(
             &mut self,
             src: &mut R,
@@ -772,6 +837,10 @@ pub mod v3 {
             Ok(())
         }
 
+        fn layers_size(&self) -> usize {
+            self.layer_size as usize
+        }
+
         fn read_layers(&mut self, src: &mut R) -> std::io::Result<()> {
             self.changed_rgb = copy_bytes_into_decoder(
                 self.requested_rgb,
@@ -848,11 +917,14 @@ pub mod v3 {
         }
 
         fn write_layers_sizes(&mut self, dst: &mut R) -> std::io::Result<()> {
-            if self.rgb_has_changed {
+            // Like LASzip, the size is written even if nothing changed
+            let layer_size = if self.rgb_has_changed {
                 self.encoder.done()?;
-                dst.write_u32::<LittleEndian>(inner_buffer_len_of(&self.encoder) as u32)?;
-            }
-            Ok(())
+                inner_buffer_len_of(&self.encoder) as u32
+            } else {
+                0
+            };
+            dst.write_u32::<LittleEndian>(layer_size)
         }
 
         fn write_layers(&mut self, dst: &mut R) -> std::io::Result<()> {
@@ -978,4 +1050,77 @@ mod test {
         assert_eq!(ColorDiff::from_points(&a, &b).0, 0b00000000);
         assert_eq!(ColorDiff::from_points(&b, &a).0, 0b00000000);
     }
+
+    /// The upper bytes of the colors are replaced (not or-ed) when they change,
+    /// and the upper blue byte has its own context.
+    #[test]
+    fn test_v1_upper_bytes_changes() {
+        use crate::las::Point2;
+        use crate::laszip::{LazItemRecordBuilder, LazVlr};
+
+        let vlr = LazVlr::from_laz_items(LazItemRecordBuilder::version_1_of::<Point2>(0));
+        let colors = [
+            [0x1234u16, 0x5678, 0x9ABC],
+            [0x0134, 0x0278, 0x03BC],
+            [0xFF00, 0x0001, 0x8000],
+            [0x00FF, 0xF0F0, 0x0F0F],
+        ];
+        let mut points = vec![0u8; 100 * 26];
+        for (i, point) in points.chunks_exact_mut(26).enumerate() {
+            point[0..4].copy_from_slice(&(i as i32).to_le_bytes());
+            for (j, color) in colors[i % colors.len()].iter().enumerate() {
+                point[20 + 2 * j..22 + 2 * j].copy_from_slice(&color.to_le_bytes());
+            }
+        }
+        let mut compressed = std::io::Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+
+        let mut decompressed = vec![0u8; points.len()];
+        crate::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr).unwrap();
+        assert_eq!(decompressed, points);
+    }
+
+    /// Like LASzip, the v3 compressors write the size of the RGB (and NIR) layer,
+    /// 0, when the colors do not change in a chunk, as the decompressors always read it.
+    #[test]
+    fn test_v3_unchanged_colors_layer_size() {
+        use byteorder::{LittleEndian, ReadBytesExt};
+        use std::io::Cursor;
+
+        // Point format, point size, number of layers of the colors
+        for &(point_format_id, point_size, num_color_layers) in &[(7u8, 36, 1), (8, 38, 2)] {
+            let vlr = crate::LazVlrBuilder::default()
+                .with_point_format(point_format_id, 0)
+                .unwrap()
+                .build();
+            let mut points = vec![0u8; 100 * point_size];
+            for (i, point) in points.chunks_exact_mut(point_size).enumerate() {
+                point[0..4].copy_from_slice(&(i as i32).to_le_bytes());
+                point[14] = 0x11;
+                // Constant colors (and NIR)
+                point[30..].iter_mut().for_each(|byte| *byte = 0xAB);
+            }
+            let mut compressed = Cursor::new(Vec::<u8>::new());
+            crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+            let compressed = compressed.into_inner();
+
+            // The offset to the chunk table, then the raw first point,
+            // the number of points, the sizes of the 9 point layers & of the colors layers
+            let mut data = Cursor::new(&compressed);
+            let offset_to_chunk_table = data.read_i64::<LittleEndian>().unwrap() as u64;
+            data.set_position(8 + point_size as u64);
+            assert_eq!(data.read_u32::<LittleEndian>().unwrap(), 100);
+            let layers_sizes = (0..9 + num_color_layers)
+                .map(|_| data.read_u32::<LittleEndian>().unwrap())
+                .collect::<Vec<u32>>();
+            assert!(layers_sizes[9..].iter().all(|&size| size == 0));
+            let end_of_chunk =
+                data.position() + layers_sizes.iter().map(|&s| u64::from(s)).sum::<u64>();
+            assert_eq!(end_of_chunk, offset_to_chunk_table, "{}", point_format_id);
+
+            let mut decompressed = vec![0u8; points.len()];
+            crate::decompress_buffer(&compressed, &mut decompressed, vlr).unwrap();
+            assert_eq!(decompressed, points, "{}", point_format_id);
+        }
+    }
 }
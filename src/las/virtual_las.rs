@@ -0,0 +1,340 @@
+//! Reading of a LAZ file as if it was the equivalent uncompressed LAS file
+//!
+//! [`VirtualLasReader`] implements `Read + Seek` over the bytes of the LAS file
+//! that decompressing the LAZ file would give: the header, the VLRs without
+//! the LASzip one, the uncompressed points and the EVLRs.
+//! Only the chunks of points that are read are decompressed,
+//! so libraries that only know LAS can read LAZ files through it.
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io::{Read, Seek, SeekFrom};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::las::file::{point_format_id_compressed_to_uncompressd, QuickHeader, Vlr};
+use crate::laszip::{invalid_data, LasZipDecompressor, LazOpenOptions, LazVlr};
+use crate::LasZipError;
+
+/// Size of the header of a VLR
+const VLR_HEADER_SIZE: usize = 54;
+/// Offsets of fields of the LAS header
+const OFFSET_TO_POINTS_POS: usize = 96;
+const NUM_VLRS_POS: usize = 100;
+const POINT_FORMAT_POS: usize = 104;
+const START_OF_WAVEFORM_DATA_POS: usize = 227;
+const START_OF_FIRST_EVLR_POS: usize = 235;
+
+/// Number of decompressed chunks kept by default
+const DEFAULT_CACHE_CAPACITY: usize = 2;
+
+/// Presents a LAZ file as the equivalent uncompressed LAS file, see the [module](self) doc.
+///
+/// The header and VLRs, as well as the EVLRs of LAS 1.4 files, are read
+/// when the reader is created, the points are decompressed a chunk at a time
+/// when they are read, the last chunks used are cached.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use std::io::{Read, Seek, SeekFrom};
+/// use laz::las::virtual_las::VirtualLasReader;
+///
+/// let laz_file = std::fs::File::open("tests/data/point-time.laz")?;
+/// let mut las = VirtualLasReader::new(laz_file)?;
+///
+/// // This is what a LAS reader would do
+/// let header = laz::las::file::QuickHeader::read_from(&mut las)?;
+/// assert_eq!(header.point_format_id, 1);
+/// las.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+/// let mut first_point = vec![0u8; usize::from(header.point_size)];
+/// las.read_exact(&mut first_point)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct VirtualLasReader<'a, R: Read + Seek + Send + 'a> {
+    decompressor: LasZipDecompressor<'a, R>,
+    /// Header and VLRs of the LAS file
+    prefix: Vec<u8>,
+    /// EVLRs of the LAS file
+    suffix: Vec<u8>,
+    point_size: u64,
+    /// Index of the first point of each chunk, followed by the number of points
+    chunk_starts: Vec<u64>,
+    /// Decompressed chunks, the most recently used first
+    cache: VecDeque<(usize, Vec<u8>)>,
+    cache_capacity: usize,
+    position: u64,
+}
+
+impl<'a, R: Read + Seek + Send + 'a> VirtualLasReader<'a, R> {
+    /// Creates the reader, the `src` position does not matter.
+    ///
+    /// Returns an error if the `src` is not a LAZ file, or if it has no chunk table.
+    pub fn new(src: R) -> crate::Result<Self> {
+        Self::with_options(src, LazOpenOptions::new())
+    }
+
+    /// Creates the reader, like [`Self::new`], with a decompressor configured
+    /// by the `options`, which also give the capacity of the cache,
+    /// see [`LazOpenOptions::with_cache_capacity`].
+    pub fn with_options(mut src: R, options: LazOpenOptions<'a>) -> crate::Result<Self> {
+        let cache_capacity = options
+            .cache_capacity
+            .map_or(DEFAULT_CACHE_CAPACITY, |capacity| capacity.max(1));
+        let header = QuickHeader::read_from(&mut src)?;
+        if header.point_format_id & 0xC0 == 0 {
+            return Err(invalid_data("The points are not compressed").into());
+        }
+
+        let offset_to_points = u64::from(header.offset_to_points);
+        let mut prefix = vec![0u8; header.offset_to_points as usize];
+        src.seek(SeekFrom::Start(0))?;
+        src.read_exact(&mut prefix)?;
+
+        // Removes the LASzip vlr
+        let mut laszip_vlr = None;
+        let mut vlr_start = usize::from(header.header_size);
+        for _ in 0..header.num_vlrs {
+            let vlr = Vlr::read_from(&mut prefix.get(vlr_start..).unwrap_or(&[]))?;
+            let vlr_end = vlr_start + VLR_HEADER_SIZE + vlr.data().len();
+            if vlr.is_laszip_vlr() {
+                laszip_vlr = Some(LazVlr::read_from(vlr.data())?);
+                prefix.drain(vlr_start..vlr_end);
+                break;
+            }
+            vlr_start = vlr_end;
+        }
+        let laszip_vlr = laszip_vlr.ok_or_else(|| invalid_data("The LASzip vlr is missing"))?;
+        let removed = offset_to_points - prefix.len() as u64;
+        let point_size = laszip_vlr.items_size();
+        let points_end = prefix.len() as u64 + header.num_points * point_size;
+
+        (&mut prefix[OFFSET_TO_POINTS_POS..])
+            .write_u32::<LittleEndian>(header.offset_to_points - removed as u32)?;
+        (&mut prefix[NUM_VLRS_POS..]).write_u32::<LittleEndian>(header.num_vlrs - 1)?;
+        prefix[POINT_FORMAT_POS] =
+            point_format_id_compressed_to_uncompressd(header.point_format_id);
+
+        let mut suffix = Vec::new();
+        if header.major == 1 && header.minor >= 4 {
+            let start_of_first_evlr =
+                (&prefix[START_OF_FIRST_EVLR_POS..]).read_u64::<LittleEndian>()?;
+            if start_of_first_evlr != 0 {
+                src.seek(SeekFrom::Start(start_of_first_evlr))?;
+                src.read_to_end(&mut suffix)?;
+                (&mut prefix[START_OF_FIRST_EVLR_POS..]).write_u64::<LittleEndian>(points_end)?;
+
+                // The waveform data may be in an EVLR, that moved
+                let start_of_waveform_data =
+                    (&prefix[START_OF_WAVEFORM_DATA_POS..]).read_u64::<LittleEndian>()?;
+                if start_of_waveform_data >= start_of_first_evlr {
+                    (&mut prefix[START_OF_WAVEFORM_DATA_POS..]).write_u64::<LittleEndian>(
+                        start_of_waveform_data - start_of_first_evlr + points_end,
+                    )?;
+                }
+            }
+        }
+
+        src.seek(SeekFrom::Start(offset_to_points))?;
+        let mut decompressor = LasZipDecompressor::with_options(src, laszip_vlr, options)?;
+        let chunk_table = decompressor
+            .load_chunk_table()?
+            .ok_or(LasZipError::MissingChunkTable)?;
+        let mut chunk_starts = Vec::with_capacity(chunk_table.len() + 1);
+        let mut num_points = 0u64;
+        for entry in chunk_table.as_ref() {
+            if num_points >= header.num_points {
+                break;
+            }
+            chunk_starts.push(num_points);
+            num_points = (num_points + entry.point_count).min(header.num_points);
+        }
+        if num_points < header.num_points {
+            return Err(invalid_data("The chunks do not hold all the points of the header").into());
+        }
+        chunk_starts.push(num_points);
+
+        Ok(Self {
+            decompressor,
+            prefix,
+            suffix,
+            point_size,
+            chunk_starts,
+            cache: VecDeque::with_capacity(cache_capacity),
+            cache_capacity,
+            position: 0,
+        })
+    }
+
+    /// Sets the number of decompressed chunks kept (at least 1), 2 by default
+    pub fn set_cache_capacity(&mut self, num_chunks: usize) {
+        self.cache_capacity = num_chunks.max(1);
+        self.cache.truncate(self.cache_capacity);
+    }
+
+    /// Returns the size of the LAS file
+    pub fn len(&self) -> u64 {
+        self.points_end() + self.suffix.len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn into_inner(self) -> R {
+        self.decompressor.into_inner()
+    }
+
+    fn points_start(&self) -> u64 {
+        self.prefix.len() as u64
+    }
+
+    fn points_end(&self) -> u64 {
+        self.points_start() + self.chunk_starts.last().copied().unwrap_or(0) * self.point_size
+    }
+
+    /// Returns the points of the chunk, decompressing them if they are not cached
+    fn chunk(&mut self, index: usize) -> std::io::Result<&[u8]> {
+        if let Some(i) = self.cache.iter().position(|(cached, _)| *cached == index) {
+            let entry = self.cache.remove(i).unwrap();
+            self.cache.push_front(entry);
+        } else {
+            let mut points = if self.cache.len() == self.cache_capacity {
+                self.cache.pop_back().unwrap().1
+            } else {
+                Vec::new()
+            };
+            let num_points = self.chunk_starts[index + 1] - self.chunk_starts[index];
+            points.resize((num_points * self.point_size) as usize, 0u8);
+            self.decompressor
+                .decompress_chunk(index, &mut points)
+                .map_err(LasZipError::into_io_error)?;
+            self.cache.push_front((index, points));
+        }
+        Ok(&self.cache[0].1)
+    }
+}
+
+impl<'a, R: Read + Seek + Send + 'a> Read for VirtualLasReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let points_start = self.points_start();
+        let points_end = self.points_end();
+        let position = self.position;
+        let n = if position < points_start {
+            let mut prefix = &self.prefix[position as usize..];
+            prefix.read(buf)?
+        } else if position < points_end {
+            let offset = position - points_start;
+            let point_index = offset / self.point_size;
+            let index = self
+                .chunk_starts
+                .partition_point(|&start| start <= point_index)
+                - 1;
+            let offset_in_chunk = offset - self.chunk_starts[index] * self.point_size;
+            let mut points = &self.chunk(index)?[offset_in_chunk as usize..];
+            points.read(buf)?
+        } else {
+            let offset = usize::try_from(position - points_end).unwrap_or(usize::MAX);
+            let mut suffix = self.suffix.get(offset..).unwrap_or(&[]);
+            suffix.read(buf)?
+        };
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<'a, R: Read + Seek + Send + 'a> Seek for VirtualLasReader<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(position) => {
+                self.position = position;
+                return Ok(position);
+            }
+            SeekFrom::End(offset) => (self.len(), offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Checks that the LAS presented by the `reader` has the points of the `las` file
+    fn check_points<R: Read + Seek + Send>(mut reader: VirtualLasReader<R>, las: &[u8]) {
+        let las_header = QuickHeader::read_from(&mut Cursor::new(las)).unwrap();
+        let las_points = &las[las_header.offset_to_points as usize..]
+            [..(las_header.num_points * u64::from(las_header.point_size)) as usize];
+
+        reader.set_cache_capacity(1);
+        let mut virtual_las = Vec::new();
+        reader.read_to_end(&mut virtual_las).unwrap();
+        assert_eq!(virtual_las.len() as u64, reader.len());
+
+        let header = QuickHeader::read_from(&mut Cursor::new(&virtual_las)).unwrap();
+        assert_eq!(header.point_format_id, las_header.point_format_id);
+        assert_eq!(header.num_vlrs, las_header.num_vlrs);
+        assert_eq!(&virtual_las[header.offset_to_points as usize..], las_points);
+
+        // Reads going back and forth between chunks
+        let point_size = usize::from(header.point_size);
+        for &point_index in &[1000usize, 3, 999, 160, 50_000, 1065, 99] {
+            let mut points = vec![0u8; point_size * 2];
+            let start = u64::from(header.offset_to_points) + (point_index * point_size) as u64;
+            reader.seek(SeekFrom::Start(start)).unwrap();
+            let n = reader.read(&mut points).unwrap();
+            let expected = las_points.get(point_index * point_size..).unwrap_or(&[]);
+            assert_eq!(&points[..n], &expected[..expected.len().min(n)]);
+        }
+        assert_eq!(
+            reader.seek(SeekFrom::End(-1)).unwrap(),
+            virtual_las.len() as u64 - 1
+        );
+        assert!(reader
+            .seek(SeekFrom::Current(-(virtual_las.len() as i64)))
+            .is_err());
+    }
+
+    #[test]
+    fn test_virtual_las_has_the_points_of_the_las() {
+        for laz_path in glob::glob("tests/data/*.laz").unwrap() {
+            let laz_path = laz_path.unwrap();
+            let las = std::fs::read(laz_path.with_extension("las")).unwrap();
+            let reader = VirtualLasReader::new(std::fs::File::open(&laz_path).unwrap()).unwrap();
+            check_points(reader, &las);
+
+            // With many chunks
+            #[cfg(feature = "parallel")]
+            {
+                let mut laz =
+                    crate::par_transcode_las_to_laz(&las, Cursor::new(Vec::new()), 100).unwrap();
+                laz.set_position(0);
+                check_points(VirtualLasReader::new(laz).unwrap(), &las);
+            }
+        }
+    }
+
+    #[test]
+    fn test_virtual_las_with_options() {
+        let laz = std::fs::File::open("tests/data/point10.laz").unwrap();
+        let options = LazOpenOptions::new()
+            .with_lazy_chunk_table(true)
+            .with_cache_capacity(3);
+        let reader = VirtualLasReader::with_options(laz, options).unwrap();
+        assert_eq!(reader.cache_capacity, 3);
+        check_points(reader, &std::fs::read("tests/data/point10.las").unwrap());
+    }
+}
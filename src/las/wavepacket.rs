@@ -0,0 +1,731 @@
+//! Defines the compressors and decompressors for the wave packet descriptors
+//! of point formats 4, 5, 9 & 10
+//!
+//! Both versions code the descriptors the way LASzip does:
+//!
+//! - The descriptor index is encoded as a symbol.
+//! - The byte offset to the waveform data is encoded as one of 4 cases
+//!   (unchanged, right after the last packet, a 32 bits difference, or the full 64 bits offset).
+//! - The packet size, return point location and the x, y, z (t) parameters
+//!   are encoded with integer compressors, the floats using their bits.
+
+use crate::packers::Packable;
+
+/// Size in bytes of a wave packet descriptor
+pub const WAVEPACKET_SIZE: usize = 29;
+
+/// The wave packet descriptor of a point, in compliance with the LAS spec
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct LasWavepacket {
+    pub descriptor_index: u8,
+    pub offset: u64,
+    pub packet_size: u32,
+    pub return_point: f32,
+    pub x_t: f32,
+    pub y_t: f32,
+    pub z_t: f32,
+}
+
+impl LasWavepacket {
+    pub const SIZE: usize = WAVEPACKET_SIZE;
+}
+
+impl Packable for LasWavepacket {
+    fn unpack_from(input: &[u8]) -> Self {
+        assert!(input.len() >= WAVEPACKET_SIZE);
+        unsafe { Self::unpack_from_unchecked(input) }
+    }
+
+    fn pack_into(&self, output: &mut [u8]) {
+        assert!(output.len() >= WAVEPACKET_SIZE);
+        unsafe { self.pack_into_unchecked(output) }
+    }
+
+    unsafe fn unpack_from_unchecked(input: &[u8]) -> Self {
+        debug_assert!(input.len() >= WAVEPACKET_SIZE);
+        Self {
+            descriptor_index: u8::unpack_from_unchecked(input.get_unchecked(0..1)),
+            offset: u64::unpack_from_unchecked(input.get_unchecked(1..9)),
+            packet_size: u32::unpack_from_unchecked(input.get_unchecked(9..13)),
+            return_point: f32::from_bits(u32::unpack_from_unchecked(input.get_unchecked(13..17))),
+            x_t: f32::from_bits(u32::unpack_from_unchecked(input.get_unchecked(17..21))),
+            y_t: f32::from_bits(u32::unpack_from_unchecked(input.get_unchecked(21..25))),
+            z_t: f32::from_bits(u32::unpack_from_unchecked(input.get_unchecked(25..29))),
+        }
+    }
+
+    unsafe fn pack_into_unchecked(&self, output: &mut [u8]) {
+        debug_assert!(output.len() >= WAVEPACKET_SIZE);
+        u8::pack_into_unchecked(&self.descriptor_index, output.get_unchecked_mut(0..1));
+        u64::pack_into_unchecked(&self.offset, output.get_unchecked_mut(1..9));
+        u32::pack_into_unchecked(&self.packet_size, output.get_unchecked_mut(9..13));
+        u32::pack_into_unchecked(
+            &self.return_point.to_bits(),
+            output.get_unchecked_mut(13..17),
+        );
+        u32::pack_into_unchecked(&self.x_t.to_bits(), output.get_unchecked_mut(17..21));
+        u32::pack_into_unchecked(&self.y_t.to_bits(), output.get_unchecked_mut(21..25));
+        u32::pack_into_unchecked(&self.z_t.to_bits(), output.get_unchecked_mut(25..29));
+    }
+}
+
+#[cfg(any(feature = "format-legacy", feature = "format-14"))]
+use codec::{WavepacketCompressionModels, WavepacketDecompressionModels};
+
+#[cfg(any(feature = "format-legacy", feature = "format-14"))]
+mod codec {
+    //! The models & coding of a descriptor, shared by v1 and v3
+    //! (v3 has one set of models per context)
+    use std::io::{Read, Write};
+
+    use crate::compressors::{IntegerCompressor, IntegerCompressorBuilder};
+    use crate::decoders::ArithmeticDecoder;
+    use crate::decompressors::{IntegerDecompressor, IntegerDecompressorBuilder};
+    use crate::encoders::ArithmeticEncoder;
+    use crate::las::wavepacket::LasWavepacket;
+    use crate::models::{ArithmeticModel, ArithmeticModelBuilder};
+
+    const OFFSET_UNCHANGED: u32 = 0;
+    const OFFSET_AFTER_LAST_PACKET: u32 = 1;
+    const OFFSET_DIFF_32: u32 = 2;
+    const OFFSET_FULL_64: u32 = 3;
+
+    fn offset_diff_models() -> [ArithmeticModel; 4] {
+        [
+            ArithmeticModelBuilder::new(4).build(),
+            ArithmeticModelBuilder::new(4).build(),
+            ArithmeticModelBuilder::new(4).build(),
+            ArithmeticModelBuilder::new(4).build(),
+        ]
+    }
+
+    pub(super) struct WavepacketCompressionModels {
+        packet_index: ArithmeticModel,
+        offset_diff: [ArithmeticModel; 4],
+        ic_offset_diff: IntegerCompressor,
+        ic_packet_size: IntegerCompressor,
+        ic_return_point: IntegerCompressor,
+        ic_xyz: IntegerCompressor,
+        last_diff_32: i32,
+        sym_last_offset_diff: u32,
+    }
+
+    impl Default for WavepacketCompressionModels {
+        fn default() -> Self {
+            Self {
+                packet_index: ArithmeticModelBuilder::new(256).build(),
+                offset_diff: offset_diff_models(),
+                ic_offset_diff: IntegerCompressorBuilder::new().bits(32).build_initialized(),
+                ic_packet_size: IntegerCompressorBuilder::new().bits(32).build_initialized(),
+                ic_return_point: IntegerCompressorBuilder::new().bits(32).build_initialized(),
+                ic_xyz: IntegerCompressorBuilder::new()
+                    .bits(32)
+                    .contexts(3)
+                    .build_initialized(),
+                last_diff_32: 0,
+                sym_last_offset_diff: 0,
+            }
+        }
+    }
+
+    impl WavepacketCompressionModels {
+        pub(super) fn compress_with<W: Write>(
+            &mut self,
+            encoder: &mut ArithmeticEncoder<W>,
+            current: &LasWavepacket,
+            last: &LasWavepacket,
+        ) -> std::io::Result<()> {
+            encoder.encode_symbol(&mut self.packet_index, u32::from(current.descriptor_index))?;
+
+            let diff_64 = current.offset.wrapping_sub(last.offset) as i64;
+            let diff_32 = diff_64 as i32;
+            let sym = if diff_64 == i64::from(diff_32) {
+                if diff_32 == 0 {
+                    OFFSET_UNCHANGED
+                } else if diff_32 == last.packet_size as i32 {
+                    OFFSET_AFTER_LAST_PACKET
+                } else {
+                    OFFSET_DIFF_32
+                }
+            } else {
+                OFFSET_FULL_64
+            };
+            encoder.encode_symbol(
+                &mut self.offset_diff[self.sym_last_offset_diff as usize],
+                sym,
+            )?;
+            self.sym_last_offset_diff = sym;
+            if sym == OFFSET_DIFF_32 {
+                self.ic_offset_diff
+                    .compress(encoder, self.last_diff_32, diff_32, 0)?;
+                self.last_diff_32 = diff_32;
+            } else if sym == OFFSET_FULL_64 {
+                encoder.write_int64(current.offset)?;
+            }
+
+            self.ic_packet_size.compress(
+                encoder,
+                last.packet_size as i32,
+                current.packet_size as i32,
+                0,
+            )?;
+            self.ic_return_point.compress(
+                encoder,
+                last.return_point.to_bits() as i32,
+                current.return_point.to_bits() as i32,
+                0,
+            )?;
+            self.ic_xyz.compress(
+                encoder,
+                last.x_t.to_bits() as i32,
+                current.x_t.to_bits() as i32,
+                0,
+            )?;
+            self.ic_xyz.compress(
+                encoder,
+                last.y_t.to_bits() as i32,
+                current.y_t.to_bits() as i32,
+                1,
+            )?;
+            self.ic_xyz.compress(
+                encoder,
+                last.z_t.to_bits() as i32,
+                current.z_t.to_bits() as i32,
+                2,
+            )
+        }
+    }
+
+    #[derive(Clone)]
+    pub(super) struct WavepacketDecompressionModels {
+        packet_index: ArithmeticModel,
+        offset_diff: [ArithmeticModel; 4],
+        ic_offset_diff: IntegerDecompressor,
+        ic_packet_size: IntegerDecompressor,
+        ic_return_point: IntegerDecompressor,
+        ic_xyz: IntegerDecompressor,
+        last_diff_32: i32,
+        sym_last_offset_diff: u32,
+    }
+
+    impl Default for WavepacketDecompressionModels {
+        fn default() -> Self {
+            Self {
+                packet_index: ArithmeticModelBuilder::new(256).build(),
+                offset_diff: offset_diff_models(),
+                ic_offset_diff: IntegerDecompressorBuilder::new()
+                    .bits(32)
+                    .build_initialized(),
+                ic_packet_size: IntegerDecompressorBuilder::new()
+                    .bits(32)
+                    .build_initialized(),
+                ic_return_point: IntegerDecompressorBuilder::new()
+                    .bits(32)
+                    .build_initialized(),
+                ic_xyz: IntegerDecompressorBuilder::new()
+                    .bits(32)
+                    .contexts(3)
+                    .build_initialized(),
+                last_diff_32: 0,
+                sym_last_offset_diff: 0,
+            }
+        }
+    }
+
+    impl WavepacketDecompressionModels {
+        pub(super) fn decompress_with<R: Read>(
+            &mut self,
+            decoder: &mut ArithmeticDecoder<R>,
+            last: &LasWavepacket,
+        ) -> std::io::Result<LasWavepacket> {
+            let descriptor_index = decoder.decode_symbol(&mut self.packet_index)? as u8;
+
+            let sym =
+                decoder.decode_symbol(&mut self.offset_diff[self.sym_last_offset_diff as usize])?;
+            self.sym_last_offset_diff = sym;
+            let offset = match sym {
+                OFFSET_UNCHANGED => last.offset,
+                OFFSET_AFTER_LAST_PACKET => last.offset.wrapping_add(u64::from(last.packet_size)),
+                OFFSET_DIFF_32 => {
+                    self.last_diff_32 =
+                        self.ic_offset_diff
+                            .decompress(decoder, self.last_diff_32, 0)?;
+                    last.offset.wrapping_add(self.last_diff_32 as i64 as u64)
+                }
+                _ => decoder.read_int_64()?,
+            };
+
+            let packet_size =
+                self.ic_packet_size
+                    .decompress(decoder, last.packet_size as i32, 0)? as u32;
+            let return_point =
+                self.ic_return_point
+                    .decompress(decoder, last.return_point.to_bits() as i32, 0)?;
+            let x_t = self
+                .ic_xyz
+                .decompress(decoder, last.x_t.to_bits() as i32, 0)?;
+            let y_t = self
+                .ic_xyz
+                .decompress(decoder, last.y_t.to_bits() as i32, 1)?;
+            let z_t = self
+                .ic_xyz
+                .decompress(decoder, last.z_t.to_bits() as i32, 2)?;
+
+            Ok(LasWavepacket {
+                descriptor_index,
+                offset,
+                packet_size,
+                return_point: f32::from_bits(return_point as u32),
+                x_t: f32::from_bits(x_t as u32),
+                y_t: f32::from_bits(y_t as u32),
+                z_t: f32::from_bits(z_t as u32),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "format-legacy")]
+pub mod v1 {
+    //! The descriptors are coded point after point, v2 of LAZ uses this same version.
+    use std::io::{Read, Write};
+
+    use crate::decoders::ArithmeticDecoder;
+    use crate::encoders::ArithmeticEncoder;
+    use crate::las::utils::read_and_unpack;
+    use crate::las::wavepacket::{
+        LasWavepacket, WavepacketCompressionModels, WavepacketDecompressionModels, WAVEPACKET_SIZE,
+    };
+    use crate::packers::Packable;
+    use crate::record::{FieldCompressor, FieldDecompressor};
+
+    #[derive(Default)]
+    pub struct LasWavepacketCompressor {
+        last_wavepacket: LasWavepacket,
+        models: WavepacketCompressionModels,
+    }
+
+    impl<W: Write> FieldCompressor<W> for LasWavepacketCompressor {
+        fn size_of_field(&self) -> usize {
+            WAVEPACKET_SIZE
+        }
+
+        fn compress_first(&mut self, dst: &mut W, buf: &[u8]) -> std::io::Result<()> {
+            self.last_wavepacket = LasWavepacket::unpack_from(buf);
+            dst.write_all(buf)
+        }
+
+        fn compress_with(
+            &mut self,
+            encoder: &mut ArithmeticEncoder<W>,
+            buf: &[u8],
+        ) -> std::io::Result<()> {
+            let current = LasWavepacket::unpack_from(buf);
+            self.models
+                .compress_with(encoder, &current, &self.last_wavepacket)?;
+            self.last_wavepacket = current;
+            Ok(())
+        }
+    }
+
+    #[derive(Default, Clone)]
+    pub struct LasWavepacketDecompressor {
+        last_wavepacket: LasWavepacket,
+        models: WavepacketDecompressionModels,
+    }
+
+    impl<R: Read> FieldDecompressor<R> for LasWavepacketDecompressor {
+        fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+            Some(Box::new(self.clone()))
+        }
+
+        fn size_of_field(&self) -> usize {
+            WAVEPACKET_SIZE
+        }
+
+        fn decompress_first(&mut self, src: &mut R, first_point: &mut [u8]) -> std::io::Result<()> {
+            self.last_wavepacket = read_and_unpack(src, first_point)?;
+            Ok(())
+        }
+
+        fn decompress_with(
+            &mut self,
+            decoder: &mut ArithmeticDecoder<R>,
+            buf: &mut [u8],
+        ) -> std::io::Result<()> {
+            self.last_wavepacket = self
+                .models
+                .decompress_with(decoder, &self.last_wavepacket)?;
+            self.last_wavepacket.pack_into(buf);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "format-14")]
+pub mod v3 {
+    //! The descriptors of all the points (but the first) of the chunk
+    //! are coded in a single layer, with one set of models per context.
+    //!
+    //! When all the descriptors of the chunk are the same as the first one,
+    //! the layer is empty.
+    use std::io::{Cursor, Read, Seek, Write};
+
+    use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+    use crate::decoders::ArithmeticDecoder;
+    use crate::encoders::ArithmeticEncoder;
+    use crate::las::utils::{
+        copy_bytes_into_decoder, copy_encoder_content_to, inner_buffer_len_of, read_and_unpack,
+    };
+    use crate::las::wavepacket::{
+        LasWavepacket, WavepacketCompressionModels, WavepacketDecompressionModels, WAVEPACKET_SIZE,
+    };
+    use crate::laszip::DecompressionSelection;
+    use crate::packers::Packable;
+    use crate::record::{LayeredFieldCompressor, LayeredFieldDecompressor};
+
+    struct WavepacketCompressionContext {
+        models: WavepacketCompressionModels,
+        last_wavepacket: LasWavepacket,
+        unused: bool,
+    }
+
+    impl WavepacketCompressionContext {
+        fn new(last_wavepacket: LasWavepacket) -> Self {
+            Self {
+                models: WavepacketCompressionModels::default(),
+                last_wavepacket,
+                unused: false,
+            }
+        }
+    }
+
+    pub struct LasWavepacketCompressor {
+        encoder: ArithmeticEncoder<Cursor<Vec<u8>>>,
+        has_wavepacket_changed: bool,
+        last_context_used: usize,
+        contexts: [Option<WavepacketCompressionContext>; 4],
+    }
+
+    impl Default for LasWavepacketCompressor {
+        fn default() -> Self {
+            Self {
+                encoder: ArithmeticEncoder::new(Cursor::new(Vec::<u8>::new())),
+                has_wavepacket_changed: false,
+                last_context_used: 0,
+                contexts: [None, None, None, None],
+            }
+        }
+    }
+
+    impl<W: Write> LayeredFieldCompressor<W> for LasWavepacketCompressor {
+        fn size_of_field(&self) -> usize {
+            WAVEPACKET_SIZE
+        }
+
+        fn init_first_point(
+            &mut self,
+            dst: &mut W,
+            first_point: &[u8],
+            context: &mut usize,
+        ) -> std::io::Result<()> {
+            for ctx in self.contexts.iter_mut().flatten() {
+                ctx.unused = true;
+            }
+            dst.write_all(first_point)?;
+            self.contexts[*context] = Some(WavepacketCompressionContext::new(
+                LasWavepacket::unpack_from(first_point),
+            ));
+            self.last_context_used = *context;
+            Ok(())
+        }
+
+        fn compress_field_with(
+            &mut self,
+            current_point: &[u8],
+            context: &mut usize,
+        ) -> std::io::Result<()> {
+            if self.last_context_used != *context {
+                let is_unused = match &self.contexts[*context] {
+                    Some(ctx) => ctx.unused,
+                    None => true,
+                };
+                if is_unused {
+                    let last_wavepacket = self.contexts[self.last_context_used]
+                        .as_ref()
+                        .expect("Last context used is not initialized")
+                        .last_wavepacket;
+                    self.contexts[*context] =
+                        Some(WavepacketCompressionContext::new(last_wavepacket));
+                }
+                self.last_context_used = *context;
+            }
+            let the_context = self.contexts[self.last_context_used]
+                .as_mut()
+                .expect("Current context is not initialized");
+
+            let current = LasWavepacket::unpack_from(current_point);
+            let mut last_bytes = [0u8; WAVEPACKET_SIZE];
+            the_context.last_wavepacket.pack_into(&mut last_bytes);
+            if last_bytes[..] != current_point[..WAVEPACKET_SIZE] {
+                self.has_wavepacket_changed = true;
+            }
+
+            the_context.models.compress_with(
+                &mut self.encoder,
+                &current,
+                &the_context.last_wavepacket,
+            )?;
+            the_context.last_wavepacket = current;
+            Ok(())
+        }
+
+        fn write_layers_sizes(&mut self, dst: &mut W) -> std::io::Result<()> {
+            // Like LASzip, the size is written even if nothing changed
+            self.encoder.done()?;
+            let layer_size = if self.has_wavepacket_changed {
+                inner_buffer_len_of(&self.encoder) as u32
+            } else {
+                0
+            };
+            dst.write_u32::<LittleEndian>(layer_size)
+        }
+
+        fn write_layers(&mut self, dst: &mut W) -> std::io::Result<()> {
+            if self.has_wavepacket_changed {
+                copy_encoder_content_to(&mut self.encoder, dst)?;
+            }
+            Ok(())
+        }
+    }
+
+    struct WavepacketDecompressionContext {
+        models: WavepacketDecompressionModels,
+        last_wavepacket: LasWavepacket,
+        unused: bool,
+    }
+
+    impl WavepacketDecompressionContext {
+        fn new(last_wavepacket: LasWavepacket) -> Self {
+            Self {
+                models: WavepacketDecompressionModels::default(),
+                last_wavepacket,
+                unused: false,
+            }
+        }
+    }
+
+    pub struct LasWavepacketDecompressor {
+        decoder: ArithmeticDecoder<Cursor<Vec<u8>>>,
+        changed_wavepacket: bool,
+        requested_wavepacket: bool,
+        layer_size: u32,
+        last_context_used: usize,
+        contexts: [Option<WavepacketDecompressionContext>; 4],
+    }
+
+    impl Default for LasWavepacketDecompressor {
+        fn default() -> Self {
+            Self::selective(DecompressionSelection::all())
+        }
+    }
+
+    impl LasWavepacketDecompressor {
+        /// Creates a decompressor that skips the layer of the descriptors
+        /// if they are not in the `selection`
+        pub fn selective(selection: DecompressionSelection) -> Self {
+            Self {
+                decoder: ArithmeticDecoder::new(Cursor::new(Vec::<u8>::new())),
+                changed_wavepacket: false,
+                requested_wavepacket: selection.contains(DecompressionSelection::WAVEPACKET),
+                layer_size: 0,
+                last_context_used: 0,
+                contexts: [None, None, None, None],
+            }
+        }
+    }
+
+    impl<R: Read + Seek> LayeredFieldDecompressor<R> for LasWavepacketDecompressor {
+        fn size_of_field(&self) -> usize {
+            WAVEPACKET_SIZE
+        }
+
+        fn init_first_point(
+            &mut self,
+            src: &mut R,
+            first_point: &mut [u8],
+            context: &mut usize,
+        ) -> std::io::Result<()> {
+            for ctx in self.contexts.iter_mut().flatten() {
+                ctx.unused = true;
+            }
+            let first_wavepacket = read_and_unpack::<_, LasWavepacket>(src, first_point)?;
+            self.contexts[*context] = Some(WavepacketDecompressionContext::new(first_wavepacket));
+            self.last_context_used = *context;
+            Ok(())
+        }
+
+        fn decompress_field_with(
+            &mut self,
+            current_point: &mut [u8],
+            context: &mut usize,
+        ) -> std::io::Result<()> {
+            if self.last_context_used != *context {
+                let is_unused = match &self.contexts[*context] {
+                    Some(ctx) => ctx.unused,
+                    None => true,
+                };
+                if is_unused {
+                    let last_wavepacket = self.contexts[self.last_context_used]
+                        .as_ref()
+                        .expect("Last context used is not initialized")
+                        .last_wavepacket;
+                    self.contexts[*context] =
+                        Some(WavepacketDecompressionContext::new(last_wavepacket));
+                }
+                self.last_context_used = *context;
+            }
+            let the_context = self.contexts[self.last_context_used]
+                .as_mut()
+                .expect("Current context is not initialized");
+
+            if self.changed_wavepacket {
+                the_context.last_wavepacket = the_context
+                    .models
+                    .decompress_with(&mut self.decoder, &the_context.last_wavepacket)?;
+            }
+            the_context.last_wavepacket.pack_into(current_point);
+            Ok(())
+        }
+
+        fn read_layers_sizes(&mut self, src: &mut R) -> std::io::Result<()> {
+            self.layer_size = src.read_u32::<LittleEndian>()?;
+            Ok(())
+        }
+
+        fn layers_size(&self) -> usize {
+            self.layer_size as usize
+        }
+
+        fn read_layers(&mut self, src: &mut R) -> std::io::Result<()> {
+            self.changed_wavepacket = copy_bytes_into_decoder(
+                self.requested_wavepacket,
+                self.layer_size as usize,
+                &mut self.decoder,
+                src,
+            )?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        compress_buffer, decompress_buffer, LazItem, LazItemRecordBuilder, LazItemType, LazVlr,
+        LazVlrBuilder,
+    };
+
+    #[test]
+    fn test_pack_unpack() {
+        let wavepacket = LasWavepacket {
+            descriptor_index: 3,
+            offset: 0x0102_0304_0506_0708,
+            packet_size: 256,
+            return_point: 12.5,
+            x_t: -0.25,
+            y_t: 0.5,
+            z_t: -1.0,
+        };
+        let mut bytes = [0u8; WAVEPACKET_SIZE];
+        wavepacket.pack_into(&mut bytes);
+        assert_eq!(bytes[0], 3);
+        assert_eq!(&bytes[1..9], &0x0102_0304_0506_0708u64.to_le_bytes());
+        assert_eq!(&bytes[25..29], &(-1.0f32).to_le_bytes());
+        assert_eq!(LasWavepacket::unpack_from(&bytes), wavepacket);
+    }
+
+    fn test_wavepacket_round_trip(items: Vec<LazItem>) {
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(50).build();
+        let point_size = vlr.items_size() as usize;
+        let wavepacket_start = point_size - WAVEPACKET_SIZE;
+        let is_point14 = vlr.items()[0].item_type == LazItemType::Point14;
+
+        // The first chunk has varied descriptors (covering all the ways the offset is coded),
+        // the second one has the same descriptor for all its points
+        let mut points = vec![0u8; 120 * point_size];
+        let mut wavepacket = LasWavepacket::default();
+        for (i, point) in points.chunks_exact_mut(point_size).enumerate() {
+            // coordinates
+            point[..4].copy_from_slice(&(i as i32).to_le_bytes());
+            point[4..8].copy_from_slice(&(2 * i as i32).to_le_bytes());
+            // return number & number of returns
+            point[14] = if is_point14 { 0x11 } else { 0x9 };
+            if is_point14 {
+                // scanner channel, which is the context of the layered compression
+                point[15] = ((i / 7 % 4) as u8) << 4;
+            }
+            if i < 50 || i >= 100 {
+                wavepacket = LasWavepacket {
+                    descriptor_index: (i % 3) as u8 + 1,
+                    offset: match i % 4 {
+                        0 => wavepacket.offset,
+                        1 => wavepacket.offset + u64::from(wavepacket.packet_size),
+                        2 => wavepacket.offset + 3 * i as u64,
+                        _ => (1u64 << 40) * i as u64,
+                    },
+                    packet_size: 64 * (i % 5) as u32,
+                    return_point: i as f32 * 0.5,
+                    x_t: -0.001 * i as f32,
+                    y_t: 0.002 * i as f32,
+                    z_t: -1.0,
+                };
+            }
+            wavepacket.pack_into(&mut point[wavepacket_start..]);
+        }
+
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        let compressed = compressed.into_inner();
+        // The descriptors are compressed, not stored as is
+        assert!(compressed.len() < 120 * WAVEPACKET_SIZE);
+
+        let mut decompressed = vec![0u8; points.len()];
+        decompress_buffer(&compressed, &mut decompressed, vlr).unwrap();
+        assert_eq!(decompressed, points);
+    }
+
+    #[test]
+    fn test_wavepacket13_round_trip() {
+        test_wavepacket_round_trip(
+            LazItemRecordBuilder::new()
+                .add_item(LazItemType::Point10)
+                .add_item(LazItemType::WavePacket13)
+                .build(),
+        );
+    }
+
+    #[test]
+    fn test_wavepacket14_round_trip() {
+        test_wavepacket_round_trip(
+            LazItemRecordBuilder::new()
+                .add_item(LazItemType::Point14)
+                .add_item(LazItemType::WavePacket14)
+                .build(),
+        );
+    }
+
+    #[test]
+    fn test_wavepacket_point_formats_round_trip() {
+        for point_format_id in [4u8, 5, 9, 10].iter() {
+            let items =
+                LazItemRecordBuilder::default_for_point_format_id(*point_format_id, 0).unwrap();
+            let vlr = LazVlr::from_laz_items(items.clone());
+            assert!(vlr.is_compatible_with_point_format(*point_format_id, 0));
+            test_wavepacket_round_trip(items);
+        }
+    }
+}
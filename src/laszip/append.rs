@@ -0,0 +1,249 @@
+//! Appending of points to already compressed data
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::chunk_table::ChunkTable;
+use super::details::{self, invalid_data, usize_from};
+use super::{CompressionCheckpoint, LasZipCompressor, LazVlr};
+
+/// Compressor that appends points to already compressed points,
+/// without decompressing and compressing them again.
+///
+/// The existing chunks are kept, the new points go into new chunks, and the
+/// chunk table is written again, after the new chunks, by [`done`].
+///
+/// With **fixed-size** chunks, if the last existing chunk is not full, its points
+/// are decompressed and compressed again at the start of the new points,
+/// so that only the last chunk of the result may be smaller than the chunk size.
+///
+/// # Note
+///
+/// Everything after the existing chunks gets overwritten (like EVLRs in a LAS file),
+/// and if the `stream` is a file, it may be bigger than the new data.
+/// Also, the number of points in the LAS header has to be updated by the caller.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::{Cursor, Seek, SeekFrom};
+/// use laz::laszip::LasZipAppender;
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+/// let points = (0..25u8).flat_map(|i| [i; 20]).collect::<Vec<u8>>();
+///
+/// let mut compressed = Cursor::new(Vec::<u8>::new());
+/// laz::compress_buffer(&mut compressed, &points[..15 * 20], vlr.clone())?;
+///
+/// compressed.seek(SeekFrom::Start(0))?;
+/// let mut appender = LasZipAppender::new(compressed, vlr.clone(), 15)?;
+/// appender.compress_many(&points[15 * 20..])?;
+/// let compressed = appender.finish()?;
+///
+/// let mut decompressed = vec![0u8; points.len()];
+/// laz::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr)?;
+/// assert_eq!(decompressed, points);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`done`]: Self::done
+pub struct LasZipAppender<'a, W: Write + Send + 'a> {
+    compressor: LasZipCompressor<'a, W>,
+    /// Number of points compressed, including the existing ones
+    num_points: u64,
+}
+
+impl<'a, W: Read + Write + Seek + Send + 'a> LasZipAppender<'a, W> {
+    /// Creates an appender to the compressed points of the `stream`, described by the `vlr`.
+    ///
+    /// The `stream` position must be at the start of the point data
+    /// (where the offset to the chunk table is), and `num_points` is the number
+    /// of points already compressed (the number of points of the LAS header).
+    ///
+    /// The chunk table must be stored after the chunks, data where it is stored in
+    /// EVLRs (see [`LazVlr::special_evlrs`]) cannot be appended to.
+    pub fn new(mut stream: W, vlr: LazVlr, num_points: u64) -> crate::Result<Self> {
+        if vlr.special_evlrs().is_some() {
+            return Err(
+                invalid_data("Cannot append to data whose chunk table is stored in EVLRs").into(),
+            );
+        }
+        let start_pos = stream.stream_position()?;
+        let mut chunk_table = ChunkTable::read_from(&mut stream, &vlr)?;
+
+        let mut last_chunk = Vec::new();
+        if num_points == 0 {
+            // Compressing no points still writes an empty chunk
+            chunk_table.truncate(0);
+        } else if vlr.uses_variable_size_chunks() {
+            if chunk_table.num_points() != num_points {
+                return Err(invalid_data(format!(
+                    "The chunk table has {} points, instead of the {} points given",
+                    chunk_table.num_points(),
+                    num_points
+                ))
+                .into());
+            }
+        } else {
+            let chunk_size = u64::from(vlr.chunk_size());
+            let num_chunks = num_points.div_ceil(chunk_size);
+            if num_chunks != chunk_table.len() as u64 {
+                return Err(invalid_data(format!(
+                    "The chunk table has {} chunks, instead of the {} chunks of the {} points given",
+                    chunk_table.len(),
+                    num_chunks,
+                    num_points
+                ))
+                .into());
+            }
+            let points_in_last_chunk = num_points - (num_chunks - 1) * chunk_size;
+            if points_in_last_chunk != chunk_size {
+                chunk_table.truncate(chunk_table.len() - 1);
+                stream.seek(SeekFrom::Start(start_pos + 8 + chunk_table.byte_count()))?;
+                last_chunk = vec![0u8; usize_from(points_in_last_chunk * vlr.items_size())?];
+                let mut decompressor = details::record_decompressor_from_vlr(&vlr, &mut stream)?;
+                decompressor.decompress_many(&mut last_chunk)?;
+            }
+        }
+
+        let checkpoint = CompressionCheckpoint::new(start_pos, chunk_table);
+        let num_points = checkpoint.num_points();
+        let mut appender = Self {
+            compressor: LasZipCompressor::resume(stream, vlr, checkpoint)?,
+            num_points,
+        };
+        appender.compress_many(&last_chunk)?;
+        Ok(appender)
+    }
+}
+
+impl<'a, W: Write + Seek + Send + 'a> LasZipAppender<'a, W> {
+    /// Compresses one point, after the existing ones
+    pub fn compress_one(&mut self, input: &[u8]) -> std::io::Result<()> {
+        self.compressor.compress_one(input)?;
+        self.num_points += 1;
+        Ok(())
+    }
+
+    /// Compresses all the points contained in the `input` slice, after the existing ones
+    pub fn compress_many(&mut self, input: &[u8]) -> std::io::Result<()> {
+        let point_size = self.compressor.vlr().items_size() as usize;
+        for point in input.chunks_exact(point_size) {
+            self.compress_one(point)?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the current chunk, see [`LasZipCompressor::finish_current_chunk`].
+    ///
+    /// # Important
+    ///
+    /// Only call this when appending to **variable-size** chunks.
+    pub fn finish_current_chunk(&mut self) -> std::io::Result<()> {
+        self.compressor.finish_current_chunk()
+    }
+
+    /// Must be called when you have compressed all your points,
+    /// it writes the chunk table of all the chunks.
+    pub fn done(&mut self) -> std::io::Result<()> {
+        self.compressor.done()
+    }
+
+    /// Calls [`done`], flushes the output and returns it,
+    /// see [`LasZipCompressor::finish`].
+    ///
+    /// [`done`]: Self::done
+    pub fn finish(self) -> crate::Result<W> {
+        self.compressor.finish()
+    }
+
+    /// Returns the number of points, the existing ones included
+    pub fn num_points(&self) -> u64 {
+        self.num_points
+    }
+
+    pub fn vlr(&self) -> &LazVlr {
+        self.compressor.vlr()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.compressor.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.compressor.get_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils::points;
+    use crate::{LasZipDecompressor, LazItemRecordBuilder, LazVlrBuilder};
+
+    fn append_and_check(vlr: LazVlr, point_size: usize, existing: usize, appended: usize) {
+        let points = points(existing + appended, point_size);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(
+            &mut compressed,
+            &points[..existing * point_size],
+            vlr.clone(),
+        )
+        .unwrap();
+
+        compressed.set_position(0);
+        let mut appender = LasZipAppender::new(compressed, vlr.clone(), existing as u64).unwrap();
+        appender
+            .compress_many(&points[existing * point_size..])
+            .unwrap();
+        assert_eq!(appender.num_points(), (existing + appended) as u64);
+        let compressed = appender.finish().unwrap();
+
+        let mut decompressed = vec![0u8; points.len()];
+        crate::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr.clone()).unwrap();
+        assert_eq!(decompressed, points);
+
+        // The chunk table must describe all the chunks, for seeking
+        let mut decompressor =
+            LasZipDecompressor::new(Cursor::new(compressed.get_ref()), vlr).unwrap();
+        let mut point = vec![0u8; point_size];
+        let last = existing + appended - 1;
+        decompressor.seek(last as u64).unwrap();
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(point, &points[last * point_size..]);
+    }
+
+    #[test]
+    fn test_append_fixed_size_chunks() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point3>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        append_and_check(vlr.clone(), 34, 20, 15);
+        append_and_check(vlr.clone(), 34, 15, 12);
+        append_and_check(vlr, 34, 0, 5);
+
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point7>(2);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        append_and_check(vlr, 38, 13, 21);
+    }
+
+    #[test]
+    fn test_append_variable_size_chunks() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point6>(0);
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        append_and_check(vlr, 30, 13, 7);
+    }
+
+    #[test]
+    fn test_append_point_count_mismatch() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points(15, 20), vlr.clone()).unwrap();
+
+        compressed.set_position(0);
+        assert!(LasZipAppender::new(compressed, vlr, 25).is_err());
+    }
+}
@@ -0,0 +1,527 @@
+//! Compression and decompression over tokio's async IO traits
+use std::io::{Cursor, SeekFrom};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use crate::record::RecordCompressor;
+use crate::LasZipError;
+
+use super::details;
+use super::{ChunkTable, ChunkTableEntry, LazOpenOptions, LazVlr, PushDecompressor};
+
+/// Number of compressed bytes read from the source at once
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Compressor writing to an [`AsyncWrite`] + [`AsyncSeek`] output.
+///
+/// The points of the current chunk are compressed in memory, and each chunk
+/// is written to the output once finished, so the compression itself does not block
+/// on IO.
+///
+/// Like [`LasZipCompressor`], fixed-size chunks are ended automatically,
+/// variable-size ones are ended by [`finish_current_chunk`].
+///
+/// # Example
+///
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::{AsyncLasZipCompressor, AsyncLasZipDecompressor};
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+/// let points = (0..25u8).flat_map(|i| [i; 20]).collect::<Vec<u8>>();
+///
+/// let mut compressor = AsyncLasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone())?;
+/// compressor.compress_many(&points).await?;
+/// let mut compressed = compressor.finish().await?;
+///
+/// compressed.set_position(0);
+/// let mut decompressor = AsyncLasZipDecompressor::new(compressed, vlr).await?;
+/// let mut decompressed = vec![0u8; points.len()];
+/// decompressor.decompress_many(&mut decompressed).await?;
+/// assert_eq!(decompressed, points);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`LasZipCompressor`]: super::LasZipCompressor
+/// [`finish_current_chunk`]: Self::finish_current_chunk
+pub struct AsyncLasZipCompressor<W: AsyncWrite + AsyncSeek + Unpin> {
+    output: W,
+    vlr: LazVlr,
+    /// Compressor of the current chunk, into memory
+    record_compressor: Box<dyn RecordCompressor<Cursor<Vec<u8>>> + Send>,
+    /// Position of the offset to the chunk table, once reserved
+    start_pos: Option<u64>,
+    chunk_table: ChunkTable,
+    /// Number of points in the current chunk
+    chunk_point_count: u64,
+}
+
+impl<W: AsyncWrite + AsyncSeek + Unpin> AsyncLasZipCompressor<W> {
+    /// Creates a compressor using the provided vlr,
+    /// the points are written from the current position of the `output`.
+    pub fn new(output: W, vlr: LazVlr) -> crate::Result<Self> {
+        let record_compressor = details::record_compressor_from_vlr(&vlr, Cursor::new(Vec::new()))?;
+        Ok(Self {
+            output,
+            vlr,
+            record_compressor,
+            start_pos: None,
+            chunk_table: ChunkTable::default(),
+            chunk_point_count: 0,
+        })
+    }
+
+    /// Compresses one point, see [`LasZipCompressor::compress_one`].
+    ///
+    /// [`LasZipCompressor::compress_one`]: super::LasZipCompressor::compress_one
+    pub async fn compress_one(&mut self, input: &[u8]) -> std::io::Result<()> {
+        if self.start_pos.is_none() {
+            self.reserve_offset_to_chunk_table().await?;
+        }
+        // In variable-size chunks mode the chunk size is u32::MAX
+        if self.chunk_point_count == u64::from(self.vlr.chunk_size()) {
+            self.finish_current_chunk_impl().await?;
+        }
+        self.record_compressor.compress_next(input)?;
+        self.chunk_point_count += 1;
+        Ok(())
+    }
+
+    /// Compresses all the points contained in the `input` slice
+    pub async fn compress_many(&mut self, input: &[u8]) -> std::io::Result<()> {
+        let point_size = self.vlr.items_size() as usize;
+        for point in input.chunks_exact(point_size) {
+            self.compress_one(point).await?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the current chunk and writes it to the output.
+    ///
+    /// # Important
+    ///
+    /// Only call this when writing **variable-size** chunks.
+    pub async fn finish_current_chunk(&mut self) -> std::io::Result<()> {
+        debug_assert!(
+            self.vlr.uses_variable_size_chunks(),
+            "finish_current_chunk called on a file which is not in variable-size chunks mode"
+        );
+        self.finish_current_chunk_impl().await
+    }
+
+    /// Must be called when you have compressed all your points,
+    /// it writes the last chunk and the chunk table.
+    pub async fn done(&mut self) -> std::io::Result<()> {
+        if self.start_pos.is_none() {
+            self.reserve_offset_to_chunk_table().await?;
+        }
+        if self.chunk_point_count != 0 || self.chunk_table.is_empty() {
+            self.finish_current_chunk_impl().await?;
+        }
+        let start_pos = self.start_pos.unwrap_or_default();
+        let chunk_table_pos = start_pos + 8 + self.chunk_table.byte_count();
+        let mut chunk_table = Vec::new();
+        self.chunk_table.write_to(&mut chunk_table, &self.vlr)?;
+
+        self.output.seek(SeekFrom::Start(start_pos)).await?;
+        self.output.write_i64_le(chunk_table_pos as i64).await?;
+        self.output.seek(SeekFrom::Start(chunk_table_pos)).await?;
+        self.output.write_all(&chunk_table).await
+    }
+
+    /// Calls [`done`], flushes the output and returns it,
+    /// positioned right after the chunk table.
+    ///
+    /// [`done`]: Self::done
+    pub async fn finish(mut self) -> crate::Result<W> {
+        self.done().await?;
+        self.output.flush().await?;
+        Ok(self.output)
+    }
+
+    pub fn vlr(&self) -> &LazVlr {
+        &self.vlr
+    }
+
+    /// Returns the table of the chunks written so far
+    pub fn chunk_table(&self) -> &ChunkTable {
+        &self.chunk_table
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+
+    async fn reserve_offset_to_chunk_table(&mut self) -> std::io::Result<()> {
+        let start_pos = self.output.stream_position().await?;
+        self.output.write_i64_le(-1).await?;
+        self.start_pos = Some(start_pos);
+        Ok(())
+    }
+
+    async fn finish_current_chunk_impl(&mut self) -> std::io::Result<()> {
+        self.record_compressor.done()?;
+        // Taken out, so that the future does not borrow the compressor,
+        // which is not Sync, and stays Send
+        let mut chunk = std::mem::take(self.record_compressor.get_mut().get_mut());
+        self.output.write_all(&chunk).await?;
+        self.chunk_table.push(ChunkTableEntry {
+            point_count: self.chunk_point_count,
+            byte_count: chunk.len() as u64,
+        });
+
+        chunk.clear();
+        let buffer = self.record_compressor.get_mut();
+        *buffer.get_mut() = chunk;
+        buffer.set_position(0);
+        self.record_compressor.reset();
+        self.record_compressor
+            .set_fields_from(self.vlr.items())
+            .map_err(LasZipError::into_io_error)?;
+        self.chunk_point_count = 0;
+        Ok(())
+    }
+}
+
+/// Decompressor reading from an [`AsyncRead`] + [`AsyncSeek`] source.
+///
+/// The compressed bytes are read in blocks and decompressed by a [`PushDecompressor`],
+/// the points decompressed are kept until they are asked for.
+///
+/// The chunk table is read at creation, if there is one, as it is needed to
+/// decompress the variable-size chunks of the point formats 0 to 5.
+/// Chunk tables stored in EVLRs (see [`LazVlr::special_evlrs`]) are not supported.
+///
+/// See [`AsyncLasZipCompressor`] for an example.
+pub struct AsyncLasZipDecompressor<R: AsyncRead + AsyncSeek + Unpin> {
+    source: R,
+    decompressor: PushDecompressor,
+    chunk_table: Option<ChunkTable>,
+    /// Number of compressed bytes left to read from the source,
+    /// `None` when the end of the chunks is not known
+    bytes_left: Option<u64>,
+    read_buffer: Vec<u8>,
+    /// Points decompressed, not yet returned from `points_pos`
+    points: Vec<u8>,
+    points_pos: usize,
+    /// Error that happened after the points that are not yet returned
+    error: Option<std::io::Error>,
+    end_reached: bool,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> AsyncLasZipDecompressor<R> {
+    /// Creates a new instance from a data source of compressed points
+    /// and the LazVlr describing the compressed data.
+    ///
+    /// The `source` position must be at the start of the point data
+    /// (where the offset to the chunk table is).
+    pub async fn new(source: R, vlr: LazVlr) -> crate::Result<Self> {
+        Self::with_options(source, vlr, LazOpenOptions::new()).await
+    }
+
+    /// Creates a decompressor configured by the `options`, see [`PushDecompressor::with_options`].
+    ///
+    /// The memory limit and the limit on the number of chunks also apply to the chunk table.
+    /// Without a number of points, the points of variable-size chunks are counted
+    /// in the chunk table, and the points of fixed-size chunks stop at the end of the last chunk.
+    pub async fn with_options(
+        mut source: R,
+        vlr: LazVlr,
+        options: LazOpenOptions<'_>,
+    ) -> crate::Result<Self> {
+        let data_start = source.stream_position().await?;
+        let chunk_table = match vlr.special_evlrs() {
+            Some(_) => None,
+            None => read_chunk_table(&mut source, &vlr, data_start, options.memory_limit).await?,
+        };
+        source.seek(SeekFrom::Start(data_start)).await?;
+        if let Some((_, chunk_table)) = &chunk_table {
+            chunk_table.check_num_chunks(options.max_num_chunks)?;
+        }
+
+        let num_points = match (&chunk_table, options.num_points) {
+            (_, Some(num_points)) => num_points,
+            (Some((_, chunk_table)), None) if vlr.uses_variable_size_chunks() => {
+                chunk_table.num_points()
+            }
+            // The points stop at the end of the last chunk
+            _ => u64::MAX,
+        };
+        let mut decompressor = PushDecompressor::with_options(vlr, num_points, options)?;
+        let bytes_left = chunk_table
+            .as_ref()
+            .map(|(chunk_table_start, _)| chunk_table_start - data_start);
+        let chunk_table = chunk_table.map(|(_, chunk_table)| chunk_table);
+        if let Some(chunk_table) = &chunk_table {
+            decompressor.set_chunk_table(chunk_table.clone());
+        }
+        Ok(Self {
+            source,
+            decompressor,
+            chunk_table,
+            bytes_left,
+            read_buffer: vec![0u8; READ_BUFFER_SIZE],
+            points: Vec::new(),
+            points_pos: 0,
+            error: None,
+            end_reached: false,
+        })
+    }
+
+    /// Decompress the next point and write the uncompressed data to the out buffer.
+    pub async fn decompress_one(&mut self, out: &mut [u8]) -> std::io::Result<()> {
+        self.decompress_many(out).await
+    }
+
+    /// Decompresses as many points as the `out` buffer can hold.
+    pub async fn decompress_many(&mut self, out: &mut [u8]) -> std::io::Result<()> {
+        let mut filled = 0;
+        while filled < out.len() {
+            if self.points_pos == self.points.len() {
+                self.decompress_next_bytes().await?;
+            }
+            let count = (out.len() - filled).min(self.points.len() - self.points_pos);
+            out[filled..filled + count]
+                .copy_from_slice(&self.points[self.points_pos..self.points_pos + count]);
+            filled += count;
+            self.points_pos += count;
+        }
+        Ok(())
+    }
+
+    pub fn vlr(&self) -> &LazVlr {
+        self.decompressor.vlr()
+    }
+
+    /// Returns the chunk table read at creation, if there was one
+    pub fn chunk_table(&self) -> Option<&ChunkTable> {
+        self.chunk_table.as_ref()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.source
+    }
+
+    /// Reads compressed bytes until some points are decompressed
+    async fn decompress_next_bytes(&mut self) -> std::io::Result<()> {
+        self.points.clear();
+        self.points_pos = 0;
+        while self.points.is_empty() {
+            if let Some(error) = self.error.take() {
+                return Err(error);
+            }
+            if self.end_reached {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            let max_len = self.bytes_left.map_or(READ_BUFFER_SIZE, |left| {
+                left.min(READ_BUFFER_SIZE as u64) as usize
+            });
+            let len = match max_len {
+                0 => 0,
+                _ => self.source.read(&mut self.read_buffer[..max_len]).await?,
+            };
+            let result = if len == 0 {
+                self.end_reached = true;
+                self.decompressor.finish(&mut self.points)
+            } else {
+                if let Some(left) = self.bytes_left.as_mut() {
+                    *left -= len as u64;
+                }
+                self.decompressor
+                    .feed(&self.read_buffer[..len], &mut self.points)
+            };
+            if let Err(error) = result {
+                self.error = Some(error.into_io_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the chunk table, returns it with its position, or `None` if
+/// the offset to the chunk table was not written.
+///
+/// The `source` must be at the start of the point data, at `data_start`.
+async fn read_chunk_table<R: AsyncRead + AsyncSeek + Unpin>(
+    source: &mut R,
+    vlr: &LazVlr,
+    data_start: u64,
+    memory_limit: Option<usize>,
+) -> crate::Result<Option<(u64, ChunkTable)>> {
+    let mut offset = source.read_i64_le().await?;
+    if offset <= data_start as i64 {
+        // The writer could not update the offset, it may be at the end of the data
+        source.seek(SeekFrom::End(-8)).await?;
+        offset = source.read_i64_le().await?;
+        if offset <= data_start as i64 {
+            return Ok(None);
+        }
+    }
+    let chunk_table_start = offset as u64;
+    source.seek(SeekFrom::Start(chunk_table_start)).await?;
+    let mut header = [0u8; 8];
+    source.read_exact(&mut header).await?;
+    let num_chunks = u64::from(u32::from_le_bytes([
+        header[4], header[5], header[6], header[7],
+    ]));
+    crate::errors::check_memory_limit(memory_limit, ChunkTable::memory_size(num_chunks as usize))?;
+    // Each of the two numbers of an entry takes at most 6 compressed bytes,
+    // the table is followed by the EVLRs (if any) so it must not be read to the end
+    let max_len = num_chunks * 16 + 16;
+    let mut bytes = header.to_vec();
+    source.take(max_len).read_to_end(&mut bytes).await?;
+    let chunk_table = ChunkTable::read_table(Cursor::new(bytes), vlr)?;
+    Ok(Some((chunk_table_start, chunk_table)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::points;
+    use crate::{LasZipDecompressor, LazVlrBuilder};
+
+    #[tokio::test]
+    async fn test_async_compression_round_trip() {
+        for vlr in [
+            LazVlrBuilder::default()
+                .with_point_format(3, 0)
+                .unwrap()
+                .with_fixed_chunk_size(50)
+                .build(),
+            LazVlrBuilder::default()
+                .with_point_format(1, 0)
+                .unwrap()
+                .with_variable_chunk_size()
+                .build(),
+            LazVlrBuilder::default()
+                .with_point_format(7, 2)
+                .unwrap()
+                .with_variable_chunk_size()
+                .build(),
+        ] {
+            let point_size = vlr.items_size() as usize;
+            let points = points(130, point_size);
+            let mut compressor =
+                AsyncLasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+            for chunk in points.chunks(40 * point_size) {
+                compressor.compress_many(chunk).await.unwrap();
+                if vlr.uses_variable_size_chunks() {
+                    compressor.finish_current_chunk().await.unwrap();
+                }
+            }
+            compressor.done().await.unwrap();
+            assert_eq!(compressor.chunk_table().num_points(), 130);
+            let mut compressed = compressor.into_inner();
+
+            let mut decompressed = vec![0u8; points.len()];
+            crate::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr.clone()).unwrap();
+            assert_eq!(decompressed, points);
+
+            compressed.set_position(0);
+            let mut decompressor = AsyncLasZipDecompressor::new(compressed, vlr).await.unwrap();
+            let mut decompressed = vec![0u8; points.len()];
+            let (first, rest) = decompressed.split_at_mut(point_size);
+            decompressor.decompress_one(first).await.unwrap();
+            decompressor.decompress_many(rest).await.unwrap();
+            assert_eq!(decompressed, points);
+            assert!(decompressor
+                .decompress_one(&mut vec![0u8; point_size])
+                .await
+                .is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_decompression_with_options() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(3, 0)
+            .unwrap()
+            .with_fixed_chunk_size(50)
+            .build();
+        let points = points(130, 34);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+
+        compressed.set_position(0);
+        let options = LazOpenOptions::new().with_num_points(120);
+        let mut decompressor =
+            AsyncLasZipDecompressor::with_options(compressed.clone(), vlr.clone(), options)
+                .await
+                .unwrap();
+        let mut decompressed = vec![0u8; 120 * 34];
+        decompressor
+            .decompress_many(&mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, points[..120 * 34]);
+        assert!(decompressor.decompress_one(&mut [0u8; 34]).await.is_err());
+
+        let options = LazOpenOptions::new().with_max_num_chunks(2);
+        let result = AsyncLasZipDecompressor::with_options(compressed, vlr, options).await;
+        assert!(matches!(
+            result,
+            Err(LasZipError::ChunkLimitExceeded {
+                limit: 2,
+                num_chunks: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_futures_are_send() {
+        fn assert_send<T: Send>(_: T) {}
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(0, 0)
+            .unwrap()
+            .build();
+        let mut compressor =
+            AsyncLasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        assert_send(compressor.compress_many(&[]));
+        assert_send(compressor.done());
+        assert_send(AsyncLasZipDecompressor::new(
+            Cursor::new(Vec::<u8>::new()),
+            vlr,
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_async_decompression_of_file() {
+        let mut laz_file = std::fs::File::open("tests/data/point10.laz").unwrap();
+        let (header, vlr) = crate::las::file::read_header_and_vlrs(&mut laz_file).unwrap();
+        let vlr = vlr.unwrap();
+        let num_points = header.num_points as usize;
+        let point_size = vlr.items_size() as usize;
+
+        let file = std::fs::read("tests/data/point10.laz").unwrap();
+        let offset_to_points = u64::from(header.offset_to_points);
+        let mut source = Cursor::new(file.as_slice());
+        source.set_position(offset_to_points);
+        let mut expected = vec![0u8; num_points * point_size];
+        LasZipDecompressor::new(&mut source, vlr.clone())
+            .unwrap()
+            .decompress_many(&mut expected)
+            .unwrap();
+
+        source.set_position(offset_to_points);
+        let mut decompressor = AsyncLasZipDecompressor::new(source, vlr).await.unwrap();
+        assert!(decompressor.chunk_table().is_some());
+        let mut decompressed = vec![0u8; num_points * point_size];
+        decompressor
+            .decompress_many(&mut decompressed)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, expected);
+    }
+}
@@ -0,0 +1,233 @@
+//! Which item types, versions and compressor types this build of the crate supports
+use std::fmt;
+
+use super::{CompressorType, LazItemType};
+
+/// Item types known by the crate, the `Byte` items are listed with a size of 0,
+/// but all sizes are supported
+const ITEM_TYPES: [LazItemType; 10] = [
+    LazItemType::Byte(0),
+    LazItemType::Point10,
+    LazItemType::GpsTime,
+    LazItemType::RGB12,
+    LazItemType::WavePacket13,
+    LazItemType::Point14,
+    LazItemType::RGB14,
+    LazItemType::RGBNIR14,
+    LazItemType::WavePacket14,
+    LazItemType::Byte14(0),
+];
+
+/// Versions of items defined by LASzip
+const VERSIONS: [u16; 4] = [1, 2, 3, 4];
+
+const COMPRESSOR_TYPES: [CompressorType; 4] = [
+    CompressorType::None,
+    CompressorType::PointWise,
+    CompressorType::PointWiseChunked,
+    CompressorType::LayeredChunked,
+];
+
+/// A combination of item type, version and compressor type
+/// that can be compressed and decompressed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Capability {
+    pub item_type: LazItemType,
+    pub version: u16,
+    pub compressor: CompressorType,
+}
+
+/// Why a combination of item type, version and compressor type is not supported
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Unsupported {
+    /// This version of the item type is not implemented
+    Version,
+    /// The version is implemented, but the crate was built without the feature
+    FeatureDisabled(&'static str),
+    /// The items of the version are not compressed with the compressor type,
+    /// the expected one is given
+    CompressorType(CompressorType),
+    /// The item type is not known by the crate ([`LazItemType::Unknown`])
+    UnknownItemType,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unsupported::Version => write!(f, "this version of the item is not implemented"),
+            Unsupported::FeatureDisabled(feature) => {
+                write!(f, "laz was built without the '{}' feature", feature)
+            }
+            Unsupported::CompressorType(expected) => {
+                write!(f, "the items must be compressed with {:?}", expected)
+            }
+            Unsupported::UnknownItemType => write!(f, "the item type is not known"),
+        }
+    }
+}
+
+/// Features of the crate that enable codecs, and are enabled in this build
+const ENABLED_FEATURES: &[&str] = &[
+    #[cfg(feature = "format-legacy")]
+    "format-legacy",
+    #[cfg(feature = "format-14")]
+    "format-14",
+    #[cfg(feature = "extra-bytes")]
+    "extra-bytes",
+];
+
+/// Checks whether the items of the `item_type` and `version`, compressed
+/// with the `compressor` can be compressed and decompressed by this build of the crate.
+///
+/// Unknown item types are never supported.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+/// # let (_, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+/// # let vlr = vlr.unwrap();
+/// use laz::laszip::check_support;
+///
+/// for item in vlr.items() {
+///     if let Err(reason) = check_support(item.item_type(), item.version(), vlr.compressor()) {
+///         eprintln!("{:?} v{} is not supported: {}", item.item_type(), item.version(), reason);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn check_support(
+    item_type: LazItemType,
+    version: u16,
+    compressor: CompressorType,
+) -> Result<(), Unsupported> {
+    let (features, expected_compressor): (&[&'static str], _) = match (item_type, version) {
+        (
+            LazItemType::Point10
+            | LazItemType::GpsTime
+            | LazItemType::RGB12
+            | LazItemType::WavePacket13,
+            1 | 2,
+        ) => (&["format-legacy"], CompressorType::PointWiseChunked),
+        (LazItemType::Unknown { .. }, _) => return Err(Unsupported::UnknownItemType),
+        (LazItemType::Byte(_), 1 | 2) => (
+            &["format-legacy", "extra-bytes"],
+            CompressorType::PointWiseChunked,
+        ),
+        (
+            LazItemType::Point14
+            | LazItemType::RGB14
+            | LazItemType::RGBNIR14
+            | LazItemType::WavePacket14,
+            3,
+        ) => (&["format-14"], CompressorType::LayeredChunked),
+        (LazItemType::Byte14(_), 3) => (
+            &["format-14", "extra-bytes"],
+            CompressorType::LayeredChunked,
+        ),
+        _ => return Err(Unsupported::Version),
+    };
+    if let Some(feature) = features
+        .iter()
+        .find(|feature| !ENABLED_FEATURES.contains(feature))
+    {
+        return Err(Unsupported::FeatureDisabled(feature));
+    }
+    if compressor != expected_compressor {
+        return Err(Unsupported::CompressorType(expected_compressor));
+    }
+    Ok(())
+}
+
+/// Returns all the combinations of item type, version and compressor type
+/// supported by this build of the crate, see [`check_support`].
+///
+/// The `Byte` items are listed with a size of 0, but all sizes are supported.
+pub fn capabilities() -> Vec<Capability> {
+    let mut capabilities = Vec::new();
+    for &item_type in &ITEM_TYPES {
+        for &version in &VERSIONS {
+            for &compressor in &COMPRESSOR_TYPES {
+                if check_support(item_type, version, compressor).is_ok() {
+                    capabilities.push(Capability {
+                        item_type,
+                        version,
+                        compressor,
+                    });
+                }
+            }
+        }
+    }
+    capabilities
+}
+
+impl LazItemType {
+    /// Returns the versions of the item type this build of the crate supports,
+    /// see [`check_support`].
+    pub fn supported_versions(self) -> Vec<u16> {
+        VERSIONS
+            .iter()
+            .copied()
+            .filter(|&version| {
+                COMPRESSOR_TYPES
+                    .iter()
+                    .any(|&compressor| check_support(self, version, compressor).is_ok())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(all(
+        feature = "format-legacy",
+        feature = "format-14",
+        feature = "extra-bytes"
+    ))]
+    fn test_capabilities() {
+        assert_eq!(LazItemType::Point10.supported_versions(), vec![1, 2]);
+        assert_eq!(LazItemType::RGBNIR14.supported_versions(), vec![3]);
+        assert_eq!(
+            check_support(LazItemType::Point14, 4, CompressorType::LayeredChunked),
+            Err(Unsupported::Version)
+        );
+        assert_eq!(
+            check_support(LazItemType::Point14, 3, CompressorType::PointWiseChunked),
+            Err(Unsupported::CompressorType(CompressorType::LayeredChunked))
+        );
+        assert_eq!(
+            check_support(LazItemType::Byte(3), 2, CompressorType::PointWiseChunked),
+            Ok(())
+        );
+        assert_eq!(
+            check_support(
+                LazItemType::Unknown {
+                    type_code: 42,
+                    size: 3
+                },
+                2,
+                CompressorType::PointWiseChunked
+            ),
+            Err(Unsupported::UnknownItemType)
+        );
+
+        let capabilities = capabilities();
+        // Each version of the default features has one compressor type
+        assert_eq!(capabilities.len(), 2 * 5 + 5);
+        for capability in capabilities {
+            assert_eq!(
+                check_support(
+                    capability.item_type,
+                    capability.version,
+                    capability.compressor
+                ),
+                Ok(())
+            );
+        }
+    }
+}
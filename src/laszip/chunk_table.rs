@@ -1,6 +1,6 @@
 //! Module with all the things related to LAZ chunk tables
 use std::io::{Read, Seek, SeekFrom, Write};
-use std::ops::Index;
+use std::ops::{Index, IndexMut};
 use std::slice::SliceIndex;
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
@@ -9,6 +9,7 @@ use crate::compressors::IntegerCompressorBuilder;
 use crate::decoders::ArithmeticDecoder;
 use crate::decompressors::IntegerDecompressorBuilder;
 use crate::encoders::ArithmeticEncoder;
+use crate::errors::LasZipWarning;
 use crate::{LasZipError, LazVlr};
 
 /// Indices of the contexts used for the IntegerCompressor/IntergerDecompressor
@@ -26,6 +27,78 @@ pub struct ChunkTableEntry {
     pub byte_count: u64,
 }
 
+/// One part of the chunks of a file, made by [`ChunkTable::partition`]
+///
+/// It is self-contained: with the LasZip VLR, a worker can fetch the `bytes`
+/// and decompress the `points` without knowing the other parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkPartition {
+    /// Indices of the chunks
+    pub chunks: std::ops::Range<usize>,
+    /// Indices of the points of the chunks
+    pub points: std::ops::Range<u64>,
+    /// Position of the compressed data of the chunks in the file
+    pub bytes: std::ops::Range<u64>,
+}
+
+/// Where the chunks of a [`ChunkTable`] start, to find the chunk of a point
+/// without going through the entries before it
+#[derive(Debug, Clone)]
+pub(super) struct ChunkIndex {
+    /// Index of the first point of each chunk, and the total number of points
+    points_before: Vec<u64>,
+    /// Offset of each chunk from the first one, and the total size of the chunks
+    bytes_before: Vec<u64>,
+}
+
+impl ChunkIndex {
+    pub(super) fn new(chunk_table: &ChunkTable) -> Self {
+        let mut points_before = Vec::with_capacity(chunk_table.len() + 1);
+        let mut bytes_before = Vec::with_capacity(chunk_table.len() + 1);
+        let (mut points, mut bytes) = (0u64, 0u64);
+        for entry in chunk_table {
+            points_before.push(points);
+            bytes_before.push(bytes);
+            points = points.saturating_add(entry.point_count);
+            bytes = bytes.saturating_add(entry.byte_count);
+        }
+        points_before.push(points);
+        bytes_before.push(bytes);
+        Self {
+            points_before,
+            bytes_before,
+        }
+    }
+
+    /// Returns the index of the chunk of the point at `point_idx`, the index of
+    /// the first point of that chunk and the offset of the chunk from the first one,
+    /// or `None` if the point is after the last chunk.
+    pub(super) fn chunk_of_point(&self, point_idx: u64) -> Option<(usize, u64, u64)> {
+        if point_idx >= self.num_points() {
+            return None;
+        }
+        // Empty chunks have the same first point as the next one, the last of them is the one
+        let chunk = self
+            .points_before
+            .partition_point(|&first| first <= point_idx)
+            - 1;
+        Some((chunk, self.points_before[chunk], self.bytes_before[chunk]))
+    }
+
+    pub(super) fn num_chunks(&self) -> usize {
+        self.points_before.len() - 1
+    }
+
+    pub(super) fn num_points(&self) -> u64 {
+        self.points_before[self.num_chunks()]
+    }
+
+    /// Total size of the chunks
+    pub(super) fn byte_count(&self) -> u64 {
+        self.bytes_before[self.num_chunks()]
+    }
+}
+
 /// The ChunkTable contains chunk entries for a LAZ file.
 ///
 /// The ChunkTable has two ways of being stored in a LAZ file
@@ -52,28 +125,138 @@ impl ChunkTable {
     ///
     /// For `variable-size` chunks the `point_count` of each entry is the one read
     /// from the source.
-    pub fn read_from<R: Read + Seek>(mut src: R, vlr: &LazVlr) -> crate::Result<Self> {
-        if vlr.uses_variable_size_chunks() {
-            ChunkTable::read_as_variably_sized(&mut src)
+    ///
+    /// When the `vlr` has special EVLRs (see [`LazVlr::special_evlrs`]), the chunk table
+    /// is read from the one with the LasZip user id, instead of after the points.
+    pub fn read_from<R: Read + Seek>(src: R, vlr: &LazVlr) -> crate::Result<Self> {
+        Self::read_from_with_warnings(src, vlr, &mut |_| {}, None)
+    }
+
+    /// Same as [`Self::read_from`], non-fatal issues are reported to `warn`.
+    ///
+    /// [`LasZipError::MemoryLimitExceeded`] is returned, before allocating the table,
+    /// if its entries would need more than `memory_limit` bytes.
+    pub(crate) fn read_from_with_warnings<R: Read + Seek>(
+        mut src: R,
+        vlr: &LazVlr,
+        warn: &mut dyn FnMut(LasZipWarning),
+        memory_limit: Option<usize>,
+    ) -> crate::Result<Self> {
+        if let Some((offset, count)) = vlr.special_evlrs() {
+            ChunkTable::read_from_special_evlrs(&mut src, vlr, offset, count, memory_limit)
+        } else if vlr.uses_variable_size_chunks() {
+            ChunkTable::read_as_variably_sized(&mut src, warn, memory_limit)
         } else {
-            ChunkTable::read_as_fixed_size(&mut src, vlr.chunk_size().into())
+            ChunkTable::read_as_fixed_size(&mut src, vlr.chunk_size().into(), warn, memory_limit)
         }
     }
 
+    /// Shortens the last fixed-size chunk, which stores the chunk size as its number
+    /// of points, so that the table holds no more than `num_points` points
+    pub(crate) fn set_num_points(&mut self, vlr: &LazVlr, num_points: u64) {
+        if vlr.uses_variable_size_chunks() || self.is_empty() {
+            return;
+        }
+        let last = self.len() - 1;
+        let points_before_last = last as u64 * u64::from(vlr.chunk_size());
+        let last_entry = &mut self[last];
+        if let Some(point_count) = num_points.checked_sub(points_before_last) {
+            last_entry.point_count = point_count.min(last_entry.point_count);
+        }
+    }
+
+    /// Returns [`LasZipError::ChunkLimitExceeded`] if the table has more than `limit` chunks
+    pub(crate) fn check_num_chunks(&self, limit: Option<usize>) -> crate::Result<()> {
+        match limit {
+            Some(limit) if self.len() > limit => Err(LasZipError::ChunkLimitExceeded {
+                limit,
+                num_chunks: self.len(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the number of bytes used by the entries of a table of `number_of_chunks`
+    pub(crate) fn memory_size(number_of_chunks: usize) -> usize {
+        number_of_chunks.saturating_mul(std::mem::size_of::<ChunkTableEntry>())
+    }
+
     /// Writes the chunk table to the `dst`.
     pub fn write_to<W: Write>(&self, mut dst: W, vlr: &LazVlr) -> std::io::Result<()> {
         self.write(&mut dst, vlr.uses_variable_size_chunks())
     }
 
+    /// Reads the chunk table stored in the first of the `count` special EVLRs
+    /// starting at `offset` that has the LasZip user id.
+    ///
+    /// Like with the chunk table stored after the points, the `src` is left
+    /// where the points actually start.
+    fn read_from_special_evlrs<R: Read + Seek>(
+        mut src: R,
+        vlr: &LazVlr,
+        offset: u64,
+        count: u64,
+        memory_limit: Option<usize>,
+    ) -> crate::Result<Self> {
+        let data_start = src.stream_position()?;
+        let mut evlr_start = offset;
+        for _ in 0..count {
+            src.seek(SeekFrom::Start(evlr_start))?;
+            let _reserved = src.read_u16::<LittleEndian>()?;
+            let mut user_id = [0u8; 16];
+            src.read_exact(&mut user_id)?;
+            let _record_id = src.read_u16::<LittleEndian>()?;
+            let record_length = src.read_u64::<LittleEndian>()?;
+            let mut description = [0u8; 32];
+            src.read_exact(&mut description)?;
+
+            if user_id.starts_with(LazVlr::USER_ID.as_bytes())
+                && user_id[LazVlr::USER_ID.len()..].iter().all(|&b| b == 0)
+            {
+                let mut chunk_table =
+                    Self::read(&mut src, vlr.uses_variable_size_chunks(), memory_limit)?;
+                src.seek(SeekFrom::Start(data_start + 8))?;
+                if !vlr.uses_variable_size_chunks() {
+                    for entry in &mut chunk_table.0 {
+                        entry.point_count = vlr.chunk_size().into();
+                    }
+                }
+                return Ok(chunk_table);
+            }
+            evlr_start = src.stream_position()? + record_length;
+        }
+        src.seek(SeekFrom::Start(data_start))?;
+        Err(LasZipError::MissingChunkTable)
+    }
+
+    /// Reads the chunk table itself, that starts at the current position of the `src`
+    /// (i.e. the offset to the chunk table was already followed).
+    ///
+    /// Like with [`Self::read_from`], the `point_count` of fixed-size chunks is the chunk size.
+    pub(crate) fn read_table<R: Read + Seek>(mut src: R, vlr: &LazVlr) -> std::io::Result<Self> {
+        let mut chunk_table = Self::read(&mut src, vlr.uses_variable_size_chunks(), None)
+            .map_err(LasZipError::into_io_error)?;
+        if !vlr.uses_variable_size_chunks() {
+            for entry in &mut chunk_table.0 {
+                entry.point_count = vlr.chunk_size().into();
+            }
+        }
+        Ok(chunk_table)
+    }
+
     /// Reads the chunk table that contains both the `point_count` and `bytes_size`.
     ///
     /// This of course will only give correct results if the chunk table stored in the source
     /// contains both these information. Which is the case for **variable-sized** chunks.
-    fn read_as_variably_sized<R: Read + Seek>(mut src: R) -> crate::Result<Self> {
+    fn read_as_variably_sized<R: Read + Seek>(
+        mut src: R,
+        warn: &mut dyn FnMut(LasZipWarning),
+        memory_limit: Option<usize>,
+    ) -> crate::Result<Self> {
         let (data_start, chunk_table_start) =
-            Self::read_offset(&mut src)?.ok_or(LasZipError::MissingChunkTable)?;
+            Self::read_offset(&mut src, warn)?.ok_or(LasZipError::MissingChunkTable)?;
         src.seek(SeekFrom::Start(chunk_table_start))?;
-        let chunk_table = Self::read(&mut src, true)?;
+        let chunk_table = Self::read(&mut src, true, memory_limit)?;
         src.seek(SeekFrom::Start(data_start + 8))?;
         Ok(chunk_table)
     }
@@ -82,11 +265,16 @@ impl ChunkTable {
     ///
     /// This is for the case when chunks are  **fixed-size**.
     /// Each chunk entry will have the given `point_count` as the point_count.
-    fn read_as_fixed_size<R: Read + Seek>(mut src: R, point_count: u64) -> crate::Result<Self> {
+    fn read_as_fixed_size<R: Read + Seek>(
+        mut src: R,
+        point_count: u64,
+        warn: &mut dyn FnMut(LasZipWarning),
+        memory_limit: Option<usize>,
+    ) -> crate::Result<Self> {
         let (data_start, chunk_table_start) =
-            Self::read_offset(&mut src)?.ok_or(LasZipError::MissingChunkTable)?;
+            Self::read_offset(&mut src, warn)?.ok_or(LasZipError::MissingChunkTable)?;
         src.seek(SeekFrom::Start(chunk_table_start))?;
-        let mut chunk_table = Self::read(&mut src, false)?;
+        let mut chunk_table = Self::read(&mut src, false, memory_limit)?;
         src.seek(SeekFrom::Start(data_start + 8))?;
 
         for entry in &mut chunk_table.0 {
@@ -100,7 +288,10 @@ impl ChunkTable {
     /// `src` should be at the start of LAZ data.
     ///
     /// This function will leave the `src` wherever it read the correct offset.
-    fn read_offset<R: Read + Seek>(src: &mut R) -> std::io::Result<Option<(u64, u64)>> {
+    fn read_offset<R: Read + Seek>(
+        src: &mut R,
+        warn: &mut dyn FnMut(LasZipWarning),
+    ) -> std::io::Result<Option<(u64, u64)>> {
         let current_pos = src.seek(SeekFrom::Current(0))?;
 
         let mut offset_to_chunk_table = src.read_i64::<LittleEndian>()?;
@@ -113,15 +304,24 @@ impl ChunkTable {
             if offset_to_chunk_table <= current_pos as i64 {
                 return Ok(None);
             }
+            warn(LasZipWarning::ChunkTableOffsetAtEnd);
         }
 
         Ok(Some((current_pos, offset_to_chunk_table as u64)))
     }
 
     /// Actual implementation of the reading of the chunk table.
-    fn read<R: Read + Seek>(mut src: &mut R, contains_point_count: bool) -> std::io::Result<Self> {
+    fn read<R: Read + Seek>(
+        mut src: &mut R,
+        contains_point_count: bool,
+        memory_limit: Option<usize>,
+    ) -> crate::Result<Self> {
         let _version = src.read_u32::<LittleEndian>()?;
         let number_of_chunks = src.read_u32::<LittleEndian>()?;
+        crate::errors::check_memory_limit(
+            memory_limit,
+            Self::memory_size(number_of_chunks as usize),
+        )?;
 
         let mut decompressor = IntegerDecompressorBuilder::new()
             .bits(32)
@@ -205,15 +405,214 @@ impl ChunkTable {
         self.0.len()
     }
 
-    #[cfg(feature = "parallel")]
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
 
-    #[cfg(feature = "parallel")]
+    /// Returns an iterator over the entries, in the order of the chunks
+    pub fn iter(&self) -> std::slice::Iter<'_, ChunkTableEntry> {
+        self.0.iter()
+    }
+
+    /// Returns an iterator over the byte ranges of the compressed chunks,
+    /// `data_start` being the position of the first chunk (right after the offset
+    /// to the chunk table).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let chunk_table = laz::laszip::ChunkTable::read_from(&mut laz_file, &vlr)?;
+    /// let data_start = laz_file.stream_position()?;
+    /// for range in chunk_table.chunk_ranges(data_start) {
+    ///     // e.g. copy the bytes of the chunk elsewhere
+    ///     println!("{:?}", range);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn chunk_ranges(&self, data_start: u64) -> impl Iterator<Item = std::ops::Range<u64>> + '_ {
+        self.0.iter().scan(data_start, |start, entry| {
+            let range = *start..*start + entry.byte_count;
+            *start = range.end;
+            Some(range)
+        })
+    }
+
+    /// Returns the sum of the point counts of the chunks
+    ///
+    /// This is the number of points of the data only for tables
+    /// of **variable-size** chunks, see [`Self::read_from`].
+    pub fn num_points(&self) -> u64 {
+        self.0.iter().map(|entry| entry.point_count).sum()
+    }
+
+    /// Returns the sum of the byte counts of the chunks
+    pub fn byte_count(&self) -> u64 {
+        self.0.iter().map(|entry| entry.byte_count).sum()
+    }
+
+    /// Appends the entries of the `other` table
     pub fn extend(&mut self, other: &ChunkTable) {
         self.0.extend(&other.0)
     }
+
+    /// Inserts the `entry` at the `index`, shifting the entries after it
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, entry: ChunkTableEntry) {
+        self.0.insert(index, entry);
+    }
+
+    /// Removes and returns the entry at the `index`, shifting the entries after it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> ChunkTableEntry {
+        self.0.remove(index)
+    }
+
+    /// Keeps the first `len` entries, and removes the others
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
+    /// Splits the chunks in at most `num_parts` parts of consecutive chunks,
+    /// each part having about the same number of points, e.g. to process
+    /// a file with one worker per part.
+    ///
+    /// `data_start` is the position of the first chunk in the file, that is,
+    /// just after the offset to the chunk table.
+    ///
+    /// Chunks are never split, so there are fewer parts when there are fewer chunks,
+    /// and none if the table is empty.
+    ///
+    /// As the last fixed-size chunk is counted as full (see [`Self::read_from`]),
+    /// the points of the last part may go past the number of points of the file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let chunk_table = laz::laszip::ChunkTable::read_from(&mut laz_file, &vlr)?;
+    /// let data_start = laz_file.stream_position()?;
+    /// for part in chunk_table.partition(data_start, 4) {
+    ///     // e.g. send the part to a worker, which seeks to `part.points.start`
+    ///     println!("{:?}", part);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn partition(&self, data_start: u64, num_parts: usize) -> Vec<ChunkPartition> {
+        let num_parts = num_parts.min(self.len());
+        let ChunkIndex {
+            points_before,
+            bytes_before,
+        } = ChunkIndex::new(self);
+        let points = points_before[self.len()];
+
+        let mut parts = Vec::with_capacity(num_parts);
+        let mut start = 0;
+        for k in 1..=num_parts {
+            let end = if k == num_parts {
+                self.len()
+            } else {
+                // Shares the points left between the parts left
+                let parts_left = (num_parts - k + 1) as u64;
+                let target = points_before[start] + (points - points_before[start]) / parts_left;
+                // Each remaining part needs at least one chunk
+                let max_end = self.len() - (num_parts - k);
+                let mut end = start + 1;
+                while end < max_end && points_before[end] < target {
+                    end += 1;
+                }
+                if end - 1 > start && target - points_before[end - 1] < points_before[end] - target
+                {
+                    end -= 1;
+                }
+                end
+            };
+            parts.push(ChunkPartition {
+                chunks: start..end,
+                points: points_before[start]..points_before[end],
+                bytes: data_start + bytes_before[start]..data_start + bytes_before[end],
+            });
+            start = end;
+        }
+        parts
+    }
+
+    /// Checks that the chunks of the table are exactly the compressed data of the `src`
+    ///
+    /// The `src` position must be at the start of the point data (where the offset
+    /// to the chunk table is), the position is restored afterwards.
+    ///
+    /// When the offset to the chunk table was not written, the chunks must
+    /// fit between the offset and the end of the `src`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::Cursor;
+    /// # use laz::laszip::ChunkTable;
+    /// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    /// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(2).build();
+    /// let mut compressed = Cursor::new(Vec::<u8>::new());
+    /// laz::compress_buffer(&mut compressed, &[0u8; 20 * 5], vlr.clone())?;
+    ///
+    /// compressed.set_position(0);
+    /// let mut chunk_table = ChunkTable::read_from(&mut compressed, &vlr)?;
+    /// compressed.set_position(0);
+    /// chunk_table.check_against(&mut compressed)?;
+    ///
+    /// // Dropping the last chunk makes the table shorter than the data
+    /// chunk_table.truncate(2);
+    /// assert!(chunk_table.check_against(&mut compressed).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn check_against<R: Read + Seek>(&self, mut src: R) -> crate::Result<()> {
+        let start = src.stream_position()?;
+        let offset = src.read_i64::<LittleEndian>()?;
+        let end = src.seek(SeekFrom::End(0))?;
+        src.seek(SeekFrom::Start(start))?;
+
+        let chunks_start = start + std::mem::size_of::<i64>() as u64;
+        let table_bytes = self.byte_count();
+        let offset_is_written = offset > chunks_start as i64;
+        let stream_bytes = if offset_is_written {
+            (offset as u64).min(end) - chunks_start
+        } else {
+            end.saturating_sub(chunks_start)
+        };
+        let consistent = if offset_is_written {
+            table_bytes == stream_bytes && offset as u64 <= end
+        } else {
+            table_bytes <= stream_bytes
+        };
+        if consistent {
+            Ok(())
+        } else {
+            Err(LasZipError::ChunkTableMismatch {
+                table_bytes,
+                stream_bytes,
+            })
+        }
+    }
 }
 
 impl AsRef<[ChunkTableEntry]> for ChunkTable {
@@ -222,6 +621,15 @@ impl AsRef<[ChunkTableEntry]> for ChunkTable {
     }
 }
 
+impl<I> IndexMut<I> for ChunkTable
+where
+    I: SliceIndex<[ChunkTableEntry]>,
+{
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
 impl<'a> IntoIterator for &'a ChunkTable {
     type Item = <std::slice::Iter<'a, ChunkTableEntry> as Iterator>::Item;
     type IntoIter = std::slice::Iter<'a, ChunkTableEntry>;
@@ -266,3 +674,132 @@ pub(super) fn update_chunk_table_offset<W: Write + Seek>(
     dst.seek(SeekFrom::Start(start_of_chunk_table_pos))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils;
+    use crate::{
+        compress_buffer, decompress_buffer, LasZipCompressor, LasZipDecompressor,
+        LazItemRecordBuilder, LazVlrBuilder,
+    };
+
+    #[test]
+    fn test_partition_chunk_table() {
+        let mut chunk_table = ChunkTable::default();
+        for &point_count in &[10u64, 10, 40, 10, 10, 10, 10] {
+            chunk_table.push(ChunkTableEntry {
+                point_count,
+                byte_count: point_count * 2,
+            });
+        }
+        let parts = chunk_table.partition(8, 3);
+        let chunks = parts.iter().map(|p| p.chunks.clone()).collect::<Vec<_>>();
+        assert_eq!(chunks, vec![0..2, 2..3, 3..7]);
+        assert_eq!(parts[1].points, 20..60);
+        assert_eq!(parts[1].bytes, 48..128);
+        assert_eq!(parts[2].bytes.end, 8 + chunk_table.byte_count());
+
+        assert_eq!(chunk_table.partition(8, 100).len(), 7);
+        assert_eq!(chunk_table.partition(8, 1)[0].points, 0..100);
+        assert!(ChunkTable::default().partition(8, 4).is_empty());
+    }
+
+    #[test]
+    fn test_edit_chunk_table() {
+        use byteorder::{LittleEndian, WriteBytesExt};
+
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        let points = test_utils::points(10, 20);
+        let compress = |points: &[u8]| {
+            let mut compressor =
+                LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+            compressor.compress_chunk(points).unwrap();
+            let mut compressed = compressor.finish().unwrap();
+            compressed.set_position(0);
+            let table = ChunkTable::read_from(&mut compressed, &vlr).unwrap();
+            (compressed.into_inner(), table)
+        };
+        let (first, first_table) = compress(&points[..4 * 20]);
+        let (second, second_table) = compress(&points[4 * 20..]);
+
+        // Splice the chunks of the second stream after the ones of the first
+        let first_chunks = &first[8..8 + first_table.byte_count() as usize];
+        let second_chunks = &second[8..8 + second_table.byte_count() as usize];
+        let mut table = first_table.clone();
+        table.extend(&second_table);
+        let mut spliced = Vec::new();
+        let table_offset = 8 + first_chunks.len() + second_chunks.len();
+        spliced
+            .write_i64::<LittleEndian>(table_offset as i64)
+            .unwrap();
+        spliced.extend_from_slice(first_chunks);
+        spliced.extend_from_slice(second_chunks);
+
+        let mut spliced = Cursor::new(spliced);
+        assert!(table.check_against(&mut spliced).is_ok());
+        let mut shorter = table.clone();
+        let removed = shorter.remove(0);
+        assert_eq!(removed.point_count, 4);
+        assert!(matches!(
+            shorter.check_against(&mut spliced),
+            Err(crate::LasZipError::ChunkTableMismatch { .. })
+        ));
+        shorter.insert(0, removed);
+        shorter[1].byte_count += 1;
+        assert!(shorter.check_against(&mut spliced).is_err());
+
+        spliced.set_position(table_offset as u64);
+        table.write_to(&mut spliced, &vlr).unwrap();
+        let mut decompressed = vec![0u8; points.len()];
+        decompress_buffer(spliced.get_ref(), &mut decompressed, vlr).unwrap();
+        assert_eq!(decompressed, points);
+    }
+
+    #[test]
+    fn test_chunk_table_in_special_evlr() {
+        use byteorder::{LittleEndian, ReadBytesExt};
+
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = test_utils::points(25, 20);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        let compressed = compressed.into_inner();
+
+        // Moves the chunk table in an EVLR, after another one
+        let table_start = (&compressed[..8]).read_i64::<LittleEndian>().unwrap() as usize;
+        let mut data = compressed[..table_start].to_vec();
+        data[..8].copy_from_slice(&(-1i64).to_le_bytes());
+        let evlrs_start = data.len();
+        for (user_id, record) in [
+            ("other", &[1u8, 2, 3][..]),
+            ("laszip encoded", &compressed[table_start..]),
+        ] {
+            data.extend_from_slice(&[0, 0]);
+            let mut id = [0u8; 16];
+            id[..user_id.len()].copy_from_slice(user_id.as_bytes());
+            data.extend_from_slice(&id);
+            data.extend_from_slice(&[0, 0]);
+            data.extend_from_slice(&(record.len() as u64).to_le_bytes());
+            data.extend_from_slice(&[0u8; 32]);
+            data.extend_from_slice(record);
+        }
+        let mut vlr_data = Vec::new();
+        vlr.write_to(&mut vlr_data).unwrap();
+        vlr_data[16..24].copy_from_slice(&2i64.to_le_bytes());
+        vlr_data[24..32].copy_from_slice(&(evlrs_start as i64).to_le_bytes());
+        let vlr = LazVlr::from_buffer(&vlr_data).unwrap();
+        assert_eq!(vlr.special_evlrs(), Some((evlrs_start as u64, 2)));
+
+        let mut decompressor = LasZipDecompressor::new(Cursor::new(&data), vlr).unwrap();
+        assert_eq!(decompressor.chunk_table().unwrap().len(), 3);
+        decompressor.seek(21).unwrap();
+        let mut point = [0u8; 20];
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(point, &points[21 * 20..22 * 20]);
+    }
+}
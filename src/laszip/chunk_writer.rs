@@ -0,0 +1,272 @@
+//! Writer that assembles whole chunks in memory
+use std::io::{Seek, SeekFrom, Write};
+
+/// Writer that keeps the data in memory until it is flushed, to then
+/// write it to the inner writer with a single `write_all` call.
+///
+/// Used with a [`LasZipCompressor`] that flushes at the end of each chunk
+/// (see [`LasZipCompressor::set_flush_on_chunk_end`]), each chunk is written at once,
+/// which avoids the many small writes of the encoders on unbuffered outputs,
+/// and lets outputs like object stores receive complete chunks.
+///
+/// Seeking inside the data not flushed yet is done in memory, seeking elsewhere
+/// writes the data first, without flushing the inner writer.
+/// When writing the data fails, it is kept and written again, from its start,
+/// by the next flush.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::ChunkBufferedWriter;
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(5).build();
+/// let output = ChunkBufferedWriter::new(Cursor::new(Vec::<u8>::new()))?;
+/// let mut compressor = laz::LasZipCompressor::new(output, vlr)?;
+/// compressor.set_flush_on_chunk_end(true);
+/// compressor.compress_many(&[0u8; 20 * 12])?;
+/// let compressed = compressor.finish()?.into_inner()?.into_inner();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`LasZipCompressor`]: crate::LasZipCompressor
+/// [`LasZipCompressor::set_flush_on_chunk_end`]: crate::LasZipCompressor::set_flush_on_chunk_end
+#[derive(Debug)]
+pub struct ChunkBufferedWriter<W: Write + Seek> {
+    inner: W,
+    buffer: Vec<u8>,
+    /// Position of the inner writer, where the buffer starts
+    buffer_start: u64,
+    /// Position in the buffer
+    pos: usize,
+}
+
+impl<W: Write + Seek> ChunkBufferedWriter<W> {
+    pub fn new(mut inner: W) -> std::io::Result<Self> {
+        let buffer_start = inner.stream_position()?;
+        Ok(Self {
+            inner,
+            buffer: Vec::new(),
+            buffer_start,
+            pos: 0,
+        })
+    }
+
+    /// Returns the number of bytes not written to the inner writer yet
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Flushes the buffered data and returns the inner writer
+    pub fn into_inner(mut self) -> std::io::Result<W> {
+        self.flush()?;
+        Ok(self.inner)
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the inner writer, writing to it while data
+    /// is buffered would corrupt the output.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes the buffer to the inner writer, which is then positioned
+    /// where the position in the buffer was.
+    fn write_buffer(&mut self) -> std::io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        if let Err(error) = self.inner.write_all(&self.buffer) {
+            // Part of the buffer may be written, it is kept
+            // to be written again from its start by the next flush
+            self.inner.seek(SeekFrom::Start(self.buffer_start))?;
+            return Err(error);
+        }
+        let new_start = self.buffer_start + self.pos as u64;
+        if self.pos != self.buffer.len() {
+            self.inner.seek(SeekFrom::Start(new_start))?;
+        }
+        self.buffer.clear();
+        self.buffer_start = new_start;
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Write for ChunkBufferedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let end = self.pos + buf.len();
+        if end > self.buffer.len() {
+            self.buffer.resize(end, 0u8);
+        }
+        self.buffer[self.pos..end].copy_from_slice(buf);
+        self.pos = end;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write_buffer()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write + Seek> Seek for ChunkBufferedWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(target) => Some(target),
+            SeekFrom::Current(delta) => Some(
+                (self.buffer_start + self.pos as u64)
+                    .checked_add_signed(delta)
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "invalid seek to a negative or overflowing position",
+                        )
+                    })?,
+            ),
+            SeekFrom::End(_) => None,
+        };
+        match target {
+            Some(target)
+                if self.buffer_start <= target
+                    && target <= self.buffer_start + self.buffer.len() as u64 =>
+            {
+                self.pos = (target - self.buffer_start) as usize;
+                Ok(target)
+            }
+            _ => {
+                self.write_buffer()?;
+                let new_pos = match target {
+                    Some(target) => self.inner.seek(SeekFrom::Start(target))?,
+                    None => self.inner.seek(pos)?,
+                };
+                self.buffer_start = new_pos;
+                Ok(new_pos)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Counts the calls to write
+    struct CountingWriter {
+        inner: Cursor<Vec<u8>>,
+        num_writes: usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.num_writes += 1;
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for CountingWriter {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    /// Writes at most 4 bytes at once, the `fail_at`-th write fails
+    struct FailingWriter {
+        inner: Cursor<Vec<u8>>,
+        num_writes: usize,
+        fail_at: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.num_writes += 1;
+            if self.num_writes == self.fail_at {
+                return Err(std::io::Error::other("write failed"));
+            }
+            self.inner.write(&buf[..buf.len().min(4)])
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for FailingWriter {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_seek_in_buffer() {
+        let mut writer = ChunkBufferedWriter::new(Cursor::new(vec![9u8; 2])).unwrap();
+        writer.seek(SeekFrom::Start(1)).unwrap();
+        writer.write_all(&[1, 2, 3]).unwrap();
+        assert_eq!(writer.seek(SeekFrom::Current(-2)).unwrap(), 2);
+        writer.write_all(&[4]).unwrap();
+        assert_eq!(writer.get_ref().get_ref(), &[9, 9]);
+        // Outside of the buffer
+        writer.seek(SeekFrom::Start(0)).unwrap();
+        writer.write_all(&[0]).unwrap();
+        assert_eq!(writer.seek(SeekFrom::End(0)).unwrap(), 4);
+        writer.write_all(&[5]).unwrap();
+        assert_eq!(writer.into_inner().unwrap().into_inner(), &[0, 1, 4, 3, 5]);
+    }
+
+    #[test]
+    fn test_one_write_per_chunk() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = crate::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(10)
+            .build();
+        let points = crate::test_utils::points(35, 20);
+
+        let mut expected = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut expected, &points, vlr.clone()).unwrap();
+
+        let output = CountingWriter {
+            inner: Cursor::new(Vec::new()),
+            num_writes: 0,
+        };
+        let mut compressor =
+            crate::LasZipCompressor::new(ChunkBufferedWriter::new(output).unwrap(), vlr).unwrap();
+        compressor.set_flush_on_chunk_end(true);
+        compressor.compress_many(&points[..15 * 20]).unwrap();
+        // The first chunk, with the offset to the chunk table
+        assert_eq!(compressor.get().get_ref().num_writes, 1);
+        compressor.compress_many(&points[15 * 20..]).unwrap();
+        let output = compressor.finish().unwrap().into_inner().unwrap();
+        // 4 chunks, the offset to the chunk table, and the chunk table
+        assert_eq!(output.num_writes, 6);
+        assert_eq!(output.inner.into_inner(), expected.into_inner());
+    }
+
+    #[test]
+    fn test_flush_after_partial_write() {
+        let output = FailingWriter {
+            inner: Cursor::new(Vec::new()),
+            num_writes: 0,
+            fail_at: 3,
+        };
+        let mut writer = ChunkBufferedWriter::new(output).unwrap();
+        writer.write_all(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]).unwrap();
+        // 8 bytes are written before the failure
+        assert!(writer.flush().is_err());
+        assert_eq!(writer.buffered_len(), 10);
+        writer.flush().unwrap();
+        let output = writer.into_inner().unwrap().inner.into_inner();
+        assert_eq!(output, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+    }
+}
@@ -1,11 +1,138 @@
-use std::io::{Seek, SeekFrom, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
 
-use byteorder::{LittleEndian, WriteBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::laszip::chunk_table::{ChunkTable, ChunkTableEntry};
+#[cfg(feature = "digest")]
+use crate::laszip::digest::PointDigest;
 use crate::record::RecordCompressor;
+use crate::LasZipError;
 
-use super::{chunk_table, details, LazItem, LazVlr};
+use super::{chunk_table, details, point_buffer, CompressorType, LazItem, LazVlr, PointBuffer};
+
+/// Progress of a [`LasZipCompressor`], at the end of its last completed chunk.
+///
+/// A checkpoint can be saved (see [`Self::write_to`]) alongside the partial output,
+/// so that a job that was interrupted can be resumed using [`LasZipCompressor::resume`]
+/// instead of being restarted from zero.
+#[derive(Debug, Clone)]
+pub struct CompressionCheckpoint {
+    /// Position where the compressor started, that is,
+    /// where the offset to the chunk table is
+    start_pos: u64,
+    /// Chunks completed so far
+    chunk_table: ChunkTable,
+}
+
+impl CompressionCheckpoint {
+    const VERSION: u32 = 1;
+
+    pub(super) fn new(start_pos: u64, chunk_table: ChunkTable) -> Self {
+        Self {
+            start_pos,
+            chunk_table,
+        }
+    }
+
+    /// Returns the number of points in the completed chunks,
+    /// which is the number of points that have to be skipped when resuming.
+    pub fn num_points(&self) -> u64 {
+        self.chunk_table.num_points()
+    }
+
+    /// Returns the chunks completed so far
+    pub fn chunk_table(&self) -> &ChunkTable {
+        &self.chunk_table
+    }
+
+    /// Returns the position, in the output, of the end of the last completed chunk.
+    ///
+    /// Data after this position is not part of the checkpoint.
+    pub fn data_end(&self) -> u64 {
+        self.start_pos
+            + std::mem::size_of::<i64>() as u64
+            + self
+                .chunk_table
+                .as_ref()
+                .iter()
+                .map(|e| e.byte_count)
+                .sum::<u64>()
+    }
+
+    /// Reads a checkpoint written by [`Self::write_to`]
+    pub fn read_from<R: Read>(mut src: R) -> std::io::Result<Self> {
+        let version = src.read_u32::<LittleEndian>()?;
+        if version != Self::VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported checkpoint version {}", version),
+            ));
+        }
+        let start_pos = src.read_u64::<LittleEndian>()?;
+        let number_of_chunks = src.read_u64::<LittleEndian>()?;
+        let mut chunk_table = ChunkTable::with_capacity(number_of_chunks as usize);
+        for _ in 0..number_of_chunks {
+            let point_count = src.read_u64::<LittleEndian>()?;
+            let byte_count = src.read_u64::<LittleEndian>()?;
+            chunk_table.push(ChunkTableEntry {
+                point_count,
+                byte_count,
+            });
+        }
+        Ok(Self {
+            start_pos,
+            chunk_table,
+        })
+    }
+
+    /// Writes the checkpoint to the `dst`
+    pub fn write_to<W: Write>(&self, mut dst: W) -> std::io::Result<()> {
+        dst.write_u32::<LittleEndian>(Self::VERSION)?;
+        dst.write_u64::<LittleEndian>(self.start_pos)?;
+        dst.write_u64::<LittleEndian>(self.chunk_table.len() as u64)?;
+        for entry in &self.chunk_table {
+            dst.write_u64::<LittleEndian>(entry.point_count)?;
+            dst.write_u64::<LittleEndian>(entry.byte_count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Settings of the content-adaptive chunking of a [`LasZipCompressor`],
+/// see [`LasZipCompressor::set_adaptive_chunking`].
+#[derive(Debug, Copy, Clone)]
+pub struct AdaptiveChunking {
+    /// Number of points over which the compression ratio is measured
+    pub window: u64,
+    /// The current chunk is ended when the number of bytes per point of a window
+    /// is greater than the best one of the chunk multiplied by this factor
+    pub degradation: f64,
+    /// Chunks are not ended early before having this number of points
+    pub min_chunk_size: u64,
+    /// Chunks are always ended when they reach this number of points
+    pub max_chunk_size: u64,
+}
+
+impl Default for AdaptiveChunking {
+    fn default() -> Self {
+        Self {
+            window: 5_000,
+            degradation: 1.5,
+            min_chunk_size: 10_000,
+            max_chunk_size: 50_000,
+        }
+    }
+}
+
+/// State of the content-adaptive chunking for the current chunk
+struct AdaptiveChunkingState {
+    settings: AdaptiveChunking,
+    /// Position of the output at the start of the current window
+    window_start_pos: u64,
+    /// Lowest number of bytes per point of the windows of the current chunk
+    best_rate: f64,
+}
 
 /// Struct that handles the compression of the points into the given destination
 ///
@@ -30,11 +157,17 @@ use super::{chunk_table, details, LazItem, LazVlr};
 /// - Use [`compress_chunks`] to compress chunks.
 /// - Use [`done`] when you have compressed all the points you wanted.
 ///
+/// # Errors
+///
+/// When an IO error occurs, the points of the current chunk
+/// can be compressed again after calling [`rewind_current_chunk`].
+///
 /// [`compress_one`]: Self::compress_one
 /// [`compress_many`]: Self::compress_many
 /// [`compress_chunks`]: Self::compress_chunks
 /// [`finish_current_chunk`]: Self::finish_current_chunk
 /// [`done`]: Self::done
+/// [`rewind_current_chunk`]: Self::rewind_current_chunk
 pub struct LasZipCompressor<'a, W: Write + Send + 'a> {
     vlr: LazVlr,
     /// Compressor used for the current chunk
@@ -48,19 +181,140 @@ pub struct LasZipCompressor<'a, W: Write + Send + 'a> {
     /// Position (offset from beginning)
     /// where the current chunk started
     chunk_start_pos: u64,
+    adaptive_chunking: Option<AdaptiveChunkingState>,
+    /// Maximum time between the first point of a chunk and the end of the chunk
+    chunk_deadline: Option<Duration>,
+    /// When the first point of the current chunk was compressed
+    chunk_started_at: Option<Instant>,
+    /// Whether the output is flushed each time a chunk is finished
+    flush_on_chunk_end: bool,
+    /// Digest of the points compressed, when enabled
+    #[cfg(feature = "digest")]
+    digest: Option<PointDigest>,
+    /// Whether the points compressed before a resume are not in the digest,
+    /// which then cannot be enabled
+    #[cfg(feature = "digest")]
+    resumed: bool,
+    /// Number of points after which chunks are ended, for variable-size chunks
+    points_per_chunk: Option<u64>,
+    /// Maximum number of bytes allocated for the chunk table and the current chunk
+    memory_limit: Option<usize>,
+    #[cfg(feature = "timing")]
+    chunk_timer: crate::timing::ChunkTimer,
 }
 
 impl<'a, W: Write + Seek + Send + 'a> LasZipCompressor<'a, W> {
     /// Creates a compressor using the provided vlr.
     pub fn new(output: W, vlr: LazVlr) -> crate::Result<Self> {
-        let record_compressor = details::record_compressor_from_laz_items(&vlr.items(), output)?;
-        Ok(Self {
+        let record_compressor = details::record_compressor_from_vlr(&vlr, output)?;
+        Ok(Self::with_record_compressor(record_compressor, vlr))
+    }
+
+    /// Creates a compressor that uses a custom [`RecordCompressor`]
+    /// to compress the points of each chunk.
+    ///
+    /// The `record_compressor` must be ready to compress the points described by the `vlr`.
+    ///
+    /// See the [`record`](crate::record) module for what the compressor relies on.
+    pub fn with_record_compressor(
+        record_compressor: Box<dyn RecordCompressor<W> + Send + 'a>,
+        vlr: LazVlr,
+    ) -> Self {
+        let chunk_table = vlr
+            .num_chunks_hint()
+            .map_or_else(ChunkTable::default, ChunkTable::with_capacity);
+        Self {
             vlr,
             record_compressor,
             chunk_start_pos: 0,
             start_pos: 0,
-            chunk_table: ChunkTable::default(),
+            chunk_table,
             current_chunk_entry: ChunkTableEntry::default(),
+            adaptive_chunking: None,
+            chunk_deadline: None,
+            chunk_started_at: None,
+            flush_on_chunk_end: false,
+            #[cfg(feature = "digest")]
+            digest: None,
+            #[cfg(feature = "digest")]
+            resumed: false,
+            points_per_chunk: None,
+            memory_limit: None,
+            #[cfg(feature = "timing")]
+            chunk_timer: Default::default(),
+        }
+    }
+
+    /// Creates a compressor that resumes the job saved in the `checkpoint`,
+    /// the `output` must contain the data written by the compressor
+    /// the checkpoint was taken from and the `vlr` must be the same.
+    ///
+    /// The compression restarts after the last chunk completed before the checkpoint
+    /// was taken, so the first [`CompressionCheckpoint::num_points`] points
+    /// must not be given again to the compressor.
+    ///
+    /// # Note
+    ///
+    /// The data after [`CompressionCheckpoint::data_end`] gets overwritten,
+    /// however, if the `output` is a file, it may be bigger than the new data,
+    /// it should be truncated to `data_end` before resuming.
+    ///
+    /// The checkpoint does not hold the state of the digest, the resumed compressor
+    /// does not compute it, see `set_compute_digest`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::Cursor;
+    /// # use laz::laszip::CompressionCheckpoint;
+    /// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    /// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(5).build();
+    /// let points = [0u8; 20 * 20];
+    ///
+    /// let mut compressor = laz::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone())?;
+    /// compressor.compress_many(&points[..12 * 20])?;
+    /// let mut saved = Vec::<u8>::new();
+    /// compressor.checkpoint().unwrap().write_to(&mut saved)?;
+    /// let output = compressor.into_inner();
+    ///
+    /// // Later, the job is resumed
+    /// let checkpoint = CompressionCheckpoint::read_from(saved.as_slice())?;
+    /// let num_done = checkpoint.num_points() as usize;
+    /// assert_eq!(num_done, 10);
+    /// let mut compressor = laz::LasZipCompressor::resume(output, vlr, checkpoint)?;
+    /// compressor.compress_many(&points[num_done * 20..])?;
+    /// compressor.done()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resume(
+        mut output: W,
+        vlr: LazVlr,
+        checkpoint: CompressionCheckpoint,
+    ) -> crate::Result<Self> {
+        let chunk_start_pos = checkpoint.data_end();
+        output.seek(SeekFrom::Start(chunk_start_pos))?;
+        let record_compressor = details::record_compressor_from_vlr(&vlr, output)?;
+        Ok(Self {
+            vlr,
+            record_compressor,
+            chunk_start_pos,
+            start_pos: checkpoint.start_pos,
+            chunk_table: checkpoint.chunk_table,
+            current_chunk_entry: ChunkTableEntry::default(),
+            adaptive_chunking: None,
+            chunk_deadline: None,
+            chunk_started_at: None,
+            flush_on_chunk_end: false,
+            #[cfg(feature = "digest")]
+            digest: None,
+            #[cfg(feature = "digest")]
+            resumed: true,
+            points_per_chunk: None,
+            memory_limit: None,
+            #[cfg(feature = "timing")]
+            chunk_timer: Default::default(),
         })
     }
 
@@ -89,12 +343,243 @@ impl<'a, W: Write + Seek + Send + 'a> LasZipCompressor<'a, W> {
 
         // Since in variable-size chunks mode the vlr.chunk_size() is
         // u32::max this should not interfere.
-        if self.current_chunk_entry.point_count == self.vlr.chunk_size() as u64 {
+        if self.current_chunk_entry.point_count == self.vlr.chunk_size() as u64
+            || Some(self.current_chunk_entry.point_count) == self.points_per_chunk
+        {
             self.finish_current_chunk_impl()?;
         }
 
-        self.record_compressor.compress_next(&input)?;
+        self.check_memory_limit(1)?;
+        self.record_compressor.compress_next(input)?;
         self.current_chunk_entry.point_count += 1;
+        #[cfg(feature = "digest")]
+        if let Some(digest) = self.digest.as_mut() {
+            digest.update(input);
+        }
+        if self.adaptive_chunking.is_some() {
+            self.adapt_chunking()?;
+        }
+        if self.chunk_deadline.is_some() {
+            if self.current_chunk_entry.point_count == 1 {
+                self.chunk_started_at = Some(Instant::now());
+            }
+            self.finish_chunk_if_deadline_passed()?;
+        }
+        Ok(())
+    }
+
+    /// Makes the compressor end the current chunk, and flush the output, when
+    /// the `deadline` has passed since its first point was compressed,
+    /// `None` disables it.
+    ///
+    /// This bounds the latency between compressing a point and the point being
+    /// readable by consumers of the output, at the cost of smaller chunks.
+    ///
+    /// The deadline is checked when compressing points, if points may stop coming,
+    /// [`finish_chunk_if_deadline_passed`] should be called regularly.
+    ///
+    /// # Important
+    ///
+    /// Only call this when writing **variable-size** chunks.
+    ///
+    /// [`finish_chunk_if_deadline_passed`]: Self::finish_chunk_if_deadline_passed
+    pub fn set_chunk_deadline(&mut self, deadline: Option<Duration>) {
+        debug_assert!(
+            self.vlr.uses_variable_size_chunks(),
+            "set_chunk_deadline called on a file which is not in variable-size chunks mode"
+        );
+        self.chunk_deadline = deadline;
+        if self.current_chunk_entry.point_count != 0 && self.chunk_started_at.is_none() {
+            self.chunk_started_at = Some(Instant::now());
+        }
+    }
+
+    /// Ends the current chunk and flushes the output if the deadline given to
+    /// [`set_chunk_deadline`] has passed.
+    ///
+    /// Returns whether the chunk was ended.
+    ///
+    /// [`set_chunk_deadline`]: Self::set_chunk_deadline
+    pub fn finish_chunk_if_deadline_passed(&mut self) -> std::io::Result<bool> {
+        let deadline_passed = match (self.chunk_deadline, self.chunk_started_at) {
+            (Some(deadline), Some(started_at)) => {
+                self.current_chunk_entry.point_count != 0 && started_at.elapsed() >= deadline
+            }
+            _ => false,
+        };
+        if deadline_passed {
+            self.finish_current_chunk_impl()?;
+            self.record_compressor.get_mut().flush()?;
+        }
+        Ok(deadline_passed)
+    }
+
+    /// Makes the compressor flush the output each time a chunk is finished,
+    /// and once the chunk table is written by [`done`].
+    ///
+    /// This way, flushes of the output happen exactly at chunk boundaries,
+    /// with a [`ChunkBufferedWriter`] each chunk is written with a single call.
+    ///
+    /// [`done`]: Self::done
+    /// [`ChunkBufferedWriter`]: super::ChunkBufferedWriter
+    pub fn set_flush_on_chunk_end(&mut self, flush_on_chunk_end: bool) {
+        self.flush_on_chunk_end = flush_on_chunk_end;
+    }
+
+    /// Applies the settings of the compressor that are part of the `profile`,
+    /// that is whether the output is flushed at the end of each chunk,
+    /// see [`CompressionProfile`].
+    ///
+    /// The chunk size and the item versions are part of the vlr,
+    /// see [`LazVlrBuilder::with_profile`].
+    ///
+    /// [`CompressionProfile`]: super::CompressionProfile
+    /// [`LazVlrBuilder::with_profile`]: super::LazVlrBuilder::with_profile
+    pub fn set_profile(&mut self, profile: super::CompressionProfile) {
+        self.set_flush_on_chunk_end(profile.flushes_on_chunk_end());
+    }
+
+    /// Sets the maximum number of bytes the compressor may allocate for the chunk table
+    /// and the compressed data of the current chunk, `None` disables it.
+    ///
+    /// Compressing a point that would go past it returns
+    /// [`LasZipError::MemoryLimitExceeded`] (wrapped in the io error),
+    /// the point is then not compressed.
+    ///
+    /// The layered compression (point formats 6 to 10) buffers the chunks,
+    /// their size is counted as the size of their uncompressed points,
+    /// so that the limit is not exceeded whatever the compression ratio.
+    /// Smaller chunks, or variable-size chunks that are finished early,
+    /// allow a lower limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::io::Cursor;
+    /// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point6>(0);
+    /// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(100).build();
+    /// let mut compressor = laz::LasZipCompressor::new(Cursor::new(Vec::new()), vlr).unwrap();
+    /// compressor.set_memory_limit(Some(1_000));
+    ///
+    /// let error = compressor.compress_many(&[0u8; 30 * 100]).unwrap_err();
+    /// assert!(matches!(
+    ///     laz::LasZipError::from(error),
+    ///     laz::LasZipError::MemoryLimitExceeded { limit: 1_000, .. }
+    /// ));
+    /// ```
+    pub fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.memory_limit = memory_limit;
+    }
+
+    /// Makes the compressor end the chunks every `points_per_chunk` points,
+    /// `None` (or 0) disables it.
+    ///
+    /// The chunks are then fixed-size, while the chunk table still stores
+    /// the number of points of each chunk, so that truncated or appended data
+    /// can be detected, and the number of points known from the chunk table alone
+    /// (see [`ChunkTable::num_points`]).
+    ///
+    /// # Important
+    ///
+    /// Only call this when writing **variable-size** chunks, as it is what tells
+    /// readers that the chunk table has the point counts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Cursor, Seek, SeekFrom};
+    /// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    /// let vlr = laz::LazVlrBuilder::new(items).with_variable_chunk_size().build();
+    /// let mut compressor = laz::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone())?;
+    /// compressor.set_points_per_chunk(Some(10));
+    /// compressor.compress_many(&[0u8; 20 * 25])?;
+    /// let mut compressed = compressor.finish()?;
+    ///
+    /// compressed.seek(SeekFrom::Start(0))?;
+    /// let chunk_table = laz::laszip::ChunkTable::read_from(&mut compressed, &vlr)?;
+    /// assert_eq!(chunk_table.len(), 3);
+    /// assert_eq!(chunk_table.num_points(), 25);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ChunkTable::num_points`]: super::ChunkTable::num_points
+    pub fn set_points_per_chunk(&mut self, points_per_chunk: Option<u64>) {
+        debug_assert!(
+            self.vlr.uses_variable_size_chunks(),
+            "set_points_per_chunk called on a file which is not in variable-size chunks mode"
+        );
+        self.points_per_chunk = points_per_chunk.filter(|&n| n != 0);
+    }
+
+    /// Makes the compressor compute the digest of the points it compresses,
+    /// to be stored in a [`DigestEvlr`] once the compression is done.
+    ///
+    /// It must be enabled before the first point is compressed,
+    /// disabling it discards the digest.
+    ///
+    /// A compressor created by [`resume`](Self::resume) cannot compute it,
+    /// as it does not get the points compressed before the checkpoint,
+    /// enabling it has no effect.
+    ///
+    /// [`DigestEvlr`]: super::DigestEvlr
+    #[cfg(feature = "digest")]
+    pub fn set_compute_digest(&mut self, compute_digest: bool) {
+        if compute_digest != self.digest.is_some() && !self.resumed {
+            self.digest = compute_digest.then(PointDigest::new);
+        }
+    }
+
+    /// Returns the digest of the points compressed so far,
+    /// `None` if [`set_compute_digest`](Self::set_compute_digest) was not enabled.
+    #[cfg(feature = "digest")]
+    pub fn digest(&self) -> Option<[u8; 32]> {
+        self.digest.clone().map(PointDigest::finish)
+    }
+
+    /// Makes the compressor end the current chunk early when its compression
+    /// ratio degrades (e.g. at flight-line boundaries), `None` disables it.
+    ///
+    /// # Important
+    ///
+    /// Only call this when writing **variable-size** chunks.
+    ///
+    /// # Note
+    ///
+    /// The compression ratio is measured by looking at how much data is written
+    /// to the output, so only point-wise chunked data (LAZ item versions 1 and 2)
+    /// support it, as layered data is only written when a chunk is done.
+    pub fn set_adaptive_chunking(&mut self, settings: Option<AdaptiveChunking>) {
+        debug_assert!(
+            self.vlr.uses_variable_size_chunks(),
+            "set_adaptive_chunking called on a file which is not in variable-size chunks mode"
+        );
+        self.adaptive_chunking = settings.map(|settings| AdaptiveChunkingState {
+            settings,
+            window_start_pos: self.chunk_start_pos,
+            best_rate: f64::INFINITY,
+        });
+    }
+
+    fn adapt_chunking(&mut self) -> std::io::Result<()> {
+        let point_count = self.current_chunk_entry.point_count;
+        let state = self.adaptive_chunking.as_mut().unwrap();
+        let settings = state.settings;
+        let mut end_chunk = point_count >= settings.max_chunk_size;
+        if !end_chunk && settings.window != 0 && point_count.is_multiple_of(settings.window) {
+            let current_pos = self.record_compressor.get_mut().stream_position()?;
+            let rate =
+                current_pos.saturating_sub(state.window_start_pos) as f64 / settings.window as f64;
+            end_chunk = point_count >= settings.min_chunk_size
+                && rate > state.best_rate * settings.degradation;
+            state.best_rate = state.best_rate.min(rate);
+            state.window_start_pos = current_pos;
+        }
+
+        if end_chunk {
+            self.finish_current_chunk_impl()?;
+        }
         Ok(())
     }
 
@@ -106,21 +591,74 @@ impl<'a, W: Write + Seek + Send + 'a> LasZipCompressor<'a, W> {
         Ok(())
     }
 
-    /// Compresses multiple chunks
+    /// Compresses all the points of the buffer
+    ///
+    /// Unlike [`compress_many`], an error is returned if the buffer does
+    /// not contain points of the size described by the vlr.
+    ///
+    /// [`compress_many`]: Self::compress_many
+    pub fn compress_points(&mut self, points: PointBuffer) -> crate::Result<()> {
+        point_buffer::check_point_size(points.point_size(), &self.vlr)?;
+        self.compress_many(points.as_bytes())?;
+        Ok(())
+    }
+
+    /// Compresses the points of the `chunk` as exactly one chunk
+    ///
+    /// Points compressed before, that do not form a finished chunk yet, are
+    /// put in their own chunk first, and the chunk is not split by the
+    /// [`set_points_per_chunk`], [`set_adaptive_chunking`] or [`set_chunk_deadline`] options,
+    /// so that the caller has precise control of which points go in which chunk.
+    ///
+    /// An empty `chunk` does not produce a chunk.
     ///
     /// # Important
     ///
     /// This **must** be called **only** when writing **variable-size** chunks.
+    ///
+    /// [`set_points_per_chunk`]: Self::set_points_per_chunk
+    /// [`set_adaptive_chunking`]: Self::set_adaptive_chunking
+    /// [`set_chunk_deadline`]: Self::set_chunk_deadline
+    pub fn compress_chunk(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        debug_assert!(self.vlr.uses_variable_size_chunks());
+        if self.current_chunk_entry.point_count != 0 {
+            self.finish_current_chunk_impl()?;
+        }
+        let point_size = self.vlr.items_size() as usize;
+        let num_points = chunk.len() / point_size;
+        if num_points == 0 {
+            return Ok(());
+        }
+        self.check_memory_limit(num_points as u64)?;
+        if self.chunk_start_pos == 0 {
+            self.reserve_offset_to_chunk_table()?;
+        }
+        let points = &chunk[..num_points * point_size];
+        for point in points.chunks_exact(point_size) {
+            self.record_compressor.compress_next(point)?;
+        }
+        self.current_chunk_entry.point_count = num_points as u64;
+        #[cfg(feature = "digest")]
+        if let Some(digest) = self.digest.as_mut() {
+            digest.update(points);
+        }
+        self.finish_current_chunk_impl()
+    }
+
+    /// Compresses multiple chunks, each one as exactly one chunk, see [`compress_chunk`].
+    ///
+    /// # Important
+    ///
+    /// This **must** be called **only** when writing **variable-size** chunks.
+    ///
+    /// [`compress_chunk`]: Self::compress_chunk
     pub fn compress_chunks<Chunks, Item>(&mut self, chunks: Chunks) -> std::io::Result<()>
     where
         Item: AsRef<[u8]>,
         Chunks: IntoIterator<Item = Item>,
     {
-        debug_assert!(self.vlr.uses_variable_size_chunks());
-        for chunks in chunks.into_iter() {
-            let chunk_points = chunks.as_ref();
-            self.compress_many(chunk_points)?;
-            self.finish_current_chunk_impl()?;
+        for chunk in chunks.into_iter() {
+            self.compress_chunk(chunk.as_ref())?;
         }
         Ok(())
     }
@@ -130,11 +668,21 @@ impl<'a, W: Write + Seek + Send + 'a> LasZipCompressor<'a, W> {
         if self.chunk_start_pos == 0 {
             self.reserve_offset_to_chunk_table()?;
         }
-        self.record_compressor.done()?;
-        self.update_chunk_table()?;
+        // The current chunk is empty if the last one was explicitly finished,
+        // in that case there is nothing more to write
+        if self.current_chunk_entry.point_count != 0 || self.chunk_table.is_empty() {
+            self.record_compressor.done()?;
+            self.update_chunk_table()?;
+            self.current_chunk_entry = ChunkTableEntry::default();
+        }
         let stream = self.record_compressor.get_mut();
+        // In case a previous call failed while writing the chunk table
+        stream.seek(SeekFrom::Start(self.chunk_start_pos))?;
         chunk_table::update_chunk_table_offset(stream, SeekFrom::Start(self.start_pos))?;
-        self.chunk_table.write_to(stream, &self.vlr)?;
+        self.chunk_table.write_to(&mut *stream, &self.vlr)?;
+        if self.flush_on_chunk_end {
+            stream.flush()?;
+        }
         Ok(())
     }
 
@@ -159,6 +707,54 @@ impl<'a, W: Write + Seek + Send + 'a> LasZipCompressor<'a, W> {
         self.finish_current_chunk_impl()
     }
 
+    /// Discards the points compressed in the current chunk, so that they can
+    /// be compressed again, typically after an IO error (which leaves the
+    /// current chunk in an undefined state).
+    ///
+    /// The output is moved back at the end of the last completed chunk,
+    /// the chunks that were already completed are kept.
+    ///
+    /// Returns the number of points in the completed chunks, that is,
+    /// the index of the first point to be compressed again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::Cursor;
+    /// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    /// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(5).build();
+    /// let points = [0u8; 20 * 20];
+    /// let mut compressor = laz::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr)?;
+    ///
+    /// let mut next = 0;
+    /// while next < 20 {
+    ///     match compressor.compress_many(&points[next * 20..]) {
+    ///         Ok(()) => next = 20,
+    ///         Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+    ///             next = compressor.rewind_current_chunk()? as usize;
+    ///         }
+    ///         Err(e) => return Err(e.into()),
+    ///     }
+    /// }
+    /// compressor.done()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rewind_current_chunk(&mut self) -> std::io::Result<u64> {
+        if self.chunk_start_pos != 0 {
+            self.record_compressor
+                .get_mut()
+                .seek(SeekFrom::Start(self.chunk_start_pos))?;
+        }
+        self.record_compressor.reset();
+        self.record_compressor
+            .set_fields_from(self.vlr.items())
+            .unwrap();
+        self.reset_chunk_state();
+        Ok(self.chunk_table.num_points())
+    }
+
     /// Reserves and prepares the offset to chunk table that will be
     /// updated when [done] is called.
     ///
@@ -170,16 +766,63 @@ impl<'a, W: Write + Seek + Send + 'a> LasZipCompressor<'a, W> {
         debug_assert_eq!(self.chunk_start_pos, 0);
         let stream = self.record_compressor.get_mut();
         self.start_pos = stream.seek(SeekFrom::Current(0))?;
-        stream.write_i64::<LittleEndian>(-1)?;
+        if let Err(e) = stream.write_i64::<LittleEndian>(-1) {
+            // So that the offset can be reserved again
+            stream.seek(SeekFrom::Start(self.start_pos))?;
+            return Err(e);
+        }
         self.chunk_start_pos = self.start_pos + std::mem::size_of::<i64>() as u64;
         Ok(())
     }
 
+    /// Calls [`done`], flushes the output and returns it.
+    ///
+    /// The output is positioned right after the chunk table,
+    /// so that other data (e.g. EVLRs) can be written after the points.
+    ///
+    /// [`done`]: Self::done
+    pub fn finish(mut self) -> crate::Result<W> {
+        self.done()?;
+        let mut output = self.into_inner();
+        output.flush()?;
+        Ok(output)
+    }
+
     /// Returns the vlr used by this compressor
     pub fn vlr(&self) -> &LazVlr {
         &self.vlr
     }
 
+    /// Returns the time spent compressing each field, and each chunk
+    /// that was finished.
+    #[cfg(feature = "timing")]
+    pub fn timing_report(&self) -> crate::timing::TimingReport {
+        self.chunk_timer
+            .report(self.vlr.items(), self.record_compressor.field_times())
+    }
+
+    /// Returns the table of the chunks completed so far
+    pub fn chunk_table(&self) -> &ChunkTable {
+        &self.chunk_table
+    }
+
+    /// Returns the progress of the compression at the end of the last completed chunk,
+    /// `None` if no point was compressed yet.
+    ///
+    /// The data of the completed chunks has been written to the output,
+    /// but if the output is buffered, it must be flushed before saving the checkpoint.
+    ///
+    /// See [`Self::resume`].
+    pub fn checkpoint(&self) -> Option<CompressionCheckpoint> {
+        if self.chunk_start_pos == 0 {
+            return None;
+        }
+        Some(CompressionCheckpoint {
+            start_pos: self.start_pos,
+            chunk_table: self.chunk_table.clone(),
+        })
+    }
+
     pub fn into_inner(self) -> W {
         self.record_compressor.box_into_inner()
     }
@@ -201,18 +844,51 @@ impl<'a, W: Write + Seek + Send + 'a> LasZipCompressor<'a, W> {
         self.current_chunk_entry.byte_count = current_pos - self.chunk_start_pos;
         self.chunk_start_pos = current_pos;
         self.chunk_table.push(self.current_chunk_entry);
+        #[cfg(feature = "timing")]
+        self.chunk_timer
+            .finish_chunk(self.record_compressor.field_times());
         Ok(())
     }
 
+    /// Returns [`LasZipError::MemoryLimitExceeded`] if adding `num_points`
+    /// to the current chunk would go past the memory limit.
+    fn check_memory_limit(&self, num_points: u64) -> std::io::Result<()> {
+        if self.memory_limit.is_none() {
+            return Ok(());
+        }
+        let table_size = ChunkTable::memory_size(self.chunk_table.len() + 1);
+        let chunk_size = if self.vlr.compressor == CompressorType::LayeredChunked {
+            details::usize_from(self.current_chunk_entry.point_count + num_points)?
+                .saturating_mul(self.vlr.items_size() as usize)
+        } else {
+            0
+        };
+        crate::errors::check_memory_limit(self.memory_limit, table_size.saturating_add(chunk_size))
+            .map_err(LasZipError::into_io_error)
+    }
+
+    /// Resets what is tracked about the current chunk, for a new one
+    fn reset_chunk_state(&mut self) {
+        self.current_chunk_entry = ChunkTableEntry::default();
+        self.chunk_started_at = None;
+        if let Some(state) = self.adaptive_chunking.as_mut() {
+            state.window_start_pos = self.chunk_start_pos;
+            state.best_rate = f64::INFINITY;
+        }
+    }
+
     #[inline]
     fn finish_current_chunk_impl(&mut self) -> std::io::Result<()> {
         self.record_compressor.done()?;
         self.record_compressor.reset();
         self.record_compressor
-            .set_fields_from(&self.vlr.items())
+            .set_fields_from(self.vlr.items())
             .unwrap();
         self.update_chunk_table()?;
-        self.current_chunk_entry = ChunkTableEntry::default();
+        self.reset_chunk_state();
+        if self.flush_on_chunk_end {
+            self.record_compressor.get_mut().flush()?;
+        }
         Ok(())
     }
 }
@@ -251,3 +927,180 @@ pub fn compress_buffer<W: Write + Seek + Send>(
     compressor.done()?;
     Ok(())
 }
+
+/// Compresses all points, like [`compress_buffer`], into the fixed-size `dst` buffer
+///
+/// Returns the number of bytes written at the start of the `dst`,
+/// or a [`LasZipError::OutputBufferTooSmall`] error with the size the `dst`
+/// needs to have, in which case the content of `dst` is unspecified.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).build();
+/// let points = [1u8; 20 * 10];
+///
+/// let mut dst = [0u8; 8];
+/// let required = match laz::compress_buffer_into(&points, &mut dst, vlr.clone()) {
+///     Err(laz::LasZipError::OutputBufferTooSmall { required }) => required,
+///     _ => unreachable!(),
+/// };
+/// let mut dst = vec![0u8; required];
+/// let written = laz::compress_buffer_into(&points, &mut dst, vlr)?;
+/// assert_eq!(written, required);
+/// # Ok(())
+/// # }
+/// ```
+pub fn compress_buffer_into(
+    uncompressed_points: &[u8],
+    dst: &mut [u8],
+    laz_vlr: LazVlr,
+) -> crate::Result<usize> {
+    let mut cursor = std::io::Cursor::new(dst);
+    match compress_buffer(&mut cursor, uncompressed_points, laz_vlr.clone()) {
+        Ok(()) => details::usize_from(cursor.position()).map_err(Into::into),
+        Err(LasZipError::IoError(e)) if e.kind() == std::io::ErrorKind::WriteZero => {
+            let size = super::dry_run(uncompressed_points, laz_vlr)?;
+            Err(LasZipError::OutputBufferTooSmall {
+                required: details::usize_from(size.total)?,
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils;
+    use crate::{decompress_buffer, LasZipDecompressor, LazItemRecordBuilder, LazVlrBuilder};
+
+    #[test]
+    fn test_points_per_chunk() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        let points = test_utils::points(25, 20);
+        let mut compressor =
+            LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        compressor.set_points_per_chunk(Some(10));
+        compressor.compress_many(&points[..5 * 20]).unwrap();
+        compressor.finish_current_chunk().unwrap();
+        compressor.compress_many(&points[5 * 20..]).unwrap();
+        let mut compressed = compressor.finish().unwrap();
+
+        compressed.set_position(0);
+        let chunk_table = ChunkTable::read_from(&mut compressed, &vlr).unwrap();
+        let counts = chunk_table
+            .iter()
+            .map(|entry| entry.point_count)
+            .collect::<Vec<u64>>();
+        assert_eq!(counts, vec![5, 10, 10]);
+        assert_eq!(chunk_table.num_points(), 25);
+
+        let mut decompressed = vec![0u8; points.len()];
+        decompress_buffer(compressed.get_ref(), &mut decompressed, vlr).unwrap();
+        assert_eq!(decompressed, points);
+    }
+
+    #[test]
+    fn test_compress_chunk() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        let points = test_utils::points(12, 20);
+        let mut compressor =
+            LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        // Does not split the chunks given explicitly
+        compressor.set_points_per_chunk(Some(4));
+        compressor.compress_many(&points[..3 * 20]).unwrap();
+        compressor
+            .compress_chunks(vec![&points[3 * 20..8 * 20], &[], &points[8 * 20..]])
+            .unwrap();
+        let mut compressed = compressor.finish().unwrap();
+
+        compressed.set_position(0);
+        let chunk_table = ChunkTable::read_from(&mut compressed, &vlr).unwrap();
+        let counts = chunk_table
+            .as_ref()
+            .iter()
+            .map(|entry| entry.point_count)
+            .collect::<Vec<u64>>();
+        assert_eq!(counts, vec![3, 5, 4]);
+
+        let mut decompressed = vec![0u8; points.len()];
+        decompress_buffer(compressed.get_ref(), &mut decompressed, vlr).unwrap();
+        assert_eq!(decompressed, points);
+    }
+
+    #[test]
+    fn test_compress_buffer_into() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point3>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = test_utils::points(35, 34);
+        let mut expected = Cursor::new(Vec::<u8>::new());
+        compress_buffer(&mut expected, &points, vlr.clone()).unwrap();
+        let expected = expected.into_inner();
+
+        let mut dst = vec![0u8; expected.len() - 1];
+        match compress_buffer_into(&points, &mut dst, vlr.clone()) {
+            Err(crate::LasZipError::OutputBufferTooSmall { required }) => {
+                assert_eq!(required, expected.len())
+            }
+            other => panic!("expected the buffer to be too small, got {:?}", other),
+        }
+
+        let mut dst = vec![0u8; expected.len() + 10];
+        let written = compress_buffer_into(&points, &mut dst, vlr).unwrap();
+        assert_eq!(&dst[..written], &expected[..]);
+    }
+
+    #[test]
+    #[cfg(feature = "format-14")]
+    fn test_memory_limit() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point6>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(100).build();
+        let points = (0..250u32)
+            .flat_map(|i| {
+                let mut point = [0u8; 30];
+                point[..4].copy_from_slice(&i.wrapping_mul(7919).to_le_bytes());
+                point
+            })
+            .collect::<Vec<u8>>();
+
+        let mut compressor =
+            LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        compressor.set_memory_limit(Some(100 * 30 + 3 * 16));
+        compressor.compress_many(&points).unwrap();
+        compressor.done().unwrap();
+        let compressed = compressor.into_inner().into_inner();
+
+        let mut compressor =
+            LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        compressor.set_memory_limit(Some(100 * 30));
+        let error = compressor.compress_many(&points).unwrap_err();
+        assert!(matches!(
+            crate::LasZipError::from(error),
+            crate::LasZipError::MemoryLimitExceeded { .. }
+        ));
+
+        // The limit allows the chunk table, not the layers of a chunk
+        let mut decompressor =
+            LasZipDecompressor::new_with_memory_limit(Cursor::new(&compressed), vlr.clone(), 64)
+                .unwrap();
+        let error = decompressor.decompress_one(&mut [0u8; 30]).unwrap_err();
+        assert!(matches!(
+            crate::LasZipError::from(error),
+            crate::LasZipError::MemoryLimitExceeded { limit: 16, .. }
+        ));
+
+        let mut decompressor =
+            LasZipDecompressor::new_with_memory_limit(Cursor::new(&compressed), vlr, 100 * 30)
+                .unwrap();
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, points);
+    }
+}
@@ -0,0 +1,421 @@
+//! Writing of the point data of COPC (Cloud Optimized Point Cloud) files
+//!
+//! In a COPC file, the points are organized in an octree, the points of each
+//! node are compressed as one variable-size chunk, and the hierarchy EVLR gives
+//! the byte range of the chunk of each node.
+//!
+//! [`CopcWriter`] compresses the nodes and writes the hierarchy EVLR, the LAS header
+//! and the VLRs (where the [`CopcInfo`] VLR must be first) are written by the caller.
+//!
+//! Appending points to an existing COPC file is not supported,
+//! the file has to be written again with a new [`CopcWriter`].
+use std::convert::TryFrom;
+use std::io::{Read, Seek, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use super::{CompressorType, LasZipCompressor, LazVlr};
+
+/// User id of the COPC VLR and EVLR
+pub const COPC_USER_ID: &str = "copc";
+
+/// Offset of the GPS time in the records of the point formats 6 to 8
+const GPS_TIME_OFFSET: usize = 22;
+/// Size of the header of an EVLR, without its data
+const EVLR_HEADER_SIZE: u64 = 60;
+
+fn padded<const N: usize>(s: &str) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes[..s.len()].copy_from_slice(s.as_bytes());
+    bytes
+}
+
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+/// Key of a node of the octree
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VoxelKey {
+    pub level: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl VoxelKey {
+    pub fn new(level: i32, x: i32, y: i32, z: i32) -> Self {
+        Self { level, x, y, z }
+    }
+
+    /// Key of the root node, which covers the whole cube
+    pub fn root() -> Self {
+        Self::new(0, 0, 0, 0)
+    }
+
+    /// Returns the key of the child in the direction `dir`,
+    /// whose bits 0, 1 and 2 select the upper half in x, y and z
+    pub fn child(&self, dir: u8) -> Self {
+        Self::new(
+            self.level + 1,
+            (self.x << 1) | i32::from(dir & 1),
+            (self.y << 1) | i32::from((dir >> 1) & 1),
+            (self.z << 1) | i32::from((dir >> 2) & 1),
+        )
+    }
+}
+
+/// Entry of the hierarchy, giving where the points of a node are
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HierarchyEntry {
+    pub key: VoxelKey,
+    /// Absolute offset of the compressed chunk of the node
+    pub offset: u64,
+    /// Size of the compressed chunk of the node
+    pub byte_size: i32,
+    pub point_count: i32,
+}
+
+impl HierarchyEntry {
+    /// Size of an entry in a hierarchy page
+    pub const SIZE: u64 = 32;
+
+    pub fn read_from<R: Read>(mut src: R) -> std::io::Result<Self> {
+        let level = src.read_i32::<LittleEndian>()?;
+        let x = src.read_i32::<LittleEndian>()?;
+        let y = src.read_i32::<LittleEndian>()?;
+        let z = src.read_i32::<LittleEndian>()?;
+        Ok(Self {
+            key: VoxelKey::new(level, x, y, z),
+            offset: src.read_u64::<LittleEndian>()?,
+            byte_size: src.read_i32::<LittleEndian>()?,
+            point_count: src.read_i32::<LittleEndian>()?,
+        })
+    }
+
+    pub fn write_to<W: Write>(&self, mut dst: W) -> std::io::Result<()> {
+        dst.write_i32::<LittleEndian>(self.key.level)?;
+        dst.write_i32::<LittleEndian>(self.key.x)?;
+        dst.write_i32::<LittleEndian>(self.key.y)?;
+        dst.write_i32::<LittleEndian>(self.key.z)?;
+        dst.write_u64::<LittleEndian>(self.offset)?;
+        dst.write_i32::<LittleEndian>(self.byte_size)?;
+        dst.write_i32::<LittleEndian>(self.point_count)
+    }
+}
+
+/// Data of the COPC info VLR, which must be the first VLR of the file
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct CopcInfo {
+    /// Center of the root node
+    pub center: [f64; 3],
+    /// Half of the size of the sides of the root node
+    pub halfsize: f64,
+    /// Space between the points of the root node
+    pub spacing: f64,
+    /// Absolute offset of the root hierarchy page
+    pub root_hier_offset: u64,
+    /// Size of the root hierarchy page
+    pub root_hier_size: u64,
+    pub gpstime_minimum: f64,
+    pub gpstime_maximum: f64,
+}
+
+impl CopcInfo {
+    pub const RECORD_ID: u16 = 1;
+    pub const DESCRIPTION: &'static str = "COPC info";
+    /// Size of the data of the VLR, after its header
+    pub const DATA_SIZE: u16 = 160;
+
+    /// Reads the data of the VLR, without its header
+    pub fn read_from<R: Read>(mut src: R) -> std::io::Result<Self> {
+        let mut info = Self::default();
+        for coordinate in &mut info.center {
+            *coordinate = src.read_f64::<LittleEndian>()?;
+        }
+        info.halfsize = src.read_f64::<LittleEndian>()?;
+        info.spacing = src.read_f64::<LittleEndian>()?;
+        info.root_hier_offset = src.read_u64::<LittleEndian>()?;
+        info.root_hier_size = src.read_u64::<LittleEndian>()?;
+        info.gpstime_minimum = src.read_f64::<LittleEndian>()?;
+        info.gpstime_maximum = src.read_f64::<LittleEndian>()?;
+        let mut reserved = [0u8; 88];
+        src.read_exact(&mut reserved)?;
+        Ok(info)
+    }
+
+    /// Writes the data of the VLR, without its header
+    pub fn write_to<W: Write>(&self, mut dst: W) -> std::io::Result<()> {
+        for coordinate in self.center {
+            dst.write_f64::<LittleEndian>(coordinate)?;
+        }
+        dst.write_f64::<LittleEndian>(self.halfsize)?;
+        dst.write_f64::<LittleEndian>(self.spacing)?;
+        dst.write_u64::<LittleEndian>(self.root_hier_offset)?;
+        dst.write_u64::<LittleEndian>(self.root_hier_size)?;
+        dst.write_f64::<LittleEndian>(self.gpstime_minimum)?;
+        dst.write_f64::<LittleEndian>(self.gpstime_maximum)?;
+        dst.write_all(&[0u8; 88])
+    }
+
+    /// Writes the whole VLR, that is the 54 bytes of the LAS VLR header
+    /// followed by the data.
+    pub fn write_full_vlr_to<W: Write>(&self, mut dst: W) -> std::io::Result<()> {
+        dst.write_u16::<LittleEndian>(0)?;
+        dst.write_all(&padded::<16>(COPC_USER_ID))?;
+        dst.write_u16::<LittleEndian>(Self::RECORD_ID)?;
+        dst.write_u16::<LittleEndian>(Self::DATA_SIZE)?;
+        dst.write_all(&padded::<32>(Self::DESCRIPTION))?;
+        self.write_to(dst)
+    }
+}
+
+/// Writer of the point data and the hierarchy EVLR of a COPC file.
+///
+/// The points of each node, given by [`write_node`], are compressed as one chunk.
+/// [`finish`] writes the chunk table and the hierarchy EVLR (with one page holding
+/// all the nodes), and returns the [`CopcInfo`] with the hierarchy offsets and the
+/// GPS time range filled.
+///
+/// The caller writes the LAS 1.4 header and the VLRs: the first VLR is the
+/// [`CopcInfo`] (so its space can be reserved and written after [`finish`]),
+/// and the header gives the EVLR, whose offset is
+/// `root_hier_offset - 60`.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::copc::{CopcInfo, CopcWriter, VoxelKey};
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point6>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_variable_chunk_size().build();
+/// let info = CopcInfo {
+///     center: [50.0, 50.0, 50.0],
+///     halfsize: 50.0,
+///     spacing: 1.0,
+///     ..Default::default()
+/// };
+///
+/// let mut writer = CopcWriter::new(Cursor::new(Vec::<u8>::new()), vlr, info)?;
+/// writer.write_node(VoxelKey::root(), &[0u8; 30 * 10])?;
+/// writer.write_node(VoxelKey::root().child(3), &[0u8; 30 * 20])?;
+/// let (_output, info) = writer.finish()?;
+/// assert_eq!(info.root_hier_size, 2 * 32);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`write_node`]: Self::write_node
+/// [`finish`]: Self::finish
+pub struct CopcWriter<'a, W: Write + Seek + Send + 'a> {
+    compressor: LasZipCompressor<'a, W>,
+    info: CopcInfo,
+    /// Offset of the first chunk
+    data_start: u64,
+    entries: Vec<HierarchyEntry>,
+}
+
+impl<'a, W: Write + Seek + Send + 'a> CopcWriter<'a, W> {
+    /// Creates a writer of the point data at the current position of the `dst`
+    /// (the offset to the point data of the header).
+    ///
+    /// The `vlr` must use variable-size chunks of the point formats 6 to 8,
+    /// the hierarchy and GPS time fields of the `info` are set by [`Self::finish`].
+    pub fn new(mut dst: W, vlr: LazVlr, info: CopcInfo) -> crate::Result<Self> {
+        let point_size = vlr.items_size();
+        if vlr.compressor() != CompressorType::LayeredChunked
+            || !vlr.uses_variable_size_chunks()
+            || point_size < GPS_TIME_OFFSET as u64 + 8
+        {
+            return Err(invalid_input(
+                "COPC needs variable-size chunks of the point formats 6 to 8".to_string(),
+            )
+            .into());
+        }
+        let data_start = dst.stream_position()? + 8;
+        let mut compressor = LasZipCompressor::new(dst, vlr)?;
+        compressor.reserve_offset_to_chunk_table()?;
+        Ok(Self {
+            compressor,
+            info: CopcInfo {
+                gpstime_minimum: f64::MAX,
+                gpstime_maximum: f64::MIN,
+                ..info
+            },
+            data_start,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Compresses the `points` of the node `key` as one chunk
+    pub fn write_node(&mut self, key: VoxelKey, points: &[u8]) -> std::io::Result<()> {
+        let point_size = self.compressor.vlr().items_size() as usize;
+        let point_count = i32::try_from(points.len() / point_size)
+            .map_err(|_| invalid_input(format!("Too many points in the node {:?}", key)))?;
+        if point_count == 0 {
+            self.entries.push(HierarchyEntry {
+                key,
+                offset: 0,
+                byte_size: 0,
+                point_count: 0,
+            });
+            return Ok(());
+        }
+
+        let offset = self.data_start + self.compressor.chunk_table().byte_count();
+        self.compressor.compress_many(points)?;
+        self.compressor.finish_current_chunk()?;
+        let byte_count = self
+            .compressor
+            .chunk_table()
+            .as_ref()
+            .last()
+            .unwrap()
+            .byte_count;
+        let byte_size = i32::try_from(byte_count)
+            .map_err(|_| invalid_input(format!("The node {:?} is too big", key)))?;
+
+        for point in points.chunks_exact(point_size) {
+            let mut gps_time = &point[GPS_TIME_OFFSET..GPS_TIME_OFFSET + 8];
+            let gps_time = gps_time.read_f64::<LittleEndian>()?;
+            self.info.gpstime_minimum = self.info.gpstime_minimum.min(gps_time);
+            self.info.gpstime_maximum = self.info.gpstime_maximum.max(gps_time);
+        }
+        self.entries.push(HierarchyEntry {
+            key,
+            offset,
+            byte_size,
+            point_count,
+        });
+        Ok(())
+    }
+
+    /// Returns the entries of the nodes written so far
+    pub fn entries(&self) -> &[HierarchyEntry] {
+        &self.entries
+    }
+
+    /// Writes the chunk table and the hierarchy EVLR, returns the output,
+    /// positioned after the EVLR, and the COPC info to write in the first VLR.
+    pub fn finish(mut self) -> crate::Result<(W, CopcInfo)> {
+        self.compressor.done()?;
+        let mut dst = self.compressor.into_inner();
+
+        let evlr_start = dst.stream_position()?;
+        let data_size = self.entries.len() as u64 * HierarchyEntry::SIZE;
+        dst.write_u16::<LittleEndian>(0)?;
+        dst.write_all(&padded::<16>(COPC_USER_ID))?;
+        dst.write_u16::<LittleEndian>(1000)?;
+        dst.write_u64::<LittleEndian>(data_size)?;
+        dst.write_all(&padded::<32>("COPC hierarchy"))?;
+        for entry in &self.entries {
+            entry.write_to(&mut dst)?;
+        }
+        dst.flush()?;
+
+        let mut info = self.info;
+        info.root_hier_offset = evlr_start + EVLR_HEADER_SIZE;
+        info.root_hier_size = data_size;
+        if info.gpstime_minimum > info.gpstime_maximum {
+            // No points
+            info.gpstime_minimum = 0.0;
+            info.gpstime_maximum = 0.0;
+        }
+        Ok((dst, info))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, SeekFrom};
+
+    use super::*;
+    use crate::{LasZipDecompressor, LazItemRecordBuilder, LazVlrBuilder};
+
+    fn points(num_points: usize, first_gps_time: f64) -> Vec<u8> {
+        let mut points = crate::test_utils::points(num_points, 30);
+        for (i, point) in points.chunks_exact_mut(30).enumerate() {
+            point[14] = 0x11;
+            point[GPS_TIME_OFFSET..GPS_TIME_OFFSET + 8]
+                .copy_from_slice(&(first_gps_time + i as f64).to_le_bytes());
+        }
+        points
+    }
+
+    #[test]
+    fn test_copc_writer() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point6>(0);
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        let nodes = [
+            (VoxelKey::root(), points(40, 100.0)),
+            (VoxelKey::root().child(0), Vec::new()),
+            (VoxelKey::root().child(5), points(25, 10.0)),
+        ];
+
+        let mut output = Cursor::new(vec![0u8; 100]);
+        output.set_position(100);
+        let mut writer = CopcWriter::new(output, vlr.clone(), CopcInfo::default()).unwrap();
+        for (key, points) in &nodes {
+            writer.write_node(*key, points).unwrap();
+        }
+        let (mut output, info) = writer.finish().unwrap();
+        assert_eq!(info.gpstime_minimum, 10.0);
+        assert_eq!(info.gpstime_maximum, 139.0);
+        assert_eq!(info.root_hier_size, 3 * HierarchyEntry::SIZE);
+
+        output.seek(SeekFrom::Start(info.root_hier_offset)).unwrap();
+        let entries = (0..3)
+            .map(|_| HierarchyEntry::read_from(&mut output).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entries[1].point_count, 0);
+
+        // The nodes can be decompressed from their byte range only
+        let data = output.into_inner();
+        for ((key, points), entry) in nodes.iter().zip(&entries) {
+            assert_eq!(*key, entry.key);
+            assert_eq!(entry.point_count as usize * 30, points.len());
+            if entry.point_count == 0 {
+                continue;
+            }
+            let start = entry.offset as usize;
+            let chunk = &data[start..start + entry.byte_size as usize];
+            let mut decompressor =
+                crate::laszip::details::record_decompressor_from_vlr(&vlr, Cursor::new(chunk))
+                    .unwrap();
+            let mut decompressed = vec![0u8; points.len()];
+            decompressor.decompress_many(&mut decompressed).unwrap();
+            assert_eq!(&decompressed, points);
+        }
+
+        let mut source = Cursor::new(&data);
+        source.set_position(100);
+        let mut decompressor = LasZipDecompressor::new(source, vlr).unwrap();
+        assert_eq!(decompressor.chunk_table().unwrap().len(), 2);
+        let mut all = vec![0u8; 65 * 30];
+        decompressor.decompress_many(&mut all).unwrap();
+        assert_eq!(&all[..40 * 30], nodes[0].1.as_slice());
+    }
+
+    #[test]
+    fn test_copc_info_round_trip() {
+        let info = CopcInfo {
+            center: [1.0, 2.0, 3.0],
+            halfsize: 4.0,
+            spacing: 0.5,
+            root_hier_offset: 1000,
+            root_hier_size: 64,
+            gpstime_minimum: -1.0,
+            gpstime_maximum: 1.0,
+        };
+        let mut vlr = Vec::new();
+        info.write_full_vlr_to(&mut vlr).unwrap();
+        assert_eq!(vlr.len(), 54 + 160);
+        assert_eq!(CopcInfo::read_from(&vlr[54..]).unwrap(), info);
+
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point3>(0);
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        assert!(CopcWriter::new(Cursor::new(Vec::new()), vlr, info).is_err());
+    }
+}
@@ -1,11 +1,40 @@
 use std::io::{Read, Seek, SeekFrom};
 
+use byteorder::{LittleEndian, ReadBytesExt};
+
 use crate::errors::LasZipError::MissingChunkTable;
-use crate::record::RecordDecompressor;
+use crate::errors::LasZipWarning;
+use crate::record::{RecordDecompressor, RecordDecompressorSnapshot};
 use crate::LasZipError;
 
-use super::chunk_table::ChunkTable;
-use super::{details, CompressorType, LazVlr};
+use super::chunk_table::{ChunkIndex, ChunkTable};
+use super::filter::CompiledPointFilter;
+use super::{
+    details, diagnostics, point_buffer, ChunkContexts, CompressorType, DecompressionSelection,
+    LazOpenOptions, LazVlr, PointBufferMut, PointFilter,
+};
+
+/// Decompression state saved in the middle of a chunk
+struct ChunkSnapshot<R> {
+    /// Index of the next point to be decompressed, relative to the start of the chunk
+    point_in_chunk: u64,
+    /// Position of the source when the snapshot was taken
+    stream_pos: u64,
+    state: RecordDecompressorSnapshot<R>,
+}
+
+/// Logical position of a [`LasZipDecompressor`] in the compressed data.
+///
+/// It can be saved (see [`LasZipDecompressor::position`]) so that a job that was
+/// interrupted can be resumed with a new source using [`LasZipDecompressor::resume`],
+/// without decompressing the chunks before the position again.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct DecompressionPosition {
+    /// Index of the chunk of the next point to be decompressed
+    pub chunk_index: u64,
+    /// Number of points of the chunk that were already decompressed
+    pub points_in_chunk: u64,
+}
 
 /// LasZip decompressor that decompresses points.
 ///
@@ -15,47 +44,477 @@ pub struct LasZipDecompressor<'a, R: Read + Seek + 'a> {
     record_decompressor: Box<dyn RecordDecompressor<R> + Send + 'a>,
     data_start: u64,
     chunk_table: Option<ChunkTable>,
+    /// Whether reading the chunk table is deferred to the first random access,
+    /// see [`Self::new_lazy`]
+    chunk_table_deferred: bool,
+    /// Where the chunks of the chunk table start, built at the first seek
+    chunk_index: Option<ChunkIndex>,
     current_chunk: usize,
     chunk_points_read: u64,
     num_points_in_chunk: u64,
+    /// Total number of points, when given by the caller
+    num_points: Option<u64>,
+    /// Number of points between two snapshots, 0 means no snapshots
+    snapshot_interval: u64,
+    /// Snapshots of the chunk `snapshots_chunk`, sorted by `point_in_chunk`,
+    /// the snapshots of the other chunks are dropped to bound the memory usage
+    snapshots: Vec<ChunkSnapshot<R>>,
+    snapshots_chunk: usize,
+    warn: Box<dyn FnMut(LasZipWarning) + Send + 'a>,
+    /// Position before the point in `peeked_point` was decompressed,
+    /// if it was peeked and not consumed yet
+    peeked_at: Option<DecompressionPosition>,
+    peeked_point: Vec<u8>,
+    /// Contexts used by the points of each chunk, when diagnostics are enabled
+    chunk_contexts: Option<Vec<ChunkContexts>>,
+    #[cfg(feature = "timing")]
+    chunk_timer: crate::timing::ChunkTimer,
+    /// State of the models at the end of each chunk, when enabled
+    #[cfg(feature = "model-stats")]
+    model_stats: Option<Vec<crate::model_stats::ChunkModelStats>>,
+    /// Maximum number of bytes allocated for the chunk table and compressed chunks,
+    /// see [`Self::new_with_memory_limit`]
+    memory_limit: Option<usize>,
+    /// Fields decompressed, see [`Self::set_selection`]
+    selection: DecompressionSelection,
+    /// Whether the issues found when reading a deferred chunk table are errors,
+    /// see [`LazOpenOptions::with_strict`]
+    strict: bool,
+    /// Maximum number of chunks of the chunk table, see [`LazOpenOptions::with_max_num_chunks`]
+    max_num_chunks: Option<usize>,
 }
 
 impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
     /// Creates a new instance from a data source of compressed points
     /// and the LazVlr describing the compressed data
-    pub fn new(mut source: R, vlr: LazVlr) -> crate::Result<Self> {
+    pub fn new(source: R, vlr: LazVlr) -> crate::Result<Self> {
+        Self::with_options(source, vlr, LazOpenOptions::new())
+    }
+
+    /// Creates a new instance, like [`Self::new`], that reports
+    /// the non-fatal issues it finds to the `warn` callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (_, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// let decompressor = laz::LasZipDecompressor::new_with_warnings(laz_file, vlr, |warning| {
+    ///     eprintln!("Warning: {}", warning);
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_warnings<F>(source: R, vlr: LazVlr, warn: F) -> crate::Result<Self>
+    where
+        F: FnMut(LasZipWarning) + Send + 'a,
+    {
+        Self::with_options(source, vlr, LazOpenOptions::new().with_warnings(warn))
+    }
+
+    /// Creates a new instance, like [`Self::new`], that only reads the chunk table
+    /// when it is first needed, by a random access (e.g. [`Self::seek`]),
+    /// or by [`Self::load_chunk_table`].
+    ///
+    /// This saves the seek to the chunk table (and, for remote sources, a request)
+    /// when the points are only read sequentially.
+    ///
+    /// The chunk table is still read at creation for the variable-size chunks
+    /// of point formats 0 to 5, as the number of points of each chunk is needed
+    /// to decompress them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let mut decompressor = laz::LasZipDecompressor::new_lazy(laz_file, vlr.clone())?;
+    /// assert!(decompressor.chunk_table().is_none());
+    ///
+    /// let mut point = vec![0u8; vlr.items_size() as usize];
+    /// decompressor.decompress_one(&mut point)?;
+    /// // Seeking reads the chunk table
+    /// decompressor.seek(5)?;
+    /// assert!(decompressor.chunk_table().is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_lazy(source: R, vlr: LazVlr) -> crate::Result<Self> {
+        Self::with_options(
+            source,
+            vlr,
+            LazOpenOptions::new().with_lazy_chunk_table(true),
+        )
+    }
+
+    /// Creates a new instance that uses a custom [`RecordDecompressor`]
+    /// to decompress the points of each chunk.
+    ///
+    /// The `record_decompressor` must be ready to decompress the points described
+    /// by the `vlr`, and its stream must be positioned at the start of the
+    /// compressed data, that is, at the offset to the chunk table.
+    ///
+    /// See the [`record`](crate::record) module for what the decompressor relies on.
+    pub fn with_record_decompressor(
+        record_decompressor: Box<dyn RecordDecompressor<R> + Send + 'a>,
+        vlr: LazVlr,
+    ) -> crate::Result<Self> {
+        Self::check_compressor_type(&vlr)?;
+        Self::from_parts(record_decompressor, vlr, LazOpenOptions::new())
+    }
+
+    /// Creates a new instance, like [`Self::new`], that does not allocate
+    /// more than `memory_limit` bytes for the chunk table and the compressed data
+    /// of the chunk being decompressed.
+    ///
+    /// The sizes of these are read from the source, so this protects against
+    /// corrupted or malicious data describing huge tables or chunks:
+    /// [`LasZipError::MemoryLimitExceeded`] is returned (wrapped in the io error,
+    /// for functions that return one) instead of making the allocation.
+    ///
+    /// The fixed-size state of the decompressor, the snapshots and
+    /// the output buffers given by the caller are not counted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (_, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// let result = laz::LasZipDecompressor::new_with_memory_limit(laz_file, vlr, 0);
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(laz::LasZipError::MemoryLimitExceeded { limit: 0, .. })
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_memory_limit(
+        source: R,
+        vlr: LazVlr,
+        memory_limit: usize,
+    ) -> crate::Result<Self> {
+        Self::with_options(
+            source,
+            vlr,
+            LazOpenOptions::new().with_memory_limit(memory_limit),
+        )
+    }
+
+    /// Creates a new instance configured by the `options`, see [`LazOpenOptions`].
+    pub fn with_options(
+        source: R,
+        vlr: LazVlr,
+        options: LazOpenOptions<'a>,
+    ) -> crate::Result<Self> {
+        Self::check_compressor_type(&vlr)?;
+        let record_decompressor = details::record_decompressor_from_vlr(&vlr, source)?;
+        Self::from_parts(record_decompressor, vlr, options)
+    }
+
+    fn check_compressor_type(vlr: &LazVlr) -> crate::Result<()> {
         if vlr.compressor != CompressorType::PointWiseChunked
             && vlr.compressor != CompressorType::LayeredChunked
         {
             return Err(LasZipError::UnsupportedCompressorType(vlr.compressor));
         }
+        Ok(())
+    }
 
-        let chunk_table = match ChunkTable::read_from(&mut source, &vlr) {
-            Ok(chunk_table) => Some(chunk_table),
-            Err(e) => {
-                if vlr.uses_variable_size_chunks()
-                    && vlr.compressor != CompressorType::LayeredChunked
-                {
-                    return Err(e);
-                } else {
-                    None
+    fn from_parts(
+        mut record_decompressor: Box<dyn RecordDecompressor<R> + Send + 'a>,
+        vlr: LazVlr,
+        options: LazOpenOptions<'a>,
+    ) -> crate::Result<Self> {
+        let mut warn = options.warn.unwrap_or_else(|| Box::new(|_| {}));
+        let (strict, memory_limit) = (options.strict, options.memory_limit);
+        // In strict mode, the first issue found is the error
+        let mut first_issue = None;
+        let mut report = |warning| {
+            if strict {
+                first_issue.get_or_insert(warning);
+            } else {
+                warn(warning);
+            }
+        };
+
+        let source = record_decompressor.get_mut();
+        let start = source.stream_position()?;
+        let chunk_table_required =
+            vlr.uses_variable_size_chunks() && vlr.compressor != CompressorType::LayeredChunked;
+        let chunk_table_deferred = options.lazy_chunk_table && !chunk_table_required;
+        let chunk_table = if chunk_table_deferred {
+            // Skip the offset to the chunk table, without seeking
+            source.read_i64::<LittleEndian>()?;
+            None
+        } else {
+            match ChunkTable::read_from_with_warnings(&mut *source, &vlr, &mut report, memory_limit)
+            {
+                Ok(chunk_table) => {
+                    chunk_table.check_num_chunks(options.max_num_chunks)?;
+                    Some(chunk_table)
+                }
+                Err(e) => {
+                    if chunk_table_required
+                        || strict
+                        || matches!(e, LasZipError::MemoryLimitExceeded { .. })
+                    {
+                        return Err(e);
+                    } else {
+                        report(LasZipWarning::ChunkTableUnreadable(e));
+                        // Skip the offset to the chunk table
+                        source.seek(SeekFrom::Start(start + 8))?;
+                        None
+                    }
                 }
             }
         };
         let data_start = source.seek(SeekFrom::Current(0))?;
 
-        let record_decompressor =
-            details::record_decompressor_from_laz_items(&vlr.items(), source)?;
+        for item in vlr.items() {
+            if item.version() == 1 {
+                report(LasZipWarning::DeprecatedItemVersion(
+                    item.item_type(),
+                    item.version(),
+                ));
+            }
+        }
+        if let Some(issue) = first_issue {
+            return Err(LasZipError::StrictModeWarning(Box::new(issue)));
+        }
 
-        Ok(Self {
+        let mut decompressor = Self {
             vlr,
             record_decompressor,
             data_start,
             chunk_table,
+            chunk_table_deferred,
+            chunk_index: None,
             current_chunk: 0,
             chunk_points_read: 0,
             num_points_in_chunk: 1,
-        })
+            num_points: None,
+            snapshot_interval: 0,
+            snapshots: Vec::new(),
+            snapshots_chunk: 0,
+            warn,
+            peeked_at: None,
+            peeked_point: Vec::new(),
+            chunk_contexts: None,
+            #[cfg(feature = "timing")]
+            chunk_timer: Default::default(),
+            #[cfg(feature = "model-stats")]
+            model_stats: None,
+            memory_limit,
+            selection: DecompressionSelection::all(),
+            strict,
+            max_num_chunks: options.max_num_chunks,
+        };
+        decompressor.update_record_memory_limit();
+        decompressor.set_snapshot_interval(options.snapshot_interval);
+        decompressor.set_context_diagnostics(options.context_diagnostics);
+        decompressor.set_selection(options.selection);
+        decompressor.num_points = options.num_points;
+        Ok(decompressor)
+    }
+
+    /// Creates a new instance, like [`Self::new`], that continues the decompression
+    /// at the `position` returned by [`Self::position`].
+    ///
+    /// The `source` must be positioned at the start of the compressed data,
+    /// and the chunk table must be readable, as it gives where the chunk starts.
+    /// Only the points of the chunk before the position are decompressed again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # let offset_to_points = SeekFrom::Start(u64::from(header.offset_to_points));
+    /// # laz_file.seek(offset_to_points)?;
+    /// let mut decompressor = laz::LasZipDecompressor::new(laz_file, vlr.clone())?;
+    /// let mut points = vec![0u8; 10 * vlr.items_size() as usize];
+    /// decompressor.decompress_many(&mut points)?;
+    /// let position = decompressor.position();
+    ///
+    /// // Later, maybe after the connection was lost
+    /// let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # laz_file.seek(offset_to_points)?;
+    /// let mut decompressor = laz::LasZipDecompressor::resume(laz_file, vlr, position)?;
+    /// decompressor.decompress_many(&mut points)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resume(source: R, vlr: LazVlr, position: DecompressionPosition) -> crate::Result<Self> {
+        let mut decompressor = Self::new(source, vlr)?;
+        let chunk_table = decompressor.chunk_table.as_ref().ok_or(MissingChunkTable)?;
+        let chunk_index = details::usize_from(position.chunk_index)?;
+        if chunk_index == chunk_table.len() && position.points_in_chunk == 0 {
+            // All the points were decompressed, as if the last chunk was read
+            if let Some(last_chunk) = chunk_index.checked_sub(1) {
+                decompressor.current_chunk = last_chunk;
+                decompressor.num_points_in_chunk = decompressor.num_points_in_current_chunk();
+            } else {
+                decompressor.num_points_in_chunk = 0;
+            }
+            decompressor.chunk_points_read = decompressor.num_points_in_chunk;
+            decompressor
+                .record_decompressor
+                .get_mut()
+                .seek(SeekFrom::Start(
+                    decompressor.data_start
+                        + chunk_table
+                            .as_ref()
+                            .iter()
+                            .map(|e| e.byte_count)
+                            .sum::<u64>(),
+                ))?;
+            return Ok(decompressor);
+        }
+        let point_count = chunk_table
+            .as_ref()
+            .get(chunk_index)
+            .map_or(0, |entry| entry.point_count);
+        if position.points_in_chunk >= point_count {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Position {:?} is outside of the data", position),
+            )
+            .into());
+        }
+        let start_of_chunk = decompressor.data_start
+            + chunk_table.as_ref()[..chunk_index]
+                .iter()
+                .map(|e| e.byte_count)
+                .sum::<u64>();
+
+        decompressor.current_chunk = chunk_index;
+        let resumed_at =
+            decompressor.rewind_current_chunk(start_of_chunk, position.points_in_chunk)?;
+        let mut tmp_out = vec![0u8; decompressor.record_decompressor.record_size()];
+        for _ in resumed_at..position.points_in_chunk {
+            decompressor.decompress_one(&mut tmp_out)?;
+        }
+        Ok(decompressor)
+    }
+
+    /// Enables, or disables and discards, the collection of the contexts (scanner channels)
+    /// used by the points of each chunk, for the layered point formats 6 to 10.
+    ///
+    /// Returns whether the diagnostics are enabled, as other point formats
+    /// do not use contexts.
+    ///
+    /// The statistics of a chunk are restarted each time it is decompressed from its start,
+    /// the context of each point is given by [`context_of`](super::context_of).
+    pub fn set_context_diagnostics(&mut self, enabled: bool) -> bool {
+        if enabled && diagnostics::uses_contexts(self.vlr.items()) {
+            self.chunk_contexts.get_or_insert_with(Vec::new);
+            true
+        } else {
+            self.chunk_contexts = None;
+            false
+        }
+    }
+
+    /// Returns the contexts used by the points of each chunk decompressed so far,
+    /// see [`Self::set_context_diagnostics`].
+    ///
+    /// Chunks that were skipped by seeking have no points.
+    pub fn context_diagnostics(&self) -> Option<&[ChunkContexts]> {
+        self.chunk_contexts.as_deref()
+    }
+
+    /// Enables, or disables and discards, the collection of the state of the entropy models
+    /// once the last point of each chunk is decompressed, see [`crate::model_stats`].
+    ///
+    /// A chunk decompressed again (after seeking) replaces its previous stats.
+    #[cfg(feature = "model-stats")]
+    pub fn set_model_stats(&mut self, enabled: bool) {
+        if enabled {
+            self.model_stats.get_or_insert_with(Vec::new);
+        } else {
+            self.model_stats = None;
+        }
+    }
+
+    /// Returns the state of the models at the end of each chunk decompressed so far,
+    /// in the order they were decompressed, see [`Self::set_model_stats`].
+    #[cfg(feature = "model-stats")]
+    pub fn model_stats(&self) -> Option<&[crate::model_stats::ChunkModelStats]> {
+        self.model_stats.as_deref()
+    }
+
+    /// Makes the decompressor save a snapshot of its state every `interval` points
+    /// of a chunk, a value of 0 disables snapshots and discards existing ones.
+    ///
+    /// Snapshots are taken while points are decompressed, and are used by
+    /// [`seek`] to resume close to the requested point instead of decompressing
+    /// the chunk from its beginning, which is interesting for large chunk sizes.
+    ///
+    /// # Note
+    ///
+    /// Only point-wise chunked data (LAZ item versions 1 and 2) support snapshots.
+    /// A snapshot contains the entropy models of all the fields, which is about 2MiB
+    /// for point format 0, so `interval` should be chosen accordingly.
+    /// Only the snapshots of the chunk being decompressed are kept, they are dropped
+    /// when snapshots of another chunk are taken.
+    ///
+    /// [`seek`]: Self::seek
+    pub fn set_snapshot_interval(&mut self, interval: u64) {
+        self.snapshot_interval = interval;
+        if interval == 0 {
+            self.snapshots.clear();
+        }
+    }
+
+    /// Only decompresses the fields in the `selection`, the layers of the other fields
+    /// are neither read nor decoded, see [`DecompressionSelection`].
+    ///
+    /// The selection applies right away if no point of the current chunk was decompressed
+    /// yet (e.g. after the creation of the decompressor or seeking to the first point
+    /// of a chunk), from the next chunk otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::Cursor;
+    /// use laz::laszip::DecompressionSelection;
+    ///
+    /// let vlr = laz::LazVlrBuilder::default().with_point_format(7, 0)?.build();
+    /// let mut points = vec![0u8; 36 * 2];
+    /// points[36 + 8..36 + 12].copy_from_slice(&15i32.to_le_bytes()); // z
+    /// points[36 + 30..36 + 32].copy_from_slice(&500u16.to_le_bytes()); // red
+    /// let mut compressed = Cursor::new(Vec::<u8>::new());
+    /// laz::compress_buffer(&mut compressed, &points, vlr.clone())?;
+    /// compressed.set_position(0);
+    ///
+    /// let mut decompressor = laz::LasZipDecompressor::new(compressed, vlr)?;
+    /// decompressor.set_selection(DecompressionSelection::Z);
+    /// let mut decompressed = vec![0u8; 36 * 2];
+    /// decompressor.decompress_many(&mut decompressed)?;
+    /// assert_eq!(&decompressed[36 + 8..36 + 12], &15i32.to_le_bytes());
+    /// // The colors are the ones of the first point of the chunk
+    /// assert_eq!(&decompressed[36 + 30..36 + 32], &[0, 0]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_selection(&mut self, selection: DecompressionSelection) {
+        self.selection = selection;
+        self.record_decompressor.set_selection(selection);
+        if self.chunk_points_read == 0 {
+            self.reset_for_new_chunk();
+        }
     }
 
     /// Decompress the next point and write the uncompressed data to the out buffer.
@@ -64,7 +523,25 @@ impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
     /// - The data is written in the buffer exactly as it would have been in a LAS File
     ///   in Little Endian order,
     pub fn decompress_one(&mut self, mut out: &mut [u8]) -> std::io::Result<()> {
+        if self.peeked_at.take().is_some() {
+            out[..self.peeked_point.len()].copy_from_slice(&self.peeked_point);
+            return Ok(());
+        }
+
         if self.chunk_points_read == self.num_points_in_chunk {
+            if self
+                .chunk_table
+                .as_ref()
+                .is_some_and(|chunk_table| self.current_chunk + 1 >= chunk_table.len())
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "All the chunks were decompressed",
+                ));
+            }
+            #[cfg(feature = "timing")]
+            self.chunk_timer
+                .finish_chunk(self.record_decompressor.field_times());
             self.reset_for_new_chunk();
             self.current_chunk += 1;
         }
@@ -72,26 +549,73 @@ impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
         self.record_decompressor.decompress_next(&mut out)?;
         self.chunk_points_read += 1;
 
-        if self.chunk_points_read == 1 {
-            if self.vlr.uses_variable_size_chunks() {
-                self.num_points_in_chunk = match (&self.chunk_table, self.vlr.compressor) {
-                    (Some(chunk_table), _) => chunk_table[self.current_chunk].point_count,
-                    (None, CompressorType::LayeredChunked) => {
-                        self.record_decompressor.record_count()
-                    }
-                    (None, _) => {
-                        // This should not be possible, the `new` method should ensure
-                        // we have the chunk table if we need one
-                        panic!("Variable-size chunks, but no chunk table");
-                    }
-                }
-            } else {
-                self.num_points_in_chunk = self.vlr.chunk_size().into();
+        if let Some(chunk_contexts) = self.chunk_contexts.as_mut() {
+            if chunk_contexts.len() <= self.current_chunk {
+                chunk_contexts.resize(self.current_chunk + 1, ChunkContexts::default());
+            }
+            let contexts = &mut chunk_contexts[self.current_chunk];
+            if self.chunk_points_read == 1 {
+                *contexts = ChunkContexts::default();
             }
+            contexts.add_point(diagnostics::context_of(out));
+        }
+
+        if self.chunk_points_read == 1 {
+            self.num_points_in_chunk = self.num_points_in_current_chunk();
+        }
+
+        #[cfg(feature = "model-stats")]
+        if self.chunk_points_read == self.num_points_in_chunk {
+            self.collect_model_stats();
+        }
+
+        if self.snapshot_interval != 0
+            && self
+                .chunk_points_read
+                .is_multiple_of(self.snapshot_interval)
+            && self.chunk_points_read < self.num_points_in_chunk
+        {
+            self.take_snapshot()?;
         }
         Ok(())
     }
 
+    /// Returns the next point without consuming it, the next call to
+    /// [`Self::decompress_one`] (or any other method decompressing points)
+    /// will return the same point.
+    ///
+    /// The point is decompressed on the first call, later calls return it again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let mut decompressor = laz::LasZipDecompressor::new(laz_file, vlr.clone())?;
+    /// let peeked = decompressor.peek()?.to_vec();
+    /// let mut point = vec![0u8; vlr.items_size() as usize];
+    /// decompressor.decompress_one(&mut point)?;
+    /// assert_eq!(point, peeked);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn peek(&mut self) -> std::io::Result<&[u8]> {
+        if self.peeked_at.is_none() {
+            let position = self.position();
+            let mut point = std::mem::take(&mut self.peeked_point);
+            point.resize(self.vlr.items_size() as usize, 0u8);
+            let result = self.decompress_one(&mut point);
+            self.peeked_point = point;
+            result?;
+            self.peeked_at = Some(position);
+        }
+        Ok(&self.peeked_point)
+    }
+
     /// Decompress as many points as the `out` slice can hold
     ///
     /// # Note
@@ -99,12 +623,234 @@ impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
     /// If the `out` slice contains more space than there are points
     /// the function will still decompress and thus and error will occur
     pub fn decompress_many(&mut self, out: &mut [u8]) -> std::io::Result<()> {
-        for point in out.chunks_exact_mut(self.vlr.items_size() as usize) {
-            self.decompress_one(point)?;
+        let point_size = self.vlr.items_size() as usize;
+        let num_bytes = out.len() / point_size * point_size;
+        let mut points = &mut out[..num_bytes];
+        while !points.is_empty() {
+            let points_left_in_chunk = self.num_points_in_chunk - self.chunk_points_read;
+            let num_points = if self.chunk_points_read == 0
+                || points_left_in_chunk == 0
+                || self.peeked_at.is_some()
+                || self.chunk_contexts.is_some()
+                || self.snapshot_interval != 0
+            {
+                // Starting a chunk, or something has to be done for each point
+                self.decompress_one(&mut points[..point_size])?;
+                1
+            } else {
+                // The rest of the points of the chunk are decompressed in one call
+                let num_points = points_left_in_chunk.min((points.len() / point_size) as u64);
+                self.record_decompressor
+                    .decompress_many(&mut points[..num_points as usize * point_size])?;
+                self.chunk_points_read += num_points;
+                #[cfg(feature = "model-stats")]
+                if self.chunk_points_read == self.num_points_in_chunk {
+                    self.collect_model_stats();
+                }
+                num_points as usize
+            };
+            points = &mut std::mem::take(&mut points)[num_points * point_size..];
         }
         Ok(())
     }
 
+    /// Decompresses as many points as the buffer can hold
+    ///
+    /// Unlike [`decompress_many`], an error is returned if the buffer does
+    /// not contain points of the size described by the vlr.
+    ///
+    /// [`decompress_many`]: Self::decompress_many
+    pub fn decompress_points(&mut self, mut points: PointBufferMut) -> crate::Result<()> {
+        point_buffer::check_point_size(points.point_size(), &self.vlr)?;
+        self.decompress_many(points.as_bytes_mut())?;
+        Ok(())
+    }
+
+    /// Decompresses the next `num_points` points and appends the ones
+    /// that pass the `filter` to `out`.
+    ///
+    /// Returns the number of points appended.
+    ///
+    /// For point formats 6 to 10, whole chunks are first decompressed with only
+    /// the fields the filter reads (see [`DecompressionSelection`]): the other layers of
+    /// the chunks without any kept point are neither read nor decoded, and the other chunks
+    /// are only decompressed up to their last kept point, with the selection of the decompressor.
+    ///
+    /// # Note
+    ///
+    /// The entropy models of a chunk depend on all of its points, so the points
+    /// that are filtered out before a kept point still have to be decompressed,
+    /// as are all the points of formats 0 to 5.
+    pub fn decompress_filtered(
+        &mut self,
+        num_points: u64,
+        filter: &PointFilter,
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<u64> {
+        let point_size = self.vlr.items_size() as usize;
+        let filter = filter.compile(self.vlr.items());
+        let mut num_kept = 0u64;
+        let mut points_left = num_points;
+        while points_left > 0 {
+            if self.whole_chunk_left(points_left, &filter) {
+                let (num_points_in_chunk, num_kept_in_chunk) =
+                    self.decompress_filtered_chunk(&filter, out)?;
+                num_kept += num_kept_in_chunk;
+                points_left -= num_points_in_chunk;
+                continue;
+            }
+            let start = out.len();
+            out.resize(start + point_size, 0u8);
+            self.decompress_one(&mut out[start..])?;
+            if filter.matches(&out[start..]) {
+                num_kept += 1;
+            } else {
+                out.truncate(start);
+            }
+            points_left -= 1;
+        }
+        Ok(num_kept)
+    }
+
+    /// Returns whether the next point starts a chunk whose points are all
+    /// in the `points_left`, and whether the `filter` can first be applied
+    /// with only the fields it reads.
+    fn whole_chunk_left(&self, points_left: u64, filter: &CompiledPointFilter) -> bool {
+        let at_start_of_chunk =
+            self.chunk_points_read == 0 || self.chunk_points_read == self.num_points_in_chunk;
+        filter.selection().is_some()
+            && at_start_of_chunk
+            && self.vlr.compressor == CompressorType::LayeredChunked
+            && self.peeked_at.is_none()
+            && self.chunk_contexts.is_none()
+            && self.snapshot_interval == 0
+            && self
+                .points_left_in_chunk()
+                .is_some_and(|num_points_in_chunk| num_points_in_chunk <= points_left)
+    }
+
+    /// Decompresses the chunk that starts at the next point for [`Self::decompress_filtered`],
+    /// see [`Self::whole_chunk_left`].
+    ///
+    /// Returns the number of points of the chunk and the number of points appended to `out`.
+    fn decompress_filtered_chunk(
+        &mut self,
+        filter: &CompiledPointFilter,
+        out: &mut Vec<u8>,
+    ) -> std::io::Result<(u64, u64)> {
+        let point_size = self.vlr.items_size() as usize;
+        let start_of_chunk = self.record_decompressor.get_mut().stream_position()?;
+        if self.chunk_points_read != 0 {
+            self.current_chunk += 1;
+        }
+
+        // 1. Find the points that are kept, only decompressing the fields the filter reads
+        let filter_selection = filter.selection().unwrap_or_default();
+        self.record_decompressor.set_selection(filter_selection);
+        self.reset_for_new_chunk();
+        let mut point = vec![0u8; point_size];
+        let mut kept = Vec::new();
+        let mut point_in_chunk = 0u64;
+        let result = loop {
+            if let Err(e) = self.record_decompressor.decompress_next(&mut point) {
+                break Err(e);
+            }
+            if filter.matches(&point) {
+                kept.push(point_in_chunk);
+            }
+            point_in_chunk += 1;
+            // Layered chunks store their number of points
+            if point_in_chunk >= self.record_decompressor.record_count() {
+                break Ok(());
+            }
+        };
+        self.record_decompressor.set_selection(self.selection);
+        result?;
+        self.num_points_in_chunk = self.num_points_in_current_chunk();
+
+        // 2. Decompress the points up to the last kept one, with all the selected fields
+        if let Some(&last_kept) = kept.last() {
+            self.record_decompressor
+                .get_mut()
+                .seek(SeekFrom::Start(start_of_chunk))?;
+            self.reset_for_new_chunk();
+            let mut kept = kept.iter().peekable();
+            for i in 0..=last_kept {
+                if kept.next_if_eq(&&i).is_some() {
+                    let start = out.len();
+                    out.resize(start + point_size, 0u8);
+                    self.decompress_one(&mut out[start..])?;
+                } else {
+                    self.decompress_one(&mut point)?;
+                }
+            }
+        }
+        // The compressed data of the chunk was read entirely with its first point
+        self.chunk_points_read = self.num_points_in_chunk;
+        Ok((point_in_chunk, kept.len() as u64))
+    }
+
+    /// Decompresses the next `num_points` points keeping only one point every `n` points
+    /// (starting with the next point), the kept points are appended to `out`.
+    ///
+    /// Returns the number of points appended.
+    ///
+    /// When the chunk table is available, chunks that do not contain any
+    /// of the kept points are not decompressed at all, which makes decimating
+    /// with an `n` greater than the chunk size much faster.
+    /// Otherwise, all the points have to be decompressed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    pub fn decompress_every_nth(
+        &mut self,
+        n: u64,
+        num_points: u64,
+        out: &mut Vec<u8>,
+    ) -> crate::Result<u64> {
+        assert!(n > 0, "n must be greater than 0");
+        let point_size = self.vlr.items_size() as usize;
+        let mut skipped = vec![0u8; point_size];
+
+        self.load_chunk_table()?;
+        let start = match self.current_point_index() {
+            Some(start) => start,
+            None => {
+                let mut num_kept = 0u64;
+                for i in 0..num_points {
+                    if i.is_multiple_of(n) {
+                        let pos = out.len();
+                        out.resize(pos + point_size, 0u8);
+                        self.decompress_one(&mut out[pos..])?;
+                        num_kept += 1;
+                    } else {
+                        self.decompress_one(&mut skipped)?;
+                    }
+                }
+                return Ok(num_kept);
+            }
+        };
+        let end = start + num_points;
+
+        let mut num_kept = 0u64;
+        let mut current = start;
+        let mut target = start;
+        while target < end {
+            self.skip_to(current, target, &mut skipped)?;
+            let pos = out.len();
+            out.resize(pos + point_size, 0u8);
+            self.decompress_one(&mut out[pos..])?;
+            num_kept += 1;
+            current = target + 1;
+            target += n;
+        }
+        if current < end {
+            self.skip_to(current, end, &mut skipped)?;
+        }
+        Ok(num_kept)
+    }
+
     /// Seeks to the point designed by the index
     ///
     /// # Important
@@ -114,32 +860,20 @@ impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
     /// and then we have to decompress points in the chunk until we reach the
     /// one we want.
     pub fn seek(&mut self, point_idx: u64) -> crate::Result<()> {
+        self.peeked_at = None;
+        self.load_chunk_table()?;
         let chunk_table = self.chunk_table.as_ref().ok_or(MissingChunkTable)?;
+        let chunk_index = self
+            .chunk_index
+            .get_or_insert_with(|| ChunkIndex::new(chunk_table));
+        let num_chunks = chunk_index.num_chunks();
+        let offset_to_chunk_table = self.data_start + chunk_index.byte_count();
 
-        let chunk_info = {
-            let mut chunk_of_point = 0usize;
-            let mut start_of_chunk = self.data_start;
-            let mut tmp_count = 0;
-            for entry in chunk_table {
-                tmp_count += entry.point_count;
-                if tmp_count >= point_idx {
-                    break;
-                }
-                start_of_chunk += entry.byte_count;
-                chunk_of_point += 1;
-            }
-
-            if point_idx > tmp_count {
-                None
-            } else {
-                Some((chunk_of_point, start_of_chunk))
-            }
-        };
-
-        if let Some((chunk_of_point, start_of_chunk)) = chunk_info {
-            self.current_chunk = chunk_of_point as usize;
-            let delta = point_idx % chunk_table[self.current_chunk].point_count;
-            if chunk_of_point == (chunk_table.len() - 1) {
+        if let Some((chunk_of_point, first_point, offset)) = chunk_index.chunk_of_point(point_idx) {
+            self.current_chunk = chunk_of_point;
+            let start_of_chunk = self.data_start + offset;
+            let delta = point_idx - first_point;
+            if chunk_of_point == (num_chunks - 1) && !self.vlr.uses_variable_size_chunks() {
                 // the requested point fall into the last chunk,
                 // but that does not mean that the point exists
                 // so we have to be careful, we will do as we would normally,
@@ -147,41 +881,35 @@ impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
                 // point is out ouf bounds so will just seek to the end cf(the else in the if let below)
                 // we do this to avoid decompressing data (ie the chunk table) thinking its a record
                 let mut tmp_out = vec![0u8; self.record_decompressor.record_size()];
-                self.record_decompressor
-                    .get_mut()
-                    .seek(SeekFrom::Start(start_of_chunk))?;
-
-                self.reset_for_new_chunk();
-                let offset_to_chunk_table = self.data_start
-                    + self
-                        .chunk_table
-                        .as_ref()
-                        .unwrap()
-                        .as_ref()
-                        .iter()
-                        .map(|e| e.byte_count)
-                        .sum::<u64>();
-
-                for _i in 0..delta {
+                let resumed_at = self.rewind_current_chunk(start_of_chunk, delta)?;
+
+                for _i in resumed_at..delta {
                     self.decompress_one(&mut tmp_out)?;
-                    let current_pos = self
-                        .record_decompressor
-                        .get_mut()
-                        .seek(SeekFrom::Current(0))?;
+                    // Layered chunks store their number of points, and are read
+                    // entirely when their first point is decompressed
+                    let past_end = match self.record_decompressor.record_count() {
+                        0 => {
+                            let current_pos = self
+                                .record_decompressor
+                                .get_mut()
+                                .seek(SeekFrom::Current(0))?;
+                            current_pos >= offset_to_chunk_table
+                        }
+                        point_count => self.chunk_points_read >= point_count,
+                    };
 
-                    if current_pos >= offset_to_chunk_table {
+                    if past_end {
+                        (self.warn)(LasZipWarning::SeekPastEnd(point_idx));
                         self.record_decompressor.get_mut().seek(SeekFrom::End(0))?;
+                        self.chunk_points_read = self.num_points_in_chunk;
                         return Ok(());
                     }
                 }
             } else {
-                self.record_decompressor
-                    .get_mut()
-                    .seek(SeekFrom::Start(start_of_chunk))?;
-                self.reset_for_new_chunk();
+                let resumed_at = self.rewind_current_chunk(start_of_chunk, delta)?;
                 let mut tmp_out = vec![0u8; self.record_decompressor.record_size()];
 
-                for _i in 0..delta {
+                for _i in resumed_at..delta {
                     self.decompress_one(&mut tmp_out)?;
                 }
             }
@@ -191,16 +919,213 @@ impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
 
             // Seek to the end so that the next call to decompress causes en error
             // like "Failed to fill whole buffer (seeking past end is allowed by the Seek Trait)
+            (self.warn)(LasZipWarning::SeekPastEnd(point_idx));
             self.record_decompressor.get_mut().seek(SeekFrom::End(0))?;
         }
         Ok(())
     }
 
+    /// Decompresses the points of the chunk at `index`, requires the chunk table.
+    ///
+    /// Decompresses as many points of the chunk as `out` can hold and returns
+    /// the number of points decompressed; the decompression then continues
+    /// after them.
+    ///
+    /// # Note
+    ///
+    /// For fixed-size chunks, the number of points of the last chunk is not stored,
+    /// `out` should not hold more than the points left according to the header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let point_size = vlr.items_size() as usize;
+    /// let mut decompressor = laz::LasZipDecompressor::new(laz_file, vlr)?;
+    /// let point_count = decompressor.chunk_table().unwrap()[0].point_count;
+    /// let point_count = point_count.min(header.num_points) as usize;
+    /// let mut points = vec![0u8; point_count * point_size];
+    /// decompressor.decompress_chunk(0, &mut points)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decompress_chunk(&mut self, index: usize, out: &mut [u8]) -> crate::Result<u64> {
+        let points = PointBufferMut::new(out, &self.vlr)?;
+        self.load_chunk_table()?;
+        let chunk_table = self.chunk_table.as_ref().ok_or(MissingChunkTable)?;
+        let entry = chunk_table
+            .as_ref()
+            .get(index)
+            .ok_or(LasZipError::ChunkIndexOutOfRange {
+                index,
+                num_chunks: chunk_table.len(),
+            })?;
+        let num_points = entry.point_count.min(points.len() as u64);
+        let start_of_chunk = self.data_start
+            + chunk_table.as_ref()[..index]
+                .iter()
+                .map(|e| e.byte_count)
+                .sum::<u64>();
+
+        self.peeked_at = None;
+        self.current_chunk = index;
+        let resumed_at = self.rewind_current_chunk(start_of_chunk, 0)?;
+        debug_assert_eq!(resumed_at, 0);
+        let point_size = points.point_size();
+        for point in out.chunks_exact_mut(point_size).take(num_points as usize) {
+            self.decompress_one(point)?;
+        }
+        Ok(num_points)
+    }
+
     /// Returns the vlr used.
     pub fn vlr(&self) -> &LazVlr {
         &self.vlr
     }
 
+    /// Returns the chunk table, if it could be read.
+    ///
+    /// With [`Self::new_lazy`], it is `None` until the chunk table is read,
+    /// see [`Self::load_chunk_table`].
+    pub fn chunk_table(&self) -> Option<&ChunkTable> {
+        self.chunk_table.as_ref()
+    }
+
+    /// Reads the chunk table if its reading was deferred (see [`Self::new_lazy`]),
+    /// and returns it, if it could be read.
+    ///
+    /// The decompression continues where it was, failing to read the chunk table
+    /// is only reported as a warning, like at creation.
+    pub fn load_chunk_table(&mut self) -> std::io::Result<Option<&ChunkTable>> {
+        if self.chunk_table_deferred {
+            self.chunk_table_deferred = false;
+            let source = self.record_decompressor.get_mut();
+            let position = source.stream_position()?;
+            source.seek(SeekFrom::Start(self.data_start - 8))?;
+            let strict = self.strict;
+            let mut first_issue = None;
+            let warn = &mut self.warn;
+            let result = ChunkTable::read_from_with_warnings(
+                &mut *source,
+                &self.vlr,
+                &mut |warning| {
+                    if strict {
+                        first_issue.get_or_insert(warning);
+                    } else {
+                        warn(warning);
+                    }
+                },
+                self.memory_limit,
+            );
+            source.seek(SeekFrom::Start(position))?;
+            if let Some(issue) = first_issue {
+                return Err(LasZipError::StrictModeWarning(Box::new(issue)).into_io_error());
+            }
+            match result {
+                Ok(chunk_table) => {
+                    chunk_table
+                        .check_num_chunks(self.max_num_chunks)
+                        .map_err(LasZipError::into_io_error)?;
+                    self.chunk_table = Some(chunk_table);
+                    self.update_record_memory_limit();
+                }
+                Err(e @ LasZipError::MemoryLimitExceeded { .. }) => return Err(e.into_io_error()),
+                Err(e) if self.strict => return Err(e.into_io_error()),
+                Err(e) => (self.warn)(LasZipWarning::ChunkTableUnreadable(e)),
+            }
+        }
+        Ok(self.chunk_table.as_ref())
+    }
+
+    /// Returns the position of the next point that will be decompressed,
+    /// to be given to [`Self::resume`].
+    pub fn position(&self) -> DecompressionPosition {
+        if let Some(position) = self.peeked_at {
+            return position;
+        }
+        if self.chunk_points_read == self.num_points_in_chunk {
+            DecompressionPosition {
+                chunk_index: self.current_chunk as u64 + 1,
+                points_in_chunk: 0,
+            }
+        } else {
+            DecompressionPosition {
+                chunk_index: self.current_chunk as u64,
+                points_in_chunk: self.chunk_points_read,
+            }
+        }
+    }
+
+    /// Sets the total number of points of the compressed data,
+    /// usually the one of the LAS header.
+    ///
+    /// Without it, the total is only known from the chunk table of variable-size chunks,
+    /// as the last fixed-size chunk may not be full.
+    pub fn set_num_points(&mut self, num_points: u64) {
+        self.num_points = Some(num_points);
+    }
+
+    /// Returns the total number of points, `None` if it is not known,
+    /// see [`Self::set_num_points`].
+    pub fn num_points(&self) -> Option<u64> {
+        self.num_points.or_else(|| {
+            self.chunk_table
+                .as_ref()
+                .filter(|_| self.vlr.uses_variable_size_chunks())
+                .map(ChunkTable::num_points)
+        })
+    }
+
+    /// Returns the number of points that were not decompressed yet,
+    /// `None` if the total number of points or the current point is not known.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let mut decompressor = laz::LasZipDecompressor::new(laz_file, vlr.clone())?;
+    /// decompressor.set_num_points(header.num_points);
+    ///
+    /// let mut point = vec![0u8; vlr.items_size() as usize];
+    /// while !decompressor.is_done() {
+    ///     decompressor.decompress_one(&mut point)?;
+    /// }
+    /// assert_eq!(decompressor.remaining_points(), Some(0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remaining_points(&self) -> Option<u64> {
+        let num_points = self.num_points()?;
+        Some(num_points.saturating_sub(self.points_read()?))
+    }
+
+    /// Returns whether all the points were decompressed,
+    /// `false` when it is not known, see [`Self::remaining_points`].
+    ///
+    /// When the total is known, an error while decompressing a point
+    /// before this is `true` means that the data is truncated.
+    pub fn is_done(&self) -> bool {
+        self.remaining_points() == Some(0)
+    }
+
+    /// Returns the time spent decompressing each field, and each chunk
+    /// that was entirely decompressed.
+    #[cfg(feature = "timing")]
+    pub fn timing_report(&self) -> crate::timing::TimingReport {
+        self.chunk_timer
+            .report(self.vlr.items(), self.record_decompressor.field_times())
+    }
+
     /// Consumes the decompressor and returns the data source.
     pub fn into_inner(self) -> R {
         self.record_decompressor.box_into_inner()
@@ -215,6 +1140,193 @@ impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
         self.record_decompressor.get()
     }
 
+    #[cfg(feature = "model-stats")]
+    fn collect_model_stats(&mut self) {
+        use crate::model_stats::{ChunkModelStats, FieldModelStats};
+        if let Some(model_stats) = self.model_stats.as_mut() {
+            let fields = self
+                .vlr
+                .items()
+                .iter()
+                .zip(self.record_decompressor.model_stats())
+                .map(|(item, models)| FieldModelStats {
+                    item_type: item.item_type(),
+                    models,
+                })
+                .collect();
+            let chunk_index = self.current_chunk;
+            model_stats.retain(|stats| stats.chunk_index != chunk_index);
+            model_stats.push(ChunkModelStats {
+                chunk_index,
+                fields,
+            });
+        }
+    }
+
+    /// Gives the record decompressor what is left of the memory limit
+    /// once the chunk table is loaded
+    fn update_record_memory_limit(&mut self) {
+        let table_size =
+            ChunkTable::memory_size(self.chunk_table.as_ref().map_or(0, ChunkTable::len));
+        self.record_decompressor.set_memory_limit(
+            self.memory_limit
+                .map(|limit| limit.saturating_sub(table_size)),
+        );
+    }
+
+    /// Returns the number of points in the chunk being decompressed
+    fn num_points_in_current_chunk(&self) -> u64 {
+        if self.vlr.uses_variable_size_chunks() {
+            match (&self.chunk_table, self.vlr.compressor) {
+                (Some(chunk_table), _) => chunk_table[self.current_chunk].point_count,
+                (None, CompressorType::LayeredChunked) => self.record_decompressor.record_count(),
+                (None, _) => {
+                    // This should not be possible, the `new` method should ensure
+                    // we have the chunk table if we need one
+                    panic!("Variable-size chunks, but no chunk table");
+                }
+            }
+        } else {
+            self.vlr.chunk_size().into()
+        }
+    }
+
+    /// Returns the number of points left in the chunk of the next point
+    /// that will be decompressed, `None` if it is not known.
+    pub(super) fn points_left_in_chunk(&self) -> Option<u64> {
+        let (chunk, points_read) = if self.chunk_points_read == self.num_points_in_chunk {
+            (self.current_chunk + 1, 0)
+        } else {
+            (self.current_chunk, self.chunk_points_read)
+        };
+        let point_count = if self.vlr.uses_variable_size_chunks() {
+            self.chunk_table.as_ref()?.as_ref().get(chunk)?.point_count
+        } else {
+            u64::from(self.vlr.chunk_size())
+        };
+        let peeked = u64::from(self.peeked_at.is_some());
+        Some(point_count.saturating_sub(points_read) + peeked)
+    }
+
+    /// Returns the index of the next point that will be decompressed,
+    /// requires the chunk table.
+    pub(super) fn current_point_index(&self) -> Option<u64> {
+        let chunk_table = self.chunk_table.as_ref()?;
+        let points_before = chunk_table.as_ref()[..self.current_chunk]
+            .iter()
+            .map(|e| e.point_count)
+            .sum::<u64>();
+        let peeked = u64::from(self.peeked_at.is_some());
+        Some(points_before + self.chunk_points_read - peeked)
+    }
+
+    /// Returns the index of the next point that will be decompressed,
+    /// like [`Self::current_point_index`], but without requiring the chunk table
+    /// for fixed-size chunks.
+    fn points_read(&self) -> Option<u64> {
+        if self.chunk_table.is_some() || self.vlr.uses_variable_size_chunks() {
+            return self.current_point_index();
+        }
+        let peeked = u64::from(self.peeked_at.is_some());
+        Some(
+            self.current_chunk as u64 * u64::from(self.vlr.chunk_size()) + self.chunk_points_read
+                - peeked,
+        )
+    }
+
+    /// Moves from the point at index `current` to the point at index `target`
+    /// (`target` >= `current`), decompressing the points in between when
+    /// `target` is in the current chunk, and seeking otherwise.
+    fn skip_to(&mut self, current: u64, target: u64, tmp_out: &mut [u8]) -> crate::Result<()> {
+        if self.peeked_at.is_some() {
+            if target == current {
+                return Ok(());
+            }
+            // Consumes the peeked point, which may be in the previous chunk
+            self.decompress_one(tmp_out)?;
+            return self.skip_to(current + 1, target, tmp_out);
+        }
+        let end_of_chunk = current - self.chunk_points_read + self.num_points_in_current_chunk();
+        if target < end_of_chunk {
+            for _ in current..target {
+                self.decompress_one(tmp_out)?;
+            }
+            Ok(())
+        } else {
+            self.seek(target)
+        }
+    }
+
+    fn take_snapshot(&mut self) -> std::io::Result<()> {
+        if self.snapshots_chunk != self.current_chunk {
+            self.snapshots.clear();
+            self.snapshots_chunk = self.current_chunk;
+        }
+        let index = match self
+            .snapshots
+            .binary_search_by_key(&self.chunk_points_read, |snapshot| snapshot.point_in_chunk)
+        {
+            // We already went through this part of the chunk
+            Ok(_) => return Ok(()),
+            Err(index) => index,
+        };
+        if let Some(state) = self.record_decompressor.snapshot() {
+            let stream_pos = self.record_decompressor.get_mut().stream_position()?;
+            self.snapshots.insert(
+                index,
+                ChunkSnapshot {
+                    point_in_chunk: self.chunk_points_read,
+                    stream_pos,
+                    state,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Prepares the decompression of the current chunk so that the point at
+    /// `point_in_chunk` can be reached.
+    ///
+    /// Uses the closest snapshot preceding the point if there is one, otherwise
+    /// moves to `start_of_chunk`.
+    ///
+    /// Returns the index in the chunk of the next point that will be decompressed.
+    fn rewind_current_chunk(
+        &mut self,
+        start_of_chunk: u64,
+        point_in_chunk: u64,
+    ) -> std::io::Result<u64> {
+        let snapshots = &self.snapshots;
+        let snapshot = if self.snapshots_chunk == self.current_chunk {
+            match snapshots
+                .binary_search_by_key(&point_in_chunk, |snapshot| snapshot.point_in_chunk)
+            {
+                Ok(index) => Some(&snapshots[index]),
+                Err(index) => index.checked_sub(1).map(|index| &snapshots[index]),
+            }
+        } else {
+            None
+        };
+
+        if let Some(snapshot) = snapshot {
+            if self.record_decompressor.restore(&snapshot.state) {
+                let (resumed_at, stream_pos) = (snapshot.point_in_chunk, snapshot.stream_pos);
+                self.record_decompressor
+                    .get_mut()
+                    .seek(SeekFrom::Start(stream_pos))?;
+                self.chunk_points_read = resumed_at;
+                self.num_points_in_chunk = self.num_points_in_current_chunk();
+                return Ok(resumed_at);
+            }
+        }
+
+        self.record_decompressor
+            .get_mut()
+            .seek(SeekFrom::Start(start_of_chunk))?;
+        self.reset_for_new_chunk();
+        Ok(0)
+    }
+
     #[inline(always)]
     fn reset_for_new_chunk(&mut self) {
         self.chunk_points_read = 0;
@@ -270,3 +1382,340 @@ pub fn decompress_buffer(
         Ok(())
     })
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use crate::laszip::LazOpenOptions;
+    use crate::test_utils;
+    use crate::{
+        compress_buffer, LasZipCompressor, LasZipDecompressor, LasZipError, LasZipWarning,
+        LazItemRecordBuilder, LazVlrBuilder,
+    };
+
+    use super::DecompressionPosition;
+
+    #[test]
+    fn test_only_the_snapshots_of_the_current_chunk_are_kept() {
+        let point_size = 20;
+        let points = test_utils::points(300, point_size);
+        let vlr =
+            LazVlrBuilder::new(LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0))
+                .with_fixed_chunk_size(100)
+                .build();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        decompressor.set_snapshot_interval(10);
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, points);
+        assert_eq!(decompressor.snapshots_chunk, 2);
+        assert!(decompressor.snapshots.len() <= 10);
+
+        let mut point = vec![0u8; point_size];
+        for index in [255, 45, 12, 99] {
+            decompressor.seek(index).unwrap();
+            decompressor.decompress_one(&mut point).unwrap();
+            let start = index as usize * point_size;
+            assert_eq!(point, &points[start..start + point_size]);
+        }
+        assert_eq!(decompressor.snapshots_chunk, 0);
+    }
+
+    #[test]
+    fn test_strict_mode_and_max_num_chunks() {
+        let points = test_utils::points(300, 20);
+        let vlr = LazVlrBuilder::new(LazItemRecordBuilder::version_1_of::<crate::las::Point0>(0))
+            .with_fixed_chunk_size(100)
+            .build();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        let compressed = compressed.into_inner();
+
+        let options = LazOpenOptions::new().with_max_num_chunks(3);
+        LasZipDecompressor::with_options(Cursor::new(&compressed), vlr.clone(), options).unwrap();
+        let options = LazOpenOptions::new().with_max_num_chunks(2);
+        let result =
+            LasZipDecompressor::with_options(Cursor::new(&compressed), vlr.clone(), options);
+        assert!(matches!(
+            result,
+            Err(LasZipError::ChunkLimitExceeded {
+                limit: 2,
+                num_chunks: 3
+            })
+        ));
+
+        // Version 1 items are deprecated
+        let options = LazOpenOptions::new().with_strict(true);
+        let result =
+            LasZipDecompressor::with_options(Cursor::new(&compressed), vlr.clone(), options);
+        assert!(matches!(
+            result,
+            Err(LasZipError::StrictModeWarning(warning))
+                if matches!(*warning, LasZipWarning::DeprecatedItemVersion(_, 1))
+        ));
+
+        // The chunk table is unreadable
+        let vlr =
+            LazVlrBuilder::new(LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0))
+                .with_fixed_chunk_size(100)
+                .build();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        let mut compressed = compressed.into_inner();
+        let past_the_end = compressed.len() as i64 + 10;
+        compressed[..8].copy_from_slice(&past_the_end.to_le_bytes());
+        let mut num_warnings = 0;
+        let options = LazOpenOptions::new().with_warnings(|_| num_warnings += 1);
+        LasZipDecompressor::with_options(Cursor::new(&compressed), vlr.clone(), options).unwrap();
+        assert_eq!(num_warnings, 1);
+        let options = LazOpenOptions::new().with_strict(true);
+        let result = LasZipDecompressor::with_options(Cursor::new(&compressed), vlr, options);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_resume_decompression() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        let chunks = [3, 1, 4, 2]
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| {
+                (0..size)
+                    .flat_map(|j| {
+                        let mut point = [0u8; 20];
+                        point[..4].copy_from_slice(&((i * 10 + j) as i32).to_le_bytes());
+                        point
+                    })
+                    .collect::<Vec<u8>>()
+            })
+            .collect::<Vec<_>>();
+        let points = chunks.concat();
+        let mut compressor =
+            LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        compressor.compress_chunks(&chunks).unwrap();
+        let compressed = compressor.finish().unwrap().into_inner();
+
+        let mut decompressor =
+            LasZipDecompressor::new(Cursor::new(&compressed), vlr.clone()).unwrap();
+        let mut point = [0u8; 20];
+        let mut positions = vec![];
+        for _ in 0..10 {
+            positions.push(decompressor.position());
+            decompressor.decompress_one(&mut point).unwrap();
+        }
+        assert_eq!(
+            positions[3],
+            DecompressionPosition {
+                chunk_index: 1,
+                points_in_chunk: 0
+            }
+        );
+        assert_eq!(
+            decompressor.position(),
+            DecompressionPosition {
+                chunk_index: 4,
+                points_in_chunk: 0
+            }
+        );
+
+        for (i, position) in positions.into_iter().enumerate() {
+            let mut decompressor =
+                LasZipDecompressor::resume(Cursor::new(&compressed), vlr.clone(), position)
+                    .unwrap();
+            assert_eq!(decompressor.position(), position);
+            let mut decompressed = vec![0u8; points.len() - i * 20];
+            decompressor.decompress_many(&mut decompressed).unwrap();
+            assert_eq!(decompressed, &points[i * 20..]);
+        }
+
+        // Resuming at the end, there is no point left
+        let end = decompressor.position();
+        let error = decompressor.decompress_one(&mut point).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        let mut decompressor =
+            LasZipDecompressor::resume(Cursor::new(&compressed), vlr.clone(), end).unwrap();
+        assert_eq!(decompressor.position(), end);
+        let error = decompressor.decompress_one(&mut point).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        let out_of_data = DecompressionPosition {
+            chunk_index: 1,
+            points_in_chunk: 1,
+        };
+        assert!(LasZipDecompressor::resume(Cursor::new(&compressed), vlr, out_of_data).is_err());
+    }
+
+    #[test]
+    fn test_peek() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(5).build();
+        let points = test_utils::points(12, 20);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        let mut point = [0u8; 20];
+        for _ in 0..5 {
+            decompressor.decompress_one(&mut point).unwrap();
+        }
+        let position = decompressor.position();
+        // Peeking the first point of the next chunk
+        assert_eq!(decompressor.peek().unwrap(), &points[100..120]);
+        assert_eq!(decompressor.peek().unwrap(), &points[100..120]);
+        assert_eq!(decompressor.position(), position);
+        assert_eq!(decompressor.current_point_index(), Some(5));
+
+        let mut kept = Vec::new();
+        decompressor.decompress_every_nth(3, 7, &mut kept).unwrap();
+        assert_eq!(
+            kept,
+            [&points[100..120], &points[160..180], &points[220..240]].concat()
+        );
+
+        decompressor.seek(2).unwrap();
+        decompressor.peek().unwrap();
+        decompressor.seek(3).unwrap();
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(point, &points[60..80]);
+    }
+
+    #[test]
+    fn test_lazy_chunk_table() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point3>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = test_utils::points(35, 34);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = LasZipDecompressor::new_lazy(compressed, vlr).unwrap();
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor
+            .decompress_many(&mut decompressed[..15 * 34])
+            .unwrap();
+        assert!(decompressor.chunk_table().is_none());
+
+        // Loading it in the middle of a chunk does not disturb the decompression
+        assert_eq!(decompressor.load_chunk_table().unwrap().unwrap().len(), 4);
+        decompressor
+            .decompress_many(&mut decompressed[15 * 34..])
+            .unwrap();
+        assert_eq!(decompressed, points);
+
+        decompressor.seek(12).unwrap();
+        let mut point = [0u8; 34];
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(&point[..], &points[12 * 34..13 * 34]);
+    }
+
+    #[test]
+    fn test_remaining_points() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let points = test_utils::points(25, 20);
+        let mut point = [0u8; 20];
+
+        // Fixed-size chunks, without the chunk table, the total comes from the caller
+        let vlr = LazVlrBuilder::new(items.clone())
+            .with_fixed_chunk_size(10)
+            .build();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+        let mut decompressor = LasZipDecompressor::new_lazy(compressed, vlr).unwrap();
+        assert_eq!(decompressor.remaining_points(), None);
+        assert!(!decompressor.is_done());
+        decompressor.set_num_points(25);
+        assert_eq!(decompressor.remaining_points(), Some(25));
+        for _ in 0..10 {
+            decompressor.decompress_one(&mut point).unwrap();
+        }
+        decompressor.peek().unwrap();
+        assert_eq!(decompressor.remaining_points(), Some(15));
+        let mut count = 0;
+        while !decompressor.is_done() {
+            decompressor.decompress_one(&mut point).unwrap();
+            count += 1;
+        }
+        assert_eq!(count, 15);
+        assert_eq!(&point[..], &points[24 * 20..]);
+
+        // Variable-size chunks, the total comes from the chunk table
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        {
+            let mut compressor = LasZipCompressor::new(&mut compressed, vlr.clone()).unwrap();
+            compressor.compress_many(&points[..7 * 20]).unwrap();
+            compressor.finish_current_chunk().unwrap();
+            compressor.compress_many(&points[7 * 20..]).unwrap();
+            compressor.done().unwrap();
+        }
+        compressed.set_position(0);
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        assert_eq!(decompressor.num_points(), Some(25));
+        let mut decompressed = vec![0u8; 20 * 20];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressor.remaining_points(), Some(5));
+    }
+
+    #[test]
+    fn test_decompress_chunk() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        let points = test_utils::points(9, 20);
+        let mut compressor =
+            LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        compressor
+            .compress_chunks([&points[..60], &points[60..80], &points[80..]])
+            .unwrap();
+        let compressed = compressor.finish().unwrap().into_inner();
+
+        let mut decompressor = LasZipDecompressor::new(Cursor::new(compressed), vlr).unwrap();
+        let mut chunk = vec![0u8; 5 * 20];
+        assert_eq!(decompressor.decompress_chunk(2, &mut chunk).unwrap(), 5);
+        assert_eq!(chunk, &points[80..]);
+        assert_eq!(decompressor.decompress_chunk(0, &mut chunk).unwrap(), 3);
+        assert_eq!(&chunk[..60], &points[..60]);
+        // The decompression continues after the chunk
+        let mut point = [0u8; 20];
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(point, &points[60..80]);
+
+        assert!(matches!(
+            decompressor.decompress_chunk(3, &mut chunk),
+            Err(crate::LasZipError::ChunkIndexOutOfRange {
+                index: 3,
+                num_chunks: 3
+            })
+        ));
+        assert!(decompressor.decompress_chunk(0, &mut chunk[..30]).is_err());
+    }
+
+    #[test]
+    fn test_seek_in_last_layered_chunk() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point6>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = test_utils::points(24, 30);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        let mut point = [0u8; 30];
+        for point_idx in [23usize, 21, 20] {
+            decompressor.seek(point_idx as u64).unwrap();
+            decompressor.decompress_one(&mut point).unwrap();
+            assert_eq!(&point[..], &points[point_idx * 30..(point_idx + 1) * 30]);
+        }
+
+        // The last chunk only has 4 points
+        decompressor.seek(25).unwrap();
+        assert!(decompressor.decompress_one(&mut point).is_err());
+    }
+}
@@ -1,18 +1,22 @@
+use std::convert::TryFrom;
 use std::io::{Read, Seek, Write};
 
+use super::DecompressionSelection;
 use crate::record::{
     LayeredPointRecordCompressor, LayeredPointRecordDecompressor, RecordCompressor,
     RecordDecompressor, SequentialPointRecordCompressor, SequentialPointRecordDecompressor,
 };
-use crate::{LasZipError, LazItem};
+use crate::{LasZipError, LazItemType, LazVlr};
 
-pub(super) fn record_decompressor_from_laz_items<'a, R: Read + Seek + Send + 'a>(
-    items: &Vec<LazItem>,
+pub(super) fn record_decompressor_from_vlr<'a, R: Read + Seek + Send + 'a>(
+    vlr: &LazVlr,
     input: R,
 ) -> crate::Result<Box<dyn RecordDecompressor<R> + Send + 'a>> {
+    let items = vlr.items();
     let first_item = items
         .get(0)
         .expect("There should be at least one LazItem to be able to create a RecordDecompressor");
+    check_is_encodable(vlr)?;
 
     let mut decompressor = match first_item.version {
         1 | 2 => {
@@ -35,13 +39,31 @@ pub(super) fn record_decompressor_from_laz_items<'a, R: Read + Seek + Send + 'a>
     Ok(decompressor)
 }
 
-pub(super) fn record_compressor_from_laz_items<'a, W: Write + Send + 'a>(
-    items: &Vec<LazItem>,
+/// Creates a record decompressor that only decompresses the fields of the `selection`
+pub(super) fn record_decompressor_with_selection<'a, R: Read + Seek + Send + 'a>(
+    vlr: &LazVlr,
+    selection: DecompressionSelection,
+    input: R,
+) -> crate::Result<Box<dyn RecordDecompressor<R> + Send + 'a>> {
+    let mut decompressor = record_decompressor_from_vlr(vlr, input)?;
+    if selection != DecompressionSelection::all() {
+        // The field decompressors are made for the selection
+        decompressor.set_selection(selection);
+        decompressor.reset();
+        decompressor.set_fields_from(vlr.items())?;
+    }
+    Ok(decompressor)
+}
+
+pub(super) fn record_compressor_from_vlr<'a, W: Write + Send + 'a>(
+    vlr: &LazVlr,
     output: W,
 ) -> crate::Result<Box<dyn RecordCompressor<W> + Send + 'a>> {
+    let items = vlr.items();
     let first_item = items
         .get(0)
         .expect("There should be at least one LazItem to be able to create a RecordCompressor");
+    check_is_encodable(vlr)?;
 
     let mut compressor = match first_item.version {
         1 | 2 => {
@@ -62,3 +84,35 @@ pub(super) fn record_compressor_from_laz_items<'a, W: Write + Send + 'a>(
     compressor.set_fields_from(items)?;
     Ok(compressor)
 }
+
+/// Only the arithmetic coder (0) is implemented, and the encoding of unknown items
+/// (see [`LazVlr::read_from_lenient`]) is not known: such vlrs can be inspected,
+/// but their points are neither compressed nor decompressed.
+fn check_is_encodable(vlr: &LazVlr) -> crate::Result<()> {
+    if vlr.coder() != 0 {
+        return Err(LasZipError::UnknownCoder(vlr.coder()));
+    }
+    match vlr.items().iter().find_map(|item| match item.item_type {
+        LazItemType::Unknown { type_code, .. } => Some(type_code),
+        _ => None,
+    }) {
+        Some(type_code) => Err(LasZipError::UnknownLazItem(type_code)),
+        None => Ok(()),
+    }
+}
+
+/// Returns an [`InvalidData`](std::io::ErrorKind::InvalidData) error with the `message`
+pub(crate) fn invalid_data<M: Into<String>>(message: M) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+/// Converts a number of bytes (or points) to a `usize`, returning an error
+/// instead of truncating it when it does not fit, e.g. on 32-bit targets.
+pub(super) fn usize_from(value: u64) -> std::io::Result<usize> {
+    usize::try_from(value).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{} bytes do not fit in memory on this target", value),
+        )
+    })
+}
@@ -0,0 +1,253 @@
+//! Diagnostics about how the points of a file were compressed
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::laszip::{ChunkTable, CoordinateTransform, LazItem, LazItemType, LazVlr};
+use crate::spatial::Bounds;
+use crate::LasZipDecompressor;
+
+/// Number of contexts of the layered point formats (6 to 10)
+pub const NUM_CONTEXTS: usize = 4;
+
+/// Returns the context a point of format 6 to 10 was compressed with,
+/// which is its scanner channel.
+pub fn context_of(point: &[u8]) -> u8 {
+    (point[15] & 0b0011_0000) >> 4
+}
+
+/// Returns whether the points described by the items are compressed using contexts
+pub(super) fn uses_contexts(items: &[LazItem]) -> bool {
+    items
+        .first()
+        .is_some_and(|item| item.item_type() == LazItemType::Point14)
+}
+
+/// Contexts used by the points of a chunk, see
+/// [`LasZipDecompressor::set_context_diagnostics`](crate::LasZipDecompressor::set_context_diagnostics)
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ChunkContexts {
+    /// Number of points decompressed with each context
+    pub num_points: [u64; NUM_CONTEXTS],
+    /// Number of times the context changed between two consecutive points
+    pub num_switches: u64,
+    last_context: Option<u8>,
+}
+
+impl ChunkContexts {
+    pub(super) fn add_point(&mut self, context: u8) {
+        self.num_points[usize::from(context)] += 1;
+        if self.last_context.is_some_and(|last| last != context) {
+            self.num_switches += 1;
+        }
+        self.last_context = Some(context);
+    }
+
+    /// Returns the context of the last point decompressed in the chunk
+    pub fn last_context(&self) -> Option<u8> {
+        self.last_context
+    }
+}
+
+/// Size of a chunk of an existing file, see [`chunk_report`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChunkReport {
+    pub point_count: u64,
+    pub byte_count: u64,
+    /// Position of the chunk, relative to the start of the compressed data
+    /// (where the offset to the chunk table is)
+    pub offset: u64,
+    /// Size of the uncompressed points divided by the size of the chunk
+    pub compression_ratio: f64,
+    /// Only computed by [`chunk_report_with_density`]
+    pub density: Option<ChunkDensity>,
+}
+
+impl ChunkReport {
+    /// Returns the average number of compressed bytes per point
+    pub fn bytes_per_point(&self) -> f64 {
+        self.byte_count as f64 / self.point_count as f64
+    }
+}
+
+/// Density of the points of a chunk, see [`chunk_report_with_density`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChunkDensity {
+    pub bounds: Bounds,
+    /// Number of points per unit of area of the bounds, on the x & y axes
+    pub points_per_area: f64,
+}
+
+/// Returns the size and compression ratio of each chunk of the `num_points` points
+/// of the compressed data in the `src`, by only reading the chunk table.
+///
+/// The `src` must be positioned at the start of the compressed data,
+/// and is left after the offset to the chunk table.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::{Seek, SeekFrom};
+/// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+/// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+/// # let vlr = vlr.unwrap();
+/// laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+/// let report = laz::laszip::chunk_report(&mut laz_file, &vlr, header.num_points)?;
+/// for (i, chunk) in report.iter().enumerate() {
+///     println!("chunk {}: {} points, ratio {:.2}", i, chunk.point_count, chunk.compression_ratio);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn chunk_report<R: Read + Seek>(
+    mut src: R,
+    vlr: &LazVlr,
+    num_points: u64,
+) -> crate::Result<Vec<ChunkReport>> {
+    let chunk_table = ChunkTable::read_from(&mut src, vlr)?;
+    let point_size = vlr.items_size() as f64;
+    let mut reports = Vec::with_capacity(chunk_table.len());
+    let mut offset = std::mem::size_of::<i64>() as u64;
+    let mut points_left = num_points;
+    for entry in &chunk_table {
+        if points_left == 0 {
+            break;
+        }
+        // For fixed-size chunks, the last chunk may have fewer points
+        let point_count = entry.point_count.min(points_left);
+        reports.push(ChunkReport {
+            point_count,
+            byte_count: entry.byte_count,
+            offset,
+            compression_ratio: point_count as f64 * point_size / entry.byte_count as f64,
+            density: None,
+        });
+        offset += entry.byte_count;
+        points_left -= point_count;
+    }
+    Ok(reports)
+}
+
+/// Same as [`chunk_report`], but also decompresses all the points to compute
+/// the density of the points of each chunk.
+pub fn chunk_report_with_density<R: Read + Seek + Send>(
+    mut src: R,
+    vlr: &LazVlr,
+    num_points: u64,
+    transform: CoordinateTransform,
+) -> crate::Result<Vec<ChunkReport>> {
+    let start = src.stream_position()?;
+    let mut reports = chunk_report(&mut src, vlr, num_points)?;
+    src.seek(SeekFrom::Start(start))?;
+    let mut decompressor = LasZipDecompressor::new(src, vlr.clone())?;
+    let all_bounds = decompressor.compute_chunk_bounds(num_points, transform)?;
+    for (report, bounds) in reports.iter_mut().zip(all_bounds) {
+        let area = (bounds.max[0] - bounds.min[0]) * (bounds.max[1] - bounds.min[1]);
+        report.density = Some(ChunkDensity {
+            bounds,
+            points_per_area: report.point_count as f64 / area,
+        });
+    }
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use crate::las::{Point0, Point6};
+    use crate::{LazItemRecordBuilder, LazVlrBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_context_diagnostics() {
+        let items = LazItemRecordBuilder::default_version_of::<Point6>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        // Channels of the 15 points
+        let channels = [0u8, 0, 1, 1, 1, 0, 3, 3, 3, 3, 2, 2, 0, 0, 0];
+        let points = channels
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &channel)| {
+                let mut point = [0u8; 30];
+                point[..4].copy_from_slice(&(i as i32).to_le_bytes());
+                point[14] = 0x11;
+                point[15] = channel << 4;
+                point
+            })
+            .collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        assert!(decompressor.set_context_diagnostics(true));
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, points);
+        let contexts = decompressor.context_diagnostics().unwrap();
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[0].num_points, [3, 3, 0, 4]);
+        assert_eq!(contexts[0].num_switches, 3);
+        assert_eq!(contexts[1].num_points, [3, 0, 2, 0]);
+        assert_eq!(contexts[1].num_switches, 1);
+        assert_eq!(contexts[1].last_context(), Some(0));
+
+        // Decompressing the second chunk again restarts its statistics
+        decompressor.seek(10).unwrap();
+        decompressor
+            .decompress_many(&mut decompressed[..60])
+            .unwrap();
+        let contexts = decompressor.context_diagnostics().unwrap();
+        assert_eq!(contexts[1].num_points, [0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_chunk_report() {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = (0..25i32)
+            .flat_map(|i| {
+                let mut point = [0u8; 20];
+                point[..4].copy_from_slice(&(i % 10).to_le_bytes());
+                point[4..8].copy_from_slice(&(i / 5).to_le_bytes());
+                point
+            })
+            .collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+        let chunk_table = ChunkTable::read_from(&mut compressed, &vlr).unwrap();
+
+        compressed.set_position(0);
+        let report = chunk_report(&mut compressed, &vlr, 25).unwrap();
+        assert_eq!(report.len(), 3);
+        assert_eq!(report[1].offset, 8 + chunk_table[0].byte_count);
+        assert_eq!(report[2].point_count, 5);
+        assert_eq!(report[2].byte_count, chunk_table[2].byte_count);
+        assert_eq!(
+            report[2].compression_ratio,
+            100.0 / chunk_table[2].byte_count as f64
+        );
+        assert!(report[0].density.is_none());
+
+        compressed.set_position(0);
+        let transform = CoordinateTransform::new([0.5, 1.0, 1.0], [0.0; 3]);
+        let report = chunk_report_with_density(&mut compressed, &vlr, 25, transform).unwrap();
+        let density = report[0].density.unwrap();
+        assert_eq!(density.bounds.max, [4.5, 1.0, 0.0]);
+        assert_eq!(density.points_per_area, 10.0 / 4.5);
+    }
+
+    #[test]
+    fn test_no_context_diagnostics_for_legacy_formats() {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let vlr = LazVlrBuilder::new(items).build();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &[0u8; 20], vlr.clone()).unwrap();
+        compressed.set_position(0);
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        assert!(!decompressor.set_context_diagnostics(true));
+        assert!(decompressor.context_diagnostics().is_none());
+    }
+}
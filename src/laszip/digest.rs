@@ -0,0 +1,233 @@
+//! Digest of the uncompressed points, stored in an EVLR, for fixity checks
+//!
+//! The digest is the SHA-256 of the uncompressed point records, in order,
+//! so it does not depend on how the points were compressed (chunk sizes, item versions).
+//!
+//! Needs the `digest` feature, the SHA-256 is computed by the `sha2` crate.
+use std::io::{Read, Seek, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use sha2::{Digest, Sha256};
+
+use super::{LasZipDecompressor, LazVlr};
+
+/// Computes the SHA-256 digest of points, incrementally
+#[derive(Debug, Clone, Default)]
+pub struct PointDigest {
+    hasher: Sha256,
+}
+
+impl PointDigest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the bytes of some points to the digest
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.hasher.update(bytes);
+    }
+
+    /// Returns the digest of all the bytes given to [`Self::update`]
+    pub fn finish(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+}
+
+/// Non-standard EVLR holding the digest of the uncompressed points
+///
+/// It uses a vendor user id, so readers that do not know it ignore it.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::{Cursor, Seek, SeekFrom};
+/// use laz::laszip::DigestEvlr;
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).build();
+/// let mut compressor = laz::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone())?;
+/// compressor.set_compute_digest(true);
+/// compressor.compress_many(&[1u8; 20 * 10])?;
+/// let evlr = DigestEvlr::new(10, compressor.digest().unwrap());
+/// let mut output = compressor.finish()?;
+/// // The EVLR goes after the points, the header of the
+/// // LAS file has to be updated to point to it
+/// evlr.write_to(&mut output)?;
+///
+/// output.set_position(0);
+/// assert!(laz::laszip::verify_digest(&mut output, vlr, &evlr)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DigestEvlr {
+    /// Number of points the digest was computed on
+    pub num_points: u64,
+    /// SHA-256 of the uncompressed points
+    pub digest: [u8; 32],
+}
+
+impl DigestEvlr {
+    pub const USER_ID: &'static str = "laz-rs";
+    pub const RECORD_ID: u16 = 1;
+    pub const DESCRIPTION: &'static str = "SHA-256 of the points";
+    /// Size of the data of the EVLR, after its header
+    const DATA_SIZE: u64 = 2 + 8 + 32;
+    /// Identifies SHA-256 in the data
+    const ALGORITHM_SHA256: u16 = 1;
+
+    pub fn new(num_points: u64, digest: [u8; 32]) -> Self {
+        Self { num_points, digest }
+    }
+
+    /// Writes the whole EVLR, header included
+    pub fn write_to<W: Write>(&self, mut dst: W) -> std::io::Result<()> {
+        dst.write_u16::<LittleEndian>(0)?;
+        dst.write_all(&padded::<16>(Self::USER_ID))?;
+        dst.write_u16::<LittleEndian>(Self::RECORD_ID)?;
+        dst.write_u64::<LittleEndian>(Self::DATA_SIZE)?;
+        dst.write_all(&padded::<32>(Self::DESCRIPTION))?;
+
+        dst.write_u16::<LittleEndian>(Self::ALGORITHM_SHA256)?;
+        dst.write_u64::<LittleEndian>(self.num_points)?;
+        dst.write_all(&self.digest)?;
+        Ok(())
+    }
+
+    /// Reads a whole EVLR, header included, returns `None` if
+    /// it is not a digest EVLR (the EVLR is then skipped).
+    pub fn read_from<R: Read>(mut src: R) -> std::io::Result<Option<Self>> {
+        src.read_u16::<LittleEndian>()?;
+        let mut user_id = [0u8; 16];
+        src.read_exact(&mut user_id)?;
+        let record_id = src.read_u16::<LittleEndian>()?;
+        let record_length = src.read_u64::<LittleEndian>()?;
+        let mut description = [0u8; 32];
+        src.read_exact(&mut description)?;
+
+        if user_id != padded::<16>(Self::USER_ID) || record_id != Self::RECORD_ID {
+            std::io::copy(&mut src.take(record_length), &mut std::io::sink())?;
+            return Ok(None);
+        }
+        let algorithm = src.read_u16::<LittleEndian>()?;
+        if algorithm != Self::ALGORITHM_SHA256 || record_length != Self::DATA_SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Unsupported digest algorithm {}", algorithm),
+            ));
+        }
+        let num_points = src.read_u64::<LittleEndian>()?;
+        let mut digest = [0u8; 32];
+        src.read_exact(&mut digest)?;
+        Ok(Some(Self { num_points, digest }))
+    }
+}
+
+fn padded<const N: usize>(s: &str) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes[..s.len()].copy_from_slice(s.as_bytes());
+    bytes
+}
+
+/// Decompresses the points of the `src`, which must be positioned at the start
+/// of the compressed data, and returns whether their digest is the one of the `evlr`.
+pub fn verify_digest<R: Read + Seek + Send>(
+    src: R,
+    vlr: LazVlr,
+    evlr: &DigestEvlr,
+) -> crate::Result<bool> {
+    const BATCH_SIZE: u64 = 50_000;
+    let point_size = vlr.items_size();
+    let mut decompressor = LasZipDecompressor::new(src, vlr)?;
+    let mut digest = PointDigest::new();
+    let mut points = Vec::new();
+    let mut points_left = evlr.num_points;
+    while points_left > 0 {
+        let n = points_left.min(BATCH_SIZE);
+        points.resize(super::details::usize_from(n * point_size)?, 0u8);
+        decompressor.decompress_many(&mut points)?;
+        digest.update(&points);
+        points_left -= n;
+    }
+    Ok(digest.finish() == evlr.digest)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn hex(digest: [u8; 32]) -> String {
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            hex(PointDigest::new().finish()),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        let mut digest = PointDigest::new();
+        digest.update(b"abc");
+        assert_eq!(
+            hex(digest.finish()),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // Updated in pieces that do not match the blocks
+        let message = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let mut digest = PointDigest::new();
+        for part in message.chunks(5) {
+            digest.update(part);
+        }
+        assert_eq!(
+            hex(digest.finish()),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn test_digest_evlr() {
+        let evlr = DigestEvlr::new(42, [7u8; 32]);
+        let mut data = Vec::new();
+        evlr.write_to(&mut data).unwrap();
+        assert_eq!(data.len(), 60 + 42);
+        assert_eq!(DigestEvlr::read_from(data.as_slice()).unwrap(), Some(evlr));
+
+        // Other EVLRs are skipped
+        data[2] = b'x';
+        let mut src = Cursor::new(&data);
+        assert_eq!(DigestEvlr::read_from(&mut src).unwrap(), None);
+        assert_eq!(src.position(), data.len() as u64);
+    }
+
+    #[test]
+    fn test_digest_survives_rechunking() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let points = (0..=255u8).cycle().take(20 * 50).collect::<Vec<u8>>();
+        let mut evlrs = Vec::new();
+        for chunk_size in [7, 50] {
+            let vlr = crate::LazVlrBuilder::new(items.clone())
+                .with_fixed_chunk_size(chunk_size)
+                .build();
+            let mut compressor =
+                crate::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+            compressor.set_compute_digest(true);
+            compressor.compress_many(&points).unwrap();
+            let evlr = DigestEvlr::new(50, compressor.digest().unwrap());
+            let mut compressed = compressor.finish().unwrap();
+            compressed.set_position(0);
+            assert!(verify_digest(&mut compressed, vlr.clone(), &evlr).unwrap());
+
+            let mut modified = points.clone();
+            modified[123] ^= 1;
+            let mut compressed = Cursor::new(Vec::<u8>::new());
+            crate::compress_buffer(&mut compressed, &modified, vlr.clone()).unwrap();
+            compressed.set_position(0);
+            assert!(!verify_digest(compressed, vlr, &evlr).unwrap());
+            evlrs.push(evlr);
+        }
+        assert_eq!(evlrs[0], evlrs[1]);
+    }
+}
@@ -0,0 +1,278 @@
+//! Compression of points of the extended point formats (6 to 10)
+//! as points of the legacy point formats (1 & 3)
+use std::io::{Seek, Write};
+
+use crate::las::Point0;
+use crate::LasZipError;
+
+use super::{LasZipCompressor, LazVlr, LazVlrBuilder};
+
+pub(super) const POINT14_SIZE: usize = 30;
+pub(super) const RGB_SIZE: usize = 6;
+const NIR_SIZE: usize = 2;
+const WAVEPACKET_SIZE: usize = crate::las::wavepacket::WAVEPACKET_SIZE;
+pub(super) const GPS_TIME_SIZE: usize = 8;
+
+/// Legacy classification given to the points which have the overlap flag set
+pub const OVERLAP_CLASSIFICATION: u8 = 12;
+/// Legacy classification given to the points whose extended classification
+/// is greater than 31 (which is the maximum legacy classification)
+pub const UNCLASSIFIED: u8 = 1;
+
+/// Returns the legacy point format which the points of the extended
+/// `point_format_id` are converted to, `None` if the format is not an extended one.
+///
+/// | extended | legacy |
+/// |----------|--------|
+/// | 6        | 1      |
+/// | 7        | 3      |
+/// | 8        | 3      |
+/// | 9        | 1      |
+/// | 10       | 3      |
+pub fn legacy_point_format_of(point_format_id: u8) -> Option<u8> {
+    match point_format_id {
+        6 | 9 => Some(1),
+        7 | 8 | 10 => Some(3),
+        _ => None,
+    }
+}
+
+/// Returns the size, without extra bytes, of points of the extended `point_format_id`
+pub(super) fn extended_point_size(point_format_id: u8) -> Option<usize> {
+    match point_format_id {
+        6 => Some(POINT14_SIZE),
+        7 => Some(POINT14_SIZE + RGB_SIZE),
+        8 => Some(POINT14_SIZE + RGB_SIZE + NIR_SIZE),
+        9 => Some(POINT14_SIZE + WAVEPACKET_SIZE),
+        10 => Some(POINT14_SIZE + RGB_SIZE + NIR_SIZE + WAVEPACKET_SIZE),
+        _ => None,
+    }
+}
+
+/// Returns the size, without extra bytes, of points of the legacy `point_format_id`
+fn legacy_point_size(point_format_id: u8) -> usize {
+    if point_format_id == 3 {
+        Point0::SIZE + GPS_TIME_SIZE + RGB_SIZE
+    } else {
+        Point0::SIZE + GPS_TIME_SIZE
+    }
+}
+
+/// Converts the `src` point of the extended `point_format_id` into a point of the
+/// legacy format given by [`legacy_point_format_of`], written into `dst`.
+///
+/// Both points are LAS records in little endian, the extra bytes (everything
+/// after the point format's fields) are copied as-is.
+///
+/// The conversion is lossy:
+///
+/// - The return number and number of returns are clamped to 7.
+/// - Extended classifications greater than 31 become [`UNCLASSIFIED`].
+/// - Points with the overlap flag become [`OVERLAP_CLASSIFICATION`].
+/// - The synthetic, key-point & withheld flags are kept.
+/// - The scanner channel is dropped.
+/// - The scan angle is rounded to the nearest degree and clamped to [-90, 90].
+/// - The NIR and the wave packet are dropped.
+///
+/// # Panics
+///
+/// Panics if the `point_format_id` is not an extended one,
+/// or if `src` and `dst` do not have the same number of extra bytes.
+pub fn downgrade_point(point_format_id: u8, src: &[u8], dst: &mut [u8]) {
+    let src_size = extended_point_size(point_format_id).expect("Not an extended point format");
+    let legacy_format = legacy_point_format_of(point_format_id).unwrap();
+    let dst_size = legacy_point_size(legacy_format);
+    assert_eq!(src.len() - src_size, dst.len() - dst_size);
+
+    // x, y, z & intensity
+    dst[..14].copy_from_slice(&src[..14]);
+
+    let return_number = (src[14] & 0xF).min(7);
+    let number_of_returns = (src[14] >> 4).min(7);
+    let classification_flags = src[15] & 0xF;
+    let scan_direction_and_edge = src[15] & 0xC0;
+    dst[14] = return_number | number_of_returns << 3 | scan_direction_and_edge;
+
+    let overlap = classification_flags & 0x8 != 0;
+    let classification = match src[16] {
+        _ if overlap => OVERLAP_CLASSIFICATION,
+        classification if classification > 31 => UNCLASSIFIED,
+        classification => classification,
+    };
+    // synthetic, key-point & withheld are the bits 5, 6 & 7
+    dst[15] = classification | (classification_flags & 0x7) << 5;
+
+    let scan_angle = i16::from_le_bytes([src[18], src[19]]);
+    let scan_angle_rank = (f32::from(scan_angle) * 0.006).round().clamp(-90.0, 90.0);
+    dst[16] = scan_angle_rank as i8 as u8;
+    // user data
+    dst[17] = src[17];
+    // point source id
+    dst[18..20].copy_from_slice(&src[20..22]);
+    // gps time
+    dst[20..28].copy_from_slice(&src[22..30]);
+
+    if legacy_format == 3 {
+        dst[28..34].copy_from_slice(&src[30..36]);
+    }
+
+    dst[dst_size..].copy_from_slice(&src[src_size..]);
+}
+
+/// Compressor that takes points of the extended point formats (6 to 10)
+/// and compresses them as points of the legacy point formats (1 & 3),
+/// for consumers that can only read legacy formats.
+///
+/// See [`downgrade_point`] for how points are converted.
+pub struct DowngradingLasZipCompressor<'a, W: Write + Send + 'a> {
+    compressor: LasZipCompressor<'a, W>,
+    point_format_id: u8,
+    point: Vec<u8>,
+    extended_point_size: usize,
+}
+
+impl<'a, W: Write + Seek + Send + 'a> DowngradingLasZipCompressor<'a, W> {
+    /// Creates a compressor for points of the extended `point_format_id`
+    /// with `num_extra_bytes`.
+    ///
+    /// The compressed points use the legacy point format given by [`legacy_point_format_of`],
+    /// the vlr to be written in the file is given by [`Self::vlr`].
+    pub fn new(output: W, point_format_id: u8, num_extra_bytes: u16) -> crate::Result<Self> {
+        let legacy_format = legacy_point_format_of(point_format_id)
+            .ok_or(LasZipError::UnsupportedPointFormat(point_format_id))?;
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(legacy_format, num_extra_bytes)?
+            .build();
+        let point = vec![0u8; vlr.items_size() as usize];
+        Ok(Self {
+            compressor: LasZipCompressor::new(output, vlr)?,
+            point_format_id,
+            point,
+            extended_point_size: extended_point_size(point_format_id).unwrap()
+                + usize::from(num_extra_bytes),
+        })
+    }
+
+    /// Converts and compresses one point
+    pub fn compress_one(&mut self, input: &[u8]) -> std::io::Result<()> {
+        downgrade_point(self.point_format_id, input, &mut self.point);
+        self.compressor.compress_one(&self.point)
+    }
+
+    /// Converts and compresses all the points contained in the `input` slice
+    pub fn compress_many(&mut self, input: &[u8]) -> std::io::Result<()> {
+        for point in input.chunks_exact(self.extended_point_size) {
+            self.compress_one(point)?;
+        }
+        Ok(())
+    }
+
+    /// Must be called when you have compressed all your points.
+    pub fn done(&mut self) -> std::io::Result<()> {
+        self.compressor.done()
+    }
+
+    /// Calls [`done`], flushes the output and returns it,
+    /// see [`LasZipCompressor::finish`].
+    ///
+    /// [`done`]: Self::done
+    pub fn finish(self) -> crate::Result<W> {
+        self.compressor.finish()
+    }
+
+    /// Returns the legacy point format id of the compressed points
+    pub fn legacy_point_format_id(&self) -> u8 {
+        legacy_point_format_of(self.point_format_id).unwrap()
+    }
+
+    /// Returns the vlr describing the compressed (legacy) points.
+    pub fn vlr(&self) -> &LazVlr {
+        self.compressor.vlr()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.compressor.into_inner()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.compressor.get_mut()
+    }
+
+    pub fn get(&self) -> &W {
+        self.compressor.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn point7(i: u8) -> Vec<u8> {
+        let mut point = vec![0u8; POINT14_SIZE + RGB_SIZE + 1];
+        point[..4].copy_from_slice(&i32::from(i).to_le_bytes());
+        point[12..14].copy_from_slice(&300u16.to_le_bytes());
+        point[17] = i;
+        point[20..22].copy_from_slice(&7u16.to_le_bytes());
+        point[22..30].copy_from_slice(&f64::from(i).to_le_bytes());
+        point[30..36].copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+        point[36] = 42;
+        point
+    }
+
+    #[test]
+    fn test_downgrade_point() {
+        let mut src = point7(3);
+        // return 9 of 12, scanner channel 2, edge of flight line, key-point & overlap
+        src[14] = 9 | 12 << 4;
+        src[15] = 0x2 | 0x8 | 2 << 4 | 0x80;
+        src[16] = 2;
+        // 45.006°
+        src[18..20].copy_from_slice(&7501i16.to_le_bytes());
+
+        let mut dst = vec![0u8; 35];
+        downgrade_point(7, &src, &mut dst);
+        assert_eq!(dst[..14], src[..14]);
+        assert_eq!(dst[14], 7 | 7 << 3 | 0x80);
+        assert_eq!(dst[15], OVERLAP_CLASSIFICATION | 0x2 << 5);
+        assert_eq!(dst[16] as i8, 45);
+        assert_eq!(dst[17], 3);
+        assert_eq!(dst[18..20], 7u16.to_le_bytes());
+        assert_eq!(dst[20..28], 3f64.to_le_bytes());
+        assert_eq!(dst[28..34], [1, 2, 3, 4, 5, 6]);
+        assert_eq!(dst[34], 42);
+
+        src[15] = 0;
+        src[16] = 64;
+        src[18..20].copy_from_slice(&(-20000i16).to_le_bytes());
+        downgrade_point(7, &src, &mut dst);
+        assert_eq!(dst[15], UNCLASSIFIED);
+        assert_eq!(dst[16] as i8, -90);
+    }
+
+    #[test]
+    fn test_downgrading_compressor() {
+        let src = (0..100).flat_map(point7).collect::<Vec<u8>>();
+        let mut compressor =
+            DowngradingLasZipCompressor::new(Cursor::new(Vec::<u8>::new()), 7, 1).unwrap();
+        assert_eq!(compressor.legacy_point_format_id(), 3);
+        compressor.compress_many(&src).unwrap();
+        compressor.done().unwrap();
+        let vlr = compressor.vlr().clone();
+        let compressed = compressor.into_inner().into_inner();
+
+        let mut points = vec![0u8; 100 * vlr.items_size() as usize];
+        crate::decompress_buffer(&compressed, &mut points, vlr).unwrap();
+        let mut expected = vec![0u8; 35];
+        for (src, point) in src.chunks_exact(37).zip(points.chunks_exact(35)) {
+            downgrade_point(7, src, &mut expected);
+            assert_eq!(point, expected.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_not_extended_format() {
+        assert!(DowngradingLasZipCompressor::new(Cursor::new(Vec::<u8>::new()), 3, 0).is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Compression that only counts the bytes it would write
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::{ChunkTable, ChunkTableEntry, LasZipCompressor, LazVlr};
+
+/// Chunk size used to sample points compressed in variable-size chunks
+const VARIABLE_CHUNK_SAMPLE_SIZE: u64 = 50_000;
+
+/// Writer that discards the data written to it, but keeps track of
+/// its position and of its length, as if it was a file.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ByteCounter {
+    pos: u64,
+    len: u64,
+}
+
+impl ByteCounter {
+    /// Returns the number of bytes that would have been written,
+    /// that is, the end of the furthest write.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pos += buf.len() as u64;
+        self.len = self.len.max(self.pos);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for ByteCounter {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        self.pos = new_pos.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+/// Exact size of compressed data, as computed by [`dry_run`]
+#[derive(Debug, Clone)]
+pub struct CompressedSize {
+    /// Total number of bytes, including the offset to the chunk table and the chunk table
+    pub total: u64,
+    /// Number of points and of bytes of each chunk
+    pub chunks: ChunkTable,
+}
+
+/// Runs the compression of the `uncompressed_points`, as [`compress_buffer`] would,
+/// but without writing anything, to get the exact size of the compressed data.
+///
+/// To compute the size of points that do not fit in memory, use a [`LasZipCompressor`]
+/// with a [`ByteCounter`] as output.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+/// let size = laz::laszip::dry_run(&[0u8; 20 * 25], vlr)?;
+/// assert_eq!(size.chunks.len(), 3);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`compress_buffer`]: crate::compress_buffer
+pub fn dry_run(uncompressed_points: &[u8], vlr: LazVlr) -> crate::Result<CompressedSize> {
+    let mut compressor = LasZipCompressor::new(ByteCounter::default(), vlr)?;
+    compressor.compress_many(uncompressed_points)?;
+    compressor.done()?;
+    Ok(CompressedSize {
+        total: compressor.get().len(),
+        chunks: compressor.chunk_table().clone(),
+    })
+}
+
+/// Estimation of the size of compressed data, as computed by [`estimate_compressed_size`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SizeEstimate {
+    /// Estimated total number of bytes
+    pub estimate: u64,
+    /// Lower bound of the 95% confidence interval of the total number of bytes
+    pub lower_bound: u64,
+    /// Upper bound of the 95% confidence interval of the total number of bytes
+    pub upper_bound: u64,
+    /// Number of chunks that were compressed to compute the estimation
+    pub num_sampled_chunks: usize,
+}
+
+/// Estimates the size the `num_points` uncompressed points of the `src` would have
+/// once compressed, by compressing `num_samples` chunks evenly spread in the `src`.
+///
+/// The `src` position must be at the start of the points, the points are
+/// read in chunks of the `vlr`'s chunk size (50 000 points for variable-size chunks).
+///
+/// When all the chunks are sampled, the estimate is exact, the chunk table
+/// being written with the sizes of the compressed chunks.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+/// let points = std::io::Cursor::new(vec![0u8; 20 * 1_000]);
+/// let estimate = laz::laszip::estimate_compressed_size(points, &vlr, 1_000, 8)?;
+/// assert!(estimate.lower_bound <= estimate.estimate);
+/// assert!(estimate.estimate <= estimate.upper_bound);
+/// # Ok(())
+/// # }
+/// ```
+pub fn estimate_compressed_size<R: Read + Seek>(
+    mut src: R,
+    vlr: &LazVlr,
+    num_points: u64,
+    num_samples: usize,
+) -> crate::Result<SizeEstimate> {
+    let point_size = vlr.items_size();
+    let chunk_size = if vlr.uses_variable_size_chunks() {
+        VARIABLE_CHUNK_SAMPLE_SIZE
+    } else {
+        u64::from(vlr.chunk_size())
+    };
+    let num_chunks = num_points.div_ceil(chunk_size);
+    let num_samples = (num_samples.max(1) as u64).min(num_chunks);
+    let start = src.stream_position()?;
+
+    // Bytes per point of each sampled chunk
+    let mut rates = Vec::with_capacity(num_samples as usize);
+    let mut sampled_chunks = ChunkTable::with_capacity(num_samples as usize);
+    let mut sampled_bytes = 0u64;
+    let mut sampled_points = 0u64;
+    let mut points = Vec::<u8>::new();
+    for i in 0..num_samples {
+        let chunk_index = i * num_chunks / num_samples;
+        let first_point = chunk_index * chunk_size;
+        let chunk_point_count = chunk_size.min(num_points - first_point);
+        src.seek(SeekFrom::Start(start + first_point * point_size))?;
+        points.resize((chunk_point_count * point_size) as usize, 0u8);
+        src.read_exact(&mut points)?;
+
+        let mut compressor = LasZipCompressor::new(ByteCounter::default(), vlr.clone())?;
+        compressor.compress_many(&points)?;
+        compressor.done()?;
+        let chunk_bytes = compressor.chunk_table()[0].byte_count;
+        rates.push(chunk_bytes as f64 / chunk_point_count as f64);
+        sampled_chunks.push(ChunkTableEntry {
+            point_count: chunk_point_count,
+            byte_count: chunk_bytes,
+        });
+        sampled_bytes += chunk_bytes;
+        sampled_points += chunk_point_count;
+    }
+
+    let all_sampled = num_samples >= num_chunks;
+    let (mean, half_width) = if rates.is_empty() {
+        (0.0, 0.0)
+    } else {
+        let n = rates.len() as f64;
+        let mean = sampled_bytes as f64 / sampled_points as f64;
+        let half_width = if all_sampled || rates.len() < 2 {
+            0.0
+        } else {
+            let variance = rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            let finite_population_correction =
+                ((num_chunks as f64 - n) / (num_chunks as f64 - 1.0)).sqrt();
+            1.96 * (variance / n).sqrt() * finite_population_correction
+        };
+        (mean, half_width)
+    };
+
+    // The offset to the chunk table and the chunk table itself,
+    // whose size depends on the byte counts of the chunks
+    let chunk_table = if all_sampled {
+        sampled_chunks
+    } else {
+        let mut chunk_table = ChunkTable::with_capacity(num_chunks as usize);
+        for i in 0..num_chunks {
+            let point_count = chunk_size.min(num_points - i * chunk_size);
+            chunk_table.push(ChunkTableEntry {
+                point_count,
+                byte_count: (mean * point_count as f64) as u64,
+            });
+        }
+        chunk_table
+    };
+    let mut overhead = ByteCounter::default();
+    overhead.write_all(&[0u8; std::mem::size_of::<i64>()])?;
+    chunk_table.write_to(&mut overhead, vlr)?;
+
+    let total = |rate: f64| (rate.max(0.0) * num_points as f64).round() as u64 + overhead.len();
+    let (estimate, lower_bound, upper_bound) = if all_sampled {
+        let exact = sampled_bytes + overhead.len();
+        (exact, exact, exact)
+    } else {
+        (
+            total(mean),
+            total(mean - half_width),
+            total(mean + half_width),
+        )
+    };
+    Ok(SizeEstimate {
+        estimate,
+        lower_bound,
+        upper_bound,
+        num_sampled_chunks: rates.len(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::las::Point0;
+    use crate::{LazItemRecordBuilder, LazVlrBuilder};
+
+    #[test]
+    fn test_dry_run_matches_compression() {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(100).build();
+        let points = (0..=255u8).cycle().take(20 * 1234).collect::<Vec<u8>>();
+
+        let size = dry_run(&points, vlr.clone()).unwrap();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        assert_eq!(size.total, compressed.get_ref().len() as u64);
+
+        compressed.set_position(0);
+        let chunk_table = ChunkTable::read_from(&mut compressed, &vlr).unwrap();
+        assert_eq!(size.chunks.len(), chunk_table.len());
+        for (a, b) in size.chunks.as_ref().iter().zip(chunk_table.as_ref()) {
+            assert_eq!(a.byte_count, b.byte_count);
+        }
+    }
+
+    #[test]
+    fn test_estimate_compressed_size() {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(100).build();
+        let points = (0..=255u8).cycle().take(20 * 1234).collect::<Vec<u8>>();
+        let exact = dry_run(&points, vlr.clone()).unwrap().total;
+
+        let estimate = estimate_compressed_size(Cursor::new(&points), &vlr, 1234, 100).unwrap();
+        assert_eq!(estimate.num_sampled_chunks, 13);
+        assert_eq!(estimate.lower_bound, estimate.upper_bound);
+        assert_eq!(estimate.estimate, exact);
+
+        let estimate = estimate_compressed_size(Cursor::new(&points), &vlr, 1234, 4).unwrap();
+        assert_eq!(estimate.num_sampled_chunks, 4);
+        assert!(estimate.lower_bound <= estimate.estimate);
+        assert!(estimate.estimate <= estimate.upper_bound);
+    }
+
+    #[test]
+    fn test_byte_counter_seek() {
+        let mut counter = ByteCounter::default();
+        counter.write_all(&[0u8; 10]).unwrap();
+        counter.seek(SeekFrom::Start(2)).unwrap();
+        counter.write_all(&[0u8; 4]).unwrap();
+        assert_eq!(counter.len(), 10);
+        assert_eq!(counter.seek(SeekFrom::Current(0)).unwrap(), 6);
+        assert!(counter.seek(SeekFrom::End(-11)).is_err());
+    }
+}
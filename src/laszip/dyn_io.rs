@@ -0,0 +1,106 @@
+//! Compressors and decompressors over type-erased sources & destinations
+//!
+//! They allow to create (de)compressors when the type of the IO is
+//! only known at runtime, e.g. in plugins or FFI layers.
+use std::io::{Read, Seek, Write};
+
+use super::{LasZipCompressor, LasZipDecompressor, LazVlr};
+
+/// Sources of compressed data, usable as trait objects
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+/// Destinations of compressed data, usable as trait objects
+pub trait WriteSeek: Write + Seek {}
+
+impl<T: Write + Seek + ?Sized> WriteSeek for T {}
+
+/// Boxed source of compressed data
+pub type DynSource<'a> = Box<dyn ReadSeek + Send + 'a>;
+
+/// Boxed destination of compressed data
+pub type DynDestination<'a> = Box<dyn WriteSeek + Send + 'a>;
+
+/// [`LasZipDecompressor`] that reads from a [`DynSource`]
+pub type DynLasZipDecompressor<'a> = LasZipDecompressor<'a, DynSource<'a>>;
+
+/// [`LasZipCompressor`] that writes to a [`DynDestination`]
+pub type DynLasZipCompressor<'a> = LasZipCompressor<'a, DynDestination<'a>>;
+
+impl<'a> LasZipDecompressor<'a, DynSource<'a>> {
+    /// Creates a decompressor reading from a boxed source
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// use laz::laszip::DynLasZipDecompressor;
+    /// # let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    /// # let vlr = laz::LazVlrBuilder::new(items).build();
+    /// # let mut compressed = std::io::Cursor::new(Vec::<u8>::new());
+    /// # laz::compress_buffer(&mut compressed, &[0u8; 20], vlr.clone())?;
+    /// # compressed.set_position(0);
+    /// let mut decompressor = DynLasZipDecompressor::new_dyn(Box::new(compressed), vlr)?;
+    /// let mut point = [0u8; 20];
+    /// decompressor.decompress_one(&mut point)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_dyn(source: DynSource<'a>, vlr: LazVlr) -> crate::Result<Self> {
+        Self::new(source, vlr)
+    }
+}
+
+impl<'a> LasZipCompressor<'a, DynDestination<'a>> {
+    /// Creates a compressor writing to a boxed destination
+    pub fn new_dyn(output: DynDestination<'a>, vlr: LazVlr) -> crate::Result<Self> {
+        Self::new(output, vlr)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<'a> super::parallel::ParLasZipDecompressor<DynSource<'a>> {
+    /// Creates a decompressor reading from a boxed source
+    pub fn new_dyn(source: DynSource<'a>, vlr: LazVlr) -> crate::Result<Self> {
+        Self::new(source, vlr)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<'a> super::parallel::ParLasZipCompressor<DynDestination<'a>> {
+    /// Creates a compressor writing to a boxed destination
+    pub fn new_dyn(dest: DynDestination<'a>, vlr: LazVlr) -> crate::Result<Self> {
+        Self::new(dest, vlr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::las::Point0;
+    use crate::{LazItemRecordBuilder, LazVlrBuilder};
+
+    #[test]
+    fn test_dyn_round_trip() {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = (0..=255u8).cycle().take(20 * 25).collect::<Vec<u8>>();
+
+        let mut output = Cursor::new(Vec::<u8>::new());
+        {
+            let mut compressor =
+                DynLasZipCompressor::new_dyn(Box::new(&mut output), vlr.clone()).unwrap();
+            compressor.compress_many(&points).unwrap();
+            compressor.done().unwrap();
+        }
+
+        output.set_position(0);
+        let mut decompressor = DynLasZipDecompressor::new_dyn(Box::new(output), vlr).unwrap();
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, points);
+    }
+}
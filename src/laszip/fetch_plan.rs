@@ -0,0 +1,337 @@
+//! Planning of the byte ranges to fetch from a remote LAZ file
+//!
+//! Starting from the first bytes of the file (the LAS header and the VLRs),
+//! [`FetchPlan`] gives the ranges to fetch to get the chunk table,
+//! then the chunks of the points wanted, so that a client that only has
+//! range requests (e.g. HTTP) knows what to fetch before decoding anything.
+use std::io::Cursor;
+use std::ops::Range;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::las::file::QuickHeader;
+use crate::LasZipError;
+
+use super::{ChunkTable, LazVlr};
+
+/// Offset of the start of the first EVLR in the LAS 1.4 header
+const START_OF_FIRST_EVLR_POS: u64 = 235;
+/// Size of the header of a VLR, without its data
+const VLR_HEADER_SIZE: usize = 54;
+
+fn unexpected_eof(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::UnexpectedEof, message.to_string())
+}
+
+/// Byte ranges of the chunks that hold some points
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointsFetch {
+    /// Range of the file to fetch
+    pub bytes: Range<u64>,
+    /// Indices of the chunks in the `bytes`
+    pub chunks: Range<usize>,
+    /// Index of the first point of the first chunk,
+    /// the points before the ones wanted have to be skipped after decompression
+    pub first_point: u64,
+}
+
+/// Ranges of bytes of a LAZ file to fetch, see the [module](self) documentation
+///
+/// Only the chunk table stored after the points is supported,
+/// not the LASzip special EVLRs.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use laz::laszip::fetch_plan::FetchPlan;
+///
+/// // e.g. as fetched with HTTP range requests
+/// let file = std::fs::read("tests/data/point10.laz")?;
+/// let fetch = |range: std::ops::Range<u64>| &file[range.start as usize..range.end as usize];
+///
+/// let len = FetchPlan::required_prefix_len(fetch(0..375))?;
+/// let mut plan = FetchPlan::from_prefix(fetch(0..len), file.len() as u64)?;
+/// // The offset to the chunk table was in the prefix
+/// assert!(plan.chunk_table_offset_range().is_none());
+///
+/// let chunk_table_range = plan.chunk_table_range().unwrap();
+/// plan.set_chunk_table(fetch(chunk_table_range))?;
+/// let points = plan.points_range(10..20)?;
+/// // The compressed points of the chunks, to decompress with a `laz::LasZipDecompressor`
+/// let chunks = fetch(points.bytes);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FetchPlan {
+    vlr: LazVlr,
+    num_points: u64,
+    offset_to_points: u64,
+    /// Start of the EVLRs, or the end of the file
+    end_of_points: u64,
+    file_len: u64,
+    chunk_table_offset: Option<u64>,
+    /// Whether the offset to the chunk table is to be read at the end of the file,
+    /// as the writer could not update it
+    offset_at_end: bool,
+    chunk_table: Option<ChunkTable>,
+}
+
+impl FetchPlan {
+    /// Returns the number of first bytes of the file needed by [`Self::from_prefix`],
+    /// the header and VLRs, and the offset to the chunk table.
+    ///
+    /// `header` must at least hold the LAS header (375 bytes are always enough).
+    pub fn required_prefix_len(header: &[u8]) -> std::io::Result<u64> {
+        let header = QuickHeader::read_from(&mut Cursor::new(header))?;
+        Ok(u64::from(header.offset_to_points) + 8)
+    }
+
+    /// Creates the plan from the first bytes of the file, that must at least
+    /// contain the header and the VLRs, and the total length of the file.
+    ///
+    /// When the `prefix` also holds the offset to the chunk table, see
+    /// [`Self::required_prefix_len`], it is used directly.
+    pub fn from_prefix(prefix: &[u8], file_len: u64) -> crate::Result<Self> {
+        let mut src = Cursor::new(prefix);
+        let header = QuickHeader::read_from(&mut src)?;
+        if header.point_format_id & 0x80 == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "data is not compressed",
+            )
+            .into());
+        }
+        let offset_to_points = u64::from(header.offset_to_points);
+
+        let mut vlr = None;
+        let mut vlr_start = usize::from(header.header_size);
+        for _ in 0..header.num_vlrs {
+            let vlr_header = prefix
+                .get(vlr_start..vlr_start + VLR_HEADER_SIZE)
+                .ok_or_else(|| unexpected_eof("the VLRs are not in the prefix"))?;
+            let user_id = &vlr_header[2..18];
+            let record_id = (&vlr_header[18..20]).read_u16::<LittleEndian>()?;
+            let record_length = usize::from((&vlr_header[20..22]).read_u16::<LittleEndian>()?);
+            let data_start = vlr_start + VLR_HEADER_SIZE;
+            let data = prefix
+                .get(data_start..data_start + record_length)
+                .ok_or_else(|| unexpected_eof("the VLRs are not in the prefix"))?;
+            if record_id == LazVlr::RECORD_ID
+                && user_id.starts_with(LazVlr::USER_ID.as_bytes())
+                && user_id[LazVlr::USER_ID.len()..].iter().all(|&b| b == 0)
+            {
+                vlr = Some(LazVlr::read_from(data)?);
+            }
+            vlr_start = data_start + record_length;
+        }
+        let vlr =
+            vlr.ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no LasZip VLR"))?;
+        if vlr.special_evlrs().is_some() {
+            return Err(LasZipError::MissingChunkTable);
+        }
+
+        let mut end_of_points = file_len;
+        if header.major == 1 && header.minor >= 4 {
+            src.set_position(START_OF_FIRST_EVLR_POS);
+            let start_of_first_evlr = src.read_u64::<LittleEndian>()?;
+            if start_of_first_evlr > offset_to_points {
+                end_of_points = end_of_points.min(start_of_first_evlr);
+            }
+        }
+
+        let mut plan = Self {
+            vlr,
+            num_points: header.num_points,
+            offset_to_points,
+            end_of_points,
+            file_len,
+            chunk_table_offset: None,
+            offset_at_end: false,
+            chunk_table: None,
+        };
+        if let Some(mut offset) =
+            prefix.get(offset_to_points as usize..offset_to_points as usize + 8)
+        {
+            plan.set_chunk_table_offset(offset.read_i64::<LittleEndian>()?)?;
+        }
+        Ok(plan)
+    }
+
+    pub fn vlr(&self) -> &LazVlr {
+        &self.vlr
+    }
+
+    /// Number of points, as given by the header
+    pub fn num_points(&self) -> u64 {
+        self.num_points
+    }
+
+    /// Returns the range to fetch to know the offset to the chunk table,
+    /// `None` if it is known.
+    ///
+    /// It is the start of the point data, or the last 8 bytes of the file
+    /// if the offset at the start of the point data was not written.
+    pub fn chunk_table_offset_range(&self) -> Option<Range<u64>> {
+        if self.chunk_table_offset.is_some() {
+            None
+        } else if self.offset_at_end {
+            Some(self.file_len.saturating_sub(8)..self.file_len)
+        } else {
+            Some(self.offset_to_points..self.offset_to_points + 8)
+        }
+    }
+
+    /// Sets the `offset` to the chunk table, read from the [`Self::chunk_table_offset_range`].
+    ///
+    /// When the offset was not written, the [`Self::chunk_table_offset_range`] becomes
+    /// the end of the file, if the offset there is not written either,
+    /// [`LasZipError::MissingChunkTable`] is returned.
+    pub fn set_chunk_table_offset(&mut self, offset: i64) -> crate::Result<()> {
+        if offset > self.offset_to_points as i64 && (offset as u64) < self.file_len {
+            self.chunk_table_offset = Some(offset as u64);
+            Ok(())
+        } else if !self.offset_at_end {
+            self.offset_at_end = true;
+            Ok(())
+        } else {
+            Err(LasZipError::MissingChunkTable)
+        }
+    }
+
+    /// Returns the range that holds the chunk table, `None` if the offset to it is not known
+    /// (see [`Self::chunk_table_offset_range`]).
+    ///
+    /// The size of the chunk table is not stored, so the range goes to the EVLRs
+    /// or to the end of the file.
+    pub fn chunk_table_range(&self) -> Option<Range<u64>> {
+        self.chunk_table_offset
+            .map(|offset| offset..self.end_of_points.max(offset))
+    }
+
+    /// Reads the chunk table from the `bytes` of the [`Self::chunk_table_range`],
+    /// or from the start of it.
+    pub fn set_chunk_table(&mut self, bytes: &[u8]) -> crate::Result<&ChunkTable> {
+        let chunk_table = ChunkTable::read_table(Cursor::new(bytes), &self.vlr)?;
+        Ok(self.chunk_table.insert(chunk_table))
+    }
+
+    pub fn chunk_table(&self) -> Option<&ChunkTable> {
+        self.chunk_table.as_ref()
+    }
+
+    /// Returns the bytes of the chunks at the `chunks` indices, that are contiguous.
+    pub fn chunks_range(&self, chunks: Range<usize>) -> crate::Result<Range<u64>> {
+        let chunk_table = self
+            .chunk_table
+            .as_ref()
+            .ok_or(LasZipError::MissingChunkTable)?;
+        if chunks.end > chunk_table.len() || chunks.start > chunks.end {
+            return Err(LasZipError::ChunkIndexOutOfRange {
+                index: chunks.end.max(chunks.start),
+                num_chunks: chunk_table.len(),
+            });
+        }
+        let start = self.offset_to_points
+            + 8
+            + chunk_table[..chunks.start]
+                .iter()
+                .map(|entry| entry.byte_count)
+                .sum::<u64>();
+        let size = chunk_table[chunks]
+            .iter()
+            .map(|entry| entry.byte_count)
+            .sum::<u64>();
+        Ok(start..start + size)
+    }
+
+    /// Returns the bytes of the chunks that hold the points at the `points` indices.
+    pub fn points_range(&self, points: Range<u64>) -> crate::Result<PointsFetch> {
+        let chunk_table = self
+            .chunk_table
+            .as_ref()
+            .ok_or(LasZipError::MissingChunkTable)?;
+        if points.end > self.num_points || points.start > points.end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("points {:?} are outside of the data", points),
+            )
+            .into());
+        }
+        let mut first_chunk = None;
+        let mut first_point = 0;
+        let mut end_chunk = 0;
+        let mut chunk_start = 0u64;
+        for (index, entry) in chunk_table.as_ref().iter().enumerate() {
+            let chunk_end = chunk_start + entry.point_count;
+            if first_chunk.is_none() && points.start < chunk_end {
+                first_chunk = Some(index);
+                first_point = chunk_start;
+            }
+            if chunk_start < points.end {
+                end_chunk = index + 1;
+            }
+            chunk_start = chunk_end;
+        }
+        let first_chunk = match first_chunk {
+            Some(first_chunk) if !points.is_empty() => first_chunk,
+            _ => {
+                return Ok(PointsFetch {
+                    bytes: self.offset_to_points + 8..self.offset_to_points + 8,
+                    chunks: 0..0,
+                    first_point: points.start,
+                })
+            }
+        };
+        let chunks = first_chunk..end_chunk;
+        Ok(PointsFetch {
+            bytes: self.chunks_range(chunks.clone())?,
+            chunks,
+            first_point,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fetch_plan() {
+        let file = std::fs::read("tests/data/point10.laz").unwrap();
+        let fetch = |range: Range<u64>| &file[range.start as usize..range.end as usize];
+
+        let len = FetchPlan::required_prefix_len(&file[..375]).unwrap();
+        // Without the offset to the chunk table
+        let mut plan = FetchPlan::from_prefix(fetch(0..len - 8), file.len() as u64).unwrap();
+        assert_eq!(plan.chunk_table_range(), None);
+        let offset_range = plan.chunk_table_offset_range().unwrap();
+        assert_eq!(offset_range, len - 8..len);
+        let offset = fetch(offset_range).read_i64::<LittleEndian>().unwrap();
+        plan.set_chunk_table_offset(offset).unwrap();
+        assert_eq!(plan.chunk_table_offset_range(), None);
+
+        let chunk_table_range = plan.chunk_table_range().unwrap();
+        assert!(matches!(
+            plan.points_range(0..1),
+            Err(LasZipError::MissingChunkTable)
+        ));
+        let chunk_table = plan
+            .set_chunk_table(fetch(chunk_table_range.clone()))
+            .unwrap();
+        assert!(!chunk_table.is_empty());
+        let chunk_size = u64::from(plan.vlr().chunk_size());
+
+        let all = plan.points_range(0..plan.num_points()).unwrap();
+        assert_eq!(all.bytes, len..chunk_table_range.start);
+        assert_eq!(all.first_point, 0);
+
+        let last = plan
+            .points_range(plan.num_points() - 1..plan.num_points())
+            .unwrap();
+        assert_eq!(last.chunks.len(), 1);
+        assert_eq!(last.bytes.end, chunk_table_range.start);
+        assert_eq!(last.first_point % chunk_size, 0);
+    }
+}
@@ -0,0 +1,263 @@
+//! Filters that can be applied to points while they are decompressed
+use crate::laszip::{DecompressionSelection, LazItem, LazItemType};
+
+/// Which returns of a pulse a [`PointFilter`] keeps
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ReturnFilter {
+    /// Keeps the first return of each pulse (single returns included)
+    First,
+    /// Keeps the last return of each pulse (single returns included)
+    Last,
+    /// Keeps only the pulses that had a single return
+    Single,
+    /// Keeps the returns that are neither the first nor the last
+    Intermediate,
+}
+
+impl ReturnFilter {
+    fn matches(self, return_number: u8, number_of_returns: u8) -> bool {
+        match self {
+            ReturnFilter::First => return_number <= 1,
+            ReturnFilter::Last => return_number >= number_of_returns,
+            ReturnFilter::Single => number_of_returns <= 1,
+            ReturnFilter::Intermediate => return_number > 1 && return_number < number_of_returns,
+        }
+    }
+}
+
+/// Where the fields a [`PointFilter`] looks at are in a point record
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum PointLayout {
+    /// Point formats 0 to 5
+    Legacy,
+    /// Point formats 6 to 10
+    Extended,
+}
+
+impl PointLayout {
+    fn from_laz_items(items: &[LazItem]) -> Option<Self> {
+        match items.first()?.item_type() {
+            LazItemType::Point10 => Some(PointLayout::Legacy),
+            LazItemType::Point14 => Some(PointLayout::Extended),
+            _ => None,
+        }
+    }
+}
+
+/// Filter on the classification and the return number of points.
+///
+/// # Example
+///
+/// ```
+/// use laz::laszip::{PointFilter, ReturnFilter};
+///
+/// // Last returns classified as ground
+/// let filter = PointFilter::new()
+///     .with_classifications(&[2])
+///     .with_returns(ReturnFilter::Last);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PointFilter {
+    classifications: Option<Box<[bool; 256]>>,
+    returns: Option<ReturnFilter>,
+}
+
+impl Default for PointFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PointFilter {
+    /// Creates a filter that keeps every point
+    pub fn new() -> Self {
+        Self {
+            classifications: None,
+            returns: None,
+        }
+    }
+
+    /// Only keeps the points which have one of the given classifications.
+    ///
+    /// Calling this multiple times adds to the set of kept classifications.
+    pub fn with_classifications(mut self, classifications: &[u8]) -> Self {
+        let kept = self
+            .classifications
+            .get_or_insert_with(|| Box::new([false; 256]));
+        for &classification in classifications {
+            kept[classification as usize] = true;
+        }
+        self
+    }
+
+    /// Only keeps the points whose return matches the given `returns`
+    pub fn with_returns(mut self, returns: ReturnFilter) -> Self {
+        self.returns = Some(returns);
+        self
+    }
+
+    /// Returns whether the point record stored in `point` passes the filter.
+    ///
+    /// `items` are the items describing the point record,
+    /// points that are not Point10 or Point14 based are always kept.
+    pub fn matches(&self, items: &[LazItem], point: &[u8]) -> bool {
+        match PointLayout::from_laz_items(items) {
+            Some(layout) => self.matches_layout(layout, point),
+            None => true,
+        }
+    }
+
+    #[inline]
+    fn matches_layout(&self, layout: PointLayout, point: &[u8]) -> bool {
+        let (classification, return_number, number_of_returns) = match layout {
+            PointLayout::Legacy => (point[15] & 0x1F, point[14] & 0x7, (point[14] >> 3) & 0x7),
+            PointLayout::Extended => (point[16], point[14] & 0xF, point[14] >> 4),
+        };
+
+        if let Some(kept) = &self.classifications {
+            if !kept[classification as usize] {
+                return false;
+            }
+        }
+        if let Some(returns) = self.returns {
+            if !returns.matches(return_number, number_of_returns) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub(crate) fn compile(&self, items: &[LazItem]) -> CompiledPointFilter<'_> {
+        CompiledPointFilter {
+            filter: self,
+            layout: PointLayout::from_laz_items(items),
+        }
+    }
+}
+
+/// A [`PointFilter`] for which the point layout is known
+pub(crate) struct CompiledPointFilter<'a> {
+    filter: &'a PointFilter,
+    layout: Option<PointLayout>,
+}
+
+impl<'a> CompiledPointFilter<'a> {
+    #[inline]
+    pub(crate) fn matches(&self, point: &[u8]) -> bool {
+        match self.layout {
+            Some(layout) => self.filter.matches_layout(layout, point),
+            None => true,
+        }
+    }
+
+    /// Returns the fields the filter reads, when the points store
+    /// their fields in separate layers (point formats 6 to 10)
+    pub(crate) fn selection(&self) -> Option<DecompressionSelection> {
+        match self.layout {
+            Some(PointLayout::Extended) if self.filter.classifications.is_some() => {
+                Some(DecompressionSelection::CLASSIFICATION)
+            }
+            Some(PointLayout::Extended) => Some(DecompressionSelection::CHANNEL_RETURNS_XY),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LazItemRecordBuilder;
+
+    fn legacy_point(classification: u8, return_number: u8, number_of_returns: u8) -> [u8; 20] {
+        let mut point = [0u8; 20];
+        point[14] = return_number | (number_of_returns << 3);
+        point[15] = classification;
+        point
+    }
+
+    fn extended_point(classification: u8, return_number: u8, number_of_returns: u8) -> [u8; 30] {
+        let mut point = [0u8; 30];
+        point[14] = return_number | (number_of_returns << 4);
+        point[16] = classification;
+        point
+    }
+
+    #[test]
+    fn test_filter_legacy_points() {
+        let items = LazItemRecordBuilder::new()
+            .add_item(LazItemType::Point10)
+            .build();
+        let filter = PointFilter::new()
+            .with_classifications(&[2, 9])
+            .with_returns(ReturnFilter::Last);
+
+        assert!(filter.matches(&items, &legacy_point(2, 1, 1)));
+        assert!(filter.matches(&items, &legacy_point(9, 3, 3)));
+        // classification flags are not part of the classification
+        assert!(filter.matches(&items, &legacy_point(2 | 0x20, 2, 2)));
+        assert!(!filter.matches(&items, &legacy_point(2, 1, 2)));
+        assert!(!filter.matches(&items, &legacy_point(5, 1, 1)));
+    }
+
+    #[test]
+    fn test_filter_extended_points() {
+        let items = LazItemRecordBuilder::new()
+            .add_item(LazItemType::Point14)
+            .build();
+        let filter = PointFilter::new().with_classifications(&[40]);
+        assert!(filter.matches(&items, &extended_point(40, 1, 1)));
+        assert!(!filter.matches(&items, &extended_point(2, 1, 1)));
+
+        let filter = PointFilter::new().with_returns(ReturnFilter::Intermediate);
+        assert!(filter.matches(&items, &extended_point(0, 7, 15)));
+        assert!(!filter.matches(&items, &extended_point(0, 15, 15)));
+        assert!(!filter.matches(&items, &extended_point(0, 1, 15)));
+    }
+
+    #[test]
+    fn test_decompress_filtered_layered() {
+        use crate::{LasZipDecompressor, LazVlrBuilder};
+        use std::io::Cursor;
+
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(6, 0)
+            .unwrap()
+            .with_fixed_chunk_size(50)
+            .build();
+        // The second chunk has no ground point
+        let points = (0..250u32)
+            .flat_map(|i| {
+                let classification = if i / 50 != 1 && i % 3 == 0 { 2 } else { 1 };
+                let mut point = extended_point(classification, 1, 1);
+                point[0..4].copy_from_slice(&i.to_le_bytes());
+                point[12..14].copy_from_slice(&(i as u16 * 7).to_le_bytes());
+                point[22..30].copy_from_slice(&f64::from(i).to_le_bytes());
+                point
+            })
+            .collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let filter = PointFilter::new().with_classifications(&[2]);
+        let expected = points
+            .chunks_exact(30)
+            .filter(|point| point[16] == 2)
+            .flatten()
+            .copied()
+            .collect::<Vec<u8>>();
+
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        let mut decompressed = Vec::new();
+        // The first call ends, and the second one starts, in the middle of a chunk
+        let num_kept = decompressor
+            .decompress_filtered(25, &filter, &mut decompressed)
+            .unwrap();
+        assert_eq!(num_kept, 9);
+        let num_kept = decompressor
+            .decompress_filtered(225, &filter, &mut decompressed)
+            .unwrap();
+        assert_eq!(num_kept, 58);
+        assert_eq!(decompressed, expected);
+    }
+}
@@ -0,0 +1,370 @@
+//! Compression of points in small self-contained frames, for streaming
+//!
+//! Unlike a LAZ file, there is no chunk table: each frame holds the points of one batch,
+//! compressed with their own codec state, after a header that gives its size
+//! and sequence number. A frame can be decompressed without the others,
+//! so frames that are lost or that arrive out of order do not prevent decoding the rest.
+//!
+//! The header of a frame is 16 bytes, in little endian:
+//!
+//! | size | content                            |
+//! |------|------------------------------------|
+//! | 4    | number of bytes of compressed data |
+//! | 8    | sequence number                    |
+//! | 4    | number of points                   |
+use std::convert::TryFrom;
+use std::io::{Cursor, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::record::RecordCompressor;
+
+use super::details::{invalid_data, record_compressor_from_vlr, record_decompressor_from_vlr};
+use super::{LazVlr, PointBuffer};
+
+/// Size of the header of a frame
+pub const FRAME_HEADER_SIZE: usize = 16;
+
+/// Default number of bytes the points of a frame can take once decompressed,
+/// see [`FrameDecoder::set_memory_limit`]
+pub const DEFAULT_FRAME_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// Header of a frame
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Number of bytes of compressed data after the header
+    pub payload_size: u32,
+    /// Index of the frame among the ones of its [`FrameEncoder`]
+    pub sequence: u64,
+    pub num_points: u32,
+}
+
+impl FrameHeader {
+    pub fn read_from<R: Read>(mut src: R) -> std::io::Result<Self> {
+        Ok(Self {
+            payload_size: src.read_u32::<LittleEndian>()?,
+            sequence: src.read_u64::<LittleEndian>()?,
+            num_points: src.read_u32::<LittleEndian>()?,
+        })
+    }
+
+    pub fn write_to<W: Write>(&self, mut dst: W) -> std::io::Result<()> {
+        dst.write_u32::<LittleEndian>(self.payload_size)?;
+        dst.write_u64::<LittleEndian>(self.sequence)?;
+        dst.write_u32::<LittleEndian>(self.num_points)?;
+        Ok(())
+    }
+}
+
+/// Compresses batches of points in frames
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use laz::laszip::frames::{FrameDecoder, FrameEncoder};
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).build();
+/// let mut encoder = FrameEncoder::new(vlr.clone())?;
+/// let mut frame_0 = Vec::new();
+/// encoder.encode_frame(&[0u8; 20 * 3], &mut frame_0)?;
+/// let mut frame_1 = Vec::new();
+/// encoder.encode_frame(&[1u8; 20 * 2], &mut frame_1)?;
+///
+/// // The frame 1 arrived first
+/// let decoder = FrameDecoder::new(vlr);
+/// let mut points = Vec::new();
+/// let header = decoder.decode_frame(&frame_1, &mut points)?;
+/// assert_eq!(header.sequence, 1);
+/// assert_eq!(points, [1u8; 20 * 2]);
+/// # Ok(())
+/// # }
+/// ```
+pub struct FrameEncoder {
+    vlr: LazVlr,
+    record_compressor: Box<dyn RecordCompressor<Cursor<Vec<u8>>> + Send>,
+    next_sequence: u64,
+}
+
+impl FrameEncoder {
+    /// Creates an encoder of the points described by the `vlr`,
+    /// only the items of the `vlr` are used
+    pub fn new(vlr: LazVlr) -> crate::Result<Self> {
+        let record_compressor = record_compressor_from_vlr(&vlr, Cursor::new(Vec::new()))?;
+        Ok(Self {
+            vlr,
+            record_compressor,
+            next_sequence: 0,
+        })
+    }
+
+    /// Returns the sequence number of the next frame
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+
+    /// Compresses the `points` in a new frame, written to the `dst`,
+    /// and returns its header.
+    ///
+    /// The frame gets the next sequence number, frames of no points are valid.
+    ///
+    /// On error, nothing of the frame is kept by the encoder and the sequence
+    /// number is not used, but a part of the frame may have been written to the `dst`.
+    pub fn encode_frame<W: Write>(&mut self, points: &[u8], dst: W) -> crate::Result<FrameHeader> {
+        let result = self.write_frame(points, dst);
+        let payload = self.record_compressor.get_mut();
+        payload.get_mut().clear();
+        payload.set_position(0);
+        if result.is_err() {
+            // The codecs may have been stopped in the middle of the points
+            self.record_compressor.reset();
+            self.record_compressor.set_fields_from(self.vlr.items())?;
+        } else {
+            self.next_sequence += 1;
+        }
+        result
+    }
+
+    fn write_frame<W: Write>(&mut self, points: &[u8], mut dst: W) -> crate::Result<FrameHeader> {
+        let points = PointBuffer::new(points, &self.vlr)?;
+        let num_points = u32::try_from(points.len())
+            .map_err(|_| invalid_data("too many points for one frame"))?;
+        if !points.is_empty() {
+            self.record_compressor.compress_many(points.as_bytes())?;
+            self.record_compressor.done()?;
+            self.record_compressor.reset();
+            self.record_compressor.set_fields_from(self.vlr.items())?;
+        }
+        let payload = self.record_compressor.get_mut();
+        let header = FrameHeader {
+            payload_size: u32::try_from(payload.get_ref().len())
+                .map_err(|_| invalid_data("frame is too big"))?,
+            sequence: self.next_sequence,
+            num_points,
+        };
+        header.write_to(&mut dst)?;
+        dst.write_all(payload.get_ref())?;
+        Ok(header)
+    }
+}
+
+/// Decompresses the points of frames, see [`FrameEncoder`]
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    vlr: LazVlr,
+    memory_limit: Option<usize>,
+}
+
+impl FrameDecoder {
+    /// Creates a decoder of the frames of the points described by the `vlr`,
+    /// with a memory limit of [`DEFAULT_FRAME_MEMORY_LIMIT`]
+    pub fn new(vlr: LazVlr) -> Self {
+        Self {
+            vlr,
+            memory_limit: Some(DEFAULT_FRAME_MEMORY_LIMIT),
+        }
+    }
+
+    /// Sets the maximum number of bytes the points of a frame can take once decompressed,
+    /// `None` means no limit.
+    ///
+    /// The number of points of a frame comes from its header, the limit prevents
+    /// corrupted or malicious frames from making the decoder allocate huge buffers,
+    /// [`Self::decode_frame`] fails with [`LasZipError::MemoryLimitExceeded`] instead.
+    ///
+    /// [`LasZipError::MemoryLimitExceeded`]: crate::LasZipError::MemoryLimitExceeded
+    pub fn set_memory_limit(&mut self, memory_limit: Option<usize>) {
+        self.memory_limit = memory_limit;
+    }
+
+    /// Decompresses the points of the `frame` (header included) at the end of `out`,
+    /// and returns the header of the frame.
+    pub fn decode_frame(&self, frame: &[u8], out: &mut Vec<u8>) -> crate::Result<FrameHeader> {
+        let header = FrameHeader::read_from(frame)?;
+        let payload = frame
+            .get(FRAME_HEADER_SIZE..)
+            .and_then(|payload| payload.get(..usize::try_from(header.payload_size).ok()?))
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "frame is truncated")
+            })?;
+        if header.num_points > 0 && payload.is_empty() {
+            return Err(invalid_data("frame has points but no compressed data").into());
+        }
+        let num_bytes = usize::try_from(header.num_points)
+            .ok()
+            .and_then(|num_points| num_points.checked_mul(self.vlr.items_size() as usize))
+            .ok_or_else(|| invalid_data("frame has too many points"))?;
+        crate::errors::check_memory_limit(self.memory_limit, num_bytes)?;
+        let start = out.len();
+        out.resize(start + num_bytes, 0u8);
+        if header.num_points > 0 {
+            let mut decompressor = record_decompressor_from_vlr(&self.vlr, Cursor::new(payload))?;
+            if let Err(e) = decompressor.decompress_many(&mut out[start..]) {
+                out.truncate(start);
+                return Err(e.into());
+            }
+        }
+        Ok(header)
+    }
+}
+
+/// Reads the next frame (header included) of the `src` into `frame`, replacing its content.
+///
+/// Returns `false`, and leaves `frame` empty, if the `src` ended before the frame.
+pub fn read_frame<R: Read>(mut src: R, frame: &mut Vec<u8>) -> std::io::Result<bool> {
+    frame.clear();
+    frame.resize(FRAME_HEADER_SIZE, 0u8);
+    let mut num_read = 0;
+    while num_read < FRAME_HEADER_SIZE {
+        match src.read(&mut frame[num_read..]) {
+            Ok(0) if num_read == 0 => {
+                frame.clear();
+                return Ok(false);
+            }
+            Ok(0) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "frame header is truncated",
+                ))
+            }
+            Ok(n) => num_read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let header = FrameHeader::read_from(&frame[..])?;
+    // The payload size comes from the header, the frame only grows
+    // as the bytes are read so that a corrupted size does not allocate more
+    let payload_size = u64::from(header.payload_size);
+    let num_read = src.take(payload_size).read_to_end(frame)?;
+    if (num_read as u64) < payload_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "frame is truncated",
+        ));
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point6>(0);
+        let vlr = crate::LazVlrBuilder::new(items).build();
+        let batches = [3u8, 0, 10]
+            .iter()
+            .map(|&n| {
+                (0..n)
+                    .flat_map(|i| {
+                        let mut point = [0u8; 30];
+                        point[..4].copy_from_slice(&i32::from(i * n).to_le_bytes());
+                        point
+                    })
+                    .collect::<Vec<u8>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut encoder = FrameEncoder::new(vlr.clone()).unwrap();
+        let mut stream = Vec::new();
+        for batch in &batches {
+            encoder.encode_frame(batch, &mut stream).unwrap();
+        }
+        assert_eq!(encoder.next_sequence(), 3);
+
+        let mut frames = Vec::new();
+        let mut src = &stream[..];
+        let mut frame = Vec::new();
+        while read_frame(&mut src, &mut frame).unwrap() {
+            frames.push(frame.clone());
+        }
+        assert_eq!(frames.len(), 3);
+
+        let decoder = FrameDecoder::new(vlr);
+        for &i in &[2, 0, 1] {
+            let mut points = Vec::new();
+            let header = decoder.decode_frame(&frames[i], &mut points).unwrap();
+            assert_eq!(header.sequence, i as u64);
+            assert_eq!(points, batches[i]);
+        }
+        assert!(decoder
+            .decode_frame(&frames[2][..frames[2].len() - 1], &mut Vec::new())
+            .is_err());
+        assert!(read_frame(&frames[2][..frames[2].len() - 1], &mut frame).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_frame_sizes() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = crate::LazVlrBuilder::new(items).build();
+        let mut frame = Vec::new();
+        FrameEncoder::new(vlr.clone())
+            .unwrap()
+            .encode_frame(&[1u8; 20 * 4], &mut frame)
+            .unwrap();
+
+        // A huge number of points
+        let mut corrupted = frame.clone();
+        corrupted[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = FrameDecoder::new(vlr.clone())
+            .decode_frame(&corrupted, &mut Vec::new())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            crate::LasZipError::MemoryLimitExceeded {
+                limit: DEFAULT_FRAME_MEMORY_LIMIT,
+                ..
+            }
+        ));
+        let mut decoder = FrameDecoder::new(vlr);
+        decoder.set_memory_limit(Some(20 * 3));
+        assert!(decoder.decode_frame(&frame, &mut Vec::new()).is_err());
+
+        // A huge payload, only what is available is read
+        corrupted = frame.clone();
+        corrupted[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut read = Vec::new();
+        let error = read_frame(corrupted.as_slice(), &mut read).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert!(read.capacity() < 1024);
+    }
+
+    #[test]
+    fn test_encode_frame_error() {
+        struct FailingWriter;
+
+        impl Write for FailingWriter {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("failed"))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = crate::LazVlrBuilder::new(items).build();
+        let points = crate::test_utils::points(10, 20);
+        let mut encoder = FrameEncoder::new(vlr.clone()).unwrap();
+        assert!(encoder.encode_frame(&points, FailingWriter).is_err());
+        assert_eq!(encoder.next_sequence(), 0);
+
+        // Nothing of the failed frame is in the next one
+        let mut frame = Vec::new();
+        let header = encoder.encode_frame(&points, &mut frame).unwrap();
+        assert_eq!(header.sequence, 0);
+        assert_eq!(
+            frame.len(),
+            FRAME_HEADER_SIZE + header.payload_size as usize
+        );
+        let mut decompressed = Vec::new();
+        FrameDecoder::new(vlr)
+            .decode_frame(&frame, &mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, points);
+    }
+}
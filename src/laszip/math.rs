@@ -0,0 +1,297 @@
+//! Decoding of points into the position types of math libraries
+use std::convert::TryFrom;
+use std::io::{Read, Seek};
+use std::marker::PhantomData;
+
+use crate::laszip::{CoordinateTransform, LazItem, LazItemType};
+use crate::packers::Packable;
+
+use super::LasZipDecompressor;
+
+/// Attributes of a point that are common to all the point formats
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointAttributes {
+    pub intensity: u16,
+    pub return_number: u8,
+    pub number_of_returns: u8,
+    pub classification: u8,
+    pub point_source_id: u16,
+    /// `None` when the point format has no gps time
+    pub gps_time: Option<f64>,
+}
+
+/// Where the attributes are in a point record
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum AttributesLayout {
+    /// Point formats 0 to 5, with the offset of the gps time
+    Legacy(Option<usize>),
+    /// Point formats 6 to 10
+    Extended,
+}
+
+impl AttributesLayout {
+    fn from_laz_items(items: &[LazItem]) -> Option<Self> {
+        match items.first()?.item_type() {
+            LazItemType::Point10 => Some(AttributesLayout::Legacy(gps_time_offset(items))),
+            LazItemType::Point14 => Some(AttributesLayout::Extended),
+            _ => None,
+        }
+    }
+
+    fn attributes_of(self, point: &[u8]) -> PointAttributes {
+        let intensity = u16::unpack_from(&point[12..14]);
+        match self {
+            AttributesLayout::Legacy(gps_time_offset) => PointAttributes {
+                intensity,
+                return_number: point[14] & 0x7,
+                number_of_returns: (point[14] >> 3) & 0x7,
+                classification: point[15] & 0x1F,
+                point_source_id: u16::unpack_from(&point[18..20]),
+                gps_time: gps_time_offset
+                    .map(|offset| f64::from_bits(u64::unpack_from(&point[offset..offset + 8]))),
+            },
+            AttributesLayout::Extended => PointAttributes {
+                intensity,
+                return_number: point[14] & 0xF,
+                number_of_returns: point[14] >> 4,
+                classification: point[16],
+                point_source_id: u16::unpack_from(&point[20..22]),
+                gps_time: Some(f64::from_bits(u64::unpack_from(&point[22..30]))),
+            },
+        }
+    }
+}
+
+/// Returns the offset of the gps time in the points described by the `items`, if they have one
+pub(super) fn gps_time_offset(items: &[LazItem]) -> Option<usize> {
+    let mut offset = 0usize;
+    for item in items {
+        match item.item_type() {
+            LazItemType::GpsTime => return Some(offset),
+            LazItemType::Point14 => return Some(offset + 22),
+            _ => offset += item.size() as usize,
+        }
+    }
+    None
+}
+
+impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
+    /// Returns an iterator that decompresses the next `num_points` points
+    /// and gives their position, with the `transform` applied, and their attributes.
+    ///
+    /// The position can be any type that converts from `[f64; 3]`,
+    /// e.g. `glam::DVec3` or `nalgebra::Point3<f64>`.
+    ///
+    /// Fails if the points are not of a point format (Point10 or Point14 based).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// use laz::laszip::CoordinateTransform;
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let mut decompressor = laz::LasZipDecompressor::new(laz_file, vlr)?;
+    /// let transform = CoordinateTransform::new([0.01; 3], [0.0; 3]);
+    /// for point in decompressor.decompress_positions::<[f64; 3]>(10, transform)? {
+    ///     let (position, attributes) = point?;
+    ///     assert!(attributes.gps_time.is_none());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decompress_positions<'d, P: From<[f64; 3]>>(
+        &'d mut self,
+        num_points: u64,
+        transform: CoordinateTransform,
+    ) -> crate::Result<Positions<'d, 'a, R, P>> {
+        let layout = AttributesLayout::from_laz_items(self.vlr().items()).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The points are not of a LAS point format",
+            )
+        })?;
+        let point = vec![0u8; self.vlr().items_size() as usize];
+        Ok(Positions {
+            decompressor: self,
+            transform,
+            layout,
+            num_points_left: num_points,
+            point,
+            failed: false,
+            position_type: PhantomData,
+        })
+    }
+
+    /// Same as [`decompress_positions`](Self::decompress_positions), with `glam` positions
+    #[cfg(feature = "glam")]
+    pub fn decompress_glam_positions<'d>(
+        &'d mut self,
+        num_points: u64,
+        transform: CoordinateTransform,
+    ) -> crate::Result<Positions<'d, 'a, R, glam::DVec3>> {
+        self.decompress_positions(num_points, transform)
+    }
+
+    /// Same as [`decompress_positions`](Self::decompress_positions), with `nalgebra` positions
+    #[cfg(feature = "nalgebra")]
+    pub fn decompress_nalgebra_positions<'d>(
+        &'d mut self,
+        num_points: u64,
+        transform: CoordinateTransform,
+    ) -> crate::Result<Positions<'d, 'a, R, nalgebra::Point3<f64>>> {
+        self.decompress_positions(num_points, transform)
+    }
+}
+
+/// Iterator over the positions and attributes of points, see
+/// [`LasZipDecompressor::decompress_positions`]
+pub struct Positions<'d, 'a, R: Read + Seek + 'a, P> {
+    decompressor: &'d mut LasZipDecompressor<'a, R>,
+    transform: CoordinateTransform,
+    layout: AttributesLayout,
+    num_points_left: u64,
+    point: Vec<u8>,
+    failed: bool,
+    position_type: PhantomData<P>,
+}
+
+impl<'d, 'a, R: Read + Seek + Send + 'a, P: From<[f64; 3]>> Iterator for Positions<'d, 'a, R, P> {
+    type Item = crate::Result<(P, PointAttributes)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.num_points_left == 0 {
+            return None;
+        }
+        if let Err(error) = self.decompressor.decompress_one(&mut self.point) {
+            self.failed = true;
+            return Some(Err(error.into()));
+        }
+        self.num_points_left -= 1;
+        let position = P::from(self.transform.coordinates_of(&self.point));
+        Some(Ok((position, self.layout.attributes_of(&self.point))))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let upper = usize::try_from(self.num_points_left).ok();
+        (0, upper)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::las::{Point0, Point6};
+    use crate::{LazItemRecordBuilder, LazVlrBuilder};
+
+    #[test]
+    fn test_legacy_attributes() {
+        let items = LazItemRecordBuilder::new()
+            .add_item(LazItemType::Point10)
+            .add_item(LazItemType::GpsTime)
+            .build();
+        let vlr = LazVlrBuilder::new(items).build();
+        let mut point = [0u8; 28];
+        point[..4].copy_from_slice(&10i32.to_le_bytes());
+        point[12..14].copy_from_slice(&300u16.to_le_bytes());
+        point[14] = 2 | (3 << 3);
+        point[15] = 6;
+        point[18..20].copy_from_slice(&7u16.to_le_bytes());
+        point[20..28].copy_from_slice(&1.5f64.to_le_bytes());
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &point, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        let transform = CoordinateTransform::new([0.5; 3], [1.0; 3]);
+        let points = decompressor
+            .decompress_positions::<[f64; 3]>(1, transform)
+            .unwrap()
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            points,
+            vec![(
+                [6.0, 1.0, 1.0],
+                PointAttributes {
+                    intensity: 300,
+                    return_number: 2,
+                    number_of_returns: 3,
+                    classification: 6,
+                    point_source_id: 7,
+                    gps_time: Some(1.5),
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_extended_attributes() {
+        let point = {
+            let mut point = [0u8; 30];
+            point[14] = 4 | (5 << 4);
+            point[16] = 200;
+            point[20..22].copy_from_slice(&9u16.to_le_bytes());
+            point[22..30].copy_from_slice(&2.5f64.to_le_bytes());
+            point
+        };
+        let items = LazItemRecordBuilder::default_version_of::<Point6>(0);
+        let layout = AttributesLayout::from_laz_items(&items).unwrap();
+        let attributes = layout.attributes_of(&point);
+        assert_eq!(attributes.return_number, 4);
+        assert_eq!(attributes.number_of_returns, 5);
+        assert_eq!(attributes.classification, 200);
+        assert_eq!(attributes.point_source_id, 9);
+        assert_eq!(attributes.gps_time, Some(2.5));
+
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        assert_eq!(
+            AttributesLayout::from_laz_items(&items),
+            Some(AttributesLayout::Legacy(None))
+        );
+    }
+
+    #[cfg(all(feature = "glam", feature = "nalgebra"))]
+    #[test]
+    fn test_math_library_positions() {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let vlr = LazVlrBuilder::new(items).build();
+        let mut points = [0u8; 40];
+        points[20..24].copy_from_slice(&4i32.to_le_bytes());
+        points[28..32].copy_from_slice(&(-2i32).to_le_bytes());
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        let compressed = compressed.into_inner();
+
+        let transform = CoordinateTransform::new([0.25; 3], [0.0, 1.0, 0.0]);
+        let mut decompressor =
+            LasZipDecompressor::new(Cursor::new(&compressed), vlr.clone()).unwrap();
+        let positions = decompressor
+            .decompress_glam_positions(2, transform)
+            .unwrap()
+            .map(|point| point.map(|(position, _)| position))
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            positions,
+            vec![
+                glam::DVec3::new(0.0, 1.0, 0.0),
+                glam::DVec3::new(1.0, 1.0, -0.5)
+            ]
+        );
+
+        let mut decompressor = LasZipDecompressor::new(Cursor::new(&compressed), vlr).unwrap();
+        let positions = decompressor
+            .decompress_nalgebra_positions(2, transform)
+            .unwrap()
+            .map(|point| point.map(|(position, _)| position))
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(positions[1], nalgebra::Point3::new(1.0, 1.0, -0.5));
+    }
+}
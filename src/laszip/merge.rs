@@ -0,0 +1,231 @@
+//! Merging of compressed points by gps time
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::io::{Read, Seek, Write};
+
+use crate::packers::Packable;
+use crate::LasZipError;
+
+use super::details::usize_from;
+use super::math::gps_time_offset;
+use super::{LasZipCompressor, LasZipDecompressor, LazVlr};
+
+/// One of the inputs of [`merge_by_gps_time`]
+#[derive(Debug)]
+pub struct MergeInput<R> {
+    /// The compressed points, its position must be at the start of the point data
+    pub src: R,
+    pub vlr: LazVlr,
+    pub num_points: u64,
+}
+
+impl<R> MergeInput<R> {
+    pub fn new(src: R, vlr: LazVlr, num_points: u64) -> Self {
+        Self {
+            src,
+            vlr,
+            num_points,
+        }
+    }
+}
+
+/// The next point of an input, ordered so that the max-heap gives the earliest one,
+/// and the first input on equal gps times
+#[derive(Debug, Copy, Clone)]
+struct NextPoint {
+    gps_time: f64,
+    input: usize,
+}
+
+impl Ord for NextPoint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .gps_time
+            .total_cmp(&self.gps_time)
+            .then_with(|| other.input.cmp(&self.input))
+    }
+}
+
+impl PartialOrd for NextPoint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for NextPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for NextPoint {}
+
+/// Decompresses the points of all the `inputs` and compresses them into `dst`,
+/// as described by the `dst_vlr`, in gps time order.
+///
+/// Each input must already be sorted by gps time (as the points of a flight line are),
+/// only one point per input is decompressed ahead, so the memory used does not depend
+/// on the number of points. Points with the same gps time keep the order of the inputs.
+///
+/// The points of all the inputs must have the size of the points of the `dst_vlr`,
+/// and a gps time at the same offset.
+///
+/// Returns the `dst`, once the compression is done.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::{merge_by_gps_time, MergeInput};
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point1>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).build();
+/// let flight_line = |gps_times: &[f64]| -> laz::Result<MergeInput<Cursor<Vec<u8>>>> {
+///     let mut points = vec![0u8; 28 * gps_times.len()];
+///     for (point, gps_time) in points.chunks_exact_mut(28).zip(gps_times) {
+///         point[20..].copy_from_slice(&gps_time.to_le_bytes());
+///     }
+///     let mut compressed = Cursor::new(Vec::<u8>::new());
+///     laz::compress_buffer(&mut compressed, &points, vlr.clone())?;
+///     compressed.set_position(0);
+///     Ok(MergeInput::new(compressed, vlr.clone(), gps_times.len() as u64))
+/// };
+///
+/// let inputs = vec![flight_line(&[1.0, 3.0])?, flight_line(&[2.0])?];
+/// let merged = merge_by_gps_time(inputs, Cursor::new(Vec::<u8>::new()), vlr.clone())?;
+///
+/// let mut points = vec![0u8; 28 * 3];
+/// laz::decompress_buffer(merged.get_ref(), &mut points, vlr)?;
+/// assert_eq!(&points[28 + 20..28 * 2], &2.0f64.to_le_bytes());
+/// # Ok(())
+/// # }
+/// ```
+pub fn merge_by_gps_time<R, W>(
+    inputs: Vec<MergeInput<R>>,
+    dst: W,
+    dst_vlr: LazVlr,
+) -> crate::Result<W>
+where
+    R: Read + Seek + Send,
+    W: Write + Seek + Send,
+{
+    let point_size = usize_from(dst_vlr.items_size())?;
+    let offset = gps_time_offset(dst_vlr.items())
+        .ok_or_else(|| invalid_input("The points of the output have no gps time".to_string()))?;
+
+    let mut decompressors = Vec::with_capacity(inputs.len());
+    let mut points_left = Vec::with_capacity(inputs.len());
+    for (i, input) in inputs.into_iter().enumerate() {
+        if input.vlr.items_size() != dst_vlr.items_size() {
+            return Err(LasZipError::PointSizeMismatch {
+                expected: point_size,
+                found: usize_from(input.vlr.items_size())?,
+            });
+        }
+        if gps_time_offset(input.vlr.items()) != Some(offset) {
+            return Err(invalid_input(format!(
+                "The points of input {} do not have their gps time where the output has it",
+                i
+            )));
+        }
+        decompressors.push(LasZipDecompressor::new(input.src, input.vlr)?);
+        points_left.push(input.num_points);
+    }
+
+    // The next point of each input
+    let mut lookahead = vec![0u8; point_size * decompressors.len()];
+    let mut heap = BinaryHeap::with_capacity(decompressors.len());
+    for (input, point) in lookahead.chunks_exact_mut(point_size).enumerate() {
+        let decompressor = &mut decompressors[input];
+        if let Some(gps_time) = read_next(decompressor, &mut points_left[input], point, offset)? {
+            heap.push(NextPoint { gps_time, input });
+        }
+    }
+
+    let mut compressor = LasZipCompressor::new(dst, dst_vlr)?;
+    while let Some(NextPoint { input, .. }) = heap.pop() {
+        let point = &mut lookahead[input * point_size..(input + 1) * point_size];
+        compressor.compress_one(point)?;
+        let decompressor = &mut decompressors[input];
+        if let Some(gps_time) = read_next(decompressor, &mut points_left[input], point, offset)? {
+            heap.push(NextPoint { gps_time, input });
+        }
+    }
+    compressor.finish()
+}
+
+/// Decompresses the next point of an input into `point`, and returns its gps time,
+/// or `None` when the input has no points left
+fn read_next<R: Read + Seek + Send>(
+    decompressor: &mut LasZipDecompressor<R>,
+    points_left: &mut u64,
+    point: &mut [u8],
+    gps_time_offset: usize,
+) -> std::io::Result<Option<f64>> {
+    if *points_left == 0 {
+        return Ok(None);
+    }
+    decompressor.decompress_one(point)?;
+    *points_left -= 1;
+    let gps_time = u64::unpack_from(&point[gps_time_offset..gps_time_offset + 8]);
+    Ok(Some(f64::from_bits(gps_time)))
+}
+
+fn invalid_input(message: String) -> LasZipError {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message).into()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Compresses points of format 6 with the `gps_times`, their x is `id + their index`
+    fn flight_line(vlr: &LazVlr, id: i32, gps_times: &[f64]) -> MergeInput<Cursor<Vec<u8>>> {
+        let points = gps_times
+            .iter()
+            .enumerate()
+            .flat_map(|(i, gps_time)| {
+                let mut point = [0u8; 30];
+                point[..4].copy_from_slice(&(id + i as i32).to_le_bytes());
+                point[22..30].copy_from_slice(&gps_time.to_le_bytes());
+                point
+            })
+            .collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+        MergeInput::new(compressed, vlr.clone(), gps_times.len() as u64)
+    }
+
+    #[test]
+    fn test_merge_by_gps_time() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point6>(0);
+        let vlr = crate::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(2)
+            .build();
+        let inputs = vec![
+            flight_line(&vlr, 100, &[1.0, 4.0, 4.0, 7.0]),
+            flight_line(&vlr, 200, &[]),
+            flight_line(&vlr, 300, &[2.0, 4.0, 8.0]),
+        ];
+        let merged = merge_by_gps_time(inputs, Cursor::new(Vec::<u8>::new()), vlr.clone())
+            .unwrap()
+            .into_inner();
+
+        let mut points = vec![0u8; 30 * 7];
+        crate::decompress_buffer(&merged, &mut points, vlr.clone()).unwrap();
+        let ids = points
+            .chunks_exact(30)
+            .map(|point| i32::unpack_from(&point[..4]))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![100, 300, 101, 102, 301, 103, 302]);
+
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(10);
+        let other_vlr = crate::LazVlrBuilder::new(items).build();
+        let inputs = vec![MergeInput::new(Cursor::new(Vec::new()), other_vlr, 0)];
+        assert!(merge_by_gps_time(inputs, Cursor::new(Vec::<u8>::new()), vlr).is_err());
+    }
+}
@@ -3,20 +3,95 @@
 //!
 //! It defines the LaszipCompressor & LaszipDecompressor
 //! as well as the Laszip VLr data  and how to build it
-pub use chunk_table::{ChunkTable, ChunkTableEntry};
-pub use compression::{compress_buffer, LasZipCompressor};
-pub use decompression::{decompress_buffer, LasZipDecompressor};
+pub use append::LasZipAppender;
+#[cfg(feature = "tokio")]
+pub use async_io::{AsyncLasZipCompressor, AsyncLasZipDecompressor};
+pub use capabilities::{capabilities, check_support, Capability, Unsupported};
+pub use chunk_table::{ChunkPartition, ChunkTable, ChunkTableEntry};
+pub use chunk_writer::ChunkBufferedWriter;
+pub use compression::{
+    compress_buffer, compress_buffer_into, AdaptiveChunking, CompressionCheckpoint,
+    LasZipCompressor,
+};
+pub use decompression::{decompress_buffer, DecompressionPosition, LasZipDecompressor};
+pub(crate) use details::invalid_data;
+pub use diagnostics::{
+    chunk_report, chunk_report_with_density, context_of, ChunkContexts, ChunkDensity, ChunkReport,
+};
+#[cfg(feature = "digest")]
+pub use digest::{verify_digest, DigestEvlr, PointDigest};
+pub use downgrade::{
+    downgrade_point, legacy_point_format_of, DowngradingLasZipCompressor, OVERLAP_CLASSIFICATION,
+    UNCLASSIFIED,
+};
+pub use dry_run::{dry_run, estimate_compressed_size, ByteCounter, CompressedSize, SizeEstimate};
+pub use dyn_io::{
+    DynDestination, DynLasZipCompressor, DynLasZipDecompressor, DynSource, ReadSeek, WriteSeek,
+};
+pub use filter::{PointFilter, ReturnFilter};
+pub use math::{PointAttributes, Positions};
+pub use merge::{merge_by_gps_time, MergeInput};
+pub use open_options::LazOpenOptions;
+pub use pipeline::Pipeline;
+pub use point_buffer::{PointBuffer, PointBufferMut};
+pub use profile::CompressionProfile;
+pub use push::PushDecompressor;
+pub use query::{CoordinateTransform, PointsInBounds};
+pub use raw_chunks::RawChunkWriter;
+#[cfg(feature = "parallel")]
+pub use reencode::par_reencode;
+pub use reencode::reencode;
+pub use selection::DecompressionSelection;
+pub use split::{split_by_point_source_id, SplitDestination, SplitOutput};
+pub use tee::{PointSink, WriteSink};
+pub use two_phase::{compress_two_phase, Chunking, CompressionPlan, InputStats};
+pub use typed_compression::{FixedSizeChunks, TypedLasZipCompressor, VariableSizeChunks};
+pub use upgrade::{extended_point_format_of, upgrade_point};
+pub(crate) use vlr::spec_order_rank;
 pub use vlr::{
     CompressorType, DefaultVersion, LazItem, LazItemRecordBuilder, LazItemType, LazVlr,
     LazVlrBuilder, Version1, Version2, Version3,
 };
 
+mod append;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod capabilities;
 mod chunk_table;
+mod chunk_writer;
 mod compression;
+pub mod copc;
 mod decompression;
 mod details;
+mod diagnostics;
+#[cfg(feature = "digest")]
+mod digest;
+mod downgrade;
+mod dry_run;
+mod dyn_io;
+pub mod fetch_plan;
+mod filter;
+pub mod frames;
+mod math;
+mod merge;
+mod open_options;
 #[cfg(feature = "parallel")]
 pub mod parallel;
+mod pipeline;
+mod point_buffer;
+mod profile;
+mod push;
+mod query;
+mod raw_chunks;
+pub mod read_at;
+mod reencode;
+mod selection;
+mod split;
+mod tee;
+pub mod tiler;
+mod two_phase;
+mod typed_compression;
+mod upgrade;
 mod vlr;
 
 #[deprecated(since = "0.6.0", note = "Please use laz::LazVlr::USER_ID")]
@@ -0,0 +1,289 @@
+//! Options of the decompression of points
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+
+use std::io::{Read, Seek};
+
+use super::{ChunkTable, DecompressionSelection, LazVlr};
+use crate::LasZipWarning;
+
+/// Options of a [`LasZipDecompressor`], see [`LasZipDecompressor::with_options`]
+///
+/// They gather the settings that otherwise need a specific constructor
+/// or a setter called after the creation of the decompressor.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::{Seek, SeekFrom};
+/// use laz::laszip::LazOpenOptions;
+/// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+/// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+/// # let vlr = vlr.unwrap();
+/// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+/// let options = LazOpenOptions::new()
+///     .with_lazy_chunk_table(true)
+///     .with_memory_limit(64 * 1024 * 1024)
+///     .with_num_points(u64::from(header.num_points))
+///     .with_warnings(|warning| eprintln!("Warning: {}", warning));
+/// let decompressor = laz::LasZipDecompressor::with_options(laz_file, vlr, options)?;
+/// assert_eq!(decompressor.num_points(), Some(u64::from(header.num_points)));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`LasZipDecompressor`]: crate::LasZipDecompressor
+/// [`LasZipDecompressor::with_options`]: crate::LasZipDecompressor::with_options
+#[derive(Default)]
+pub struct LazOpenOptions<'a> {
+    pub(super) lazy_chunk_table: bool,
+    pub(super) memory_limit: Option<usize>,
+    pub(super) snapshot_interval: u64,
+    pub(super) context_diagnostics: bool,
+    pub(super) num_points: Option<u64>,
+    pub(super) selection: DecompressionSelection,
+    pub(super) warn: Option<Box<dyn FnMut(LasZipWarning) + Send + 'a>>,
+    pub(super) strict: bool,
+    pub(super) max_num_chunks: Option<usize>,
+    pub(crate) cache_capacity: Option<usize>,
+    #[cfg(feature = "parallel")]
+    pub(super) thread_pool: Option<Arc<rayon::ThreadPool>>,
+    #[cfg(feature = "parallel")]
+    pub(super) max_in_flight_chunks: Option<usize>,
+}
+
+impl std::fmt::Debug for LazOpenOptions<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("LazOpenOptions");
+        debug
+            .field("lazy_chunk_table", &self.lazy_chunk_table)
+            .field("memory_limit", &self.memory_limit)
+            .field("snapshot_interval", &self.snapshot_interval)
+            .field("context_diagnostics", &self.context_diagnostics)
+            .field("num_points", &self.num_points)
+            .field("selection", &self.selection)
+            .field("warn", &self.warn.is_some())
+            .field("strict", &self.strict)
+            .field("max_num_chunks", &self.max_num_chunks)
+            .field("cache_capacity", &self.cache_capacity);
+        #[cfg(feature = "parallel")]
+        debug
+            .field("thread_pool", &self.thread_pool.is_some())
+            .field("max_in_flight_chunks", &self.max_in_flight_chunks);
+        debug.finish()
+    }
+}
+
+impl<'a> LazOpenOptions<'a> {
+    /// Options giving the same decompressor as [`LasZipDecompressor::new`]
+    ///
+    /// [`LasZipDecompressor::new`]: crate::LasZipDecompressor::new
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only reads the chunk table when it is first needed,
+    /// see [`LasZipDecompressor::new_lazy`].
+    ///
+    /// [`LasZipDecompressor::new_lazy`]: crate::LasZipDecompressor::new_lazy
+    pub fn with_lazy_chunk_table(mut self, lazy: bool) -> Self {
+        self.lazy_chunk_table = lazy;
+        self
+    }
+
+    /// Does not allocate more than `memory_limit` bytes for the chunk table and
+    /// the compressed data of a chunk, see [`LasZipDecompressor::new_with_memory_limit`].
+    ///
+    /// [`LasZipDecompressor::new_with_memory_limit`]: crate::LasZipDecompressor::new_with_memory_limit
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = Some(memory_limit);
+        self
+    }
+
+    /// Saves a snapshot every `interval` points of a chunk, 0 means no snapshots,
+    /// see [`LasZipDecompressor::set_snapshot_interval`].
+    ///
+    /// [`LasZipDecompressor::set_snapshot_interval`]: crate::LasZipDecompressor::set_snapshot_interval
+    pub fn with_snapshot_interval(mut self, interval: u64) -> Self {
+        self.snapshot_interval = interval;
+        self
+    }
+
+    /// Collects the contexts used by the points of each chunk,
+    /// see [`LasZipDecompressor::set_context_diagnostics`].
+    ///
+    /// [`LasZipDecompressor::set_context_diagnostics`]: crate::LasZipDecompressor::set_context_diagnostics
+    pub fn with_context_diagnostics(mut self, enabled: bool) -> Self {
+        self.context_diagnostics = enabled;
+        self
+    }
+
+    /// Gives the total number of points, see [`LasZipDecompressor::set_num_points`].
+    ///
+    /// [`LasZipDecompressor::set_num_points`]: crate::LasZipDecompressor::set_num_points
+    pub fn with_num_points(mut self, num_points: u64) -> Self {
+        self.num_points = Some(num_points);
+        self
+    }
+
+    /// Only decompresses the fields in the `selection`,
+    /// see [`LasZipDecompressor::set_selection`].
+    ///
+    /// [`LasZipDecompressor::set_selection`]: crate::LasZipDecompressor::set_selection
+    pub fn with_selection(mut self, selection: DecompressionSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Reports the non-fatal issues found to the `warn` callback,
+    /// see [`LasZipDecompressor::new_with_warnings`].
+    ///
+    /// [`LasZipDecompressor::new_with_warnings`]: crate::LasZipDecompressor::new_with_warnings
+    pub fn with_warnings<F>(mut self, warn: F) -> Self
+    where
+        F: FnMut(LasZipWarning) + Send + 'a,
+    {
+        self.warn = Some(Box::new(warn));
+        self
+    }
+
+    /// Makes the non-fatal issues found while opening the data errors,
+    /// [`LasZipError::StrictModeWarning`], instead of warnings.
+    ///
+    /// The chunk table is opened with the data, or when it is first needed
+    /// if it is read lazily. When it cannot be read, its error is returned.
+    /// Issues found later, such as seeking past the end, are still warnings.
+    ///
+    /// [`LasZipError::StrictModeWarning`]: crate::LasZipError::StrictModeWarning
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Fails with [`LasZipError::ChunkLimitExceeded`] when the chunk table
+    /// has more than `max_num_chunks` chunks.
+    ///
+    /// Unlike the memory limit, which bounds the allocations,
+    /// this bounds the work done to decompress or index the data.
+    ///
+    /// [`LasZipError::ChunkLimitExceeded`]: crate::LasZipError::ChunkLimitExceeded
+    pub fn with_max_num_chunks(mut self, max_num_chunks: usize) -> Self {
+        self.max_num_chunks = Some(max_num_chunks);
+        self
+    }
+
+    /// Keeps up to `num_chunks` decompressed chunks (at least 1),
+    /// see [`VirtualLasReader::set_cache_capacity`].
+    ///
+    /// Only used by [`VirtualLasReader::with_options`],
+    /// the decompressors do not keep the chunks they decompressed.
+    ///
+    /// [`VirtualLasReader::set_cache_capacity`]: crate::las::virtual_las::VirtualLasReader::set_cache_capacity
+    /// [`VirtualLasReader::with_options`]: crate::las::virtual_las::VirtualLasReader::with_options
+    pub fn with_cache_capacity(mut self, num_chunks: usize) -> Self {
+        self.cache_capacity = Some(num_chunks);
+        self
+    }
+
+    /// Decompresses the chunks in the given thread pool instead of the global one,
+    /// see [`ParLasZipDecompressor::with_thread_pool`].
+    ///
+    /// Only used by [`ParLasZipDecompressor::with_options`] and [`ReadAtDecompressor::with_options`],
+    /// a [`LasZipDecompressor`] decompresses in the calling thread.
+    ///
+    /// [`ParLasZipDecompressor::with_thread_pool`]: crate::ParLasZipDecompressor::with_thread_pool
+    /// [`ParLasZipDecompressor::with_options`]: crate::ParLasZipDecompressor::with_options
+    /// [`ReadAtDecompressor::with_options`]: crate::laszip::read_at::ReadAtDecompressor::with_options
+    /// [`LasZipDecompressor`]: crate::LasZipDecompressor
+    #[cfg(feature = "parallel")]
+    pub fn with_thread_pool(mut self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    /// Reads ahead and decompresses at most `max_in_flight_chunks` chunks at once,
+    /// see [`ParLasZipDecompressor::set_max_in_flight_chunks`].
+    ///
+    /// Only used by [`ParLasZipDecompressor::with_options`].
+    ///
+    /// [`ParLasZipDecompressor::set_max_in_flight_chunks`]: crate::ParLasZipDecompressor::set_max_in_flight_chunks
+    /// [`ParLasZipDecompressor::with_options`]: crate::ParLasZipDecompressor::with_options
+    #[cfg(feature = "parallel")]
+    pub fn with_max_in_flight_chunks(mut self, max_in_flight_chunks: usize) -> Self {
+        self.max_in_flight_chunks = Some(max_in_flight_chunks);
+        self
+    }
+
+    /// Returns [`LasZipError::UnsupportedOption`] if an option that only
+    /// a [`LasZipDecompressor`] supports is set, for the decompressors
+    /// that need the chunk table and decompress whole chunks.
+    ///
+    /// [`LasZipError::UnsupportedOption`]: crate::LasZipError::UnsupportedOption
+    /// [`LasZipDecompressor`]: crate::LasZipDecompressor
+    pub(crate) fn check_whole_chunk_options(&self) -> crate::Result<()> {
+        if self.lazy_chunk_table {
+            return Err(crate::LasZipError::UnsupportedOption("lazy_chunk_table"));
+        }
+        if self.snapshot_interval != 0 {
+            return Err(crate::LasZipError::UnsupportedOption("snapshot_interval"));
+        }
+        if self.context_diagnostics {
+            return Err(crate::LasZipError::UnsupportedOption("context_diagnostics"));
+        }
+        Ok(())
+    }
+
+    /// Reports the `warning` to the callback, in strict mode it is returned as an error
+    pub(crate) fn report(&mut self, warning: LasZipWarning) -> crate::Result<()> {
+        if self.strict {
+            return Err(crate::LasZipError::StrictModeWarning(Box::new(warning)));
+        }
+        if let Some(warn) = self.warn.as_mut() {
+            warn(warning);
+        }
+        Ok(())
+    }
+
+    /// Reports the items of the `vlr` that use a deprecated version
+    pub(crate) fn check_item_versions(&mut self, vlr: &LazVlr) -> crate::Result<()> {
+        for item in vlr.items() {
+            if item.version() == 1 {
+                self.report(LasZipWarning::DeprecatedItemVersion(
+                    item.item_type(),
+                    item.version(),
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the chunk table of the point data at the current position of `src`,
+    /// applying the memory limit, the limit on the number of chunks and the number of points.
+    pub(crate) fn read_chunk_table<R: Read + Seek>(
+        &mut self,
+        src: R,
+        vlr: &LazVlr,
+    ) -> crate::Result<ChunkTable> {
+        // In strict mode, the first issue found is the error
+        let mut first_issue = None;
+        let (strict, warn) = (self.strict, &mut self.warn);
+        let mut report = |warning| {
+            if strict {
+                first_issue.get_or_insert(warning);
+            } else if let Some(warn) = warn.as_mut() {
+                warn(warning);
+            }
+        };
+        let mut chunk_table =
+            ChunkTable::read_from_with_warnings(src, vlr, &mut report, self.memory_limit)?;
+        if let Some(issue) = first_issue {
+            return Err(crate::LasZipError::StrictModeWarning(Box::new(issue)));
+        }
+        chunk_table.check_num_chunks(self.max_num_chunks)?;
+        if let Some(num_points) = self.num_points {
+            chunk_table.set_num_points(vlr, num_points);
+        }
+        Ok(chunk_table)
+    }
+}
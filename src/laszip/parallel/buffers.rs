@@ -0,0 +1,70 @@
+//! Buffers used by the parallel compressor & decompressor
+
+/// Buffers in which the parallel compressor and decompressor hold
+/// compressed chunks and uncompressed points.
+///
+/// They can be taken back from a (de)compressor with `into_parts` and given
+/// to the next one, so that processing many files or frames does not allocate
+/// new buffers each time, they can also be allocated up-front.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use laz::laszip::parallel::ScratchBuffers;
+/// use laz::{LazItemRecordBuilder, LazVlrBuilder, ParLasZipCompressor};
+///
+/// let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(100).build();
+/// let mut buffers = ScratchBuffers::with_capacity(0, 100 * 20);
+/// for _ in 0..3 {
+///     let output = std::io::Cursor::new(Vec::<u8>::new());
+///     let mut compressor = ParLasZipCompressor::with_scratch_buffers(output, vlr.clone(), buffers)?;
+///     compressor.compress_many(&[0u8; 150 * 20])?;
+///     compressor.done()?;
+///     buffers = compressor.into_parts().1;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ScratchBuffers {
+    pub(super) compressed: Vec<u8>,
+    pub(super) uncompressed: Vec<u8>,
+}
+
+impl ScratchBuffers {
+    /// Creates buffers that can hold `compressed` bytes of compressed chunks
+    /// and `uncompressed` bytes of points without reallocating.
+    pub fn with_capacity(compressed: usize, uncompressed: usize) -> Self {
+        Self {
+            compressed: Vec::with_capacity(compressed),
+            uncompressed: Vec::with_capacity(uncompressed),
+        }
+    }
+
+    /// Creates buffers from existing vecs, their content is discarded.
+    pub fn from_vecs(mut compressed: Vec<u8>, mut uncompressed: Vec<u8>) -> Self {
+        compressed.clear();
+        uncompressed.clear();
+        Self {
+            compressed,
+            uncompressed,
+        }
+    }
+
+    /// Returns the buffers of compressed and uncompressed data as vecs
+    pub fn into_vecs(self) -> (Vec<u8>, Vec<u8>) {
+        (self.compressed, self.uncompressed)
+    }
+
+    /// Returns the capacity of the buffer of compressed chunks
+    pub fn compressed_capacity(&self) -> usize {
+        self.compressed.capacity()
+    }
+
+    /// Returns the capacity of the buffer of uncompressed points
+    pub fn uncompressed_capacity(&self) -> usize {
+        self.uncompressed.capacity()
+    }
+}
@@ -1,11 +1,19 @@
-use std::io::{Seek, SeekFrom, Write};
+use std::io::{Cursor, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
 
 use byteorder::{LittleEndian, WriteBytesExt};
 use rayon::prelude::*;
 
 use crate::laszip::chunk_table::{update_chunk_table_offset, ChunkTable, ChunkTableEntry};
-use crate::laszip::details::record_compressor_from_laz_items;
-use crate::LazVlr;
+use crate::laszip::details::record_compressor_from_vlr;
+use crate::laszip::parallel::{install, ScratchBuffers};
+use crate::laszip::point_buffer;
+use crate::record::RecordCompressor;
+use crate::{LazVlr, PointBuffer};
+
+/// Record compressors writing in memory, kept between calls so that
+/// they (and their buffers) are not created again for each chunk.
+pub(super) type CompressorPool = Vec<Box<dyn RecordCompressor<Cursor<Vec<u8>>> + Send>>;
 
 /// LasZip compressor that compresses using multiple threads
 ///
@@ -47,6 +55,14 @@ pub struct ParLasZipCompressor<W> {
     // They are prepended to the points data passed to the compress_many fn.
     // The rest is compressed when done is called, forming the last chunk
     rest: Vec<u8>,
+    // Buffer of the scratch buffers the compressor does not use,
+    // kept to be given back by into_parts
+    unused_buffer: Vec<u8>,
+    // Compressors used by the threads, reused by each call to
+    // compress_many / compress_chunks
+    compressors: CompressorPool,
+    // Pool in which the chunks are compressed, the current one if None
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
     dest: W,
 }
 
@@ -54,19 +70,70 @@ pub struct ParLasZipCompressor<W> {
 impl<W: Write + Seek + Send> ParLasZipCompressor<W> {
     /// Creates a new ParLasZipCompressor
     pub fn new(dest: W, vlr: LazVlr) -> crate::Result<Self> {
-        let mut rest = Vec::<u8>::new();
+        Self::with_scratch_buffers(dest, vlr, ScratchBuffers::default())
+    }
+
+    /// Creates a new ParLasZipCompressor that uses the given buffers
+    /// instead of allocating its own, see [`ScratchBuffers`].
+    ///
+    /// Only the buffer of uncompressed points is used.
+    pub fn with_scratch_buffers(
+        dest: W,
+        vlr: LazVlr,
+        buffers: ScratchBuffers,
+    ) -> crate::Result<Self> {
+        let (unused_buffer, mut rest) = buffers.into_vecs();
+        rest.clear();
         if !vlr.uses_variable_size_chunks() {
             rest.reserve(vlr.num_bytes_in_decompressed_chunk() as usize);
         }
+        let chunk_table = vlr
+            .num_chunks_hint()
+            .map_or_else(ChunkTable::default, ChunkTable::with_capacity);
         Ok(Self {
             vlr,
-            chunk_table: ChunkTable::default(),
+            chunk_table,
             table_offset: -1,
             rest,
+            unused_buffer,
+            compressors: CompressorPool::new(),
+            thread_pool: None,
             dest,
         })
     }
 
+    /// Makes the compressor compress the chunks in the given thread pool,
+    /// instead of the global one (or the one it is called from).
+    ///
+    /// This allows to control the number of threads used by each compressor
+    /// when several compressions run concurrently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// use std::sync::Arc;
+    ///
+    /// let pool = rayon::ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .unwrap();
+    /// let vlr = laz::LazVlr::from_laz_items(
+    ///     laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0),
+    /// );
+    /// let mut compressor =
+    ///     laz::ParLasZipCompressor::new(std::io::Cursor::new(Vec::new()), vlr)?
+    ///         .with_thread_pool(Arc::new(pool));
+    /// compressor.compress_many(&[0u8; 20 * 100])?;
+    /// compressor.done()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_thread_pool(mut self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
     /// Reserves and prepares the offset to chunk table that will be
     /// updated when [done] is called.
     ///
@@ -138,14 +205,34 @@ impl<W: Write + Seek + Send> ParLasZipCompressor<W> {
         }
 
         if !compressible_buf.is_empty() {
-            let chunk_table = par_compress(&mut self.dest, compressible_buf, &self.vlr)
-                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+            let (dest, vlr, compressors) = (&mut self.dest, &self.vlr, &mut self.compressors);
+            let chunk_table = install(self.thread_pool.as_deref(), || {
+                par_compress_chunks_with(
+                    dest,
+                    compressible_buf.par_chunks(chunk_size_in_bytes),
+                    vlr,
+                    compressors,
+                )
+            })
+            .map_err(std::io::Error::other)?;
             self.chunk_table.extend(&chunk_table);
         }
 
         Ok(())
     }
 
+    /// Compresses all the points of the buffer, see [`compress_many`].
+    ///
+    /// Unlike [`compress_many`], an error is returned if the buffer does
+    /// not contain points of the size described by the vlr.
+    ///
+    /// [`compress_many`]: Self::compress_many
+    pub fn compress_points(&mut self, points: PointBuffer) -> crate::Result<()> {
+        point_buffer::check_point_size(points.point_size(), &self.vlr)?;
+        self.compress_many(points.as_bytes())?;
+        Ok(())
+    }
+
     /// Compresses multiple chunks using multiple threads.
     ///
     /// # Important
@@ -167,8 +254,12 @@ impl<W: Write + Seek + Send> ParLasZipCompressor<W> {
         if self.table_offset == -1 {
             self.reserve_offset_to_chunk_table()?;
         }
-        let chunk_table = par_compress_chunks(&mut self.dest, chunks, &self.vlr)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        let chunks = chunks.into_par_iter();
+        let (dest, vlr, compressors) = (&mut self.dest, &self.vlr, &mut self.compressors);
+        let chunk_table = install(self.thread_pool.as_deref(), || {
+            par_compress_chunks_with(dest, chunks, vlr, compressors)
+        })
+        .map_err(std::io::Error::other)?;
         self.chunk_table.extend(&chunk_table);
         Ok(())
     }
@@ -198,6 +289,18 @@ impl<W: Write + Seek + Send> ParLasZipCompressor<W> {
         Ok(())
     }
 
+    /// Calls [`done`], flushes the destination and returns it.
+    ///
+    /// The destination is positioned right after the chunk table,
+    /// so that other data (e.g. EVLRs) can be written after the points.
+    ///
+    /// [`done`]: Self::done
+    pub fn finish(mut self) -> crate::Result<W> {
+        self.done()?;
+        self.dest.flush()?;
+        Ok(self.dest)
+    }
+
     pub fn vlr(&self) -> &LazVlr {
         &self.vlr
     }
@@ -206,6 +309,18 @@ impl<W: Write + Seek + Send> ParLasZipCompressor<W> {
         self.dest
     }
 
+    /// Returns the destination and the buffers, so that they can be reused
+    ///
+    /// Points that were not compressed, because [`done`] was not called, are lost.
+    ///
+    /// [`done`]: Self::done
+    pub fn into_parts(self) -> (W, ScratchBuffers) {
+        (
+            self.dest,
+            ScratchBuffers::from_vecs(self.unused_buffer, self.rest),
+        )
+    }
+
     pub fn get_mut(&mut self) -> &mut W {
         &mut self.dest
     }
@@ -277,7 +392,7 @@ pub fn par_compress<W: Write>(
     par_compress_chunks(dst, all_slices, laz_vlr)
 }
 
-fn par_compress_chunks<'a, W, Chunks, Item>(
+pub(super) fn par_compress_chunks<W, Chunks, Item>(
     dst: &mut W,
     chunks: Chunks,
     laz_vlr: &LazVlr,
@@ -287,18 +402,49 @@ where
     Item: AsRef<[u8]> + Send,
     Chunks: IntoParallelIterator<Item = Item>,
 {
-    use std::io::Cursor;
+    par_compress_chunks_with(dst, chunks, laz_vlr, &mut CompressorPool::new())
+}
 
+/// Same as [`par_compress_chunks`], but the record compressors are taken from
+/// the `compressors` pool (created if there are not enough) and put back in it
+/// as soon as their chunk is compressed, at most one per thread is kept.
+pub(super) fn par_compress_chunks_with<W, Chunks, Item>(
+    dst: &mut W,
+    chunks: Chunks,
+    laz_vlr: &LazVlr,
+    compressors: &mut CompressorPool,
+) -> crate::Result<ChunkTable>
+where
+    W: Write,
+    Item: AsRef<[u8]> + Send,
+    Chunks: IntoParallelIterator<Item = Item>,
+{
+    let pool = Mutex::new(std::mem::take(compressors));
     let chunks = chunks
         .into_par_iter()
         .map(|data| {
             let slc = data.as_ref();
-            let mut output = Cursor::new(Vec::<u8>::new());
-            compress_one_chunk(slc, laz_vlr, &mut output)?;
-            let vec = output.into_inner();
-            Ok((slc.len(), vec))
+            let reused = pool.lock().unwrap().pop();
+            let mut compressor = match reused {
+                Some(mut compressor) => {
+                    compressor.reset();
+                    compressor.set_fields_from(laz_vlr.items())?;
+                    compressor.get_mut().set_position(0);
+                    compressor
+                }
+                None => record_compressor_from_vlr(laz_vlr, Cursor::new(Vec::<u8>::new()))?,
+            };
+            compressor.compress_many(slc)?;
+            compressor.done()?;
+            // Only the compressed bytes are kept until the chunks are written,
+            // the compressor (and its models) can be used for the next chunk
+            let compressed_data = std::mem::take(compressor.get_mut().get_mut());
+            pool.lock().unwrap().push(compressor);
+            Ok((slc.len(), compressed_data))
         })
-        .collect::<Vec<crate::Result<(usize, Vec<u8>)>>>();
+        .collect::<Vec<crate::Result<_>>>();
+    // No more compressors than chunks compressed at the same time were created
+    *compressors = pool.into_inner().unwrap();
 
     let mut chunk_table = ChunkTable::with_capacity(chunks.len());
     let point_size = laz_vlr.items_size() as usize;
@@ -326,7 +472,7 @@ fn compress_one_chunk<W: Write + Seek + Send>(
 ) -> std::io::Result<u64> {
     let start = dest.seek(SeekFrom::Current(0))?;
     {
-        let mut compressor = record_compressor_from_laz_items(&vlr.items(), &mut dest).unwrap();
+        let mut compressor = record_compressor_from_vlr(vlr, &mut dest).unwrap();
         compressor.compress_many(chunk_data)?;
         compressor.done()?;
     }
@@ -377,4 +523,31 @@ mod test {
         compressor.compress_many(&points).unwrap();
         assert_eq!(compressor.table_offset, 0);
     }
+
+    #[test]
+    fn test_compress_many_chunks() {
+        // Compressing a chunk does not keep its compressor alive until all chunks are done,
+        // which would use a few MiB per chunk
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = crate::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(10)
+            .build();
+        let points = crate::test_utils::points(200 * 10, 20);
+
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let mut compressor = ParLasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone())
+            .unwrap()
+            .with_thread_pool(std::sync::Arc::new(thread_pool));
+        compressor.compress_many(&points).unwrap();
+        assert_eq!(compressor.chunk_table.len(), 200);
+        assert!(compressor.compressors.len() <= 2);
+        let compressed = compressor.finish().unwrap().into_inner();
+
+        let mut decompressed = vec![0u8; points.len()];
+        crate::decompress_buffer(&compressed, &mut decompressed, vlr).unwrap();
+        assert_eq!(decompressed, points);
+    }
 }
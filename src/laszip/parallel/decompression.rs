@@ -1,11 +1,22 @@
 use std::io::{Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use rayon::prelude::*;
 
 use crate::byteslice::ChunksIrregularMut;
-use crate::laszip::chunk_table::{ChunkTable, ChunkTableEntry};
-use crate::laszip::details::record_decompressor_from_laz_items;
-use crate::LazVlr;
+use crate::laszip::chunk_table::{ChunkIndex, ChunkTable, ChunkTableEntry};
+use crate::laszip::details::{record_decompressor_with_selection, usize_from};
+use crate::laszip::parallel::{install, ScratchBuffers};
+use crate::laszip::point_buffer;
+use crate::laszip::read_at::{ReadAt, ReadAtCursor};
+use crate::laszip::{DecompressionSelection, LazOpenOptions};
+use crate::{LasZipError, LazVlr, PointBufferMut};
+
+/// Reads each buffer at its offset in the source, using multiple threads
+/// (in the given thread pool, if any).
+type ParReadAt<R> =
+    fn(&R, Option<&rayon::ThreadPool>, &mut [(u64, &mut [u8])]) -> std::io::Result<()>;
 
 #[cfg(feature = "parallel")]
 /// Laszip decompressor, that can decompress data using multiple threads
@@ -15,6 +26,8 @@ pub struct ParLasZipDecompressor<R> {
     vlr: LazVlr,
     /// Table of chunks read from the source.
     chunk_table: ChunkTable,
+    /// Where the chunks of the chunk table start, built at the first seek
+    chunk_index: Option<ChunkIndex>,
     last_chunk_read: isize,
     /// Position of the first compressed point.
     start_of_data: u64,
@@ -28,6 +41,18 @@ pub struct ParLasZipDecompressor<R> {
     // the compressed data is much much smaller that uncompressed data.
     internal_buffer: Vec<u8>,
     source: R,
+    /// Maximum number of chunks read and decompressed at once,
+    /// see [`Self::set_max_in_flight_chunks`]
+    max_in_flight_chunks: Option<usize>,
+    /// Pool in which the chunks are decompressed, the current one if None
+    thread_pool: Option<Arc<rayon::ThreadPool>>,
+    /// Set when the source allows positional reads, see [`ParLasZipDecompressor::new_read_at`]
+    par_read_at: Option<ParReadAt<R>>,
+    /// Maximum number of bytes allocated for the chunk table, the compressed chunks
+    /// and the rest, see [`LazOpenOptions::with_memory_limit`]
+    memory_limit: Option<usize>,
+    /// Fields decompressed, see [`LazOpenOptions::with_selection`]
+    selection: DecompressionSelection,
 }
 
 #[cfg(feature = "parallel")]
@@ -35,8 +60,31 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
     /// Creates a new decompressor
     ///
     /// Fails if no chunk table could be found.
-    pub fn new(mut source: R, vlr: LazVlr) -> crate::Result<Self> {
-        let chunk_table = ChunkTable::read_from(&mut source, &vlr)?;
+    pub fn new(source: R, vlr: LazVlr) -> crate::Result<Self> {
+        Self::with_scratch_buffers(source, vlr, ScratchBuffers::default())
+    }
+
+    /// Creates a new decompressor that uses the given buffers
+    /// instead of allocating its own, see [`ScratchBuffers`].
+    ///
+    /// Fails if no chunk table could be found.
+    pub fn with_scratch_buffers(
+        source: R,
+        vlr: LazVlr,
+        buffers: ScratchBuffers,
+    ) -> crate::Result<Self> {
+        Self::from_parts(source, vlr, buffers, LazOpenOptions::new())
+    }
+
+    fn from_parts(
+        mut source: R,
+        vlr: LazVlr,
+        buffers: ScratchBuffers,
+        mut options: LazOpenOptions<'_>,
+    ) -> crate::Result<Self> {
+        options.check_whole_chunk_options()?;
+        let chunk_table = options.read_chunk_table(&mut source, &vlr)?;
+        options.check_item_versions(&vlr)?;
         let start_of_data = source.seek(SeekFrom::Current(0))?;
         let biggest_chunk = chunk_table
             .as_ref()
@@ -44,20 +92,149 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
             .map(|entry| entry.point_count)
             .max()
             .unwrap();
-        let vec = Vec::<u8>::with_capacity(biggest_chunk as usize);
+        let (internal_buffer, mut vec) = buffers.into_vecs();
+        vec.clear();
+        vec.reserve(biggest_chunk as usize);
         let rest = std::io::Cursor::new(vec);
 
         Ok(Self {
             source,
             vlr,
             chunk_table,
+            chunk_index: None,
             rest,
-            internal_buffer: vec![],
+            internal_buffer,
             last_chunk_read: -1,
             start_of_data,
+            max_in_flight_chunks: options.max_in_flight_chunks.map(|max| max.max(1)),
+            thread_pool: options.thread_pool,
+            par_read_at: None,
+            memory_limit: options.memory_limit,
+            selection: options.selection,
         })
     }
 
+    /// Makes the decompressor decompress the chunks in the given thread pool,
+    /// instead of the global one (or the one it is called from).
+    ///
+    /// This allows to control the number of threads used by each decompressor
+    /// when several decompressions run concurrently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// use std::sync::Arc;
+    ///
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let pool = rayon::ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut points = vec![0u8; header.num_points as usize * vlr.items_size() as usize];
+    /// let mut decompressor =
+    ///     laz::ParLasZipDecompressor::new(laz_file, vlr)?.with_thread_pool(Arc::new(pool));
+    /// decompressor.decompress_many(&mut points)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_thread_pool(mut self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    /// Creates a new decompressor configured by the `options`, see [`LazOpenOptions`].
+    ///
+    /// The memory limit bounds the chunk table, the compressed data of the chunks
+    /// read at once, and the points of a chunk kept for the next call.
+    /// The number of points gives the number of points of the last chunk,
+    /// for fixed-size chunks.
+    ///
+    /// Fails if no chunk table could be found, and with [`LasZipError::UnsupportedOption`]
+    /// if the `options` defer the reading of the chunk table, save snapshots
+    /// or collect the contexts of the chunks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// use laz::laszip::LazOpenOptions;
+    /// use std::sync::Arc;
+    ///
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let pool = rayon::ThreadPoolBuilder::new()
+    ///     .num_threads(2)
+    ///     .build()
+    ///     .unwrap();
+    /// let options = LazOpenOptions::new().with_thread_pool(Arc::new(pool));
+    /// let mut points = vec![0u8; header.num_points as usize * vlr.items_size() as usize];
+    /// let mut decompressor = laz::ParLasZipDecompressor::with_options(laz_file, vlr, options)?;
+    /// decompressor.decompress_many(&mut points)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_options(
+        source: R,
+        vlr: LazVlr,
+        options: LazOpenOptions<'_>,
+    ) -> crate::Result<Self> {
+        Self::from_parts(source, vlr, ScratchBuffers::default(), options)
+    }
+
+    /// Sets the maximum number of chunks that are read and decompressed at once,
+    /// `None` (the default) means no limit.
+    ///
+    /// Without limit, the compressed data of all the chunks needed by a call
+    /// is loaded at once, and all the chunks are decompressed at the same time.
+    /// With a limit, the chunks are processed in batches of at most that many chunks,
+    /// bounding the memory used, while the chunks of a batch still use all the threads.
+    ///
+    /// It is best set to a multiple of the number of threads.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let mut points = vec![0u8; header.num_points as usize * vlr.items_size() as usize];
+    /// let mut decompressor = laz::ParLasZipDecompressor::new(laz_file, vlr)?;
+    /// decompressor.set_max_in_flight_chunks(Some(2 * rayon::current_num_threads()));
+    /// decompressor.decompress_many(&mut points)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_max_in_flight_chunks(&mut self, max_in_flight_chunks: Option<usize>) {
+        self.max_in_flight_chunks = max_in_flight_chunks.map(|max| max.max(1));
+    }
+
+    /// Returns the number of chunks processed at once, for `num_chunks` chunks
+    fn batch_size(&self, num_chunks: usize) -> usize {
+        self.max_in_flight_chunks.unwrap_or(num_chunks).max(1)
+    }
+
+    /// Decompresses as many points as the buffer can hold, see [`decompress_many`].
+    ///
+    /// Unlike [`decompress_many`], an error is returned if the buffer does
+    /// not contain points of the size described by the vlr.
+    ///
+    /// [`decompress_many`]: Self::decompress_many
+    pub fn decompress_points(&mut self, mut points: PointBufferMut) -> crate::Result<()> {
+        point_buffer::check_point_size(points.point_size(), &self.vlr)?;
+        self.decompress_many(points.as_bytes_mut())
+    }
+
     /// Decompresses many points using multiple threads
     ///
     /// For this function to actually use multiple threads, the `points`
@@ -66,6 +243,21 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
         let point_size = self.vlr.items_size() as usize;
         assert_eq!(out.len() % point_size, 0);
 
+        // With a limit of chunks in flight, the points of the chunks after
+        // the limit are decompressed by the next batches
+        let mut out = out;
+        while !out.is_empty() {
+            let num_bytes_decompressed = self.decompress_next_batch(out)?;
+            out = &mut std::mem::take(&mut out)[num_bytes_decompressed..];
+        }
+        Ok(())
+    }
+
+    /// Decompresses the points of the next batch of chunks (at most `max_in_flight_chunks`)
+    /// into `out`, and returns the number of bytes written.
+    fn decompress_next_batch(&mut self, out: &mut [u8]) -> crate::Result<usize> {
+        let point_size = self.vlr.items_size() as usize;
+        let out_len = out.len();
         let num_bytes_in_rest = self.rest.get_ref().len() - self.rest.position() as usize;
         debug_assert!(num_bytes_in_rest % point_size == 0);
 
@@ -81,7 +273,7 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
         }
 
         if out_decompress.is_empty() {
-            return Ok(());
+            return Ok(out_len);
         }
 
         debug_assert_eq!(
@@ -96,27 +288,54 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
         // and load their compressed bytes in our internal buffer
         let num_requested_points_left = out_decompress.len() / point_size;
         let start_index = (self.last_chunk_read + 1) as usize;
+        if start_index >= self.chunk_table.len() {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
         let mut num_points = 0usize;
         let mut num_chunks_to_decompress = 0;
-        let mut num_bytes_to_read = 0usize;
+        let mut num_bytes_to_read = 0u64;
         for entry in &self.chunk_table[start_index..] {
-            num_points += entry.point_count as usize;
+            num_points += usize_from(entry.point_count)?;
             num_chunks_to_decompress += 1;
-            num_bytes_to_read += entry.byte_count as usize;
-            if num_points >= num_requested_points_left {
+            num_bytes_to_read += entry.byte_count;
+            if num_points >= num_requested_points_left
+                || Some(num_chunks_to_decompress) == self.max_in_flight_chunks
+            {
                 break;
             }
         }
         let end_index = start_index + num_chunks_to_decompress;
 
         debug_assert!(num_chunks_to_decompress >= 1);
-        debug_assert!(num_points >= num_requested_points_left);
-        // TODO if num_points >= num_requested_points_left then the user ask to decompress more points
-        //      than there are
+        // TODO if num_points < num_requested_points_left and there are no chunks left,
+        //      then the user ask to decompress more points than there are
+
+        // The points of the chunks after the limit are left to the next batch
+        let num_bytes_in_batch =
+            if num_points < num_requested_points_left && end_index < self.chunk_table.len() {
+                num_points * point_size
+            } else {
+                out_decompress.len()
+            };
+        let num_bytes_not_in_batch = out_decompress.len() - num_bytes_in_batch;
+        let out_decompress = &mut out_decompress[..num_bytes_in_batch];
 
         // Read the necessary compressed bytes into our internal buffer
-        self.internal_buffer.resize(num_bytes_to_read, 0u8);
-        self.source.read(&mut self.internal_buffer)?;
+        crate::errors::check_memory_limit(self.memory_limit, usize_from(num_bytes_to_read)?)?;
+        if let Some(par_read_at) = self.par_read_at {
+            let mut offset = self.source.stream_position()?;
+            let mut reads = Vec::with_capacity(num_chunks_to_decompress);
+            for entry in &self.chunk_table[start_index..end_index] {
+                reads.push((offset, usize_from(entry.byte_count)?));
+                offset += entry.byte_count;
+            }
+            self.read_chunks_at(par_read_at, &reads)?;
+            self.source.seek(SeekFrom::Start(offset))?;
+        } else {
+            self.internal_buffer
+                .resize(usize_from(num_bytes_to_read)?, 0u8);
+            self.source.read(&mut self.internal_buffer)?;
+        }
 
         // 3. Decompress
         // The idea is that if we have `n` chunks to decompress
@@ -127,8 +346,8 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
         // and one for the `n` chunk.
         let head_chunks_table = &self.chunk_table[start_index..end_index - 1];
         let tail_chunk_entry = self.chunk_table[end_index - 1];
-        let num_bytes_in_head_chunks = num_bytes_to_read - tail_chunk_entry.byte_count as usize;
-        let num_points_in_head_chunks = num_points - tail_chunk_entry.point_count as usize;
+        let num_bytes_in_head_chunks = usize_from(num_bytes_to_read - tail_chunk_entry.byte_count)?;
+        let num_points_in_head_chunks = num_points - usize_from(tail_chunk_entry.point_count)?;
         let (head_chunks, tail_chunk) = self.internal_buffer.split_at(num_bytes_in_head_chunks);
         let (head_output, tail_output) =
             out_decompress.split_at_mut(num_points_in_head_chunks * point_size);
@@ -136,40 +355,44 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
         // These are to make the borrow checker happy self is not `Send`.
         let rest = &mut self.rest;
         let vlr = &self.vlr;
+        let (selection, memory_limit) = (self.selection, self.memory_limit);
         let chunk_table_len = self.chunk_table.len();
-        let (res1, res2) = rayon::join(
-            || -> crate::Result<()> {
-                par_decompress(head_chunks, head_output, &vlr, head_chunks_table)
-            },
-            || -> crate::Result<()> {
-                let mut last_src = std::io::Cursor::new(tail_chunk);
-                let mut decompressor =
-                    record_decompressor_from_laz_items(&vlr.items(), &mut last_src)?;
-                // Decompress what we can in the caller's buffer
-                decompressor.decompress_many(tail_output)?;
-                // Then, decompress what we did not, into our rest buffer
-                let num_bytes_left =
-                    (tail_chunk_entry.point_count as usize * point_size) - tail_output.len();
-                if !vlr.uses_variable_size_chunks() && end_index == chunk_table_len {
-                    // When fixed-size chunks are used, for the last chunk, the number of point
-                    // is unknown, so we have to decompress it until an end of file appears
-                    rest.get_mut().resize(num_bytes_left, 0u8);
-                    let num_actually_decompressed =
-                        decompressor.decompress_until_end_of_file(rest.get_mut())?;
-                    rest.get_mut().resize(num_actually_decompressed, 0u8);
-                } else {
-                    rest.get_mut().resize(num_bytes_left, 0u8);
-                    decompressor.decompress_many(rest.get_mut())?;
-                }
-                rest.set_position(0);
-                Ok(())
-            },
-        );
+        let (res1, res2) = install(self.thread_pool.as_deref(), || {
+            rayon::join(
+                || -> crate::Result<()> {
+                    par_decompress(head_chunks, head_output, vlr, head_chunks_table, selection)
+                },
+                || -> crate::Result<()> {
+                    let mut last_src = std::io::Cursor::new(tail_chunk);
+                    let mut decompressor =
+                        record_decompressor_with_selection(vlr, selection, &mut last_src)?;
+                    // Decompress what we can in the caller's buffer
+                    decompressor.decompress_many(tail_output)?;
+                    // Then, decompress what we did not, into our rest buffer
+                    let num_bytes_left = (usize_from(tail_chunk_entry.point_count)? * point_size)
+                        - tail_output.len();
+                    crate::errors::check_memory_limit(memory_limit, num_bytes_left)?;
+                    if !vlr.uses_variable_size_chunks() && end_index == chunk_table_len {
+                        // When fixed-size chunks are used, for the last chunk, the number of point
+                        // is unknown, so we have to decompress it until an end of file appears
+                        rest.get_mut().resize(num_bytes_left, 0u8);
+                        let num_actually_decompressed =
+                            decompressor.decompress_until_end_of_file(rest.get_mut())?;
+                        rest.get_mut().resize(num_actually_decompressed, 0u8);
+                    } else {
+                        rest.get_mut().resize(num_bytes_left, 0u8);
+                        decompressor.decompress_many(rest.get_mut())?;
+                    }
+                    rest.set_position(0);
+                    Ok(())
+                },
+            )
+        });
         res1?;
         res2?;
 
         self.last_chunk_read += num_chunks_to_decompress as isize;
-        Ok(())
+        Ok(out_len - num_bytes_not_in_batch)
     }
 
     /// Seeks to the position of the point at the given index
@@ -178,38 +401,47 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
         self.rest.set_position(0);
         self.rest.get_mut().clear();
 
-        let chunk_of_point = (index / self.vlr.chunk_size() as u64) as usize;
-        if chunk_of_point >= self.chunk_table.len() {
-            let _ = self.source.seek(SeekFrom::End(0))?;
-            return Ok(());
-        }
+        let chunk_table = &self.chunk_table;
+        let chunk_index = self
+            .chunk_index
+            .get_or_insert_with(|| ChunkIndex::new(chunk_table));
+        let (chunk_of_point, first_point, offset) = match chunk_index.chunk_of_point(index) {
+            Some(found) => found,
+            None => {
+                // No chunk is left, so that the next decompression fails
+                let _ = self.source.seek(SeekFrom::End(0))?;
+                self.last_chunk_read = self.chunk_table.len() as isize - 1;
+                return Ok(());
+            }
+        };
+        self.last_chunk_read = chunk_of_point as isize;
+        let entry = self.chunk_table[chunk_of_point];
         // Seek to the start of the points chunk
         // and read the chunk data
-        let start_of_chunk_pos = self.start_of_data
-            + self.chunk_table[..chunk_of_point]
-                .iter()
-                .map(|entry| entry.byte_count)
-                .sum::<u64>();
-        self.source.seek(SeekFrom::Start(start_of_chunk_pos))?;
+        self.source
+            .seek(SeekFrom::Start(self.start_of_data + offset))?;
+        crate::errors::check_memory_limit(self.memory_limit, usize_from(entry.byte_count)?)?;
         self.internal_buffer
-            .resize(self.chunk_table[chunk_of_point].byte_count as usize, 0u8);
+            .resize(usize_from(entry.byte_count)?, 0u8);
         self.source.read(&mut self.internal_buffer)?;
 
         // Completely decompress the chunk
-        self.rest
-            .get_mut()
-            .resize(self.vlr.num_bytes_in_decompressed_chunk() as usize, 0u8);
-        let mut decompressor = record_decompressor_from_laz_items(
-            self.vlr.items(),
+        let num_bytes_in_chunk = usize_from(entry.point_count * self.vlr.items_size())?;
+        crate::errors::check_memory_limit(self.memory_limit, num_bytes_in_chunk)?;
+        self.rest.get_mut().resize(num_bytes_in_chunk, 0u8);
+        let mut decompressor = record_decompressor_with_selection(
+            &self.vlr,
+            self.selection,
             std::io::Cursor::new(&self.internal_buffer),
         )?;
+        let pos_in_chunk = index - first_point;
         let is_last_chunk = chunk_of_point == (self.chunk_table.len() - 1);
         if is_last_chunk {
             let num_bytes_decompressed =
                 decompressor.decompress_until_end_of_file(self.rest.get_mut())?;
-            let num_points_in_last_chunk = num_bytes_decompressed / self.vlr.items_size() as usize;
-            let pos_in_chunk = index % self.vlr.chunk_size() as u64;
-            if pos_in_chunk as usize >= num_points_in_last_chunk as usize {
+            let num_points_in_last_chunk =
+                (num_bytes_decompressed / self.vlr.items_size() as usize) as u64;
+            if pos_in_chunk >= num_points_in_last_chunk {
                 // Make the rest appear as fully consumed to
                 // force EOF error on next decompression
                 self.rest.set_position(self.rest.get_ref().len() as u64);
@@ -220,16 +452,351 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
         }
         // This effectively discard points that were
         // before the one we just seeked to
-        let pos_in_chunk = index % self.vlr.chunk_size() as u64;
         self.rest.set_position(pos_in_chunk * self.vlr.items_size());
-        self.last_chunk_read = chunk_of_point as isize;
         Ok(())
     }
 
+    /// Decompresses the chunks at the `chunk_indices` using multiple threads,
+    /// and returns the points of each chunk with the index of the chunk,
+    /// in the order of the `chunk_indices`.
+    ///
+    /// The position of the decompressor is not changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let mut decompressor = laz::ParLasZipDecompressor::new(laz_file, vlr)?;
+    /// // e.g. the chunks found by a spatial query
+    /// let chunks = decompressor.decompress_selected_chunks(&[0])?;
+    /// let (index, points) = &chunks[0];
+    /// assert_eq!(*index, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decompress_selected_chunks(
+        &mut self,
+        chunk_indices: &[usize],
+    ) -> crate::Result<Vec<(usize, Vec<u8>)>> {
+        let mut chunks = Vec::with_capacity(chunk_indices.len());
+        for batch in chunk_indices.chunks(self.batch_size(chunk_indices.len())) {
+            let ranges = self.read_selected_chunks(batch)?;
+
+            let internal_buffer = &self.internal_buffer;
+            let vlr = &self.vlr;
+            let chunk_table = &self.chunk_table;
+            let selection = self.selection;
+            let batch_chunks = install(self.thread_pool.as_deref(), || {
+                batch
+                    .par_iter()
+                    .zip(ranges)
+                    .map(|(&index, range)| {
+                        let points = decompress_one_chunk(
+                            &internal_buffer[range],
+                            vlr,
+                            chunk_table,
+                            index,
+                            selection,
+                        )?;
+                        Ok((index, points))
+                    })
+                    .collect::<crate::Result<Vec<_>>>()
+            })?;
+            chunks.extend(batch_chunks);
+        }
+        Ok(chunks)
+    }
+
+    /// Decompresses the chunks at the `chunk_indices` using multiple threads,
+    /// directly into the `out` buffer, and returns the number of points of each chunk.
+    ///
+    /// The chunks are stored one after the other in the order of the `chunk_indices`,
+    /// each one taking the space for the number of points the chunk table gives it.
+    /// Only the last chunk of a file using fixed-size chunks may have fewer points
+    /// than that, in which case the end of its space is left untouched.
+    ///
+    /// Unlike [`Self::decompress_selected_chunks`], no buffer is allocated per chunk.
+    ///
+    /// The position of the decompressor is not changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let mut points = vec![0u8; vlr.chunk_size() as usize * vlr.items_size() as usize];
+    /// let mut decompressor = laz::ParLasZipDecompressor::new(laz_file, vlr)?;
+    /// let counts = decompressor.decompress_selected_chunks_into(&[0], &mut points)?;
+    /// assert_eq!(counts.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decompress_selected_chunks_into(
+        &mut self,
+        chunk_indices: &[usize],
+        out: &mut [u8],
+    ) -> crate::Result<Vec<usize>> {
+        let point_size = self.vlr.items_size() as usize;
+        let mut sizes = Vec::with_capacity(chunk_indices.len());
+        for &index in chunk_indices {
+            let entry = check_chunk_index(&self.chunk_table, index)?;
+            sizes.push(usize_from(entry.point_count)? * point_size);
+        }
+        let required = sizes.iter().sum::<usize>();
+        if out.len() < required {
+            return Err(LasZipError::OutputBufferTooSmall { required });
+        }
+
+        let batch_size = self.batch_size(chunk_indices.len());
+        let mut counts = Vec::with_capacity(chunk_indices.len());
+        let mut out = &mut out[..required];
+        for (batch, batch_sizes) in chunk_indices
+            .chunks(batch_size)
+            .zip(sizes.chunks(batch_size))
+        {
+            let (batch_out, next_out) =
+                std::mem::take(&mut out).split_at_mut(batch_sizes.iter().sum());
+            out = next_out;
+            let ranges = self.read_selected_chunks(batch)?;
+
+            let internal_buffer = &self.internal_buffer;
+            let vlr = &self.vlr;
+            let chunk_table = &self.chunk_table;
+            let selection = self.selection;
+            let jobs: Vec<(&usize, _, &mut [u8])> = batch
+                .iter()
+                .zip(ranges)
+                .zip(ChunksIrregularMut::new(batch_out, batch_sizes.to_vec()))
+                .map(|((index, range), chunk_out)| (index, range, chunk_out))
+                .collect();
+            let batch_counts = install(self.thread_pool.as_deref(), || {
+                jobs.into_par_iter()
+                    .map(|(&index, range, chunk_out)| {
+                        let num_bytes = decompress_one_chunk_into(
+                            &internal_buffer[range],
+                            chunk_out,
+                            vlr,
+                            chunk_table,
+                            index,
+                            selection,
+                        )?;
+                        Ok(num_bytes / point_size)
+                    })
+                    .collect::<crate::Result<Vec<usize>>>()
+            })?;
+            counts.extend(batch_counts);
+        }
+        Ok(counts)
+    }
+
+    /// Reads the compressed data of the chunks at the `chunk_indices` one after the other
+    /// in the internal buffer, and returns the range of each one in it.
+    ///
+    /// The position of the source is not changed.
+    fn read_selected_chunks(
+        &mut self,
+        chunk_indices: &[usize],
+    ) -> crate::Result<Vec<std::ops::Range<usize>>> {
+        if let Some(par_read_at) = self.par_read_at {
+            let mut reads = Vec::with_capacity(chunk_indices.len());
+            let mut ranges = Vec::with_capacity(chunk_indices.len());
+            let mut end = 0;
+            for &index in chunk_indices {
+                let entry = check_chunk_index(&self.chunk_table, index)?;
+                let start_of_chunk = self.start_of_data
+                    + self.chunk_table[..index]
+                        .iter()
+                        .map(|entry| entry.byte_count)
+                        .sum::<u64>();
+                let size = usize_from(entry.byte_count)?;
+                reads.push((start_of_chunk, size));
+                ranges.push(end..end + size);
+                end += size;
+            }
+            crate::errors::check_memory_limit(self.memory_limit, end)?;
+            self.read_chunks_at(par_read_at, &reads)?;
+            return Ok(ranges);
+        }
+
+        let position = self.source.stream_position()?;
+        let mut ranges = Vec::with_capacity(chunk_indices.len());
+        self.internal_buffer.clear();
+        for &index in chunk_indices {
+            let entry = check_chunk_index(&self.chunk_table, index)?;
+            let start_of_chunk = self.start_of_data
+                + self.chunk_table[..index]
+                    .iter()
+                    .map(|entry| entry.byte_count)
+                    .sum::<u64>();
+            let start = self.internal_buffer.len();
+            let end = start + usize_from(entry.byte_count)?;
+            crate::errors::check_memory_limit(self.memory_limit, end)?;
+            self.source.seek(SeekFrom::Start(start_of_chunk))?;
+            self.internal_buffer.resize(end, 0u8);
+            self.source
+                .read_exact(&mut self.internal_buffer[start..end])?;
+            ranges.push(start..end);
+        }
+        self.source.seek(SeekFrom::Start(position))?;
+        Ok(ranges)
+    }
+
+    /// Reads the chunks of the `reads` (their offset in the source and size)
+    /// one after the other in our internal buffer, with positional reads in parallel.
+    fn read_chunks_at(
+        &mut self,
+        par_read_at: ParReadAt<R>,
+        reads: &[(u64, usize)],
+    ) -> crate::Result<()> {
+        let sizes = reads.iter().map(|&(_, size)| size);
+        self.internal_buffer.resize(sizes.clone().sum(), 0u8);
+        let mut buffers = reads
+            .iter()
+            .map(|&(offset, _)| offset)
+            .zip(ChunksIrregularMut::new(&mut self.internal_buffer, sizes))
+            .collect::<Vec<_>>();
+        par_read_at(&self.source, self.thread_pool.as_deref(), &mut buffers)?;
+        Ok(())
+    }
+
+    /// Decompresses the chunks at the `chunk_indices` using multiple threads,
+    /// and gives the points of each chunk to `on_chunk` with the index of the chunk,
+    /// as soon as it is decompressed.
+    ///
+    /// The chunks are read and start being decompressed in the order of the `chunk_indices`,
+    /// so that the first ones (e.g. the chunks that are closest to the camera of a viewer)
+    /// are available first, but they may finish in a different order.
+    ///
+    /// `on_chunk` is called from the threads of the decompression, one chunk at a time.
+    /// Once a chunk fails, the chunks that did not start are not decompressed,
+    /// and the error is returned.
+    ///
+    /// The position of the decompressor is not changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let mut decompressor = laz::ParLasZipDecompressor::new(laz_file, vlr)?;
+    /// let (sender, receiver) = std::sync::mpsc::channel();
+    /// decompressor.decompress_scheduled_chunks(&[0], |index, points| {
+    ///     // e.g. send them to the renderer
+    ///     sender.send((index, points)).unwrap();
+    /// })?;
+    /// assert_eq!(receiver.recv().unwrap().0, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decompress_scheduled_chunks<F>(
+        &mut self,
+        chunk_indices: &[usize],
+        on_chunk: F,
+    ) -> crate::Result<()>
+    where
+        R: Send,
+        F: FnMut(usize, Vec<u8>) + Send,
+    {
+        for &index in chunk_indices {
+            check_chunk_index(&self.chunk_table, index)?;
+        }
+        let mut chunk_starts = Vec::with_capacity(self.chunk_table.len());
+        let mut start = self.start_of_data;
+        for entry in &self.chunk_table {
+            chunk_starts.push(start);
+            start += entry.byte_count;
+        }
+
+        let position = self.source.stream_position()?;
+        let batch_size = self.batch_size(chunk_indices.len());
+        let thread_pool = self.thread_pool.as_deref();
+        let source = &mut self.source;
+        let vlr = &self.vlr;
+        let chunk_table = &self.chunk_table;
+        let (selection, memory_limit) = (self.selection, self.memory_limit);
+        let on_chunk = Mutex::new(on_chunk);
+        let error = Mutex::new(None);
+        let failed = AtomicBool::new(false);
+        // Each batch is done before the next one is read, to bound the chunks in flight
+        for batch in chunk_indices.chunks(batch_size) {
+            if failed.load(Ordering::Relaxed) {
+                break;
+            }
+            install(thread_pool, || {
+                rayon::scope_fifo(|scope| {
+                    for &index in batch {
+                        if failed.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let entry = chunk_table[index];
+                        let chunk_data = match read_chunk(
+                            &mut *source,
+                            chunk_starts[index],
+                            entry,
+                            memory_limit,
+                        ) {
+                            Ok(chunk_data) => chunk_data,
+                            Err(e) => {
+                                failed.store(true, Ordering::Relaxed);
+                                error.lock().unwrap().get_or_insert(e);
+                                break;
+                            }
+                        };
+                        let (on_chunk, error, failed) = (&on_chunk, &error, &failed);
+                        scope.spawn_fifo(move |_| {
+                            if failed.load(Ordering::Relaxed) {
+                                return;
+                            }
+                            match decompress_one_chunk(
+                                &chunk_data,
+                                vlr,
+                                chunk_table,
+                                index,
+                                selection,
+                            ) {
+                                Ok(points) => (on_chunk.lock().unwrap())(index, points),
+                                Err(e) => {
+                                    failed.store(true, Ordering::Relaxed);
+                                    error.lock().unwrap().get_or_insert(e);
+                                }
+                            }
+                        });
+                    }
+                })
+            });
+        }
+        self.source.seek(SeekFrom::Start(position))?;
+        match error.into_inner().unwrap() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
     pub fn into_inner(self) -> R {
         self.source
     }
 
+    /// Returns the source and the buffers, so that they can be reused
+    pub fn into_parts(self) -> (R, ScratchBuffers) {
+        (
+            self.source,
+            ScratchBuffers::from_vecs(self.internal_buffer, self.rest.into_inner()),
+        )
+    }
+
     pub fn get_mut(&mut self) -> &mut R {
         &mut self.source
     }
@@ -239,6 +806,58 @@ impl<R: Read + Seek> ParLasZipDecompressor<R> {
     }
 }
 
+#[cfg(feature = "parallel")]
+impl<S: ReadAt + Sync> ParLasZipDecompressor<ReadAtCursor<S>> {
+    /// Creates a decompressor of the point data that starts at `offset_to_points`
+    /// (where the offset to the chunk table is) in the `source`,
+    /// that reads the chunks with positional reads.
+    ///
+    /// Instead of reading all the chunks through one `Read + Seek` source before
+    /// decompressing them, each thread reads its chunk with its own [`ReadAt::read_exact_at`],
+    /// so that the reads are not serialized, in [`decompress_many`], [`decompress_selected_chunks`]
+    /// and [`decompress_selected_chunks_into`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// let file = std::fs::File::open("tests/data/point10.laz")?;
+    /// let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut &file)?;
+    /// let vlr = vlr.unwrap();
+    /// let mut points = vec![0u8; header.num_points as usize * vlr.items_size() as usize];
+    /// let mut decompressor = laz::ParLasZipDecompressor::new_read_at(
+    ///     &file,
+    ///     vlr,
+    ///     u64::from(header.offset_to_points),
+    /// )?;
+    /// decompressor.decompress_many(&mut points)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`decompress_many`]: Self::decompress_many
+    /// [`decompress_selected_chunks`]: Self::decompress_selected_chunks
+    /// [`decompress_selected_chunks_into`]: Self::decompress_selected_chunks_into
+    pub fn new_read_at(source: S, vlr: LazVlr, offset_to_points: u64) -> crate::Result<Self> {
+        Self::new_read_at_with_options(source, vlr, offset_to_points, LazOpenOptions::new())
+    }
+
+    /// Same as [`Self::new_read_at`], configured by the `options`,
+    /// see [`Self::with_options`].
+    pub fn new_read_at_with_options(
+        source: S,
+        vlr: LazVlr,
+        offset_to_points: u64,
+        options: LazOpenOptions<'_>,
+    ) -> crate::Result<Self> {
+        let mut cursor = ReadAtCursor::new(source);
+        cursor.set_position(offset_to_points);
+        let mut decompressor = Self::with_options(cursor, vlr, options)?;
+        decompressor.par_read_at = Some(super::read_at::par_read_exact_at);
+        Ok(decompressor)
+    }
+}
+
 impl<R: Read + Seek> crate::laszip::LazDecompressor for ParLasZipDecompressor<R> {
     fn decompress_many(&mut self, points: &mut [u8]) -> crate::Result<()> {
         self.decompress_many(points)
@@ -271,11 +890,13 @@ pub fn par_decompress_buffer(
     let mut cursor = std::io::Cursor::new(compressed_points_data);
     let chunk_table = ChunkTable::read_from(&mut cursor, &laz_vlr)?;
 
-    let num_point_bytes = chunk_table
-        .as_ref()
-        .iter()
-        .map(|entry| entry.byte_count as usize)
-        .sum::<usize>();
+    let num_point_bytes = usize_from(
+        chunk_table
+            .as_ref()
+            .iter()
+            .map(|entry| entry.byte_count)
+            .sum::<u64>(),
+    )?;
 
     let compressed_points = &compressed_points_data[std::mem::size_of::<i64>()..num_point_bytes];
     par_decompress(
@@ -283,9 +904,136 @@ pub fn par_decompress_buffer(
         decompressed_points,
         laz_vlr,
         chunk_table.as_ref(),
+        DecompressionSelection::all(),
     )
 }
 
+/// Decompresses the chunks at the `chunk_indices` of the compressed data,
+/// using multiple threads.
+///
+/// Returns the points of each chunk with the index of the chunk,
+/// in the order of the `chunk_indices`.
+///
+/// Like [`par_decompress_buffer`], the chunk table is mandatory.
+///
+/// The chunks are decompressed in the `thread_pool` if one is given,
+/// in the current one otherwise.
+#[cfg(feature = "parallel")]
+pub fn par_decompress_selected_chunks(
+    compressed_points_data: &[u8],
+    laz_vlr: &LazVlr,
+    chunk_indices: &[usize],
+    thread_pool: Option<&rayon::ThreadPool>,
+) -> crate::Result<Vec<(usize, Vec<u8>)>> {
+    let mut cursor = std::io::Cursor::new(compressed_points_data);
+    let chunk_table = ChunkTable::read_from(&mut cursor, laz_vlr)?;
+    let mut chunk_starts = Vec::with_capacity(chunk_table.len());
+    let mut start = std::mem::size_of::<i64>() as u64;
+    for entry in &chunk_table {
+        chunk_starts.push(start);
+        start += entry.byte_count;
+    }
+
+    install(thread_pool, || {
+        chunk_indices
+            .par_iter()
+            .map(|&index| {
+                let entry = check_chunk_index(&chunk_table, index)?;
+                let start = usize_from(chunk_starts[index])?;
+                let end = start + usize_from(entry.byte_count)?;
+                let chunk_data = compressed_points_data.get(start..end).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "The compressed data is shorter than the chunk table says",
+                    )
+                })?;
+                let points = decompress_one_chunk(
+                    chunk_data,
+                    laz_vlr,
+                    &chunk_table,
+                    index,
+                    DecompressionSelection::all(),
+                )?;
+                Ok((index, points))
+            })
+            .collect()
+    })
+}
+
+#[cfg(feature = "parallel")]
+fn check_chunk_index(chunk_table: &ChunkTable, index: usize) -> crate::Result<ChunkTableEntry> {
+    chunk_table
+        .as_ref()
+        .get(index)
+        .copied()
+        .ok_or(LasZipError::ChunkIndexOutOfRange {
+            index,
+            num_chunks: chunk_table.len(),
+        })
+}
+
+/// Reads the compressed data of the chunk of the `entry`, that starts at `start`
+#[cfg(feature = "parallel")]
+fn read_chunk<R: Read + Seek>(
+    source: &mut R,
+    start: u64,
+    entry: ChunkTableEntry,
+    memory_limit: Option<usize>,
+) -> crate::Result<Vec<u8>> {
+    crate::errors::check_memory_limit(memory_limit, usize_from(entry.byte_count)?)?;
+    let mut chunk_data = vec![0u8; usize_from(entry.byte_count)?];
+    source.seek(SeekFrom::Start(start))?;
+    source.read_exact(&mut chunk_data)?;
+    Ok(chunk_data)
+}
+
+/// Decompresses all the points of the chunk at `index` from its data
+#[cfg(feature = "parallel")]
+fn decompress_one_chunk(
+    chunk_data: &[u8],
+    laz_vlr: &LazVlr,
+    chunk_table: &ChunkTable,
+    index: usize,
+    selection: DecompressionSelection,
+) -> crate::Result<Vec<u8>> {
+    let num_bytes = usize_from(chunk_table[index].point_count * laz_vlr.items_size())?;
+    let mut points = vec![0u8; num_bytes];
+    let num_decompressed = decompress_one_chunk_into(
+        chunk_data,
+        &mut points,
+        laz_vlr,
+        chunk_table,
+        index,
+        selection,
+    )?;
+    points.truncate(num_decompressed);
+    Ok(points)
+}
+
+/// Decompresses all the points of the chunk at `index` from its data
+/// into `out`, which must be able to hold the points the chunk table says it has.
+///
+/// Returns the number of bytes decompressed.
+#[cfg(feature = "parallel")]
+fn decompress_one_chunk_into(
+    chunk_data: &[u8],
+    out: &mut [u8],
+    laz_vlr: &LazVlr,
+    chunk_table: &ChunkTable,
+    index: usize,
+    selection: DecompressionSelection,
+) -> crate::Result<usize> {
+    let mut decompressor =
+        record_decompressor_with_selection(laz_vlr, selection, std::io::Cursor::new(chunk_data))?;
+    if !laz_vlr.uses_variable_size_chunks() && index == chunk_table.len() - 1 {
+        // The number of points of the last fixed-size chunk is unknown
+        Ok(decompressor.decompress_until_end_of_file(out)?)
+    } else {
+        decompressor.decompress_many(out)?;
+        Ok(out.len())
+    }
+}
+
 /// Actual the parallel decompression
 ///
 /// `compressed_points` must contains only the bytes corresponding to the points
@@ -296,12 +1044,17 @@ fn par_decompress(
     decompressed_points: &mut [u8],
     laz_vlr: &LazVlr,
     chunk_table: &[ChunkTableEntry],
+    selection: DecompressionSelection,
 ) -> crate::Result<()> {
     use crate::byteslice::ChunksIrregular;
-    let sizes = chunk_table.iter().map(|entry| entry.byte_count as usize);
+    let sizes = chunk_table
+        .iter()
+        .map(|entry| usize_from(entry.byte_count))
+        .collect::<std::io::Result<Vec<usize>>>()?;
     let counts = chunk_table
         .iter()
-        .map(|entry| (entry.point_count * laz_vlr.items_size()) as usize);
+        .map(|entry| usize_from(entry.point_count * laz_vlr.items_size()))
+        .collect::<std::io::Result<Vec<usize>>>()?;
     let input_chunks_iter = ChunksIrregular::new(compressed_points, sizes);
     let output_chunks_iter = ChunksIrregularMut::new(decompressed_points, counts);
 
@@ -313,7 +1066,8 @@ fn par_decompress(
         .into_par_iter()
         .map(|(chunk_in, chunk_out)| {
             let src = std::io::Cursor::new(chunk_in);
-            let mut record_decompressor = record_decompressor_from_laz_items(laz_vlr.items(), src)?;
+            let mut record_decompressor =
+                record_decompressor_with_selection(laz_vlr, selection, src)?;
             record_decompressor.decompress_many(chunk_out)?;
             Ok(())
         })
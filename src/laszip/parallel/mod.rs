@@ -1,5 +1,26 @@
+pub use buffers::ScratchBuffers;
 pub use compression::{par_compress, par_compress_buffer, ParLasZipCompressor};
-pub use decompression::{par_decompress_buffer, ParLasZipDecompressor};
+pub use decompression::{
+    par_decompress_buffer, par_decompress_selected_chunks, ParLasZipDecompressor,
+};
+pub use read_at::par_decompress_read_at;
+pub use transcode::par_transcode_las_to_laz;
 
+mod buffers;
 mod compression;
 mod decompression;
+mod read_at;
+mod transcode;
+
+/// Runs `op` in the given thread pool, or in the current one (the global one
+/// when not called from a pool) if there is none.
+fn install<OP, T>(pool: Option<&rayon::ThreadPool>, op: OP) -> T
+where
+    OP: FnOnce() -> T + Send,
+    T: Send,
+{
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
@@ -0,0 +1,145 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::laszip::details::usize_from;
+use crate::laszip::parallel::install;
+use crate::laszip::read_at::{ReadAt, ReadAtCursor, ReadAtDecompressor};
+use crate::LazVlr;
+
+#[cfg(feature = "parallel")]
+impl<R: ReadAt + Sync> ReadAtDecompressor<R> {
+    /// Makes the decompressor decompress the chunks in the given thread pool,
+    /// instead of the global one (or the one it is called from),
+    /// see [`ParLasZipDecompressor::with_thread_pool`].
+    ///
+    /// [`ParLasZipDecompressor::with_thread_pool`]: crate::ParLasZipDecompressor::with_thread_pool
+    pub fn with_thread_pool(mut self, thread_pool: Arc<rayon::ThreadPool>) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    /// Decompresses the chunks of the `chunks` range into `out`, using multiple threads.
+    ///
+    /// Like the [`ParLasZipDecompressor`] created with [`new_read_at`],
+    /// each thread reads its chunk with its own [`ReadAt::read_exact_at`],
+    /// so the reads are not serialized.
+    ///
+    /// `out` is filled with the points of the chunks, in order, it may hold less points
+    /// than the chunks (e.g. for the last fixed-size chunk, whose number of points is unknown),
+    /// the chunks after the ones that fill it are not decompressed.
+    ///
+    /// Returns the number of points decompressed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// use laz::laszip::read_at::ReadAtDecompressor;
+    ///
+    /// let file = std::fs::File::open("tests/data/point10.laz")?;
+    /// let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut &file)?;
+    /// let vlr = vlr.unwrap();
+    /// let decompressor =
+    ///     ReadAtDecompressor::new(&file, vlr.clone(), u64::from(header.offset_to_points))?;
+    ///
+    /// let mut points = vec![0u8; header.num_points as usize * vlr.items_size() as usize];
+    /// let num_chunks = decompressor.chunk_table().len();
+    /// decompressor.par_decompress_chunks(0..num_chunks, &mut points)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ParLasZipDecompressor`]: crate::ParLasZipDecompressor
+    /// [`new_read_at`]: crate::ParLasZipDecompressor::new_read_at
+    pub fn par_decompress_chunks(
+        &self,
+        chunks: Range<usize>,
+        out: &mut [u8],
+    ) -> crate::Result<u64> {
+        self.par_decompress_chunks_in(self.thread_pool.as_deref(), chunks, out)
+    }
+
+    /// [`Self::par_decompress_chunks`], in the given thread pool
+    fn par_decompress_chunks_in(
+        &self,
+        thread_pool: Option<&rayon::ThreadPool>,
+        chunks: Range<usize>,
+        out: &mut [u8],
+    ) -> crate::Result<u64> {
+        let point_size = self.vlr().items_size();
+        let entries = self.chunk_table().as_ref().get(chunks.clone()).ok_or(
+            crate::LasZipError::ChunkIndexOutOfRange {
+                index: chunks.end.saturating_sub(1),
+                num_chunks: self.chunk_table().len(),
+            },
+        )?;
+
+        let mut jobs = Vec::with_capacity(entries.len());
+        let mut rest = out;
+        for (index, entry) in chunks.zip(entries) {
+            if rest.is_empty() {
+                break;
+            }
+            let num_bytes = usize_from(entry.point_count * point_size)?.min(rest.len());
+            let (chunk_out, tail) = rest.split_at_mut(num_bytes);
+            jobs.push((index, chunk_out));
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "The chunks hold less points than the output buffer",
+            )
+            .into());
+        }
+
+        install(thread_pool, || {
+            jobs.into_par_iter()
+                .map(|(index, chunk_out)| self.decompress_chunk(index, chunk_out))
+                .sum()
+        })
+    }
+}
+
+/// Decompresses the points of the point data at `offset_to_points` in the `source`
+/// into `out`, using multiple threads that each read their chunk with positional reads.
+///
+/// Like [`par_decompress_buffer`], the chunk table is mandatory,
+/// see [`ReadAtDecompressor::par_decompress_chunks`].
+///
+/// The chunks are decompressed in the `thread_pool` if one is given,
+/// in the current one otherwise.
+///
+/// [`par_decompress_buffer`]: crate::par_decompress_buffer
+#[cfg(feature = "parallel")]
+pub fn par_decompress_read_at<R: ReadAt + Sync>(
+    source: R,
+    vlr: LazVlr,
+    offset_to_points: u64,
+    out: &mut [u8],
+    thread_pool: Option<&rayon::ThreadPool>,
+) -> crate::Result<()> {
+    let decompressor = ReadAtDecompressor::new(source, vlr, offset_to_points)?;
+    let num_chunks = decompressor.chunk_table().len();
+    decompressor.par_decompress_chunks_in(thread_pool, 0..num_chunks, out)?;
+    Ok(())
+}
+
+/// Reads each buffer of the `reads` at its offset in the source of the `cursor`,
+/// using multiple threads, see [`ParLasZipDecompressor::new_read_at`].
+///
+/// [`ParLasZipDecompressor::new_read_at`]: crate::ParLasZipDecompressor::new_read_at
+pub(super) fn par_read_exact_at<R: ReadAt + Sync>(
+    cursor: &ReadAtCursor<R>,
+    thread_pool: Option<&rayon::ThreadPool>,
+    reads: &mut [(u64, &mut [u8])],
+) -> std::io::Result<()> {
+    let source = cursor.get_ref();
+    install(thread_pool, || {
+        reads
+            .par_iter_mut()
+            .try_for_each(|(offset, buffer)| source.read_exact_at(*offset, buffer))
+    })
+}
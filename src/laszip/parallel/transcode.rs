@@ -0,0 +1,147 @@
+use std::convert::TryFrom;
+use std::io::{Cursor, Seek, SeekFrom, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use rayon::prelude::*;
+
+use crate::las::file::QuickHeader;
+use crate::laszip::chunk_table::{update_chunk_table_offset, ChunkTable};
+use crate::laszip::invalid_data;
+use crate::{LasZipError, LazVlrBuilder};
+
+use super::compression::{par_compress_chunks_with, CompressorPool};
+
+/// Size of the header of a VLR
+const VLR_HEADER_SIZE: usize = 54;
+/// Offsets of fields of the LAS header
+const OFFSET_TO_POINTS_POS: usize = 96;
+const NUM_VLRS_POS: usize = 100;
+const POINT_FORMAT_POS: usize = 104;
+const START_OF_FIRST_EVLR_POS: usize = 235;
+
+/// Returns the `range` of the `las`, or an error if the `las` is too short
+fn slice(las: &[u8], start: usize, end: usize) -> std::io::Result<&[u8]> {
+    las.get(start..end)
+        .ok_or_else(|| invalid_data("LAS data is truncated"))
+}
+
+/// Converts the uncompressed LAS file in `las` to a LAZ file written to the `dst`,
+/// the points are compressed in parallel, in fixed-size chunks of `chunk_size` points.
+///
+/// The header, VLRs and EVLRs are copied, the LasZip VLR is added after the VLRs.
+///
+/// The `las` bytes are only read, a few chunks at a time, and never copied,
+/// so it is meant to be a memory map of the LAS file (e.g. made with the `memmap2` crate),
+/// which avoids the sequential reads & copies of the usual path.
+///
+/// Returns the `dst`.
+///
+/// # Panics
+///
+/// Panics if the `chunk_size` is 0.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// // A memory map of the file works too
+/// let las = std::fs::read("tests/data/point-time-color.las")?;
+/// let laz = laz::par_transcode_las_to_laz(&las, std::io::Cursor::new(Vec::<u8>::new()), 5_000)?;
+///
+/// let mut reader = laz::las::file::SimpleReader::new(laz)?;
+/// assert_eq!(reader.header.point_format_id, 3);
+/// # Ok(())
+/// # }
+/// ```
+pub fn par_transcode_las_to_laz<W: Write + Seek + Send>(
+    las: &[u8],
+    mut dst: W,
+    chunk_size: u32,
+) -> crate::Result<W> {
+    assert_ne!(chunk_size, 0, "the chunk size must not be 0");
+    let header = QuickHeader::read_from(&mut Cursor::new(las))?;
+    if header.point_format_id & 0xC0 != 0 {
+        return Err(invalid_data("LAS data is already compressed").into());
+    }
+    if !matches!(header.point_format_id, 0..=3 | 6..=8) {
+        return Err(LasZipError::UnsupportedPointFormat(header.point_format_id));
+    }
+    let vlr = LazVlrBuilder::default()
+        .with_point_format(header.point_format_id, header.num_extra_bytes())?
+        .with_fixed_chunk_size(chunk_size)
+        .build();
+    let point_size = vlr.items_size() as usize;
+
+    // The VLRs, the bytes between them and the points are kept after the LasZip VLR
+    let header_size = usize::from(header.header_size);
+    let offset_to_points = header.offset_to_points as usize;
+    let mut vlrs_end = header_size;
+    for _ in 0..header.num_vlrs {
+        let mut vlr_header = slice(las, vlrs_end, vlrs_end + VLR_HEADER_SIZE)?;
+        vlr_header = &vlr_header[20..];
+        let record_length = vlr_header.read_u16::<LittleEndian>()?;
+        vlrs_end += VLR_HEADER_SIZE + usize::from(record_length);
+    }
+    if vlrs_end > offset_to_points {
+        return Err(invalid_data("VLRs overlap the points").into());
+    }
+    let mut laszip_vlr = Vec::new();
+    vlr.write_full_vlr_to(&mut laszip_vlr)?;
+
+    let mut new_header = slice(las, 0, header_size)?.to_vec();
+    let new_offset_to_points = u32::try_from(offset_to_points + laszip_vlr.len())
+        .map_err(|_| invalid_data("offset to points does not fit in the LAS header"))?;
+    (&mut new_header[OFFSET_TO_POINTS_POS..]).write_u32::<LittleEndian>(new_offset_to_points)?;
+    (&mut new_header[NUM_VLRS_POS..]).write_u32::<LittleEndian>(header.num_vlrs + 1)?;
+    new_header[POINT_FORMAT_POS] |= 0x80;
+
+    let start_pos = dst.stream_position()?;
+    dst.write_all(&new_header)?;
+    dst.write_all(slice(las, header_size, vlrs_end)?)?;
+    dst.write_all(&laszip_vlr)?;
+    dst.write_all(slice(las, vlrs_end, offset_to_points)?)?;
+
+    let points_end = header
+        .num_points
+        .checked_mul(point_size as u64)
+        .and_then(|size| usize::try_from(size).ok())
+        .and_then(|size| size.checked_add(offset_to_points))
+        .ok_or_else(|| invalid_data("LAS data is truncated"))?;
+    let points = slice(las, offset_to_points, points_end)?;
+
+    let points_start = dst.stream_position()?;
+    dst.write_i64::<LittleEndian>(points_start as i64)?;
+    let chunk_size_in_bytes = chunk_size as usize * point_size;
+    let batch_size_in_bytes = chunk_size_in_bytes * rayon::current_num_threads();
+    let mut chunk_table = ChunkTable::default();
+    let mut compressors = CompressorPool::new();
+    for batch in points.chunks(batch_size_in_bytes.max(1)) {
+        let batch_table = par_compress_chunks_with(
+            &mut dst,
+            batch.par_chunks(chunk_size_in_bytes),
+            &vlr,
+            &mut compressors,
+        )?;
+        chunk_table.extend(&batch_table);
+    }
+    update_chunk_table_offset(&mut dst, SeekFrom::Start(points_start))?;
+    chunk_table.write_to(&mut dst, &vlr)?;
+
+    if header.major == 1 && header.minor >= 4 {
+        let start_of_first_evlr =
+            (&slice(las, START_OF_FIRST_EVLR_POS, START_OF_FIRST_EVLR_POS + 8)?[..])
+                .read_u64::<LittleEndian>()?;
+        if start_of_first_evlr != 0 {
+            let evlrs_start = usize::try_from(start_of_first_evlr)
+                .map_err(|_| invalid_data("LAS data is truncated"))?;
+            let evlrs = slice(las, evlrs_start, las.len())?;
+            let new_start_of_first_evlr = dst.stream_position()? - start_pos;
+            dst.write_all(evlrs)?;
+            let end = dst.stream_position()?;
+            dst.seek(SeekFrom::Start(start_pos + START_OF_FIRST_EVLR_POS as u64))?;
+            dst.write_u64::<LittleEndian>(new_start_of_first_evlr)?;
+            dst.seek(SeekFrom::Start(end))?;
+        }
+    }
+    Ok(dst)
+}
@@ -0,0 +1,419 @@
+//! Multi-threaded pipelines of (de)compression, using bounded channels
+//!
+//! A [`Pipeline`] reads, (de)compresses and consumes points on different threads,
+//! one chunk at a time:
+//!
+//! - Decompression: a reader thread reads the compressed chunks, worker threads
+//!   decompress them, and the consumer receives them in order on the calling thread.
+//! - Compression: the calling thread gives the points, worker threads compress the chunks,
+//!   and a writer thread writes them in order.
+//!
+//! At most [`Pipeline::max_chunks_in_flight`] chunks are between the first and the last
+//! stage, so the memory usage stays bounded even if one of the stages is slow.
+//!
+//! The first error of any stage stops the whole pipeline and is returned.
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use super::chunk_table::{update_chunk_table_offset, ChunkTable, ChunkTableEntry};
+use super::details::{record_compressor_from_vlr, record_decompressor_from_vlr, usize_from};
+use super::LazVlr;
+use crate::LasZipError;
+
+/// A chunk going through the pipeline, with its index
+type Indexed<T> = (usize, T);
+
+/// Builder & runner of (de)compression pipelines, see the [module](self) documentation
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::Pipeline;
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(100).build();
+/// let points = vec![7u8; 20 * 1_000];
+///
+/// let pipeline = Pipeline::new().num_workers(2).max_chunks_in_flight(4);
+/// let batches = points.chunks(20 * 300).map(|batch| batch.to_vec());
+/// let mut compressed = pipeline.compress(Cursor::new(Vec::new()), vlr.clone(), batches)?;
+/// compressed.set_position(0);
+///
+/// let mut decompressed = Vec::new();
+/// pipeline.decompress(compressed, vlr, 1_000, |chunk_points| {
+///     decompressed.extend_from_slice(chunk_points);
+///     Ok(())
+/// })?;
+/// assert_eq!(decompressed, points);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    num_workers: usize,
+    max_chunks_in_flight: usize,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pipeline {
+    /// Creates a pipeline with one worker per available CPU
+    pub fn new() -> Self {
+        let num_workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            num_workers,
+            max_chunks_in_flight: 2 * num_workers,
+        }
+    }
+
+    /// Sets the number of threads that (de)compress chunks (at least 1)
+    pub fn num_workers(mut self, num_workers: usize) -> Self {
+        self.num_workers = num_workers.max(1);
+        self
+    }
+
+    /// Sets the maximum number of chunks between the first and the last stage (at least 1)
+    pub fn max_chunks_in_flight(mut self, max_chunks_in_flight: usize) -> Self {
+        self.max_chunks_in_flight = max_chunks_in_flight.max(1);
+        self
+    }
+
+    pub fn get_num_workers(&self) -> usize {
+        self.num_workers
+    }
+
+    pub fn get_max_chunks_in_flight(&self) -> usize {
+        self.max_chunks_in_flight
+    }
+
+    /// Decompresses the `num_points` points of the `src` and gives them, in order and
+    /// one chunk at a time, to the `consumer`, that runs on the calling thread.
+    ///
+    /// The `src` position must be at the start of the point data, and the chunk table
+    /// is required.
+    pub fn decompress<R, F>(
+        &self,
+        mut src: R,
+        vlr: LazVlr,
+        num_points: u64,
+        mut consumer: F,
+    ) -> crate::Result<()>
+    where
+        R: Read + Seek + Send,
+        F: FnMut(&[u8]) -> crate::Result<()>,
+    {
+        let chunk_table = ChunkTable::read_from(&mut src, &vlr)?;
+        let point_size = vlr.items_size() as usize;
+        let mut chunks = Vec::with_capacity(chunk_table.len());
+        let mut points_left = num_points;
+        for entry in &chunk_table {
+            if points_left == 0 {
+                break;
+            }
+            let point_count = entry.point_count.min(points_left);
+            points_left -= point_count;
+            chunks.push(ChunkTableEntry {
+                point_count,
+                byte_count: entry.byte_count,
+            });
+        }
+        let vlr = &vlr;
+
+        std::thread::scope(|scope| {
+            let (tokens, token_receiver) = tokens(self.max_chunks_in_flight);
+            let (work_sender, work_receiver) =
+                sync_channel::<Indexed<(u64, Vec<u8>)>>(self.max_chunks_in_flight);
+            let (result_sender, result_receiver) =
+                sync_channel::<Indexed<crate::Result<Vec<u8>>>>(self.max_chunks_in_flight);
+
+            let reader_result_sender = result_sender.clone();
+            scope.spawn(move || {
+                for (index, entry) in chunks.iter().enumerate() {
+                    if token_receiver.recv().is_err() {
+                        return;
+                    }
+                    let mut data = vec![0u8; entry.byte_count as usize];
+                    if let Err(error) = src.read_exact(&mut data) {
+                        let _ = reader_result_sender.send((index, Err(error.into())));
+                        return;
+                    }
+                    if work_sender
+                        .send((index, (entry.point_count, data)))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            });
+
+            let work_receiver = Arc::new(Mutex::new(work_receiver));
+            for _ in 0..self.num_workers {
+                let work_receiver = Arc::clone(&work_receiver);
+                let result_sender = result_sender.clone();
+                scope.spawn(move || {
+                    while let Some((index, (point_count, data))) = next_work(&work_receiver) {
+                        let points = decompress_chunk(vlr, point_size, point_count, data);
+                        if result_sender.send((index, points)).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+            drop(result_sender);
+
+            reorder(result_receiver, tokens, |points| consumer(&points))
+        })
+    }
+
+    /// Compresses the points of the `batches` into the `dst`, and returns it,
+    /// positioned after the chunk table.
+    ///
+    /// With fixed-size chunks, the batches can contain any number of points,
+    /// with variable-size chunks, each batch is one chunk.
+    pub fn compress<W, I>(&self, mut dst: W, vlr: LazVlr, batches: I) -> crate::Result<W>
+    where
+        W: Write + Seek + Send,
+        I: IntoIterator<Item = Vec<u8>>,
+    {
+        let point_size = vlr.items_size() as usize;
+        let start_pos = dst.stream_position()?;
+        dst.write_i64::<LittleEndian>(start_pos as i64)?;
+        let vlr = &vlr;
+
+        std::thread::scope(|scope| {
+            let (tokens, token_receiver) = tokens(self.max_chunks_in_flight);
+            let (work_sender, work_receiver) =
+                sync_channel::<Indexed<Vec<u8>>>(self.max_chunks_in_flight);
+            let (result_sender, result_receiver) =
+                sync_channel::<Indexed<crate::Result<(u64, Vec<u8>)>>>(self.max_chunks_in_flight);
+
+            let work_receiver = Arc::new(Mutex::new(work_receiver));
+            for _ in 0..self.num_workers {
+                let work_receiver = Arc::clone(&work_receiver);
+                let result_sender = result_sender.clone();
+                scope.spawn(move || {
+                    while let Some((index, points)) = next_work(&work_receiver) {
+                        let compressed = compress_chunk(vlr, point_size, points);
+                        if result_sender.send((index, compressed)).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+            drop(result_sender);
+
+            let writer = scope.spawn(move || {
+                let mut chunk_table = ChunkTable::default();
+                reorder(result_receiver, tokens, |(point_count, data)| {
+                    dst.write_all(&data)?;
+                    chunk_table.push(ChunkTableEntry {
+                        point_count,
+                        byte_count: data.len() as u64,
+                    });
+                    Ok(())
+                })?;
+                update_chunk_table_offset(&mut dst, SeekFrom::Start(start_pos))?;
+                chunk_table.write_to(&mut dst, vlr)?;
+                Ok(dst)
+            });
+
+            // The points are cut in chunks on the calling thread
+            let mut index = 0;
+            let mut send = |points: Vec<u8>| -> bool {
+                if token_receiver.recv().is_err() || work_sender.send((index, points)).is_err() {
+                    return false;
+                }
+                index += 1;
+                true
+            };
+            let mut pending = Vec::<u8>::new();
+            let chunk_size_in_bytes = if vlr.uses_variable_size_chunks() {
+                None
+            } else {
+                Some(vlr.chunk_size() as usize * point_size)
+            };
+            'batches: for batch in batches {
+                match chunk_size_in_bytes {
+                    None => {
+                        if !batch.is_empty() && !send(batch) {
+                            break;
+                        }
+                    }
+                    Some(chunk_size_in_bytes) => {
+                        pending.extend_from_slice(&batch);
+                        while pending.len() >= chunk_size_in_bytes {
+                            let rest = pending.split_off(chunk_size_in_bytes);
+                            if !send(std::mem::replace(&mut pending, rest)) {
+                                break 'batches;
+                            }
+                        }
+                    }
+                }
+            }
+            if !pending.is_empty() {
+                send(pending);
+            }
+            drop(work_sender);
+
+            writer.join().expect("the writer thread panicked")
+        })
+    }
+}
+
+/// Returns the sender & receiver of a channel holding `count` tokens
+///
+/// The first stage takes a token before each chunk, the last one gives it back.
+fn tokens(count: usize) -> (SyncSender<()>, Receiver<()>) {
+    let (sender, receiver) = sync_channel(count);
+    for _ in 0..count {
+        sender.send(()).unwrap();
+    }
+    (sender, receiver)
+}
+
+fn next_work<T>(receiver: &Mutex<Receiver<T>>) -> Option<T> {
+    receiver.lock().ok()?.recv().ok()
+}
+
+/// Gives the results to `consume` in the order of their index, returns the first error
+///
+/// The `tokens` are dropped on return, which unblocks the first stage.
+fn reorder<T, F>(
+    results: Receiver<Indexed<crate::Result<T>>>,
+    tokens: SyncSender<()>,
+    mut consume: F,
+) -> crate::Result<()>
+where
+    F: FnMut(T) -> crate::Result<()>,
+{
+    let mut waiting = BTreeMap::new();
+    let mut next = 0usize;
+    for (index, result) in results {
+        waiting.insert(index, result?);
+        while let Some(value) = waiting.remove(&next) {
+            consume(value)?;
+            next += 1;
+            // The first stage may already be done
+            let _ = tokens.try_send(());
+        }
+    }
+    Ok(())
+}
+
+fn decompress_chunk(
+    vlr: &LazVlr,
+    point_size: usize,
+    point_count: u64,
+    data: Vec<u8>,
+) -> crate::Result<Vec<u8>> {
+    let mut decompressor = record_decompressor_from_vlr(vlr, Cursor::new(data))?;
+    let mut points = vec![0u8; usize_from(point_count)? * point_size];
+    decompressor.decompress_many(&mut points)?;
+    Ok(points)
+}
+
+fn compress_chunk(
+    vlr: &LazVlr,
+    point_size: usize,
+    points: Vec<u8>,
+) -> crate::Result<(u64, Vec<u8>)> {
+    if !points.len().is_multiple_of(point_size) {
+        return Err(LasZipError::BufferLenNotMultipleOfPointSize {
+            buffer_len: points.len(),
+            point_size,
+        });
+    }
+    let mut compressor = record_compressor_from_vlr(vlr, Cursor::new(Vec::new()))?;
+    compressor.compress_many(&points)?;
+    compressor.done()?;
+    let point_count = (points.len() / point_size) as u64;
+    Ok((point_count, compressor.box_into_inner().into_inner()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn points(num_points: i32) -> Vec<u8> {
+        (0..num_points)
+            .flat_map(|i| {
+                let mut point = [0u8; 20];
+                point[..4].copy_from_slice(&i.to_le_bytes());
+                point[4..8].copy_from_slice(&(i * 3 % 17).to_le_bytes());
+                point
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pipeline_matches_sequential() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = crate::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(50)
+            .build();
+        let points = points(1_234);
+        let mut expected = Cursor::new(Vec::new());
+        crate::compress_buffer(&mut expected, &points, vlr.clone()).unwrap();
+
+        let pipeline = Pipeline::new().num_workers(3).max_chunks_in_flight(2);
+        let batches = points.chunks(20 * 33).map(|batch| batch.to_vec());
+        let compressed = pipeline
+            .compress(Cursor::new(Vec::new()), vlr.clone(), batches)
+            .unwrap();
+        assert_eq!(compressed.get_ref(), expected.get_ref());
+
+        let mut decompressed = Vec::new();
+        let mut num_chunks = 0;
+        pipeline
+            .decompress(Cursor::new(compressed.into_inner()), vlr, 1_234, |chunk| {
+                decompressed.extend_from_slice(chunk);
+                num_chunks += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(num_chunks, 25);
+        assert_eq!(decompressed, points);
+    }
+
+    #[test]
+    fn test_pipeline_errors_stop_it() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = crate::LazVlrBuilder::new(items)
+            .with_variable_chunk_size()
+            .build();
+        let pipeline = Pipeline::new().num_workers(2).max_chunks_in_flight(1);
+        let batches = (0..100).map(|i| if i == 5 { vec![0u8; 7] } else { points(10) });
+        let result = pipeline.compress(Cursor::new(Vec::new()), vlr.clone(), batches);
+        assert!(matches!(
+            result,
+            Err(LasZipError::BufferLenNotMultipleOfPointSize { .. })
+        ));
+
+        let batches = (0..100).map(|_| points(10));
+        let mut compressed = pipeline
+            .compress(Cursor::new(Vec::new()), vlr.clone(), batches)
+            .unwrap();
+        compressed.set_position(0);
+        let mut num_chunks = 0;
+        let result = pipeline.decompress(compressed, vlr, 1_000, |_| {
+            num_chunks += 1;
+            if num_chunks == 3 {
+                Err(LasZipError::MissingChunkTable)
+            } else {
+                Ok(())
+            }
+        });
+        assert!(matches!(result, Err(LasZipError::MissingChunkTable)));
+        assert_eq!(num_chunks, 3);
+    }
+}
@@ -0,0 +1,205 @@
+//! Buffers of uncompressed points whose length is checked against the point size
+use std::slice::{ChunksExact, ChunksExactMut};
+
+use crate::LasZipError;
+
+use super::LazVlr;
+
+fn check_len(buffer_len: usize, point_size: usize) -> crate::Result<()> {
+    if point_size == 0 || !buffer_len.is_multiple_of(point_size) {
+        Err(LasZipError::BufferLenNotMultipleOfPointSize {
+            buffer_len,
+            point_size,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Ensures the `buffer` contains points of the size described by the `vlr`
+pub(crate) fn check_point_size(buffer_point_size: usize, vlr: &LazVlr) -> crate::Result<()> {
+    let expected = vlr.items_size() as usize;
+    if buffer_point_size != expected {
+        Err(LasZipError::PointSizeMismatch {
+            expected,
+            found: buffer_point_size,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Slice of uncompressed points, its length is guaranteed to be a
+/// multiple of the point size.
+#[derive(Debug, Copy, Clone)]
+pub struct PointBuffer<'a> {
+    data: &'a [u8],
+    point_size: usize,
+}
+
+impl<'a> PointBuffer<'a> {
+    /// Creates a buffer of the points described by the `vlr`
+    pub fn new(data: &'a [u8], vlr: &LazVlr) -> crate::Result<Self> {
+        Self::with_point_size(data, vlr.items_size() as usize)
+    }
+
+    /// Creates a buffer of points of `point_size` bytes
+    pub fn with_point_size(data: &'a [u8], point_size: usize) -> crate::Result<Self> {
+        check_len(data.len(), point_size)?;
+        Ok(Self { data, point_size })
+    }
+
+    /// Returns the number of points
+    pub fn len(&self) -> usize {
+        self.data.len() / self.point_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the size in bytes of one point
+    pub fn point_size(&self) -> usize {
+        self.point_size
+    }
+
+    /// Returns the point at `index`, if any
+    pub fn get(&self, index: usize) -> Option<&'a [u8]> {
+        let start = index.checked_mul(self.point_size)?;
+        self.data.get(start..start + self.point_size)
+    }
+
+    /// Returns an iterator over the points
+    pub fn iter(&self) -> ChunksExact<'a, u8> {
+        self.data.chunks_exact(self.point_size)
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> IntoIterator for PointBuffer<'a> {
+    type Item = &'a [u8];
+    type IntoIter = ChunksExact<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Mutable slice of uncompressed points, its length is guaranteed to be a
+/// multiple of the point size.
+#[derive(Debug)]
+pub struct PointBufferMut<'a> {
+    data: &'a mut [u8],
+    point_size: usize,
+}
+
+impl<'a> PointBufferMut<'a> {
+    /// Creates a buffer of the points described by the `vlr`
+    pub fn new(data: &'a mut [u8], vlr: &LazVlr) -> crate::Result<Self> {
+        Self::with_point_size(data, vlr.items_size() as usize)
+    }
+
+    /// Creates a buffer of points of `point_size` bytes
+    pub fn with_point_size(data: &'a mut [u8], point_size: usize) -> crate::Result<Self> {
+        check_len(data.len(), point_size)?;
+        Ok(Self { data, point_size })
+    }
+
+    /// Returns the number of points
+    pub fn len(&self) -> usize {
+        self.data.len() / self.point_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the size in bytes of one point
+    pub fn point_size(&self) -> usize {
+        self.point_size
+    }
+
+    /// Returns the point at `index`, if any
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        let start = index.checked_mul(self.point_size)?;
+        self.data.get(start..start + self.point_size)
+    }
+
+    /// Returns the point at `index`, if any
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut [u8]> {
+        let start = index.checked_mul(self.point_size)?;
+        self.data.get_mut(start..start + self.point_size)
+    }
+
+    /// Returns an iterator over the points
+    pub fn iter(&self) -> ChunksExact<'_, u8> {
+        self.data.chunks_exact(self.point_size)
+    }
+
+    /// Returns an iterator over the points, that allows to modify them
+    pub fn iter_mut(&mut self) -> ChunksExactMut<'_, u8> {
+        self.data.chunks_exact_mut(self.point_size)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.data
+    }
+
+    /// Returns a non-mutable view of the points
+    pub fn as_point_buffer(&self) -> PointBuffer<'_> {
+        PointBuffer {
+            data: self.data,
+            point_size: self.point_size,
+        }
+    }
+}
+
+impl<'a> IntoIterator for PointBufferMut<'a> {
+    type Item = &'a mut [u8];
+    type IntoIter = ChunksExactMut<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.chunks_exact_mut(self.point_size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_invalid_len() {
+        let data = [0u8; 41];
+        assert!(matches!(
+            PointBuffer::with_point_size(&data, 20),
+            Err(LasZipError::BufferLenNotMultipleOfPointSize {
+                buffer_len: 41,
+                point_size: 20
+            })
+        ));
+        assert!(PointBuffer::with_point_size(&data, 0).is_err());
+        assert!(PointBuffer::with_point_size(&data[..40], 20).is_ok());
+    }
+
+    #[test]
+    fn test_points_access() {
+        let mut data = (0..60u8).collect::<Vec<u8>>();
+        let mut points = PointBufferMut::with_point_size(&mut data, 20).unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points.get(1).unwrap()[0], 20);
+        assert!(points.get(3).is_none());
+        for point in points.iter_mut() {
+            point[0] = 255;
+        }
+        let points = points.as_point_buffer();
+        assert!(points.iter().all(|point| point[0] == 255));
+        assert_eq!(points.into_iter().count(), 3);
+    }
+}
@@ -0,0 +1,80 @@
+//! Presets of compression settings
+
+/// Presets of the settings of the compression, for users that
+/// do not want to choose each of them.
+///
+/// A profile sets the chunk size and the item versions of the vlr with
+/// [`LazVlrBuilder::with_profile`], and the flushing of the compressor with
+/// [`LasZipCompressor::set_profile`].
+/// All the profiles use the default item versions, the only ones that
+/// other readers are sure to support.
+///
+/// Whether to use a parallel compressor is only advice, given by [`Self::prefers_parallel`],
+/// as the compressor is chosen before the profile is applied.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use laz::laszip::CompressionProfile;
+///
+/// let profile = CompressionProfile::RandomAccess;
+/// let items = laz::LazItemRecordBuilder::version_1_of::<laz::las::Point1>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_profile(profile).build();
+/// assert_eq!(vlr.chunk_size(), profile.chunk_size());
+/// assert!(vlr.items().iter().all(|item| item.version() == 2));
+///
+/// let mut compressor = laz::LasZipCompressor::new(std::io::Cursor::new(Vec::new()), vlr)?;
+/// compressor.set_profile(profile);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`LazVlrBuilder::with_profile`]: crate::LazVlrBuilder::with_profile
+/// [`LasZipCompressor::set_profile`]: crate::LasZipCompressor::set_profile
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum CompressionProfile {
+    /// Chunks small enough to be spread on many threads,
+    /// to be compressed with a parallel compressor.
+    Fastest,
+    /// The settings of LASzip, good in most cases.
+    #[default]
+    Balanced,
+    /// Large chunks, so that the models are reset less often, at the cost of
+    /// slower random access and less parallelism.
+    BestRatio,
+    /// Small chunks, flushed as soon as they are finished, so that reading
+    /// any point only needs to decompress a few points before it.
+    RandomAccess,
+}
+
+impl CompressionProfile {
+    /// Returns the number of points of the chunks
+    pub fn chunk_size(self) -> u32 {
+        match self {
+            CompressionProfile::Fastest => 25_000,
+            CompressionProfile::Balanced => 50_000,
+            CompressionProfile::BestRatio => 500_000,
+            CompressionProfile::RandomAccess => 5_000,
+        }
+    }
+
+    /// Returns whether the points are best compressed with a parallel compressor,
+    /// when the `parallel` feature is enabled.
+    ///
+    /// No compressor applies it, it is for the caller to choose between
+    /// a [`LasZipCompressor`] and a `ParLasZipCompressor`.
+    ///
+    /// [`LasZipCompressor`]: crate::LasZipCompressor
+    pub fn prefers_parallel(self) -> bool {
+        match self {
+            CompressionProfile::Fastest | CompressionProfile::Balanced => true,
+            CompressionProfile::BestRatio | CompressionProfile::RandomAccess => false,
+        }
+    }
+
+    /// Returns whether the compressor flushes its output at the end of each chunk
+    pub fn flushes_on_chunk_end(self) -> bool {
+        self == CompressionProfile::RandomAccess
+    }
+}
@@ -0,0 +1,452 @@
+//! Decompression of compressed bytes pushed by the caller, without a reader
+use std::io::Cursor;
+
+use crate::record::RecordDecompressor;
+use crate::LasZipError;
+
+use super::details::{self, usize_from};
+use super::{ChunkTable, CompressorType, LazOpenOptions, LazVlr};
+
+/// Size of the offset to the chunk table, before the first chunk
+const OFFSET_SIZE: usize = std::mem::size_of::<i64>();
+
+/// Decompressor that is given the compressed bytes as they arrive, instead of
+/// reading them, so that it can be driven by async runtimes, WASM
+/// or network protocols without blocking IO.
+///
+/// The bytes are the point data of a LAZ file, starting with the offset to
+/// the chunk table, given in order by [`feed`], which decompresses the points
+/// that the bytes received so far allow. Once all the bytes are given, [`finish`]
+/// decompresses the remaining points.
+///
+/// The bytes of the current chunk are kept until the chunk is done.
+///
+/// Variable-size chunks of the point formats 0 to 5 do not store their number
+/// of points, the chunk table has to be given using [`set_chunk_table`].
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::PushDecompressor;
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(100).build();
+/// let points = (0..250u32).flat_map(|i| [i as u8; 20]).collect::<Vec<u8>>();
+/// let mut compressed = Cursor::new(Vec::<u8>::new());
+/// laz::compress_buffer(&mut compressed, &points, vlr.clone())?;
+///
+/// let mut decompressor = PushDecompressor::new(vlr, 250)?;
+/// let mut decompressed = Vec::new();
+/// // e.g. packets received from the network
+/// for packet in compressed.get_ref().chunks(100) {
+///     decompressor.feed(packet, &mut decompressed)?;
+/// }
+/// decompressor.finish(&mut decompressed)?;
+/// assert_eq!(decompressed, points);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`feed`]: Self::feed
+/// [`finish`]: Self::finish
+/// [`set_chunk_table`]: Self::set_chunk_table
+pub struct PushDecompressor {
+    vlr: LazVlr,
+    /// Decompressor of the current chunk, whose stream holds the bytes received
+    /// since the start of the chunk
+    record_decompressor: Box<dyn RecordDecompressor<Cursor<Vec<u8>>> + Send>,
+    chunk_table: Option<ChunkTable>,
+    num_points: u64,
+    points_read: u64,
+    /// Whether the offset to the chunk table was received
+    offset_skipped: bool,
+    current_chunk: usize,
+    chunk_points_read: u64,
+    /// Number of points of the current chunk, `None` until it is known
+    num_points_in_chunk: Option<u64>,
+    /// Number of bytes that have to be available to decompress a point
+    /// in the middle of a chunk, without it failing for lack of data
+    margin: usize,
+    /// Number of bytes of the current chunk to receive before trying again
+    /// to decompress a point that failed for lack of bytes
+    retry_len: usize,
+    skipped_point: Vec<u8>,
+    /// Maximum number of bytes kept, see [`LazOpenOptions::with_memory_limit`]
+    memory_limit: Option<usize>,
+}
+
+impl PushDecompressor {
+    /// Creates a decompressor of the `num_points` points described by the `vlr`
+    pub fn new(vlr: LazVlr, num_points: u64) -> crate::Result<Self> {
+        Self::with_options(vlr, num_points, LazOpenOptions::new())
+    }
+
+    /// Creates a decompressor of the `num_points` points described by the `vlr`,
+    /// configured by the `options`.
+    ///
+    /// - The `num_points` given here are the ones decompressed,
+    ///   the number of points of the `options` is not used.
+    /// - The memory limit bounds the compressed bytes kept,
+    ///   [`feed`] fails with [`LasZipError::MemoryLimitExceeded`] above it.
+    /// - The limit on the number of chunks does not apply to the chunk table
+    ///   given with [`set_chunk_table`], which the caller reads.
+    ///
+    /// The lazy chunk table, snapshots & context diagnostics
+    /// are not supported, and give [`LasZipError::UnsupportedOption`].
+    ///
+    /// [`feed`]: Self::feed
+    /// [`set_chunk_table`]: Self::set_chunk_table
+    pub fn with_options(
+        vlr: LazVlr,
+        num_points: u64,
+        mut options: LazOpenOptions<'_>,
+    ) -> crate::Result<Self> {
+        if vlr.compressor() != CompressorType::PointWiseChunked
+            && vlr.compressor() != CompressorType::LayeredChunked
+        {
+            return Err(LasZipError::UnsupportedCompressorType(vlr.compressor()));
+        }
+        options.check_whole_chunk_options()?;
+        options.check_item_versions(&vlr)?;
+        let mut record_decompressor = details::record_decompressor_with_selection(
+            &vlr,
+            options.selection,
+            Cursor::new(Vec::new()),
+        )?;
+        record_decompressor.set_memory_limit(options.memory_limit);
+        let point_size = usize_from(vlr.items_size())?;
+        Ok(Self {
+            vlr,
+            record_decompressor,
+            chunk_table: None,
+            num_points,
+            points_read: 0,
+            offset_skipped: false,
+            current_chunk: 0,
+            chunk_points_read: 0,
+            num_points_in_chunk: None,
+            // The arithmetic decoder reads a few bytes ahead, and
+            // the symbols of a point take at most a few bytes each
+            margin: 2 * point_size + 32,
+            retry_len: 0,
+            skipped_point: vec![0u8; point_size],
+            memory_limit: options.memory_limit,
+        })
+    }
+
+    /// Sets the chunk table (e.g. read using a [`FetchPlan`]),
+    /// required for the variable-size chunks of the point formats 0 to 5.
+    ///
+    /// With the chunk table, the points of a chunk are all decompressed
+    /// as soon as all its bytes are received.
+    ///
+    /// [`FetchPlan`]: super::fetch_plan::FetchPlan
+    pub fn set_chunk_table(&mut self, chunk_table: ChunkTable) {
+        if self.num_points_in_chunk.is_none() {
+            self.num_points_in_chunk = chunk_table
+                .as_ref()
+                .get(self.current_chunk)
+                .map(|entry| entry.point_count);
+        }
+        self.chunk_table = Some(chunk_table);
+    }
+
+    /// Gives the next compressed `bytes`, and appends the points
+    /// they allow to decompress to `out`.
+    ///
+    /// Returns the number of points appended.
+    ///
+    /// A point is only decompressed once enough bytes follow it in the chunk,
+    /// if it still misses bytes, all the points of the chunk decompressed
+    /// so far are decompressed again once more bytes are received.
+    /// Giving the bytes in packets of at least a few points limits how often that happens.
+    pub fn feed(&mut self, bytes: &[u8], out: &mut Vec<u8>) -> crate::Result<u64> {
+        if !self.is_done() {
+            let required = self.buffered_len() + bytes.len();
+            crate::errors::check_memory_limit(self.memory_limit, required)?;
+            self.buffer_mut().extend_from_slice(bytes);
+        }
+        self.decompress_available(out, false)
+    }
+
+    /// Appends the points that were not decompressed yet to `out`,
+    /// once all the compressed bytes were given.
+    ///
+    /// Returns the number of points appended, fails if the bytes given
+    /// do not hold all the points.
+    pub fn finish(&mut self, out: &mut Vec<u8>) -> crate::Result<u64> {
+        self.decompress_available(out, true)
+    }
+
+    /// Returns whether all the points were decompressed
+    pub fn is_done(&self) -> bool {
+        self.points_read == self.num_points
+    }
+
+    /// Returns the number of points decompressed so far
+    pub fn points_read(&self) -> u64 {
+        self.points_read
+    }
+
+    /// Returns the number of bytes received and kept, as they are not
+    /// entirely decompressed yet
+    pub fn buffered_len(&self) -> usize {
+        self.record_decompressor.get().get_ref().len()
+    }
+
+    pub fn vlr(&self) -> &LazVlr {
+        &self.vlr
+    }
+
+    fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        self.record_decompressor.get_mut().get_mut()
+    }
+
+    /// Decompresses the points of the bytes received, when `at_end`
+    /// is false, the points that may need more bytes are left for later
+    fn decompress_available(&mut self, out: &mut Vec<u8>, at_end: bool) -> crate::Result<u64> {
+        if !self.offset_skipped {
+            if self.buffered_len() < OFFSET_SIZE {
+                return if at_end && !self.is_done() {
+                    Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into())
+                } else {
+                    Ok(0)
+                };
+            }
+            self.record_decompressor
+                .get_mut()
+                .set_position(OFFSET_SIZE as u64);
+            self.offset_skipped = true;
+            self.start_chunk()?;
+        }
+
+        let point_size = self.skipped_point.len();
+        let mut count = 0;
+        while !self.is_done() {
+            if Some(self.chunk_points_read) == self.num_points_in_chunk {
+                self.current_chunk += 1;
+                self.start_chunk()?;
+            }
+            let source = self.record_decompressor.get();
+            let available = source.get_ref().len() - source.position() as usize;
+            let chunk_received = self
+                .chunk_table
+                .as_ref()
+                .and_then(|chunk_table| chunk_table.as_ref().get(self.current_chunk))
+                .is_some_and(|entry| source.get_ref().len() as u64 >= entry.byte_count);
+            if !at_end
+                && !chunk_received
+                && (available < self.margin || source.get_ref().len() < self.retry_len)
+            {
+                break;
+            }
+
+            let len = out.len();
+            out.resize(len + point_size, 0u8);
+            if let Err(error) = self.record_decompressor.decompress_next(&mut out[len..]) {
+                out.truncate(len);
+                if at_end || error.kind() != std::io::ErrorKind::UnexpectedEof {
+                    return Err(error.into());
+                }
+                self.retry_len = self.buffered_len() + self.margin;
+                self.rewind_chunk()?;
+                break;
+            }
+            self.chunk_points_read += 1;
+            self.points_read += 1;
+            count += 1;
+            if self.num_points_in_chunk.is_none() {
+                // Layered chunks start with their number of points
+                self.num_points_in_chunk = match self.record_decompressor.record_count() {
+                    0 => return Err(LasZipError::MissingChunkTable),
+                    point_count => Some(point_count),
+                };
+            }
+        }
+        Ok(count)
+    }
+
+    /// Starts the current chunk, at the current position of the bytes received
+    fn start_chunk(&mut self) -> crate::Result<()> {
+        let source = self.record_decompressor.get_mut();
+        let start = source.position() as usize;
+        source.get_mut().drain(..start);
+        source.set_position(0);
+        self.record_decompressor.reset();
+        self.record_decompressor.set_fields_from(self.vlr.items())?;
+        self.chunk_points_read = 0;
+        self.retry_len = 0;
+
+        let table_count = self
+            .chunk_table
+            .as_ref()
+            .and_then(|chunk_table| chunk_table.as_ref().get(self.current_chunk))
+            .map(|entry| entry.point_count);
+        let points_left = self.num_points - self.points_read;
+        self.num_points_in_chunk = if !self.vlr.uses_variable_size_chunks() {
+            Some(u64::from(self.vlr.chunk_size()).min(points_left))
+        } else if self.vlr.compressor() == CompressorType::LayeredChunked {
+            table_count
+        } else {
+            Some(table_count.ok_or(LasZipError::MissingChunkTable)?)
+        };
+        Ok(())
+    }
+
+    /// Decompresses the points of the current chunk that were already given
+    /// again, after a point failed to be decompressed for lack of bytes
+    fn rewind_chunk(&mut self) -> crate::Result<()> {
+        self.record_decompressor.get_mut().set_position(0);
+        self.record_decompressor.reset();
+        self.record_decompressor.set_fields_from(self.vlr.items())?;
+        for _ in 0..self.chunk_points_read {
+            self.record_decompressor
+                .decompress_next(&mut self.skipped_point)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::points;
+    use crate::LazVlrBuilder;
+
+    fn feed_by(
+        decompressor: &mut PushDecompressor,
+        compressed: &[u8],
+        packet_size: usize,
+    ) -> Vec<u8> {
+        let mut decompressed = Vec::new();
+        for packet in compressed.chunks(packet_size) {
+            decompressor.feed(packet, &mut decompressed).unwrap();
+        }
+        decompressor.finish(&mut decompressed).unwrap();
+        assert!(decompressor.is_done());
+        decompressed
+    }
+
+    #[test]
+    fn test_push_decompression() {
+        for vlr in [
+            LazVlrBuilder::default()
+                .with_point_format(3, 2)
+                .unwrap()
+                .with_fixed_chunk_size(50)
+                .build(),
+            LazVlrBuilder::default()
+                .with_point_format(7, 0)
+                .unwrap()
+                .with_fixed_chunk_size(50)
+                .build(),
+            LazVlrBuilder::default()
+                .with_point_format(6, 0)
+                .unwrap()
+                .with_variable_chunk_size()
+                .build(),
+        ] {
+            let point_size = vlr.items_size() as usize;
+            let points = points(170, point_size);
+            let mut compressor =
+                crate::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+            if vlr.uses_variable_size_chunks() {
+                compressor.set_points_per_chunk(Some(60));
+            }
+            compressor.compress_many(&points).unwrap();
+            let compressed = compressor.finish().unwrap().into_inner();
+
+            for packet_size in [1, 7, 100, compressed.len()] {
+                let mut decompressor = PushDecompressor::new(vlr.clone(), 170).unwrap();
+                assert_eq!(feed_by(&mut decompressor, &compressed, packet_size), points);
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_decompression_with_chunk_table() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(1, 0)
+            .unwrap()
+            .with_variable_chunk_size()
+            .build();
+        let points = points(100, 28);
+        let chunks = [&points[..30 * 28], &points[30 * 28..]];
+        let mut compressor =
+            crate::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        compressor.compress_chunks(chunks).unwrap();
+        let mut compressed = compressor.finish().unwrap();
+        compressed.set_position(0);
+        let chunk_table = ChunkTable::read_from(&mut compressed, &vlr).unwrap();
+        let compressed = compressed.into_inner();
+
+        let mut decompressor = PushDecompressor::new(vlr.clone(), 100).unwrap();
+        let mut decompressed = Vec::new();
+        assert!(decompressor.feed(&compressed, &mut decompressed).is_err());
+
+        let mut decompressor = PushDecompressor::new(vlr, 100).unwrap();
+        decompressor.set_chunk_table(chunk_table);
+        let mut decompressed = Vec::new();
+        // The points are decompressed as soon as the chunks are received
+        assert_eq!(
+            decompressor.feed(&compressed, &mut decompressed).unwrap(),
+            100
+        );
+        assert_eq!(decompressed, points);
+    }
+
+    #[test]
+    fn test_push_decompression_truncated() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(0, 0)
+            .unwrap()
+            .build();
+        let points = points(100, 20);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+
+        let mut decompressor = PushDecompressor::new(vlr, 100).unwrap();
+        let mut decompressed = Vec::new();
+        let half = compressed.get_ref().len() / 2;
+        decompressor
+            .feed(&compressed.get_ref()[..half], &mut decompressed)
+            .unwrap();
+        assert!(decompressor.finish(&mut decompressed).is_err());
+        assert!(!decompressor.is_done());
+    }
+
+    #[test]
+    fn test_push_decompression_with_options() {
+        let items = crate::LazItemRecordBuilder::version_1_of::<crate::las::Point0>(0);
+        let vlr = LazVlr::from_laz_items(items);
+        let points = points(100, 20);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        let compressed = compressed.into_inner();
+
+        let mut num_warnings = 0;
+        let options = LazOpenOptions::new().with_warnings(|_| num_warnings += 1);
+        let mut decompressor = PushDecompressor::with_options(vlr.clone(), 100, options).unwrap();
+        assert_eq!(feed_by(&mut decompressor, &compressed, 50), points);
+        assert_eq!(num_warnings, 1);
+
+        let options = LazOpenOptions::new().with_strict(true);
+        assert!(matches!(
+            PushDecompressor::with_options(vlr.clone(), 100, options),
+            Err(LasZipError::StrictModeWarning(_))
+        ));
+
+        let options = LazOpenOptions::new().with_memory_limit(100);
+        let mut decompressor = PushDecompressor::with_options(vlr, 100, options).unwrap();
+        let mut decompressed = Vec::new();
+        decompressor
+            .feed(&compressed[..100], &mut decompressed)
+            .unwrap();
+        assert!(matches!(
+            decompressor.feed(&compressed[100..200], &mut decompressed),
+            Err(LasZipError::MemoryLimitExceeded { limit: 100, .. })
+        ));
+    }
+}
@@ -0,0 +1,293 @@
+//! Spatial queries on compressed points
+use std::io::{Read, Seek};
+
+use crate::packers::Packable;
+use crate::spatial::Bounds;
+use crate::LasZipError;
+
+use super::LasZipDecompressor;
+
+/// Scales & offsets of the coordinates, found in the LAS header
+///
+/// The coordinates of a point are `integer * scale + offset`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CoordinateTransform {
+    pub scales: [f64; 3],
+    pub offsets: [f64; 3],
+}
+
+impl Default for CoordinateTransform {
+    fn default() -> Self {
+        Self {
+            scales: [1.0; 3],
+            offsets: [0.0; 3],
+        }
+    }
+}
+
+impl CoordinateTransform {
+    pub fn new(scales: [f64; 3], offsets: [f64; 3]) -> Self {
+        Self { scales, offsets }
+    }
+
+    /// Returns the coordinates of a raw point, of any point format
+    pub fn coordinates_of(&self, point: &[u8]) -> [f64; 3] {
+        let mut coordinates = [0.0; 3];
+        for (i, coordinate) in coordinates.iter_mut().enumerate() {
+            let value = i32::unpack_from(&point[i * 4..(i + 1) * 4]);
+            *coordinate = f64::from(value) * self.scales[i] + self.offsets[i];
+        }
+        coordinates
+    }
+}
+
+/// Returns the indices of the first and after last points of each chunk
+fn chunk_ranges<R: Read + Seek + Send>(
+    decompressor: &LasZipDecompressor<R>,
+    num_points: u64,
+) -> crate::Result<Vec<(u64, u64)>> {
+    let counts = match decompressor.chunk_table() {
+        Some(chunk_table) => chunk_table
+            .as_ref()
+            .iter()
+            .map(|entry| entry.point_count)
+            .collect::<Vec<u64>>(),
+        None if !decompressor.vlr().uses_variable_size_chunks() => {
+            let chunk_size = u64::from(decompressor.vlr().chunk_size());
+            vec![chunk_size; num_points.div_ceil(chunk_size) as usize]
+        }
+        None => return Err(LasZipError::MissingChunkTable),
+    };
+    let mut start = 0u64;
+    let mut ranges = Vec::with_capacity(counts.len());
+    for count in counts {
+        if start >= num_points {
+            break;
+        }
+        let end = (start + count).min(num_points);
+        ranges.push((start, end));
+        start = end;
+    }
+    Ok(ranges)
+}
+
+impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
+    /// Decompresses the `num_points` points, starting at the current one which must be
+    /// the first of the data, and returns the bounds of the points of each chunk.
+    ///
+    /// The bounds can be stored alongside the file to speed up later
+    /// [`query_points_in_bounds`](Self::query_points_in_bounds).
+    pub fn compute_chunk_bounds(
+        &mut self,
+        num_points: u64,
+        transform: CoordinateTransform,
+    ) -> crate::Result<Vec<Bounds>> {
+        let ranges = chunk_ranges(self, num_points)?;
+        let mut point = vec![0u8; self.vlr().items_size() as usize];
+        let mut all_bounds = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            let mut bounds = Bounds::new([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]);
+            for _ in start..end {
+                self.decompress_one(&mut point)?;
+                let coordinates = transform.coordinates_of(&point);
+                for (i, &coordinate) in coordinates.iter().enumerate() {
+                    bounds.min[i] = bounds.min[i].min(coordinate);
+                    bounds.max[i] = bounds.max[i].max(coordinate);
+                }
+            }
+            all_bounds.push(bounds);
+        }
+        Ok(all_bounds)
+    }
+
+    /// Returns an iterator over the points, among the `num_points`, that are in the `bounds`,
+    /// boundaries included.
+    ///
+    /// The iteration starts at the current point, which must be the first of the data
+    /// if the chunk table could not be read.
+    ///
+    /// When the `chunk_bounds` (see [`compute_chunk_bounds`](Self::compute_chunk_bounds))
+    /// and the chunk table are available, the chunks whose bounds do not intersect the
+    /// `bounds` are skipped without being decompressed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// use laz::laszip::CoordinateTransform;
+    /// use laz::spatial::Bounds;
+    /// # let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    /// # let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+    /// # let points = (0..30i32).flat_map(|i| {
+    /// #     let mut point = [0u8; 20];
+    /// #     point[..4].copy_from_slice(&i.to_le_bytes());
+    /// #     point
+    /// # }).collect::<Vec<u8>>();
+    /// # let mut compressed = std::io::Cursor::new(Vec::<u8>::new());
+    /// # laz::compress_buffer(&mut compressed, &points, vlr.clone())?;
+    /// # compressed.set_position(0);
+    /// let mut decompressor = laz::LasZipDecompressor::new(compressed, vlr)?;
+    /// let transform = CoordinateTransform::new([0.5, 1.0, 1.0], [0.0; 3]);
+    /// let bounds = Bounds::new([2.0, 0.0, 0.0], [3.0, 0.0, 0.0]);
+    /// let points = decompressor
+    ///     .query_points_in_bounds(30, bounds, transform, None)?
+    ///     .collect::<laz::Result<Vec<Vec<u8>>>>()?;
+    /// assert_eq!(points.len(), 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_points_in_bounds<'d>(
+        &'d mut self,
+        num_points: u64,
+        bounds: Bounds,
+        transform: CoordinateTransform,
+        chunk_bounds: Option<&[Bounds]>,
+    ) -> crate::Result<PointsInBounds<'d, 'a, R>> {
+        self.load_chunk_table()?;
+        let current = self.current_point_index().unwrap_or(0);
+        let mut skipped_chunks = Vec::new();
+        if let (Some(chunk_bounds), true) = (chunk_bounds, self.chunk_table().is_some()) {
+            skipped_chunks = chunk_ranges(self, num_points)?
+                .into_iter()
+                .zip(chunk_bounds)
+                .filter(|(_, chunk_bounds)| !chunk_bounds.intersects(&bounds))
+                .map(|(range, _)| range)
+                .filter(|&(_, end)| end > current)
+                .collect();
+            skipped_chunks.reverse();
+        }
+        let point = vec![0u8; self.vlr().items_size() as usize];
+        Ok(PointsInBounds {
+            decompressor: self,
+            bounds,
+            transform,
+            skipped_chunks,
+            current,
+            num_points,
+            point,
+            failed: false,
+        })
+    }
+}
+
+/// Iterator over the points in some bounds, see
+/// [`LasZipDecompressor::query_points_in_bounds`]
+pub struct PointsInBounds<'d, 'a, R: Read + Seek + 'a> {
+    decompressor: &'d mut LasZipDecompressor<'a, R>,
+    bounds: Bounds,
+    transform: CoordinateTransform,
+    /// Ranges of points of the chunks to skip, the next one is the last
+    skipped_chunks: Vec<(u64, u64)>,
+    current: u64,
+    num_points: u64,
+    point: Vec<u8>,
+    failed: bool,
+}
+
+impl<'d, 'a, R: Read + Seek + Send + 'a> PointsInBounds<'d, 'a, R> {
+    fn next_point_in_bounds(&mut self) -> crate::Result<Option<Vec<u8>>> {
+        while self.current < self.num_points {
+            if let Some(&(start, end)) = self.skipped_chunks.last() {
+                if start <= self.current {
+                    self.skipped_chunks.pop();
+                    if end >= self.num_points {
+                        self.current = self.num_points;
+                        break;
+                    }
+                    self.decompressor.seek(end)?;
+                    self.current = end;
+                    continue;
+                }
+            }
+
+            self.decompressor.decompress_one(&mut self.point)?;
+            self.current += 1;
+            if self
+                .bounds
+                .contains(self.transform.coordinates_of(&self.point))
+            {
+                return Ok(Some(self.point.clone()));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'d, 'a, R: Read + Seek + Send + 'a> Iterator for PointsInBounds<'d, 'a, R> {
+    type Item = crate::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = self.next_point_in_bounds().transpose();
+        if let Some(Err(_)) = result {
+            self.failed = true;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::las::Point0;
+    use crate::{LazItemRecordBuilder, LazVlrBuilder};
+
+    fn compressed_points(num_points: i32) -> (Vec<u8>, crate::LazVlr) {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = (0..num_points)
+            .flat_map(|i| {
+                let mut point = [0u8; 20];
+                point[..4].copy_from_slice(&i.to_le_bytes());
+                point[4..8].copy_from_slice(&(i % 7).to_le_bytes());
+                point
+            })
+            .collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        (compressed.into_inner(), vlr)
+    }
+
+    fn xs_of(points: Vec<Vec<u8>>) -> Vec<i32> {
+        points
+            .iter()
+            .map(|point| i32::unpack_from(&point[..4]))
+            .collect()
+    }
+
+    #[test]
+    fn test_query_points_in_bounds() {
+        let (compressed, vlr) = compressed_points(45);
+        let transform = CoordinateTransform::default();
+        let bounds = Bounds::new([12.0, 0.0, 0.0], [31.0, 2.0, 0.0]);
+        let expected = (12..=31).filter(|i| i % 7 <= 2).collect::<Vec<i32>>();
+
+        let mut decompressor =
+            LasZipDecompressor::new(Cursor::new(&compressed), vlr.clone()).unwrap();
+        let points = decompressor
+            .query_points_in_bounds(45, bounds, transform, None)
+            .unwrap()
+            .collect::<crate::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(xs_of(points), expected);
+
+        let mut decompressor =
+            LasZipDecompressor::new(Cursor::new(&compressed), vlr.clone()).unwrap();
+        let chunk_bounds = decompressor.compute_chunk_bounds(45, transform).unwrap();
+        assert_eq!(chunk_bounds.len(), 5);
+        assert_eq!(chunk_bounds[4].min, [40.0, 0.0, 0.0]);
+        assert_eq!(chunk_bounds[4].max, [44.0, 6.0, 0.0]);
+
+        let mut decompressor = LasZipDecompressor::new(Cursor::new(&compressed), vlr).unwrap();
+        let mut points = decompressor
+            .query_points_in_bounds(45, bounds, transform, Some(&chunk_bounds))
+            .unwrap();
+        assert_eq!(points.skipped_chunks, vec![(40, 45), (0, 10)]);
+        let points = points.by_ref().collect::<crate::Result<Vec<_>>>().unwrap();
+        assert_eq!(xs_of(points), expected);
+    }
+}
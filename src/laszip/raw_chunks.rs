@@ -0,0 +1,275 @@
+//! Copying of compressed chunks, without decompressing them
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::LasZipError;
+
+use super::chunk_table::{self, ChunkTable, ChunkTableEntry};
+use super::LazVlr;
+
+/// Writer of compressed chunks as they are, with the chunk table describing them.
+///
+/// This allows merging the compressed points of files that have compatible vlrs
+/// (see [`LazVlr::compatible_with`]) by concatenating their chunks,
+/// which is much faster than decompressing and compressing the points again.
+///
+/// With **fixed-size** chunks, only the last chunk may have less points than
+/// the chunk size, so merging inputs whose number of points is not a multiple of the
+/// chunk size requires a vlr with **variable-size** chunks for the merged points.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::{Cursor, Seek, SeekFrom};
+/// use laz::laszip::RawChunkWriter;
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point6>(0);
+/// let vlr = laz::LazVlrBuilder::new(items.clone()).with_fixed_chunk_size(10).build();
+/// let mut inputs = Vec::new();
+/// for num_points in [25, 12] {
+///     let mut compressed = Cursor::new(Vec::<u8>::new());
+///     laz::compress_buffer(&mut compressed, &vec![0u8; 30 * num_points], vlr.clone())?;
+///     compressed.seek(SeekFrom::Start(0))?;
+///     inputs.push((compressed, num_points as u64));
+/// }
+///
+/// let merged_vlr = laz::LazVlrBuilder::new(items).with_variable_chunk_size().build();
+/// let mut writer = RawChunkWriter::new(Cursor::new(Vec::<u8>::new()), merged_vlr.clone())?;
+/// for (input, num_points) in inputs {
+///     writer.copy_chunks_from(input, &vlr, num_points)?;
+/// }
+/// let merged = writer.finish()?;
+///
+/// let mut points = vec![0u8; 30 * 37];
+/// laz::decompress_buffer(merged.get_ref(), &mut points, merged_vlr)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RawChunkWriter<W: Write + Seek> {
+    dst: W,
+    vlr: LazVlr,
+    /// Position of the offset to the chunk table
+    start_pos: u64,
+    chunk_table: ChunkTable,
+}
+
+impl<W: Write + Seek> RawChunkWriter<W> {
+    /// Creates a writer of chunks described by the `vlr`, the offset to
+    /// the chunk table is reserved at the current position of the `dst`.
+    pub fn new(mut dst: W, vlr: LazVlr) -> std::io::Result<Self> {
+        let start_pos = dst.stream_position()?;
+        dst.write_i64::<LittleEndian>(-1)?;
+        Ok(Self {
+            dst,
+            vlr,
+            start_pos,
+            chunk_table: ChunkTable::default(),
+        })
+    }
+
+    /// Writes the compressed `chunk`, which holds `point_count` points
+    ///
+    /// The chunk must have been compressed with items compatible
+    /// with the ones of the vlr of this writer.
+    pub fn write_chunk(&mut self, chunk: &[u8], point_count: u64) -> crate::Result<()> {
+        self.check_chunk_fits(point_count)?;
+        self.dst.write_all(chunk)?;
+        self.chunk_table.push(ChunkTableEntry {
+            point_count,
+            byte_count: chunk.len() as u64,
+        });
+        Ok(())
+    }
+
+    /// Copies all the chunks of the `src`, whose `num_points` points
+    /// are described by the `src_vlr`.
+    ///
+    /// The `src` position must be at the start of the point data
+    /// (where the offset to the chunk table is), it must have a chunk table.
+    pub fn copy_chunks_from<R: Read + Seek>(
+        &mut self,
+        mut src: R,
+        src_vlr: &LazVlr,
+        num_points: u64,
+    ) -> crate::Result<()> {
+        if !src_vlr.compatible_with(&self.vlr) {
+            return Err(invalid_input(
+                "The chunks to copy are not compatible with the vlr of the writer".to_string(),
+            ));
+        }
+        let chunk_table = ChunkTable::read_from(&mut src, src_vlr)?;
+        let data_start = src.stream_position()?;
+
+        let mut remaining = num_points;
+        for (entry, range) in chunk_table.iter().zip(chunk_table.chunk_ranges(data_start)) {
+            // For fixed-size chunks, the point count of the last entry
+            // may be greater than the actual number of points
+            let point_count = entry.point_count.min(remaining);
+            if point_count == 0 {
+                continue;
+            }
+            remaining -= point_count;
+            self.check_chunk_fits(point_count)?;
+
+            src.seek(SeekFrom::Start(range.start))?;
+            let copied = std::io::copy(&mut (&mut src).take(entry.byte_count), &mut self.dst)?;
+            if copied != entry.byte_count {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            self.chunk_table.push(ChunkTableEntry {
+                point_count,
+                byte_count: entry.byte_count,
+            });
+        }
+        Ok(())
+    }
+
+    /// Must be called once all the chunks are written, it writes the chunk table.
+    pub fn done(&mut self) -> std::io::Result<()> {
+        let end_of_chunks = self.start_pos + 8 + self.chunk_table.byte_count();
+        self.dst.seek(SeekFrom::Start(end_of_chunks))?;
+        chunk_table::update_chunk_table_offset(&mut self.dst, SeekFrom::Start(self.start_pos))?;
+        self.chunk_table.write_to(&mut self.dst, &self.vlr)
+    }
+
+    /// Calls [`done`], flushes the output and returns it,
+    /// positioned right after the chunk table.
+    ///
+    /// [`done`]: Self::done
+    pub fn finish(mut self) -> crate::Result<W> {
+        self.done()?;
+        self.dst.flush()?;
+        Ok(self.dst)
+    }
+
+    /// Returns the table of the chunks written so far
+    pub fn chunk_table(&self) -> &ChunkTable {
+        &self.chunk_table
+    }
+
+    pub fn vlr(&self) -> &LazVlr {
+        &self.vlr
+    }
+
+    pub fn into_inner(self) -> W {
+        self.dst
+    }
+
+    /// Checks that a chunk of `point_count` points can be written after the chunks
+    /// written so far, which is always the case with variable-size chunks
+    fn check_chunk_fits(&self, point_count: u64) -> crate::Result<()> {
+        if self.vlr.uses_variable_size_chunks() {
+            return Ok(());
+        }
+        let chunk_size = u64::from(self.vlr.chunk_size());
+        let last_is_full = match self.chunk_table.as_ref().last() {
+            Some(entry) => entry.point_count == chunk_size,
+            None => true,
+        };
+        if point_count > chunk_size || !last_is_full {
+            return Err(invalid_input(format!(
+                "Only the last of the fixed-size chunks ({} points) may have less points",
+                chunk_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn invalid_input(message: String) -> LasZipError {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message).into()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils::points;
+    use crate::{LasZipDecompressor, LazItemRecordBuilder, LazVlrBuilder};
+
+    fn compress(points: &[u8], vlr: &LazVlr) -> Cursor<Vec<u8>> {
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+        compressed
+    }
+
+    #[test]
+    fn test_merge_chunks() {
+        for items in [
+            LazItemRecordBuilder::default_version_of::<crate::las::Point3>(0),
+            LazItemRecordBuilder::default_version_of::<crate::las::Point7>(0),
+        ] {
+            let point_size = items.iter().map(|item| item.size() as usize).sum::<usize>();
+            let vlr = LazVlrBuilder::new(items.clone())
+                .with_fixed_chunk_size(10)
+                .build();
+            let expected = points(32, point_size);
+            let (first, second) = expected.split_at(25 * point_size);
+
+            let merged_vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+            let mut writer =
+                RawChunkWriter::new(Cursor::new(Vec::<u8>::new()), merged_vlr.clone()).unwrap();
+            writer
+                .copy_chunks_from(compress(first, &vlr), &vlr, 25)
+                .unwrap();
+            writer
+                .copy_chunks_from(compress(second, &vlr), &vlr, 7)
+                .unwrap();
+            let counts = writer
+                .chunk_table()
+                .iter()
+                .map(|entry| entry.point_count)
+                .collect::<Vec<u64>>();
+            assert_eq!(counts, vec![10, 10, 5, 7]);
+            let merged = writer.finish().unwrap();
+
+            let mut decompressed = vec![0u8; expected.len()];
+            crate::decompress_buffer(merged.get_ref(), &mut decompressed, merged_vlr.clone())
+                .unwrap();
+            assert_eq!(decompressed, expected);
+
+            let mut decompressor =
+                LasZipDecompressor::new(Cursor::new(merged.get_ref()), merged_vlr).unwrap();
+            let mut point = vec![0u8; point_size];
+            decompressor.seek(27).unwrap();
+            decompressor.decompress_one(&mut point).unwrap();
+            assert_eq!(point, &expected[27 * point_size..28 * point_size]);
+        }
+    }
+
+    #[test]
+    fn test_fixed_size_chunks_must_be_full() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+
+        let points = points(30, 20);
+        let mut writer = RawChunkWriter::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        writer
+            .copy_chunks_from(compress(&points[..20 * 20], &vlr), &vlr, 20)
+            .unwrap();
+        writer
+            .copy_chunks_from(compress(&points[20 * 20..25 * 20], &vlr), &vlr, 5)
+            .unwrap();
+        assert!(writer
+            .copy_chunks_from(compress(&points[25 * 20..], &vlr), &vlr, 5)
+            .is_err());
+        let merged = writer.finish().unwrap();
+
+        let mut decompressed = vec![0u8; 25 * 20];
+        crate::decompress_buffer(merged.get_ref(), &mut decompressed, vlr.clone()).unwrap();
+        assert_eq!(decompressed, &points[..25 * 20]);
+
+        let other_vlr = LazVlrBuilder::default()
+            .with_point_format(1, 0)
+            .unwrap()
+            .build();
+        let mut writer = RawChunkWriter::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        assert!(writer
+            .copy_chunks_from(Cursor::new(Vec::new()), &other_vlr, 0)
+            .is_err());
+    }
+}
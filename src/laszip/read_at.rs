@@ -0,0 +1,561 @@
+//! Positional reading of the compressed data
+//!
+//! Sources implementing [`ReadAt`] are read at given offsets instead of
+//! being streamed, which maps to range requests for remote sources (HTTP, S3),
+//! and allows several threads to read the same file.
+//!
+//! - [`ReadAtCursor`] adapts them to [`Read`] + [`Seek`], for the [`LasZipDecompressor`]
+//! - [`ReadAtDecompressor`] fetches and decompresses whole chunks, with one read each
+//!
+//! [`LasZipDecompressor`]: super::LasZipDecompressor
+use std::convert::TryFrom;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+use crate::LasZipError;
+
+use super::details;
+use super::{ChunkTable, DecompressionSelection, LazOpenOptions, LazVlr, PointBufferMut};
+
+/// Size of the blocks read by a [`ReadAtCursor`], unless changed
+const DEFAULT_BLOCK_SIZE: usize = 256 * 1024;
+
+/// Source of bytes that can be read at any offset, without a position.
+///
+/// Unlike [`Read`] + [`Seek`], reading takes `&self`, so that it can be shared.
+pub trait ReadAt {
+    /// Reads bytes starting at `offset` into `buf`, returns the number of bytes read,
+    /// 0 meaning that the end of the source was reached.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize>;
+
+    /// Returns the total number of bytes of the source
+    fn size(&self) -> std::io::Result<u64>;
+
+    /// Reads exactly the bytes needed to fill `buf`, starting at `offset`
+    fn read_exact_at(&self, mut offset: u64, mut buf: &mut [u8]) -> std::io::Result<()> {
+        while !buf.is_empty() {
+            match self.read_at(offset, buf) {
+                Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    offset += n as u64;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let start = usize::try_from(offset)
+            .unwrap_or(usize::MAX)
+            .min(self.len());
+        let count = buf.len().min(self.len() - start);
+        buf[..count].copy_from_slice(&self[start..start + count]);
+        Ok(count)
+    }
+
+    fn size(&self) -> std::io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.as_slice().read_at(offset, buf)
+    }
+
+    fn size(&self) -> std::io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+#[cfg(any(unix, windows))]
+impl ReadAt for std::fs::File {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::FileExt::read_at(self, buf, offset)
+        }
+        #[cfg(windows)]
+        {
+            // Also moves the file cursor, which is not used by ReadAt
+            std::os::windows::fs::FileExt::seek_read(self, buf, offset)
+        }
+    }
+
+    fn size(&self) -> std::io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for &T {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+
+    fn size(&self) -> std::io::Result<u64> {
+        (**self).size()
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Box<T> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+
+    fn size(&self) -> std::io::Result<u64> {
+        (**self).size()
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+
+    fn size(&self) -> std::io::Result<u64> {
+        (**self).size()
+    }
+}
+
+/// Adapter of a [`ReadAt`] source to [`Read`] + [`Seek`].
+///
+/// Small reads are served from a buffer, filled by reading blocks of
+/// [`block_size`](Self::with_block_size) bytes, so that a decompressor reading
+/// its source a few bytes at a time only makes one [`ReadAt::read_at`] call per block.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use laz::laszip::read_at::ReadAtCursor;
+///
+/// // e.g. a source doing HTTP range requests
+/// let file = std::fs::read("tests/data/point10.laz")?;
+/// let mut source = ReadAtCursor::new(file);
+/// let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut source)?;
+/// source.set_position(u64::from(header.offset_to_points));
+///
+/// let vlr = vlr.unwrap();
+/// let mut decompressor = laz::LasZipDecompressor::new(source, vlr.clone())?;
+/// decompressor.seek(10)?;
+/// let mut point = vec![0u8; vlr.items_size() as usize];
+/// decompressor.decompress_one(&mut point)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReadAtCursor<R: ReadAt> {
+    source: R,
+    position: u64,
+    /// Bytes of the source, starting at `buffer_start`
+    buffer: Vec<u8>,
+    buffer_start: u64,
+    block_size: usize,
+}
+
+impl<R: ReadAt> ReadAtCursor<R> {
+    /// Creates a cursor at the start of the `source`
+    pub fn new(source: R) -> Self {
+        Self {
+            source,
+            position: 0,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            block_size: DEFAULT_BLOCK_SIZE,
+        }
+    }
+
+    /// Sets the number of bytes read at once (256 KiB by default),
+    /// reads of at least this size are not buffered.
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.source
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    /// Returns the buffered bytes at the current position
+    fn buffered(&self) -> &[u8] {
+        match self.position.checked_sub(self.buffer_start) {
+            Some(offset) if offset < self.buffer.len() as u64 => &self.buffer[offset as usize..],
+            _ => &[],
+        }
+    }
+}
+
+impl<R: ReadAt> Read for ReadAtCursor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.buffered().is_empty() {
+            if buf.len() >= self.block_size {
+                let count = self.source.read_at(self.position, buf)?;
+                self.position += count as u64;
+                return Ok(count);
+            }
+            self.buffer.resize(self.block_size, 0u8);
+            let count = self.source.read_at(self.position, &mut self.buffer)?;
+            self.buffer.truncate(count);
+            self.buffer_start = self.position;
+        }
+        let buffered = self.buffered();
+        let count = buf.len().min(buffered.len());
+        buf[..count].copy_from_slice(&buffered[..count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl<R: ReadAt> Seek for ReadAtCursor<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(position) => {
+                self.position = position;
+                return Ok(position);
+            }
+            SeekFrom::End(offset) => (self.source.size()?, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        self.position = base.checked_add_signed(offset).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+
+    fn stream_position(&mut self) -> std::io::Result<u64> {
+        Ok(self.position)
+    }
+}
+
+/// Decompressor of whole chunks, read from a [`ReadAt`] source.
+///
+/// The compressed bytes of a chunk are fetched with one [`ReadAt::read_exact_at`],
+/// using the byte ranges of the chunk table.
+/// As decompressing a chunk only takes `&self`, chunks can be decompressed
+/// by several threads at the same time.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use laz::laszip::read_at::ReadAtDecompressor;
+///
+/// let file = std::fs::File::open("tests/data/point10.laz")?;
+/// let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut &file)?;
+/// let vlr = vlr.unwrap();
+/// let decompressor =
+///     ReadAtDecompressor::new(&file, vlr.clone(), u64::from(header.offset_to_points))?;
+///
+/// let point_count = decompressor.chunk_table()[0].point_count.min(header.num_points);
+/// let mut points = vec![0u8; point_count as usize * vlr.items_size() as usize];
+/// decompressor.decompress_chunk(0, &mut points)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ReadAtDecompressor<R: ReadAt> {
+    source: R,
+    vlr: LazVlr,
+    chunk_table: ChunkTable,
+    /// Offset of the first chunk
+    data_start: u64,
+    /// Maximum number of compressed bytes of a chunk read at once
+    memory_limit: Option<usize>,
+    selection: DecompressionSelection,
+    /// Pool in which the chunks are decompressed in parallel, the current one if None
+    #[cfg(feature = "parallel")]
+    pub(super) thread_pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl<R: ReadAt> ReadAtDecompressor<R> {
+    /// Creates a decompressor of the point data that starts at `offset_to_points`
+    /// (where the offset to the chunk table is) in the `source`,
+    /// its chunk table is read at creation.
+    pub fn new(source: R, vlr: LazVlr, offset_to_points: u64) -> crate::Result<Self> {
+        Self::with_options(source, vlr, offset_to_points, LazOpenOptions::new())
+    }
+
+    /// Creates a decompressor configured by the `options`,
+    /// see [`ParLasZipDecompressor::with_options`] for the options supported.
+    ///
+    /// [`ParLasZipDecompressor::with_options`]: crate::ParLasZipDecompressor::with_options
+    pub fn with_options(
+        source: R,
+        vlr: LazVlr,
+        offset_to_points: u64,
+        mut options: LazOpenOptions<'_>,
+    ) -> crate::Result<Self> {
+        options.check_whole_chunk_options()?;
+        let mut cursor = ReadAtCursor::new(&source).with_block_size(8 * 1024);
+        cursor.set_position(offset_to_points);
+        let chunk_table = options.read_chunk_table(&mut cursor, &vlr)?;
+        options.check_item_versions(&vlr)?;
+        Ok(Self {
+            source,
+            vlr,
+            chunk_table,
+            data_start: offset_to_points + 8,
+            memory_limit: options.memory_limit,
+            selection: options.selection,
+            #[cfg(feature = "parallel")]
+            thread_pool: options.thread_pool,
+        })
+    }
+
+    /// Creates a decompressor using an already read `chunk_table`,
+    /// for the point data that starts at `offset_to_points`.
+    pub fn with_chunk_table(
+        source: R,
+        vlr: LazVlr,
+        offset_to_points: u64,
+        chunk_table: ChunkTable,
+    ) -> Self {
+        Self {
+            source,
+            vlr,
+            chunk_table,
+            data_start: offset_to_points + 8,
+            memory_limit: None,
+            selection: DecompressionSelection::all(),
+            #[cfg(feature = "parallel")]
+            thread_pool: None,
+        }
+    }
+
+    /// Decompresses the points of the chunk at `index` into `out`,
+    /// see [`LasZipDecompressor::decompress_chunk`].
+    ///
+    /// Returns the number of points decompressed.
+    ///
+    /// [`LasZipDecompressor::decompress_chunk`]: super::LasZipDecompressor::decompress_chunk
+    pub fn decompress_chunk(&self, index: usize, out: &mut [u8]) -> crate::Result<u64> {
+        let points = PointBufferMut::new(out, &self.vlr)?;
+        let entry =
+            self.chunk_table
+                .as_ref()
+                .get(index)
+                .ok_or(LasZipError::ChunkIndexOutOfRange {
+                    index,
+                    num_chunks: self.chunk_table.len(),
+                })?;
+        let num_points = entry.point_count.min(points.len() as u64);
+        let point_size = points.point_size();
+        let start_of_chunk = self
+            .chunk_table
+            .chunk_ranges(self.data_start)
+            .nth(index)
+            .map_or(self.data_start, |range| range.start);
+
+        let byte_count = details::usize_from(entry.byte_count)?;
+        crate::errors::check_memory_limit(self.memory_limit, byte_count)?;
+        let mut chunk = vec![0u8; byte_count];
+        self.source.read_exact_at(start_of_chunk, &mut chunk)?;
+        let mut decompressor = details::record_decompressor_with_selection(
+            &self.vlr,
+            self.selection,
+            Cursor::new(chunk),
+        )?;
+        decompressor.set_memory_limit(self.memory_limit);
+        for point in out.chunks_exact_mut(point_size).take(num_points as usize) {
+            decompressor.decompress_next(point)?;
+        }
+        Ok(num_points)
+    }
+
+    pub fn vlr(&self) -> &LazVlr {
+        &self.vlr
+    }
+
+    pub fn chunk_table(&self) -> &ChunkTable {
+        &self.chunk_table
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.source
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::{LasZipDecompressor, LazVlrBuilder};
+
+    /// Source counting the calls to `read_at`
+    struct CountingSource {
+        data: Vec<u8>,
+        reads: AtomicUsize,
+    }
+
+    impl ReadAt for CountingSource {
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            self.data.read_at(offset, buf)
+        }
+
+        fn size(&self) -> std::io::Result<u64> {
+            self.data.size()
+        }
+    }
+
+    fn compressed(num_points: usize, vlr: &LazVlr) -> (Vec<u8>, Vec<u8>) {
+        let point_size = vlr.items_size() as usize;
+        let points = crate::test_utils::points(num_points, point_size);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        (points, compressed.into_inner())
+    }
+
+    #[test]
+    fn test_read_at_cursor() {
+        let data = (0..100u8).collect::<Vec<u8>>();
+        let mut cursor = ReadAtCursor::new(data.as_slice()).with_block_size(16);
+        let mut buf = [0u8; 10];
+        cursor.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        cursor.read_exact(&mut buf).unwrap();
+        assert_eq!(buf[0], 10);
+        assert_eq!(cursor.seek(SeekFrom::End(-5)).unwrap(), 95);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], &[95, 96, 97, 98, 99]);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+        assert!(cursor.seek(SeekFrom::Current(-200)).is_err());
+
+        let mut big = [0u8; 32];
+        cursor.seek(SeekFrom::Start(50)).unwrap();
+        cursor.read_exact(&mut big).unwrap();
+        assert_eq!(big[31], 81);
+    }
+
+    #[test]
+    fn test_decompression_through_read_at_cursor() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(3, 0)
+            .unwrap()
+            .with_fixed_chunk_size(100)
+            .build();
+        let (points, compressed) = compressed(350, &vlr);
+        let source = CountingSource {
+            data: compressed,
+            reads: AtomicUsize::new(0),
+        };
+
+        let cursor = ReadAtCursor::new(&source).with_block_size(1024);
+        let mut decompressor = LasZipDecompressor::new(cursor, vlr).unwrap();
+        let mut point = [0u8; 34];
+        decompressor.seek(260).unwrap();
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(point, points[260 * 34..261 * 34]);
+        // The offset, the chunk table and the start of the chunk
+        assert!(source.reads.load(Ordering::Relaxed) <= 4);
+    }
+
+    #[test]
+    fn test_read_at_decompressor() {
+        for vlr in [
+            LazVlrBuilder::default()
+                .with_point_format(1, 0)
+                .unwrap()
+                .with_fixed_chunk_size(100)
+                .build(),
+            LazVlrBuilder::default()
+                .with_point_format(6, 0)
+                .unwrap()
+                .with_fixed_chunk_size(100)
+                .build(),
+        ] {
+            let point_size = vlr.items_size() as usize;
+            let (points, compressed) = compressed(250, &vlr);
+            let source = CountingSource {
+                data: compressed,
+                reads: AtomicUsize::new(0),
+            };
+            let decompressor = ReadAtDecompressor::new(&source, vlr, 0).unwrap();
+            assert_eq!(decompressor.chunk_table().len(), 3);
+
+            source.reads.store(0, Ordering::Relaxed);
+            let mut chunk = vec![0u8; 50 * point_size];
+            assert_eq!(decompressor.decompress_chunk(2, &mut chunk).unwrap(), 50);
+            assert_eq!(chunk, &points[200 * point_size..]);
+            assert_eq!(source.reads.load(Ordering::Relaxed), 1);
+
+            let mut chunk = vec![0u8; 100 * point_size];
+            assert_eq!(decompressor.decompress_chunk(1, &mut chunk).unwrap(), 100);
+            assert_eq!(chunk, &points[100 * point_size..200 * point_size]);
+            assert!(decompressor.decompress_chunk(3, &mut chunk).is_err());
+        }
+    }
+
+    #[test]
+    fn test_read_at_decompressor_with_options() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(1, 0)
+            .unwrap()
+            .with_fixed_chunk_size(100)
+            .build();
+        let (points, compressed) = compressed(250, &vlr);
+        let options = LazOpenOptions::new().with_num_points(230);
+        let decompressor =
+            ReadAtDecompressor::with_options(&compressed, vlr.clone(), 0, options).unwrap();
+        assert_eq!(decompressor.chunk_table().num_points(), 230);
+        let mut chunk = vec![0u8; 100 * 28];
+        assert_eq!(decompressor.decompress_chunk(2, &mut chunk).unwrap(), 30);
+        assert_eq!(chunk[..30 * 28], points[200 * 28..230 * 28]);
+
+        let options = LazOpenOptions::new().with_memory_limit(64);
+        let decompressor =
+            ReadAtDecompressor::with_options(&compressed, vlr.clone(), 0, options).unwrap();
+        assert!(matches!(
+            decompressor.decompress_chunk(0, &mut chunk),
+            Err(LasZipError::MemoryLimitExceeded { limit: 64, .. })
+        ));
+
+        let options = LazOpenOptions::new().with_max_num_chunks(2);
+        assert!(matches!(
+            ReadAtDecompressor::with_options(&compressed, vlr.clone(), 0, options),
+            Err(LasZipError::ChunkLimitExceeded {
+                limit: 2,
+                num_chunks: 3
+            })
+        ));
+        let options = LazOpenOptions::new().with_snapshot_interval(10);
+        assert!(matches!(
+            ReadAtDecompressor::with_options(&compressed, vlr, 0, options),
+            Err(LasZipError::UnsupportedOption("snapshot_interval"))
+        ));
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn test_read_at_file() {
+        let file = std::fs::File::open("tests/data/point10.laz").unwrap();
+        let data = std::fs::read("tests/data/point10.laz").unwrap();
+        assert_eq!(file.size().unwrap(), data.len() as u64);
+        let mut buf = [0u8; 16];
+        file.read_exact_at(100, &mut buf).unwrap();
+        assert_eq!(buf, data[100..116]);
+    }
+}
@@ -0,0 +1,249 @@
+//! Re-encoding of compressed points using other items or another chunking
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use super::details::usize_from;
+use super::{
+    extended_point_format_of, upgrade_point, ChunkTable, LasZipCompressor, LasZipDecompressor,
+    LazVlr,
+};
+
+/// Decompresses the `num_points` points of the `src`, described by the `src_vlr`,
+/// and compresses them into the `dst` as described by the `dst_vlr`.
+///
+/// The `src` position must be at the start of the point data,
+/// points are re-encoded one chunk at a time, so the memory usage stays low.
+///
+/// When the `dst_vlr` uses variable-size chunks, the chunks of the `src`
+/// are kept as-is, which requires the `src` to have a chunk table.
+///
+/// When the `src_vlr` describes points of a legacy point format (0 to 5) and the
+/// `dst_vlr` the points of its extended point format, as returned by [`LazVlr::to_layered`],
+/// the points are converted with [`upgrade_point`].
+///
+/// Returns the `dst`.
+///
+/// # Example
+///
+/// Changing the chunk size
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::{Cursor, Seek, SeekFrom};
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items.clone()).build();
+/// # let mut src = Cursor::new(Vec::<u8>::new());
+/// # laz::compress_buffer(&mut src, &[0u8; 200], vlr.clone())?;
+/// # src.seek(SeekFrom::Start(0))?;
+/// let new_vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(5_000).build();
+/// let dst = laz::laszip::reencode(src, vlr, Cursor::new(Vec::<u8>::new()), new_vlr, 10)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the size of the points described by the two vlrs is not the same,
+/// and the points are not upgraded.
+pub fn reencode<R, W>(
+    mut src: R,
+    src_vlr: LazVlr,
+    dst: W,
+    dst_vlr: LazVlr,
+    num_points: u64,
+) -> crate::Result<W>
+where
+    R: Read + Seek + Send,
+    W: Write + Seek + Send,
+{
+    let legacy_point_format = legacy_point_format_to_upgrade(&src_vlr, &dst_vlr);
+    if legacy_point_format.is_none() {
+        assert_eq!(src_vlr.items_size(), dst_vlr.items_size());
+    }
+    let point_size = src_vlr.items_size() as usize;
+    let dst_point_size = dst_vlr.items_size() as usize;
+    let batches = reencode_batches(&mut src, &src_vlr, &dst_vlr, num_points, 1)?;
+
+    let mut decompressor = LasZipDecompressor::new(src, src_vlr)?;
+    let mut compressor = LasZipCompressor::new(dst, dst_vlr)?;
+    let mut points = Vec::<u8>::new();
+    let mut upgraded = Vec::<u8>::new();
+    for batch in batches {
+        points.resize(usize_from(batch.num_points)? * point_size, 0u8);
+        decompressor.decompress_many(&mut points)?;
+        let points = upgrade_points(
+            legacy_point_format,
+            &points,
+            &mut upgraded,
+            point_size,
+            dst_point_size,
+        );
+        if batch.chunks.is_empty() {
+            compressor.compress_many(points)?;
+        } else {
+            compressor.compress_chunks(split_chunks(points, &batch.chunks, dst_point_size))?;
+        }
+    }
+    compressor.done()?;
+    Ok(compressor.into_inner())
+}
+
+/// Same as [`reencode`] but the decompression and compression happen in multiple threads.
+///
+/// The `src` must have a chunk table,
+/// points are re-encoded a few chunks per thread at a time.
+#[cfg(feature = "parallel")]
+pub fn par_reencode<R, W>(
+    mut src: R,
+    src_vlr: LazVlr,
+    dst: W,
+    dst_vlr: LazVlr,
+    num_points: u64,
+) -> crate::Result<W>
+where
+    R: Read + Seek,
+    W: Write + Seek + Send,
+{
+    use super::parallel::{ParLasZipCompressor, ParLasZipDecompressor};
+
+    let legacy_point_format = legacy_point_format_to_upgrade(&src_vlr, &dst_vlr);
+    if legacy_point_format.is_none() {
+        assert_eq!(src_vlr.items_size(), dst_vlr.items_size());
+    }
+    let point_size = src_vlr.items_size() as usize;
+    let dst_point_size = dst_vlr.items_size() as usize;
+    let batches = reencode_batches(
+        &mut src,
+        &src_vlr,
+        &dst_vlr,
+        num_points,
+        rayon::current_num_threads(),
+    )?;
+
+    let mut decompressor = ParLasZipDecompressor::new(src, src_vlr)?;
+    let mut compressor = ParLasZipCompressor::new(dst, dst_vlr)?;
+    let mut points = Vec::<u8>::new();
+    let mut upgraded = Vec::<u8>::new();
+    for batch in batches {
+        points.resize(usize_from(batch.num_points)? * point_size, 0u8);
+        decompressor.decompress_many(&mut points)?;
+        let points = upgrade_points(
+            legacy_point_format,
+            &points,
+            &mut upgraded,
+            point_size,
+            dst_point_size,
+        );
+        if batch.chunks.is_empty() {
+            compressor.compress_many(points)?;
+        } else {
+            compressor.compress_chunks(split_chunks(points, &batch.chunks, dst_point_size))?;
+        }
+    }
+    compressor.done()?;
+    Ok(compressor.into_inner())
+}
+
+/// Returns the legacy point format of the points of the `src_vlr`
+/// if they have to be upgraded to be compressed with the `dst_vlr`.
+fn legacy_point_format_to_upgrade(src_vlr: &LazVlr, dst_vlr: &LazVlr) -> Option<u8> {
+    let (point_format_id, num_extra_bytes) = src_vlr.point_format()?;
+    let extended_point_format_id = extended_point_format_of(point_format_id)?;
+    dst_vlr
+        .is_compatible_with_point_format(extended_point_format_id, num_extra_bytes)
+        .then_some(point_format_id)
+}
+
+/// Returns the `points` upgraded into the `upgraded` buffer,
+/// or the `points` when there is no `legacy_point_format` to upgrade.
+fn upgrade_points<'a>(
+    legacy_point_format: Option<u8>,
+    points: &'a [u8],
+    upgraded: &'a mut Vec<u8>,
+    point_size: usize,
+    upgraded_point_size: usize,
+) -> &'a [u8] {
+    let Some(point_format_id) = legacy_point_format else {
+        return points;
+    };
+    upgraded.resize(points.len() / point_size * upgraded_point_size, 0u8);
+    for (src, dst) in points
+        .chunks_exact(point_size)
+        .zip(upgraded.chunks_exact_mut(upgraded_point_size))
+    {
+        upgrade_point(point_format_id, src, dst);
+    }
+    upgraded
+}
+
+/// Points to be re-encoded at once
+struct Batch {
+    num_points: u64,
+    /// Number of points of each chunk to be written,
+    /// empty when the destination uses fixed-size chunks
+    chunks: Vec<u64>,
+}
+
+/// Splits the work of re-encoding in batches of roughly `chunks_per_batch` chunks.
+///
+/// Leaves the `src` position unchanged.
+fn reencode_batches<R: Read + Seek>(
+    src: &mut R,
+    src_vlr: &LazVlr,
+    dst_vlr: &LazVlr,
+    num_points: u64,
+    chunks_per_batch: usize,
+) -> crate::Result<Vec<Batch>> {
+    let chunks_per_batch = chunks_per_batch.max(1);
+    let mut batches = Vec::new();
+    if dst_vlr.uses_variable_size_chunks() {
+        let start = src.stream_position()?;
+        let chunk_table = ChunkTable::read_from(&mut *src, src_vlr)?;
+        src.seek(SeekFrom::Start(start))?;
+
+        let mut remaining = num_points;
+        for entries in chunk_table.as_ref().chunks(chunks_per_batch) {
+            let chunks = entries
+                .iter()
+                .map(|entry| {
+                    // For fixed-size chunks, the point count of the last entry
+                    // may be greater than the actual number of points
+                    let count = entry.point_count.min(remaining);
+                    remaining -= count;
+                    count
+                })
+                .filter(|&count| count > 0)
+                .collect::<Vec<u64>>();
+            if chunks.is_empty() {
+                break;
+            }
+            batches.push(Batch {
+                num_points: chunks.iter().sum(),
+                chunks,
+            });
+        }
+    } else {
+        let batch_size = u64::from(dst_vlr.chunk_size()) * chunks_per_batch as u64;
+        let mut remaining = num_points;
+        while remaining > 0 {
+            let count = batch_size.min(remaining);
+            remaining -= count;
+            batches.push(Batch {
+                num_points: count,
+                chunks: vec![],
+            });
+        }
+    }
+    Ok(batches)
+}
+
+fn split_chunks<'a>(points: &'a [u8], chunks: &[u64], point_size: usize) -> Vec<&'a [u8]> {
+    let mut rest = points;
+    chunks
+        .iter()
+        .map(|&count| {
+            let (chunk, remaining) = rest.split_at(count as usize * point_size);
+            rest = remaining;
+            chunk
+        })
+        .collect()
+}
@@ -0,0 +1,186 @@
+//! Selection of the fields to decompress
+use std::ops::{BitOr, BitOrAssign};
+
+/// Bitmask of the fields to decompress, like LASzip's `decompress_selective`
+/// (the values of the masks are the same).
+///
+/// The layered point formats (6 to 10) store each field in its own layer,
+/// the layers of the fields that are not selected are skipped: they are neither read
+/// nor decoded. In the decompressed points, these fields have the value they have in
+/// the first point of their chunk.
+///
+/// The x, y, returns & scanner channel are always decompressed, as the other fields
+/// depend on them. The point-wise formats (0 to 5) always decompress all the fields.
+///
+/// # Example
+///
+/// ```
+/// use laz::laszip::DecompressionSelection;
+///
+/// let selection = DecompressionSelection::Z | DecompressionSelection::CLASSIFICATION;
+/// assert!(selection.contains(DecompressionSelection::Z));
+/// assert!(!selection.contains(DecompressionSelection::RGB));
+/// assert!(DecompressionSelection::all().extra_byte_requested(3));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DecompressionSelection(u32);
+
+impl DecompressionSelection {
+    /// Only the x, y, returns & scanner channel, which are always decompressed
+    pub const CHANNEL_RETURNS_XY: Self = Self(0x0000_0000);
+    pub const Z: Self = Self(0x0000_0001);
+    pub const CLASSIFICATION: Self = Self(0x0000_0002);
+    pub const FLAGS: Self = Self(0x0000_0004);
+    pub const INTENSITY: Self = Self(0x0000_0008);
+    pub const SCAN_ANGLE: Self = Self(0x0000_0010);
+    pub const USER_DATA: Self = Self(0x0000_0020);
+    pub const POINT_SOURCE_ID: Self = Self(0x0000_0040);
+    pub const GPS_TIME: Self = Self(0x0000_0080);
+    pub const RGB: Self = Self(0x0000_0100);
+    pub const NIR: Self = Self(0x0000_0200);
+    pub const WAVEPACKET: Self = Self(0x0000_0400);
+    /// The first extra byte, see [`Self::extra_byte`] for the others
+    pub const BYTE0: Self = Self(0x0001_0000);
+    pub const ALL_EXTRA_BYTES: Self = Self(0xFFFF_0000);
+    pub const ALL: Self = Self(0xFFFF_FFFF);
+
+    /// Selects all the fields, this is the default
+    pub fn all() -> Self {
+        Self::ALL
+    }
+
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Selects the extra byte at `index`, only the first 16 extra bytes
+    /// can be selected individually.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is 16 or more
+    pub fn extra_byte(index: usize) -> Self {
+        assert!(index < 16, "only the first 16 extra bytes can be selected");
+        Self(Self::BYTE0.0 << index)
+    }
+
+    /// Returns whether all the fields of `other` are selected
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns whether the extra byte at `index` is selected,
+    /// the extra bytes after the 16th are selected with the 16th.
+    pub fn extra_byte_requested(&self, index: usize) -> bool {
+        self.contains(Self::extra_byte(index.min(15)))
+    }
+}
+
+impl Default for DecompressionSelection {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl BitOr for DecompressionSelection {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for DecompressionSelection {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{LasZipDecompressor, LazVlrBuilder};
+
+    const POINT_SIZE: usize = 40;
+
+    /// Points of format 8 with 2 extra bytes, where all the fields change
+    fn points(num_points: usize) -> Vec<u8> {
+        let mut points = vec![0u8; num_points * POINT_SIZE];
+        for (i, point) in points.chunks_exact_mut(POINT_SIZE).enumerate() {
+            let i32_value = i as i32;
+            point[0..4].copy_from_slice(&i32_value.to_le_bytes());
+            point[4..8].copy_from_slice(&(2 * i32_value).to_le_bytes());
+            point[8..12].copy_from_slice(&(3 * i32_value).to_le_bytes());
+            point[12..14].copy_from_slice(&(i as u16).to_le_bytes());
+            point[14] = 0x11;
+            point[16] = (i % 4) as u8 + 1;
+            point[17] = i as u8;
+            point[20..22].copy_from_slice(&(i as u16).to_le_bytes());
+            point[22..30].copy_from_slice(&(i as f64).to_le_bytes());
+            for j in 0..4 {
+                point[30 + 2 * j..32 + 2 * j]
+                    .copy_from_slice(&((i * (j + 3)) as u16).to_le_bytes());
+            }
+            point[38] = i as u8;
+            point[39] = (2 * i) as u8;
+        }
+        points
+    }
+
+    #[test]
+    fn test_selective_decompression() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(8, 2)
+            .unwrap()
+            .with_fixed_chunk_size(100)
+            .build();
+        let points = points(250);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let selection = DecompressionSelection::Z
+            | DecompressionSelection::CLASSIFICATION
+            | DecompressionSelection::extra_byte(1);
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        decompressor.set_selection(selection);
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+
+        // intensity, user data, point source id, gps time, rgb, nir & the first extra byte
+        let not_selected = [12..14, 17..18, 20..30, 30..38, 38..39];
+        let mut expected = points.clone();
+        for (chunk, expected_chunk) in points
+            .chunks(100 * POINT_SIZE)
+            .zip(expected.chunks_mut(100 * POINT_SIZE))
+        {
+            let first_point = &chunk[..POINT_SIZE];
+            for point in expected_chunk.chunks_exact_mut(POINT_SIZE) {
+                for range in not_selected.iter().cloned() {
+                    point[range.clone()].copy_from_slice(&first_point[range]);
+                }
+            }
+        }
+        assert_eq!(decompressed, expected);
+    }
+
+    #[test]
+    fn test_selection_bits() {
+        assert_eq!(
+            DecompressionSelection::default(),
+            DecompressionSelection::ALL
+        );
+        assert!(DecompressionSelection::ALL_EXTRA_BYTES.extra_byte_requested(20));
+        let selection = DecompressionSelection::extra_byte(15);
+        assert!(selection.extra_byte_requested(15));
+        assert!(selection.extra_byte_requested(16));
+        assert!(!selection.extra_byte_requested(14));
+        assert!(!DecompressionSelection::CHANNEL_RETURNS_XY.contains(DecompressionSelection::Z));
+    }
+}
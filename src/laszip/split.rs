@@ -0,0 +1,254 @@
+//! Splitting of compressed points by flight line
+use std::collections::HashMap;
+use std::io::{Read, Seek, Write};
+
+use crate::packers::Packable;
+
+use super::details::usize_from;
+use super::diagnostics::uses_contexts;
+use super::{LasZipCompressor, LasZipDecompressor, LazVlr};
+
+/// Number of points decompressed at once
+const BATCH_SIZE: u64 = 50_000;
+
+/// Description of one output of [`split_by_point_source_id`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SplitOutput {
+    pub point_source_id: u16,
+    /// Index of the output among the ones of the same point source id,
+    /// greater than 0 when the previous one was closed to respect the open outputs limit
+    pub part: u32,
+    pub num_points: u64,
+}
+
+/// Where [`split_by_point_source_id`] writes the points
+pub trait SplitDestination {
+    type Output: Write + Seek + Send;
+
+    /// Opens the output for the `part` of the points of the `point_source_id`
+    fn open(&mut self, point_source_id: u16, part: u32) -> std::io::Result<Self::Output>;
+
+    /// Called once all the points of the `output` are compressed into `dst`,
+    /// (e.g. to write the LAS header, that needs the number of points).
+    fn close(&mut self, output: SplitOutput, dst: Self::Output) -> std::io::Result<()>;
+}
+
+struct OpenOutput<'a, W: Write + Seek + Send + 'a> {
+    compressor: LasZipCompressor<'a, W>,
+    output: SplitOutput,
+    last_used: u64,
+}
+
+/// Decompresses the `num_points` points of the `src`, described by the `src_vlr`,
+/// and compresses them into one output per Point Source ID, as described by the `dst_vlr`.
+///
+/// The `src` position must be at the start of the point data.
+///
+/// At most `max_open_outputs` outputs (at least 1) are open at the same time, when another
+/// one is needed, the least recently used one is closed and its point source id gets
+/// a new part if points of it come later.
+///
+/// Returns the description of all the outputs, in the order they were closed.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::{split_by_point_source_id, SplitDestination, SplitOutput};
+///
+/// #[derive(Default)]
+/// struct InMemory(Vec<(SplitOutput, Vec<u8>)>);
+///
+/// impl SplitDestination for InMemory {
+///     type Output = Cursor<Vec<u8>>;
+///
+///     fn open(&mut self, _: u16, _: u32) -> std::io::Result<Self::Output> {
+///         Ok(Cursor::new(Vec::new()))
+///     }
+///
+///     fn close(&mut self, output: SplitOutput, dst: Self::Output) -> std::io::Result<()> {
+///         self.0.push((output, dst.into_inner()));
+///         Ok(())
+///     }
+/// }
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).build();
+/// let mut points = vec![0u8; 20 * 4];
+/// points[3 * 20 + 18] = 1;
+/// let mut src = Cursor::new(Vec::<u8>::new());
+/// laz::compress_buffer(&mut src, &points, vlr.clone())?;
+/// src.set_position(0);
+///
+/// let mut destination = InMemory::default();
+/// let outputs = split_by_point_source_id(src, vlr.clone(), 4, vlr, 16, &mut destination)?;
+/// assert_eq!(outputs.len(), 2);
+/// assert_eq!(destination.0[0].0.num_points, 3);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if the size of the points described by the two vlrs is not the same.
+pub fn split_by_point_source_id<R, D>(
+    src: R,
+    src_vlr: LazVlr,
+    num_points: u64,
+    dst_vlr: LazVlr,
+    max_open_outputs: usize,
+    destination: &mut D,
+) -> crate::Result<Vec<SplitOutput>>
+where
+    R: Read + Seek + Send,
+    D: SplitDestination,
+{
+    assert_eq!(src_vlr.items_size(), dst_vlr.items_size());
+    let max_open_outputs = max_open_outputs.max(1);
+    let point_size = usize_from(src_vlr.items_size())?;
+    let point_source_id_offset = if uses_contexts(src_vlr.items()) {
+        20
+    } else {
+        18
+    };
+
+    let mut decompressor = LasZipDecompressor::new(src, src_vlr)?;
+    let mut open_outputs = HashMap::<u16, OpenOutput<D::Output>>::new();
+    let mut next_parts = HashMap::<u16, u32>::new();
+    let mut closed = Vec::new();
+    let mut num_used = 0u64;
+    let mut points = Vec::<u8>::new();
+    let mut points_left = num_points;
+    while points_left > 0 {
+        let n = points_left.min(BATCH_SIZE);
+        points.resize(usize_from(n)? * point_size, 0u8);
+        decompressor.decompress_many(&mut points)?;
+        points_left -= n;
+
+        for point in points.chunks_exact(point_size) {
+            let point_source_id =
+                u16::unpack_from(&point[point_source_id_offset..point_source_id_offset + 2]);
+            if !open_outputs.contains_key(&point_source_id) {
+                if open_outputs.len() == max_open_outputs {
+                    let least_recently_used = open_outputs
+                        .iter()
+                        .min_by_key(|(_, open)| open.last_used)
+                        .map(|(&id, _)| id)
+                        .unwrap();
+                    let open = open_outputs.remove(&least_recently_used).unwrap();
+                    closed.push(close_output(open, destination)?);
+                }
+                let part = next_parts.entry(point_source_id).or_insert(0);
+                let dst = destination.open(point_source_id, *part)?;
+                let output = SplitOutput {
+                    point_source_id,
+                    part: *part,
+                    num_points: 0,
+                };
+                *part += 1;
+                let open = OpenOutput {
+                    compressor: LasZipCompressor::new(dst, dst_vlr.clone())?,
+                    output,
+                    last_used: 0,
+                };
+                open_outputs.insert(point_source_id, open);
+            }
+            let open = open_outputs.get_mut(&point_source_id).unwrap();
+            open.compressor.compress_one(point)?;
+            open.output.num_points += 1;
+            num_used += 1;
+            open.last_used = num_used;
+        }
+    }
+
+    let mut open_outputs = open_outputs.into_values().collect::<Vec<_>>();
+    open_outputs.sort_by_key(|open| open.last_used);
+    for open in open_outputs {
+        closed.push(close_output(open, destination)?);
+    }
+    Ok(closed)
+}
+
+fn close_output<'a, D: SplitDestination>(
+    mut open: OpenOutput<'a, D::Output>,
+    destination: &mut D,
+) -> crate::Result<SplitOutput>
+where
+    D::Output: 'a,
+{
+    open.compressor.done()?;
+    destination.close(open.output, open.compressor.into_inner())?;
+    Ok(open.output)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct InMemory {
+        opened: Vec<(u16, u32)>,
+        closed: Vec<(SplitOutput, Vec<u8>)>,
+    }
+
+    impl SplitDestination for InMemory {
+        type Output = Cursor<Vec<u8>>;
+
+        fn open(&mut self, point_source_id: u16, part: u32) -> std::io::Result<Self::Output> {
+            self.opened.push((point_source_id, part));
+            Ok(Cursor::new(Vec::new()))
+        }
+
+        fn close(&mut self, output: SplitOutput, dst: Self::Output) -> std::io::Result<()> {
+            self.closed.push((output, dst.into_inner()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_split_with_rollover() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point6>(0);
+        let vlr = crate::LazVlrBuilder::new(items).build();
+        let ids = [1u16, 1, 2, 3, 1, 3, 2, 2];
+        let points = ids
+            .iter()
+            .enumerate()
+            .flat_map(|(i, id)| {
+                let mut point = [0u8; 30];
+                point[..4].copy_from_slice(&(i as i32).to_le_bytes());
+                point[20..22].copy_from_slice(&id.to_le_bytes());
+                point
+            })
+            .collect::<Vec<u8>>();
+        let mut src = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut src, &points, vlr.clone()).unwrap();
+        src.set_position(0);
+
+        let mut destination = InMemory::default();
+        let outputs =
+            split_by_point_source_id(src, vlr.clone(), 8, vlr.clone(), 2, &mut destination)
+                .unwrap();
+        // Opening 3 closes 1, that gets a new part when it comes back
+        assert_eq!(
+            destination.opened,
+            vec![(1, 0), (2, 0), (3, 0), (1, 1), (2, 1)]
+        );
+        let summary = outputs
+            .iter()
+            .map(|o| (o.point_source_id, o.part, o.num_points))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![(1, 0, 2), (2, 0, 1), (1, 1, 1), (3, 0, 2), (2, 1, 2)]
+        );
+
+        let (output, compressed) = &destination.closed[3];
+        let mut decompressed = vec![0u8; 30 * output.num_points as usize];
+        crate::decompress_buffer(compressed, &mut decompressed, vlr).unwrap();
+        let expected = [&points[90..120], &points[150..180]].concat();
+        assert_eq!(decompressed, expected);
+    }
+}
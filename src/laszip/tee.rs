@@ -0,0 +1,135 @@
+//! Decompression of points into multiple consumers
+use std::io::{Read, Seek, Write};
+
+use super::{details, LasZipDecompressor};
+
+/// Number of points given at once to the sinks when the chunk sizes are not known
+const FALLBACK_BATCH_SIZE: u64 = 50_000;
+
+/// Consumer of decompressed points, see [`LasZipDecompressor::decompress_into_sinks`]
+///
+/// Closures taking the points are sinks.
+pub trait PointSink {
+    /// Consumes some points, the slice contains whole points
+    fn consume(&mut self, points: &[u8]) -> crate::Result<()>;
+}
+
+impl<F> PointSink for F
+where
+    F: FnMut(&[u8]) -> crate::Result<()>,
+{
+    fn consume(&mut self, points: &[u8]) -> crate::Result<()> {
+        self(points)
+    }
+}
+
+/// Sink that writes the raw points, as found in a LAS file, to a [`Write`]
+#[derive(Debug)]
+pub struct WriteSink<W: Write>(pub W);
+
+impl<W: Write> WriteSink<W> {
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+impl<W: Write> PointSink for WriteSink<W> {
+    fn consume(&mut self, points: &[u8]) -> crate::Result<()> {
+        self.0.write_all(points)?;
+        Ok(())
+    }
+}
+
+impl<'a, R: Read + Seek + Send + 'a> LasZipDecompressor<'a, R> {
+    /// Decompresses the next `num_points` points, giving them to each of the `sinks`
+    /// in order, so that they all get the points while they are decompressed only once.
+    ///
+    /// The points are given chunk by chunk, or by batches when the size
+    /// of the chunks is not known (variable-size chunks without a chunk table).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> laz::Result<()> {
+    /// # use std::io::{Seek, SeekFrom};
+    /// use laz::laszip::{PointSink, WriteSink};
+    ///
+    /// # let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+    /// # let (header, vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file)?;
+    /// # let vlr = vlr.unwrap();
+    /// # laz_file.seek(SeekFrom::Start(u64::from(header.offset_to_points)))?;
+    /// let point_size = vlr.items_size() as usize;
+    /// let mut decompressor = laz::LasZipDecompressor::new(laz_file, vlr)?;
+    ///
+    /// let mut las_points = WriteSink(Vec::<u8>::new());
+    /// let mut max_x = i32::MIN;
+    /// let mut stats = |points: &[u8]| -> laz::Result<()> {
+    ///     for point in points.chunks_exact(point_size) {
+    ///         max_x = max_x.max(i32::from_le_bytes([point[0], point[1], point[2], point[3]]));
+    ///     }
+    ///     Ok(())
+    /// };
+    /// decompressor.decompress_into_sinks(header.num_points, &mut [&mut las_points, &mut stats])?;
+    /// assert_eq!(las_points.0.len(), header.num_points as usize * point_size);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn decompress_into_sinks(
+        &mut self,
+        num_points: u64,
+        sinks: &mut [&mut dyn PointSink],
+    ) -> crate::Result<()> {
+        let point_size = self.vlr().items_size();
+        let mut points = Vec::new();
+        let mut points_left = num_points;
+        while points_left > 0 {
+            let batch_size = match self.points_left_in_chunk() {
+                Some(0) | None => FALLBACK_BATCH_SIZE,
+                Some(points_left_in_chunk) => points_left_in_chunk,
+            }
+            .min(points_left);
+            points.resize(details::usize_from(batch_size * point_size)?, 0u8);
+            self.decompress_many(&mut points)?;
+            for sink in sinks.iter_mut() {
+                sink.consume(&points)?;
+            }
+            points_left -= batch_size;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::las::Point0;
+    use crate::{LazItemRecordBuilder, LazVlrBuilder};
+
+    #[test]
+    fn test_decompress_into_sinks() {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = crate::test_utils::points(25, 20);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        let mut first = [0u8; 20];
+        decompressor.decompress_one(&mut first).unwrap();
+
+        let mut writer = WriteSink(Vec::<u8>::new());
+        let mut batch_sizes = Vec::new();
+        let mut counter = |points: &[u8]| -> crate::Result<()> {
+            batch_sizes.push(points.len() / 20);
+            Ok(())
+        };
+        decompressor
+            .decompress_into_sinks(24, &mut [&mut writer, &mut counter])
+            .unwrap();
+        assert_eq!(batch_sizes, vec![9, 10, 5]);
+        assert_eq!(writer.into_inner(), &points[20..]);
+    }
+}
@@ -0,0 +1,621 @@
+//! Splitting of compressed points in a regular grid of tiles, and merging of tiles
+use std::collections::{HashMap, HashSet};
+use std::io::{Seek, Write};
+
+use crate::spatial::Bounds;
+
+use super::details::usize_from;
+use super::{CoordinateTransform, LasZipCompressor, LazDecompressor, LazVlr};
+
+/// Number of points decompressed at once
+const BATCH_SIZE: u64 = 50_000;
+
+/// Position of a tile in the grid, the tile `(0, 0)` starts at the origin of the grid
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileKey {
+    pub column: i64,
+    pub row: i64,
+}
+
+impl TileKey {
+    pub fn new(column: i64, row: i64) -> Self {
+        Self { column, row }
+    }
+
+    /// Returns the bounds of the tile without its buffer,
+    /// the z axis is not bounded.
+    pub fn bounds(&self, options: &TilingOptions) -> Bounds {
+        let min_x = options.origin[0] + self.column as f64 * options.tile_size;
+        let min_y = options.origin[1] + self.row as f64 * options.tile_size;
+        Bounds::new(
+            [min_x, min_y, f64::NEG_INFINITY],
+            [
+                min_x + options.tile_size,
+                min_y + options.tile_size,
+                f64::INFINITY,
+            ],
+        )
+    }
+}
+
+/// Bits of a byte of the point records, that mark the points
+/// given to a tile only because they are in its buffer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BufferFlag {
+    /// Offset of the byte in the point record
+    pub offset: usize,
+    pub mask: u8,
+}
+
+impl BufferFlag {
+    /// The withheld flag of the point formats 0 to 5
+    pub const WITHHELD_LEGACY: BufferFlag = BufferFlag {
+        offset: 15,
+        mask: 0x80,
+    };
+    /// The withheld flag of the point formats 6 to 10
+    pub const WITHHELD_EXTENDED: BufferFlag = BufferFlag {
+        offset: 15,
+        mask: 0x04,
+    };
+
+    pub fn is_set(&self, point: &[u8]) -> bool {
+        point[self.offset] & self.mask != 0
+    }
+
+    fn set(&self, point: &mut [u8]) {
+        point[self.offset] |= self.mask;
+    }
+}
+
+/// How the points are split in tiles
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TilingOptions {
+    tile_size: f64,
+    buffer: f64,
+    buffer_flag: Option<BufferFlag>,
+    origin: [f64; 2],
+    max_open_outputs: usize,
+}
+
+impl TilingOptions {
+    /// Tiles of `tile_size` by `tile_size`, in the units of the coordinates,
+    /// starting at `(0, 0)`, without buffer
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `tile_size` is not strictly positive and finite.
+    pub fn new(tile_size: f64) -> Self {
+        assert!(
+            tile_size > 0.0 && tile_size.is_finite(),
+            "the tile size must be positive"
+        );
+        Self {
+            tile_size,
+            buffer: 0.0,
+            buffer_flag: None,
+            origin: [0.0, 0.0],
+            max_open_outputs: 64,
+        }
+    }
+
+    /// Also gives to each tile the points that are at most at `buffer`
+    /// from its bounds, on the x & y axes.
+    ///
+    /// These points are in several tiles.
+    pub fn with_buffer(mut self, buffer: f64) -> Self {
+        self.buffer = buffer.max(0.0);
+        self
+    }
+
+    /// Sets the `flag` of the points given to a tile only because they are in its buffer,
+    /// so that [`TileMerger`] can drop them with [`Duplicates::Flagged`].
+    ///
+    /// The points that already have the flag set in the inputs are dropped too
+    /// when merging.
+    pub fn with_buffer_flag(mut self, flag: BufferFlag) -> Self {
+        self.buffer_flag = Some(flag);
+        self
+    }
+
+    /// Moves the corner of the tile `(0, 0)` to the `origin`
+    pub fn with_origin(mut self, origin: [f64; 2]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Keeps at most `max_open_outputs` outputs (at least 1) open at the same time,
+    /// see [`Tiler`].
+    pub fn with_max_open_outputs(mut self, max_open_outputs: usize) -> Self {
+        self.max_open_outputs = max_open_outputs.max(1);
+        self
+    }
+
+    pub fn tile_size(&self) -> f64 {
+        self.tile_size
+    }
+
+    pub fn buffer(&self) -> f64 {
+        self.buffer
+    }
+
+    pub fn origin(&self) -> [f64; 2] {
+        self.origin
+    }
+
+    pub fn buffer_flag(&self) -> Option<BufferFlag> {
+        self.buffer_flag
+    }
+
+    /// Returns the column, or row, of the tile that contains the `coordinate` of the `axis`
+    fn cell_of(&self, coordinate: f64, axis: usize) -> i64 {
+        ((coordinate - self.origin[axis]) / self.tile_size).floor() as i64
+    }
+
+    /// Returns the range of the columns, or rows, whose tiles
+    /// (with their buffer) contain the `coordinate` of the `axis`
+    fn cells_of(&self, coordinate: f64, axis: usize) -> std::ops::RangeInclusive<i64> {
+        self.cell_of(coordinate - self.buffer, axis)..=self.cell_of(coordinate + self.buffer, axis)
+    }
+}
+
+/// Description of one output of the [`Tiler`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TileOutput {
+    pub tile: TileKey,
+    /// Index of the output among the ones of the same tile,
+    /// greater than 0 when the previous one was closed to respect the open outputs limit
+    pub part: u32,
+    pub num_points: u64,
+}
+
+/// Where the [`Tiler`] writes the points
+pub trait TileDestination {
+    type Output: Write + Seek + Send;
+
+    /// Opens the output for the `part` of the points of the `tile`
+    fn open(&mut self, tile: TileKey, part: u32) -> std::io::Result<Self::Output>;
+
+    /// Called once all the points of the `output` are compressed into `dst`,
+    /// (e.g. to write the LAS header, that needs the number of points).
+    fn close(&mut self, output: TileOutput, dst: Self::Output) -> std::io::Result<()>;
+}
+
+struct OpenTile<'a, W: Write + Seek + Send + 'a> {
+    compressor: LasZipCompressor<'a, W>,
+    output: TileOutput,
+    last_used: u64,
+}
+
+/// Splits the points of one or more inputs in a regular grid of tiles,
+/// and compresses the points of each tile in its own output.
+///
+/// The points are streamed: only the points being decompressed and the
+/// compressors of the open outputs are kept in memory.
+/// When a point goes to a tile whose output is not open and `max_open_outputs`
+/// are already open, the least recently used one is closed, and its tile gets
+/// a new part if more points go to it later.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::tiler::{TileDestination, TileKey, TileOutput, Tiler, TilingOptions};
+/// use laz::laszip::CoordinateTransform;
+///
+/// #[derive(Default)]
+/// struct InMemory(Vec<(TileOutput, Vec<u8>)>);
+///
+/// impl TileDestination for InMemory {
+///     type Output = Cursor<Vec<u8>>;
+///
+///     fn open(&mut self, _: TileKey, _: u32) -> std::io::Result<Self::Output> {
+///         Ok(Cursor::new(Vec::new()))
+///     }
+///
+///     fn close(&mut self, output: TileOutput, dst: Self::Output) -> std::io::Result<()> {
+///         self.0.push((output, dst.into_inner()));
+///         Ok(())
+///     }
+/// }
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).build();
+/// let mut points = vec![0u8; 20 * 2];
+/// // x = 150
+/// points[20..24].copy_from_slice(&150i32.to_le_bytes());
+///
+/// let mut tiler = Tiler::new(vlr, TilingOptions::new(100.0), InMemory::default());
+/// tiler.add_points(&points, &CoordinateTransform::default())?;
+/// let (destination, outputs) = tiler.finish()?;
+/// assert_eq!(outputs.len(), 2);
+/// assert_eq!(destination.0[1].0.tile, TileKey::new(1, 0));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Tiler<'a, D: TileDestination>
+where
+    D::Output: 'a,
+{
+    vlr: LazVlr,
+    options: TilingOptions,
+    destination: D,
+    open_tiles: HashMap<TileKey, OpenTile<'a, D::Output>>,
+    next_parts: HashMap<TileKey, u32>,
+    closed: Vec<TileOutput>,
+    num_used: u64,
+    /// Copy of the point being added, with the buffer flag set
+    flagged_point: Vec<u8>,
+}
+
+impl<'a, D: TileDestination> Tiler<'a, D>
+where
+    D::Output: 'a,
+{
+    /// Creates a tiler that compresses the tiles as described by the `vlr`
+    pub fn new(vlr: LazVlr, options: TilingOptions, destination: D) -> Self {
+        Self {
+            vlr,
+            options,
+            destination,
+            open_tiles: HashMap::new(),
+            next_parts: HashMap::new(),
+            closed: Vec::new(),
+            num_used: 0,
+            flagged_point: Vec::new(),
+        }
+    }
+
+    pub fn options(&self) -> &TilingOptions {
+        &self.options
+    }
+
+    /// Adds the uncompressed `points`, whose coordinates are given by the `transform`,
+    /// to their tiles.
+    ///
+    /// The points must have the layout described by the `vlr` of the tiler.
+    pub fn add_points(
+        &mut self,
+        points: &[u8],
+        transform: &CoordinateTransform,
+    ) -> crate::Result<()> {
+        let point_size = usize_from(self.vlr.items_size())?;
+        for point in points.chunks_exact(point_size) {
+            let [x, y, _] = transform.coordinates_of(point);
+            let own_tile = TileKey::new(self.options.cell_of(x, 0), self.options.cell_of(y, 1));
+            for column in self.options.cells_of(x, 0) {
+                for row in self.options.cells_of(y, 1) {
+                    let tile = TileKey { column, row };
+                    match self.options.buffer_flag {
+                        Some(flag) if tile != own_tile => {
+                            let mut flagged_point = std::mem::take(&mut self.flagged_point);
+                            flagged_point.clear();
+                            flagged_point.extend_from_slice(point);
+                            flag.set(&mut flagged_point);
+                            let result = self.add_point(tile, &flagged_point);
+                            self.flagged_point = flagged_point;
+                            result?;
+                        }
+                        _ => self.add_point(tile, point)?,
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decompresses the next `num_points` points of the `decompressor`
+    /// and adds them to their tiles, see [`Self::add_points`].
+    ///
+    /// Giving a [`ParLasZipDecompressor`](crate::ParLasZipDecompressor)
+    /// decompresses several chunks in parallel.
+    pub fn add_input<Dec: LazDecompressor>(
+        &mut self,
+        decompressor: &mut Dec,
+        num_points: u64,
+        transform: &CoordinateTransform,
+    ) -> crate::Result<()> {
+        let point_size = usize_from(self.vlr.items_size())?;
+        let mut points = Vec::<u8>::new();
+        let mut points_left = num_points;
+        while points_left > 0 {
+            let n = points_left.min(BATCH_SIZE);
+            points.resize(usize_from(n)? * point_size, 0u8);
+            decompressor.decompress_many(&mut points)?;
+            points_left -= n;
+            self.add_points(&points, transform)?;
+        }
+        Ok(())
+    }
+
+    /// Closes all the outputs, and returns the destination and
+    /// the description of all the outputs, in the order they were closed.
+    pub fn finish(mut self) -> crate::Result<(D, Vec<TileOutput>)> {
+        let mut open_tiles = self
+            .open_tiles
+            .drain()
+            .map(|(_, open)| open)
+            .collect::<Vec<_>>();
+        open_tiles.sort_by_key(|open| open.last_used);
+        for open in open_tiles {
+            let output = close_tile(open, &mut self.destination)?;
+            self.closed.push(output);
+        }
+        Ok((self.destination, self.closed))
+    }
+
+    fn add_point(&mut self, tile: TileKey, point: &[u8]) -> crate::Result<()> {
+        if !self.open_tiles.contains_key(&tile) {
+            if self.open_tiles.len() == self.options.max_open_outputs {
+                let least_recently_used = self
+                    .open_tiles
+                    .iter()
+                    .min_by_key(|(_, open)| open.last_used)
+                    .map(|(&tile, _)| tile)
+                    .unwrap();
+                let open = self.open_tiles.remove(&least_recently_used).unwrap();
+                let output = close_tile(open, &mut self.destination)?;
+                self.closed.push(output);
+            }
+            let part = self.next_parts.entry(tile).or_insert(0);
+            let dst = self.destination.open(tile, *part)?;
+            let output = TileOutput {
+                tile,
+                part: *part,
+                num_points: 0,
+            };
+            *part += 1;
+            let open = OpenTile {
+                compressor: LasZipCompressor::new(dst, self.vlr.clone())?,
+                output,
+                last_used: 0,
+            };
+            self.open_tiles.insert(tile, open);
+        }
+        let open = self.open_tiles.get_mut(&tile).unwrap();
+        open.compressor.compress_one(point)?;
+        open.output.num_points += 1;
+        self.num_used += 1;
+        open.last_used = self.num_used;
+        Ok(())
+    }
+}
+
+/// How [`TileMerger`] finds the points that are in several tiles
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Drops the points whose record is exactly the same as the one of a point
+    /// that was already merged.
+    ///
+    /// All the distinct records are kept in memory.
+    ExactMatch,
+    /// Drops the points that have the flag set, see [`TilingOptions::with_buffer_flag`]
+    Flagged(BufferFlag),
+}
+
+/// Number of points of a [`TileMerger`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct MergeOutput {
+    /// Number of points written to the output
+    pub num_points: u64,
+    /// Number of points dropped as duplicates
+    pub num_duplicates: u64,
+}
+
+/// Merges the points of tiles in one output, dropping the points
+/// that are in several tiles because of their buffer.
+///
+/// It is the inverse of the [`Tiler`], the points are streamed in the same way.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// # use std::io::Cursor;
+/// use laz::laszip::tiler::{Duplicates, TileMerger};
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let vlr = laz::LazVlrBuilder::new(items).build();
+/// let tile_1 = [[0u8; 20], [1u8; 20]].concat();
+/// // The second point was in the buffer of both tiles
+/// let tile_2 = [[2u8; 20], [1u8; 20]].concat();
+///
+/// let dst = Cursor::new(Vec::<u8>::new());
+/// let mut merger = TileMerger::new(dst, vlr, Duplicates::ExactMatch)?;
+/// merger.add_points(&tile_1)?;
+/// merger.add_points(&tile_2)?;
+/// let (_, output) = merger.finish()?;
+/// assert_eq!(output.num_points, 3);
+/// assert_eq!(output.num_duplicates, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TileMerger<'a, W: Write + Seek + Send + 'a> {
+    compressor: LasZipCompressor<'a, W>,
+    point_size: usize,
+    duplicates: Duplicates,
+    merged_records: HashSet<Vec<u8>>,
+    output: MergeOutput,
+}
+
+impl<'a, W: Write + Seek + Send + 'a> TileMerger<'a, W> {
+    /// Creates a merger that compresses the points to the `dst`, as described by the `vlr`
+    pub fn new(dst: W, vlr: LazVlr, duplicates: Duplicates) -> crate::Result<Self> {
+        let point_size = usize_from(vlr.items_size())?;
+        Ok(Self {
+            compressor: LasZipCompressor::new(dst, vlr)?,
+            point_size,
+            duplicates,
+            merged_records: HashSet::new(),
+            output: MergeOutput::default(),
+        })
+    }
+
+    /// Adds the uncompressed `points` of a tile,
+    /// they must have the layout described by the `vlr` of the merger.
+    pub fn add_points(&mut self, points: &[u8]) -> crate::Result<()> {
+        for point in points.chunks_exact(self.point_size) {
+            let is_duplicate = match self.duplicates {
+                Duplicates::ExactMatch => !self.merged_records.insert(point.to_vec()),
+                Duplicates::Flagged(flag) => flag.is_set(point),
+            };
+            if is_duplicate {
+                self.output.num_duplicates += 1;
+            } else {
+                self.compressor.compress_one(point)?;
+                self.output.num_points += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Decompresses the next `num_points` points of the `decompressor`
+    /// and adds them, see [`Self::add_points`].
+    pub fn add_input<Dec: LazDecompressor>(
+        &mut self,
+        decompressor: &mut Dec,
+        num_points: u64,
+    ) -> crate::Result<()> {
+        let mut points = Vec::<u8>::new();
+        let mut points_left = num_points;
+        while points_left > 0 {
+            let n = points_left.min(BATCH_SIZE);
+            points.resize(usize_from(n)? * self.point_size, 0u8);
+            decompressor.decompress_many(&mut points)?;
+            points_left -= n;
+            self.add_points(&points)?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the compression, and returns the output with its number of points
+    pub fn finish(mut self) -> crate::Result<(W, MergeOutput)> {
+        self.compressor.done()?;
+        Ok((self.compressor.into_inner(), self.output))
+    }
+}
+
+fn close_tile<'a, D: TileDestination>(
+    mut open: OpenTile<'a, D::Output>,
+    destination: &mut D,
+) -> crate::Result<TileOutput>
+where
+    D::Output: 'a,
+{
+    open.compressor.done()?;
+    destination.close(open.output, open.compressor.into_inner())?;
+    Ok(open.output)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct InMemory {
+        closed: Vec<(TileOutput, Vec<u8>)>,
+    }
+
+    impl TileDestination for InMemory {
+        type Output = Cursor<Vec<u8>>;
+
+        fn open(&mut self, _: TileKey, _: u32) -> std::io::Result<Self::Output> {
+            Ok(Cursor::new(Vec::new()))
+        }
+
+        fn close(&mut self, output: TileOutput, dst: Self::Output) -> std::io::Result<()> {
+            self.closed.push((output, dst.into_inner()));
+            Ok(())
+        }
+    }
+
+    fn point_at(x: i32, y: i32) -> [u8; 20] {
+        let mut point = [0u8; 20];
+        point[..4].copy_from_slice(&x.to_le_bytes());
+        point[4..8].copy_from_slice(&y.to_le_bytes());
+        point
+    }
+
+    #[test]
+    fn test_tiles_with_buffer() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = crate::LazVlrBuilder::new(items).build();
+        // Coordinates are scaled by 0.1
+        let coordinates = [(50, 50), (995, 50), (1500, 1500), (-5, 500)];
+        let points = coordinates
+            .iter()
+            .flat_map(|&(x, y)| point_at(x, y))
+            .collect::<Vec<u8>>();
+        let mut src = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut src, &points, vlr.clone()).unwrap();
+        src.set_position(0);
+
+        let options = TilingOptions::new(100.0).with_buffer(1.0);
+        let mut tiler = Tiler::new(vlr.clone(), options, InMemory::default());
+        let transform = CoordinateTransform::new([0.1; 3], [0.0; 3]);
+        let mut decompressor = crate::LasZipDecompressor::new(src, vlr.clone()).unwrap();
+        tiler.add_input(&mut decompressor, 4, &transform).unwrap();
+        let (destination, outputs) = tiler.finish().unwrap();
+
+        let mut summary = outputs
+            .iter()
+            .map(|o| (o.tile.column, o.tile.row, o.num_points))
+            .collect::<Vec<_>>();
+        summary.sort();
+        // 99.5 is in the buffer of the column 1, -0.5 in the one of the column 0
+        assert_eq!(summary, vec![(-1, 0, 1), (0, 0, 3), (1, 0, 1), (1, 1, 1)]);
+
+        let (output, compressed) = destination
+            .closed
+            .iter()
+            .find(|(output, _)| output.tile == TileKey::new(0, 0))
+            .unwrap();
+        let mut decompressed = vec![0u8; 20 * output.num_points as usize];
+        crate::decompress_buffer(compressed, &mut decompressed, vlr).unwrap();
+        assert_eq!(decompressed, [&points[..40], &points[60..]].concat());
+    }
+
+    #[test]
+    fn test_tile_and_merge() {
+        let items = crate::LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = crate::LazVlrBuilder::new(items).build();
+        let points = (0..200).flat_map(|i| point_at(i, 25)).collect::<Vec<u8>>();
+
+        let flag = BufferFlag::WITHHELD_LEGACY;
+        for duplicates in [Duplicates::ExactMatch, Duplicates::Flagged(flag)] {
+            let mut options = TilingOptions::new(50.0).with_buffer(5.0);
+            if duplicates != Duplicates::ExactMatch {
+                options = options.with_buffer_flag(flag);
+            }
+            let mut tiler = Tiler::new(vlr.clone(), options, InMemory::default());
+            tiler
+                .add_points(&points, &CoordinateTransform::default())
+                .unwrap();
+            let (destination, _) = tiler.finish().unwrap();
+
+            let dst = Cursor::new(Vec::<u8>::new());
+            let mut merger = TileMerger::new(dst, vlr.clone(), duplicates).unwrap();
+            for (output, compressed) in &destination.closed {
+                let mut decompressor =
+                    crate::LasZipDecompressor::new(Cursor::new(compressed), vlr.clone()).unwrap();
+                merger
+                    .add_input(&mut decompressor, output.num_points)
+                    .unwrap();
+            }
+            let (dst, output) = merger.finish().unwrap();
+            // 10 points around each of the 3 inner boundaries, and 5 at each end
+            assert_eq!(output.num_duplicates, 3 * 10 + 2 * 5);
+            assert_eq!(output.num_points, 200);
+
+            let mut merged = vec![0u8; points.len()];
+            crate::decompress_buffer(dst.get_ref(), &mut merged, vlr.clone()).unwrap();
+            let mut merged = merged.chunks_exact(20).collect::<Vec<_>>();
+            merged
+                .sort_by_key(|point| i32::from_le_bytes([point[0], point[1], point[2], point[3]]));
+            assert_eq!(merged.concat(), points);
+        }
+    }
+}
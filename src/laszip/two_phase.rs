@@ -0,0 +1,314 @@
+//! Compression in two passes: statistics of the points, then the compression
+//! with parameters chosen from them
+use std::convert::TryFrom;
+use std::io::{Seek, Write};
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::packers::Packable;
+use crate::spatial::{Bounds, NodeKey, Quadtree};
+use crate::LazVlrBuilder;
+
+use super::details::usize_from;
+use super::{AdaptiveChunking, LasZipCompressor, LazItem, LazItemType, LazVlr, PointBuffer};
+
+/// Chunk size of the LASzip tools, used when nothing calls for another one
+pub const DEFAULT_CHUNK_SIZE: u32 = 50_000;
+
+/// Points under which all the points are put in a single chunk
+const MIN_POINTS_FOR_CHUNKS: u64 = 2 * DEFAULT_CHUNK_SIZE as u64;
+/// Fraction of consecutive points going forward in time
+/// above which the points are considered to be in acquisition order
+const TIME_ORDERED_FRACTION: f64 = 0.95;
+/// Depth of the quadtree whose leaves order the points when they are sorted
+const SORT_DEPTH: u32 = 16;
+
+/// Statistics of points, computed by [`InputStats::compute`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InputStats {
+    pub num_points: u64,
+    /// Bounds of the x, y & z integer coordinates, `None` when there are no points
+    pub bounds: Option<Bounds>,
+    /// Number of points per square unit of the integer x & y coordinates
+    pub density: f64,
+    /// Fraction of the consecutive points whose GPS time does not decrease,
+    /// `None` when the points have no GPS time
+    pub time_ordered_fraction: Option<f64>,
+}
+
+impl InputStats {
+    /// Computes the statistics of the `points`, described by the `items`
+    pub fn compute(points: &[u8], items: &[LazItem]) -> crate::Result<Self> {
+        let point_size = usize_from(items.iter().map(|item| u64::from(item.size())).sum())?;
+        let points = PointBuffer::with_point_size(points, point_size)?;
+        let gps_time_offset = gps_time_offset(items);
+
+        let mut bounds: Option<Bounds> = None;
+        let mut num_forward = 0u64;
+        let mut last_gps_time = None;
+        for point in points.iter() {
+            let coords = [
+                f64::from(i32::unpack_from(&point[0..4])),
+                f64::from(i32::unpack_from(&point[4..8])),
+                f64::from(i32::unpack_from(&point[8..12])),
+            ];
+            let bounds = bounds.get_or_insert(Bounds::new(coords, coords));
+            for (i, &c) in coords.iter().enumerate() {
+                bounds.min[i] = bounds.min[i].min(c);
+                bounds.max[i] = bounds.max[i].max(c);
+            }
+            if let Some(offset) = gps_time_offset {
+                let gps_time = LittleEndian::read_f64(&point[offset..offset + 8]);
+                if last_gps_time.is_some_and(|last| gps_time >= last) {
+                    num_forward += 1;
+                }
+                last_gps_time = Some(gps_time);
+            }
+        }
+
+        let num_points = points.len() as u64;
+        let density = bounds.map_or(0.0, |b| {
+            // Each point covers at least a square unit
+            let area = ((b.max[0] - b.min[0]) * (b.max[1] - b.min[1])).max(num_points as f64);
+            num_points as f64 / area
+        });
+        let time_ordered_fraction = gps_time_offset.map(|_| {
+            if num_points > 1 {
+                num_forward as f64 / (num_points - 1) as f64
+            } else {
+                1.0
+            }
+        });
+        Ok(Self {
+            num_points,
+            bounds,
+            density,
+            time_ordered_fraction,
+        })
+    }
+
+    /// Returns whether the points seem to be in acquisition order,
+    /// which is the order that compresses best
+    pub fn is_time_ordered(&self) -> bool {
+        self.time_ordered_fraction
+            .is_none_or(|fraction| fraction >= TIME_ORDERED_FRACTION)
+    }
+}
+
+/// How the points are split in chunks
+#[derive(Debug, Copy, Clone)]
+pub enum Chunking {
+    /// Chunks of a fixed number of points
+    Fixed(u32),
+    /// Variable-size chunks, ended when the compression ratio degrades
+    Adaptive(AdaptiveChunking),
+}
+
+/// Parameters of the compression, chosen by [`CompressionPlan::from_stats`]
+#[derive(Debug, Copy, Clone)]
+pub struct CompressionPlan {
+    pub chunking: Chunking,
+    /// Whether the points are sorted spatially before being compressed
+    pub spatial_sort: bool,
+}
+
+impl CompressionPlan {
+    /// Chooses the compression parameters of points that have the `stats`,
+    /// the points are only sorted spatially if `allow_reordering` is true.
+    ///
+    /// - Few points are put in a single chunk.
+    /// - Points in acquisition order are kept in that order, in fixed-size chunks.
+    /// - Other points are sorted spatially if allowed, as neighbouring points
+    ///   compress better, otherwise the chunks are ended when the compression
+    ///   ratio degrades (for the point formats 0 to 5, that support it).
+    pub fn from_stats(stats: &InputStats, items: &[LazItem], allow_reordering: bool) -> Self {
+        if stats.num_points < MIN_POINTS_FOR_CHUNKS {
+            let chunk_size = u32::try_from(stats.num_points).unwrap_or(u32::MAX).max(1);
+            return Self {
+                chunking: Chunking::Fixed(chunk_size),
+                spatial_sort: false,
+            };
+        }
+        if stats.is_time_ordered() {
+            return Self {
+                chunking: Chunking::Fixed(DEFAULT_CHUNK_SIZE),
+                spatial_sort: false,
+            };
+        }
+        let is_layered = items
+            .first()
+            .is_some_and(|item| item.item_type() == LazItemType::Point14);
+        let chunking = if allow_reordering || is_layered {
+            Chunking::Fixed(DEFAULT_CHUNK_SIZE)
+        } else {
+            Chunking::Adaptive(AdaptiveChunking::default())
+        };
+        Self {
+            chunking,
+            spatial_sort: allow_reordering,
+        }
+    }
+
+    /// Returns the vlr to compress points described by the `items` with
+    pub fn laz_vlr(&self, items: Vec<LazItem>) -> LazVlr {
+        let builder = LazVlrBuilder::new(items);
+        match self.chunking {
+            Chunking::Fixed(chunk_size) => builder.with_fixed_chunk_size(chunk_size),
+            Chunking::Adaptive(_) => builder.with_variable_chunk_size(),
+        }
+        .build()
+    }
+}
+
+/// Computes the statistics of the `points`, chooses the compression parameters
+/// (see [`CompressionPlan::from_stats`]) and compresses the points into the `dst`.
+///
+/// Returns the vlr the points were compressed with, to be written in the
+/// LAS header, and the plan that was followed. When the plan sorted the points,
+/// they are compressed in the sorted order, the `points` are not modified.
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use laz::laszip::compress_two_phase;
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let points = [0u8; 20 * 100];
+/// let mut compressed = std::io::Cursor::new(Vec::<u8>::new());
+/// let (vlr, plan) = compress_two_phase(&mut compressed, &points, items, false)?;
+/// assert!(!plan.spatial_sort);
+///
+/// let mut decompressed = vec![0u8; points.len()];
+/// laz::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr)?;
+/// assert_eq!(&decompressed[..], &points[..]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn compress_two_phase<W: Write + Seek + Send>(
+    dst: &mut W,
+    points: &[u8],
+    items: Vec<LazItem>,
+    allow_reordering: bool,
+) -> crate::Result<(LazVlr, CompressionPlan)> {
+    let stats = InputStats::compute(points, &items)?;
+    let plan = CompressionPlan::from_stats(&stats, &items, allow_reordering);
+    let vlr = plan.laz_vlr(items);
+
+    let mut compressor = LasZipCompressor::new(dst, vlr.clone())?;
+    if let Chunking::Adaptive(settings) = plan.chunking {
+        compressor.set_adaptive_chunking(Some(settings));
+    }
+    match (plan.spatial_sort, stats.bounds) {
+        (true, Some(bounds)) => {
+            let point_size = vlr.items_size() as usize;
+            let quadtree = Quadtree::new(bounds, SORT_DEPTH);
+            let mut order = points
+                .chunks_exact(point_size)
+                .map(|point| {
+                    let x = f64::from(i32::unpack_from(&point[0..4]));
+                    let y = f64::from(i32::unpack_from(&point[4..8]));
+                    // The points are all inside the bounds they were computed from
+                    let leaf = quadtree.leaf_of([x, y]).unwrap_or(NodeKey::ROOT);
+                    morton_code(leaf.x, leaf.y)
+                })
+                .enumerate()
+                .collect::<Vec<(usize, u64)>>();
+            order.sort_by_key(|&(_, code)| code);
+            for (index, _) in order {
+                compressor.compress_one(&points[index * point_size..(index + 1) * point_size])?;
+            }
+        }
+        _ => compressor.compress_many(points)?,
+    }
+    compressor.done()?;
+    Ok((vlr, plan))
+}
+
+/// Returns the offset of the GPS time in the points described by the `items`
+fn gps_time_offset(items: &[LazItem]) -> Option<usize> {
+    match items.first()?.item_type() {
+        LazItemType::Point14 => Some(22),
+        LazItemType::Point10 => items
+            .get(1)
+            .filter(|item| item.item_type() == LazItemType::GpsTime)
+            .map(|_| 20),
+        _ => None,
+    }
+}
+
+/// Interleaves the bits of `x` and `y`, so that close cells have close codes
+fn morton_code(x: u32, y: u32) -> u64 {
+    fn spread(v: u32) -> u64 {
+        let mut v = u64::from(v);
+        v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+        v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+        v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+        v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+        v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+        v
+    }
+    spread(x) | (spread(y) << 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LazItemRecordBuilder;
+
+    fn shuffled_points(num_points: u32) -> Vec<u8> {
+        // Points of a grid, with a GPS time that goes back & forth
+        (0..num_points)
+            .flat_map(|i| {
+                let mut point = [0u8; 28];
+                let j = (i.wrapping_mul(7_919)) % num_points;
+                point[0..4].copy_from_slice(&((j % 300) as i32).to_le_bytes());
+                point[4..8].copy_from_slice(&((j / 300) as i32).to_le_bytes());
+                point[20..28].copy_from_slice(&f64::from(j).to_le_bytes());
+                point
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_input_stats() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point1>(0);
+        let points = shuffled_points(3_000);
+        let stats = InputStats::compute(&points, &items).unwrap();
+        assert_eq!(stats.num_points, 3_000);
+        let bounds = stats.bounds.unwrap();
+        assert_eq!(bounds.min, [0.0, 0.0, 0.0]);
+        assert_eq!(bounds.max, [299.0, 9.0, 0.0]);
+        assert!(!stats.is_time_ordered());
+
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let stats = InputStats::compute(&[0u8; 20 * 3], &items).unwrap();
+        assert_eq!(stats.time_ordered_fraction, None);
+        assert!(InputStats::compute(&[0u8; 21], &items).is_err());
+    }
+
+    #[test]
+    fn test_compress_two_phase() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point1>(0);
+        let points = shuffled_points(120_000);
+        let stats = InputStats::compute(&points, &items).unwrap();
+        assert!(matches!(
+            CompressionPlan::from_stats(&stats, &items, false).chunking,
+            Chunking::Adaptive(_)
+        ));
+
+        let mut compressed = std::io::Cursor::new(Vec::<u8>::new());
+        let (vlr, plan) = compress_two_phase(&mut compressed, &points, items, true).unwrap();
+        assert!(plan.spatial_sort);
+        assert!(matches!(plan.chunking, Chunking::Fixed(DEFAULT_CHUNK_SIZE)));
+
+        let mut decompressed = vec![0u8; points.len()];
+        crate::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr).unwrap();
+        assert_ne!(decompressed, points);
+        let mut sorted_points = points.chunks_exact(28).collect::<Vec<_>>();
+        let mut sorted_decompressed = decompressed.chunks_exact(28).collect::<Vec<_>>();
+        sorted_points.sort();
+        sorted_decompressed.sort();
+        assert_eq!(sorted_decompressed, sorted_points);
+    }
+}
@@ -0,0 +1,153 @@
+//! Compressor where the chunking and the end of the compression are part of the type
+use std::io::{Seek, Write};
+use std::marker::PhantomData;
+
+use super::{LasZipCompressor, LazItem, LazVlr, LazVlrBuilder};
+
+/// Marker for a [`TypedLasZipCompressor`] writing fixed-size chunks
+#[derive(Debug, Copy, Clone)]
+pub struct FixedSizeChunks;
+
+/// Marker for a [`TypedLasZipCompressor`] writing variable-size chunks
+#[derive(Debug, Copy, Clone)]
+pub struct VariableSizeChunks;
+
+/// Wrapper around a [`LasZipCompressor`] that turns its runtime contracts into compile-time ones:
+///
+/// - [`done`] consumes the compressor, so no point can be compressed after it,
+///   and it returns the writer, so it cannot be forgotten when the writer is needed.
+/// - Finishing chunks manually is only possible with [`VariableSizeChunks`].
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> laz::Result<()> {
+/// use laz::laszip::TypedLasZipCompressor;
+/// # use std::io::Cursor;
+///
+/// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+/// let mut compressor =
+///     TypedLasZipCompressor::with_variable_size_chunks(Cursor::new(Vec::<u8>::new()), items)?;
+/// compressor.compress_many(&[0u8; 20 * 5])?;
+/// compressor.finish_current_chunk()?;
+/// compressor.compress_many(&[0u8; 20 * 3])?;
+/// let output = compressor.done()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`done`]: Self::done
+#[must_use = "the chunk table is only written when calling `done`"]
+pub struct TypedLasZipCompressor<'a, W: Write + Send + 'a, Chunks> {
+    compressor: LasZipCompressor<'a, W>,
+    chunks: PhantomData<Chunks>,
+}
+
+impl<'a, W: Write + Seek + Send + 'a> TypedLasZipCompressor<'a, W, FixedSizeChunks> {
+    /// Creates a compressor of the `items` that writes chunks of `chunk_size` points.
+    pub fn with_fixed_size_chunks(
+        output: W,
+        items: Vec<LazItem>,
+        chunk_size: u32,
+    ) -> crate::Result<Self> {
+        let vlr = LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(chunk_size)
+            .build();
+        Self::from_vlr(output, vlr)
+    }
+}
+
+impl<'a, W: Write + Seek + Send + 'a> TypedLasZipCompressor<'a, W, VariableSizeChunks> {
+    /// Creates a compressor of the `items` that writes variable-size chunks.
+    pub fn with_variable_size_chunks(output: W, items: Vec<LazItem>) -> crate::Result<Self> {
+        let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+        Self::from_vlr(output, vlr)
+    }
+
+    /// Finishes the current chunk, see [`LasZipCompressor::finish_current_chunk`].
+    pub fn finish_current_chunk(&mut self) -> std::io::Result<()> {
+        self.compressor.finish_current_chunk()
+    }
+
+    /// Compresses the points as one chunk, see [`LasZipCompressor::compress_chunk`].
+    pub fn compress_chunk(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        self.compressor.compress_chunk(chunk)
+    }
+
+    /// Compresses multiple chunks, see [`LasZipCompressor::compress_chunks`].
+    pub fn compress_chunks<Chunks, Item>(&mut self, chunks: Chunks) -> std::io::Result<()>
+    where
+        Item: AsRef<[u8]>,
+        Chunks: IntoIterator<Item = Item>,
+    {
+        self.compressor.compress_chunks(chunks)
+    }
+}
+
+impl<'a, W: Write + Seek + Send + 'a, Chunks> TypedLasZipCompressor<'a, W, Chunks> {
+    fn from_vlr(output: W, vlr: LazVlr) -> crate::Result<Self> {
+        Ok(Self {
+            compressor: LasZipCompressor::new(output, vlr)?,
+            chunks: PhantomData,
+        })
+    }
+
+    /// Compresses one point, see [`LasZipCompressor::compress_one`].
+    pub fn compress_one(&mut self, input: &[u8]) -> std::io::Result<()> {
+        self.compressor.compress_one(input)
+    }
+
+    /// Compresses all the points contained in the `input` slice,
+    /// see [`LasZipCompressor::compress_many`].
+    pub fn compress_many(&mut self, input: &[u8]) -> std::io::Result<()> {
+        self.compressor.compress_many(input)
+    }
+
+    /// Returns the vlr used by this compressor
+    pub fn vlr(&self) -> &LazVlr {
+        self.compressor.vlr()
+    }
+
+    /// Finishes the compression, flushes the writer and returns it,
+    /// see [`LasZipCompressor::finish`].
+    pub fn done(self) -> crate::Result<W> {
+        self.compressor.finish()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        self.compressor.get_mut()
+    }
+
+    pub fn get(&self) -> &W {
+        self.compressor.get()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::las::Point0;
+    use crate::LazItemRecordBuilder;
+
+    #[test]
+    fn test_same_output_as_compressor() {
+        let items = LazItemRecordBuilder::default_version_of::<Point0>(0);
+        let points = (0..200u8).collect::<Vec<u8>>().repeat(10);
+
+        let mut compressor = TypedLasZipCompressor::with_fixed_size_chunks(
+            Cursor::new(Vec::<u8>::new()),
+            items.clone(),
+            7,
+        )
+        .unwrap();
+        compressor.compress_many(&points).unwrap();
+        let vlr = compressor.vlr().clone();
+        let output = compressor.done().unwrap().into_inner();
+
+        let mut expected = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut expected, &points, vlr).unwrap();
+        assert_eq!(output, expected.into_inner());
+    }
+}
@@ -0,0 +1,181 @@
+//! Conversion of points of the legacy point formats (0 to 5)
+//! to points of the extended point formats (6 to 10)
+use crate::las::Point0;
+
+use super::downgrade::{extended_point_size, GPS_TIME_SIZE, POINT14_SIZE, RGB_SIZE};
+
+const NIR_SIZE: usize = 2;
+const WAVEPACKET_SIZE: usize = crate::las::wavepacket::WAVEPACKET_SIZE;
+
+/// Returns the extended point format which the points of the legacy
+/// `point_format_id` are converted to, `None` if the format is not a legacy one.
+///
+/// | legacy | extended |
+/// |--------|----------|
+/// | 0      | 6        |
+/// | 1      | 6        |
+/// | 2      | 7        |
+/// | 3      | 7        |
+/// | 4      | 9        |
+/// | 5      | 10       |
+pub fn extended_point_format_of(point_format_id: u8) -> Option<u8> {
+    match point_format_id {
+        0 | 1 => Some(6),
+        2 | 3 => Some(7),
+        4 => Some(9),
+        5 => Some(10),
+        _ => None,
+    }
+}
+
+fn has_gps_time(point_format_id: u8) -> bool {
+    matches!(point_format_id, 1 | 3 | 4 | 5)
+}
+
+fn has_rgb(point_format_id: u8) -> bool {
+    matches!(point_format_id, 2 | 3 | 5)
+}
+
+fn has_wavepacket(point_format_id: u8) -> bool {
+    matches!(point_format_id, 4 | 5)
+}
+
+/// Returns the size, without extra bytes, of points of the legacy `point_format_id`
+fn legacy_point_size(point_format_id: u8) -> usize {
+    let mut size = Point0::SIZE;
+    if has_gps_time(point_format_id) {
+        size += GPS_TIME_SIZE;
+    }
+    if has_rgb(point_format_id) {
+        size += RGB_SIZE;
+    }
+    if has_wavepacket(point_format_id) {
+        size += WAVEPACKET_SIZE;
+    }
+    size
+}
+
+/// Converts the `src` point of the legacy `point_format_id` into a point of the
+/// extended format given by [`extended_point_format_of`], written into `dst`.
+///
+/// Both points are LAS records in little endian, the extra bytes (everything
+/// after the point format's fields) are copied as-is.
+///
+/// No information is lost, the fields the legacy point does not have are zeroed:
+///
+/// - The scanner channel and the overlap flag are 0.
+/// - The GPS time is 0 for the point formats 0 & 2.
+/// - The NIR is 0 for the point format 5.
+///
+/// The scan angle rank (in degrees) is converted to the scan angle (in 0.006 degrees).
+///
+/// # Panics
+///
+/// Panics if the `point_format_id` is not a legacy one,
+/// or if `src` and `dst` do not have the same number of extra bytes.
+pub fn upgrade_point(point_format_id: u8, src: &[u8], dst: &mut [u8]) {
+    let extended_format =
+        extended_point_format_of(point_format_id).expect("Not a legacy point format");
+    let src_size = legacy_point_size(point_format_id);
+    let dst_size = extended_point_size(extended_format).unwrap();
+    assert_eq!(src.len() - src_size, dst.len() - dst_size);
+
+    // x, y, z & intensity
+    dst[..14].copy_from_slice(&src[..14]);
+
+    let return_number = src[14] & 0x7;
+    let number_of_returns = (src[14] >> 3) & 0x7;
+    dst[14] = return_number | number_of_returns << 4;
+    // synthetic, key-point & withheld are the bits 5, 6 & 7 of the classification,
+    // the scan direction & edge of flight line keep their bits
+    dst[15] = src[15] >> 5 | (src[14] & 0xC0);
+    dst[16] = src[15] & 0x1F;
+    // user data
+    dst[17] = src[17];
+    let scan_angle = (f32::from(src[16] as i8) / 0.006).round() as i16;
+    dst[18..20].copy_from_slice(&scan_angle.to_le_bytes());
+    // point source id
+    dst[20..22].copy_from_slice(&src[18..20]);
+
+    let mut src_offset = Point0::SIZE;
+    if has_gps_time(point_format_id) {
+        dst[22..30].copy_from_slice(&src[src_offset..src_offset + GPS_TIME_SIZE]);
+        src_offset += GPS_TIME_SIZE;
+    } else {
+        dst[22..30].fill(0);
+    }
+
+    let mut dst_offset = POINT14_SIZE;
+    if has_rgb(point_format_id) {
+        dst[dst_offset..dst_offset + RGB_SIZE]
+            .copy_from_slice(&src[src_offset..src_offset + RGB_SIZE]);
+        src_offset += RGB_SIZE;
+        dst_offset += RGB_SIZE;
+        if extended_format == 10 {
+            dst[dst_offset..dst_offset + NIR_SIZE].fill(0);
+            dst_offset += NIR_SIZE;
+        }
+    }
+    if has_wavepacket(point_format_id) {
+        dst[dst_offset..dst_offset + WAVEPACKET_SIZE]
+            .copy_from_slice(&src[src_offset..src_offset + WAVEPACKET_SIZE]);
+    }
+
+    dst[dst_size..].copy_from_slice(&src[src_size..]);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::laszip::downgrade::downgrade_point;
+
+    fn point3(i: u8) -> Vec<u8> {
+        let mut point = vec![0u8; 35];
+        point[..4].copy_from_slice(&i32::from(i).to_le_bytes());
+        point[12..14].copy_from_slice(&300u16.to_le_bytes());
+        // return 2 of 3, edge of flight line
+        point[14] = 2 | 3 << 3 | 0x80;
+        // ground, withheld
+        point[15] = 2 | 0x4 << 5;
+        point[16] = (-30i8) as u8;
+        point[17] = i;
+        point[18..20].copy_from_slice(&7u16.to_le_bytes());
+        point[20..28].copy_from_slice(&f64::from(i).to_le_bytes());
+        point[28..34].copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+        point[34] = 42;
+        point
+    }
+
+    #[test]
+    fn test_upgrade_point() {
+        let src = point3(3);
+        let mut dst = vec![0u8; 37];
+        upgrade_point(3, &src, &mut dst);
+        assert_eq!(dst[..14], src[..14]);
+        assert_eq!(dst[14], 2 | 3 << 4);
+        assert_eq!(dst[15], 0x4 | 0x80);
+        assert_eq!(dst[16], 2);
+        assert_eq!(dst[17], 3);
+        assert_eq!(i16::from_le_bytes([dst[18], dst[19]]), -5000);
+        assert_eq!(dst[20..22], 7u16.to_le_bytes());
+        assert_eq!(dst[22..30], 3f64.to_le_bytes());
+        assert_eq!(dst[30..36], [1, 2, 3, 4, 5, 6]);
+        assert_eq!(dst[36], 42);
+
+        // Going back to the legacy format gives the same point
+        let mut legacy = vec![0u8; 35];
+        downgrade_point(7, &dst, &mut legacy);
+        assert_eq!(legacy, src);
+    }
+
+    #[test]
+    fn test_upgrade_point_without_gps_time() {
+        // point format 2 to 7
+        let src = point3(5);
+        let src = [&src[..20], &src[28..34]].concat();
+        let mut dst = vec![0xFFu8; 36];
+        upgrade_point(2, &src, &mut dst);
+        assert_eq!(dst[22..30], [0u8; 8]);
+        assert_eq!(dst[30..36], [1, 2, 3, 4, 5, 6]);
+    }
+}
@@ -1,9 +1,11 @@
+use std::convert::TryFrom;
 use std::io::{Read, Write};
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::las::nir::Nir;
 use crate::las::pointtypes::RGB;
+use crate::las::wavepacket::WAVEPACKET_SIZE;
 use crate::las::{Point0, Point6};
 use crate::LasZipError;
 
@@ -45,6 +47,7 @@ impl Default for Version {
 
 /// The different type of data / fields found in the definition of LAS points
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum LazItemType {
     /// ExtraBytes for LAS versions <= 1.3 & point format <= 5
     Byte(u16),
@@ -54,16 +57,33 @@ pub enum LazItemType {
     GpsTime,
     /// RGB for LAS versions <= 1.3 & point format <= 5
     RGB12,
-    //WavePacket13,
+    /// Wave packet descriptor for LAS versions <= 1.3 & point format <= 5
+    ///
+    /// Compressed like LASzip does, see [`crate::las::wavepacket`]
+    WavePacket13,
     /// Point14 is the Point format id 6 of LAS for versions >= 1.4 & point format >= 6
     Point14,
     /// RGB for LAS versions >= 1.4
     RGB14,
     /// RGB + Nir for LAS versions >= 1.4
     RGBNIR14,
-    //WavePacket14,
+    /// Wave packet descriptor for LAS versions >= 1.4
+    ///
+    /// Compressed like LASzip does, see [`crate::las::wavepacket`]
+    WavePacket14,
     /// ExtraBytes for LAS versions >= 1.4
     Byte14(u16),
+    /// An item type this crate does not know about.
+    ///
+    /// Only created when reading a vlr with [`LazVlr::read_from_lenient`],
+    /// for inspection: the encoding of such items is not known,
+    /// so (de)compressors fail with [`LasZipError::UnknownLazItem`].
+    Unknown {
+        /// The type code of the item, as written in the vlr
+        type_code: u16,
+        /// The number of bytes of the item
+        size: u16,
+    },
 }
 
 impl LazItemType {
@@ -73,26 +93,29 @@ impl LazItemType {
             6 => Some(LazItemType::Point10),
             7 => Some(LazItemType::GpsTime),
             8 => Some(LazItemType::RGB12),
-            //9 => LazItemType::WavePacket13,
+            9 => Some(LazItemType::WavePacket13),
             10 => Some(LazItemType::Point14),
             11 => Some(LazItemType::RGB14),
             12 => Some(LazItemType::RGBNIR14),
-            //13 => LazItemType::WavePacket14,
+            13 => Some(LazItemType::WavePacket14),
             14 => Some(LazItemType::Byte14(size)),
             _ => None,
         }
     }
 
-    fn size(&self) -> u16 {
+    pub(crate) fn size(&self) -> u16 {
         match self {
             LazItemType::Byte(size) => *size,
             LazItemType::Point10 => Point0::SIZE as u16,
             LazItemType::GpsTime => std::mem::size_of::<f64>() as u16,
             LazItemType::RGB12 => RGB::SIZE as u16,
+            LazItemType::WavePacket13 => WAVEPACKET_SIZE as u16,
             LazItemType::Point14 => Point6::SIZE as u16,
             LazItemType::RGB14 => RGB::SIZE as u16,
             LazItemType::RGBNIR14 => (RGB::SIZE + Nir::SIZE) as u16,
+            LazItemType::WavePacket14 => WAVEPACKET_SIZE as u16,
             LazItemType::Byte14(size) => *size,
+            LazItemType::Unknown { size, .. } => *size,
         }
     }
 
@@ -102,10 +125,13 @@ impl LazItemType {
             LazItemType::Point10 => 2,
             LazItemType::GpsTime => 2,
             LazItemType::RGB12 => 2,
+            LazItemType::WavePacket13 => 1,
             LazItemType::Point14 => 3,
             LazItemType::RGB14 => 3,
             LazItemType::RGBNIR14 => 3,
+            LazItemType::WavePacket14 => 3,
             LazItemType::Byte14(_) => 3,
+            LazItemType::Unknown { .. } => 2,
         }
     }
 }
@@ -117,12 +143,13 @@ impl From<LazItemType> for u16 {
             LazItemType::Point10 => 6,
             LazItemType::GpsTime => 7,
             LazItemType::RGB12 => 8,
-            //LazItemType::WavePacket13 => 9,
+            LazItemType::WavePacket13 => 9,
             LazItemType::Point14 => 10,
             LazItemType::RGB14 => 11,
             LazItemType::RGBNIR14 => 12,
-            //LazItemType::WavePacket14 => 13,
+            LazItemType::WavePacket14 => 13,
             LazItemType::Byte14(_) => 14,
+            LazItemType::Unknown { type_code, .. } => type_code,
         }
     }
 }
@@ -157,15 +184,40 @@ impl LazItem {
         self.size
     }
 
+    /// Returns about how many bytes the models and contexts of the codecs
+    /// of the item take, measured on the codecs of this crate and rounded up.
+    fn codec_state_size(&self) -> u64 {
+        let bytes = u64::from(self.size);
+        match (self.item_type, self.version) {
+            (LazItemType::Point10, 1) => 800_000,
+            (LazItemType::Point10, _) => 2_150_000,
+            (LazItemType::GpsTime, _) => 80_000,
+            (LazItemType::RGB12, _) => 20_000,
+            (LazItemType::Point14, _) => 1_900_000,
+            (LazItemType::RGB14, _) => 100_000,
+            (LazItemType::RGBNIR14, _) => 150_000,
+            (LazItemType::WavePacket13 | LazItemType::WavePacket14, _) => 1_000,
+            // Layered extra bytes have one state per context
+            (LazItemType::Byte14(_), _) | (LazItemType::Unknown { .. }, 3) => bytes * 12_500,
+            (LazItemType::Byte(_), _) | (LazItemType::Unknown { .. }, _) => bytes * 2_500,
+        }
+    }
+
     pub fn version(&self) -> u16 {
         self.version
     }
 
-    fn read_from<R: Read>(src: &mut R) -> crate::Result<Self> {
+    fn read_from<R: Read>(src: &mut R, allow_unknown: bool) -> crate::Result<Self> {
         let item_type = src.read_u16::<LittleEndian>()?;
         let size = src.read_u16::<LittleEndian>()?;
-        let item_type = LazItemType::from_u16(item_type, size)
-            .ok_or_else(|| LasZipError::UnknownLazItem(item_type))?;
+        let item_type = match LazItemType::from_u16(item_type, size) {
+            Some(item_type) => item_type,
+            None if allow_unknown => LazItemType::Unknown {
+                type_code: item_type,
+                size,
+            },
+            None => return Err(LasZipError::UnknownLazItem(item_type)),
+        };
         Ok(Self {
             item_type,
             size,
@@ -208,6 +260,7 @@ define_trait_for_version!(Version3, version_3);
 
 pub struct LazItemRecordBuilder {
     items: Vec<LazItemType>,
+    normalize: bool,
 }
 
 impl LazItemRecordBuilder {
@@ -241,7 +294,7 @@ impl LazItemRecordBuilder {
         point_format_id: u8,
         num_extra_bytes: u16,
     ) -> crate::Result<Vec<LazItem>> {
-        use crate::las::{Point1, Point2, Point3, Point7, Point8};
+        use crate::las::{Point1, Point10, Point2, Point3, Point4, Point5, Point7, Point8, Point9};
         match point_format_id {
             0 => Ok(LazItemRecordBuilder::default_version_of::<Point0>(
                 num_extra_bytes,
@@ -255,6 +308,12 @@ impl LazItemRecordBuilder {
             3 => Ok(LazItemRecordBuilder::default_version_of::<Point3>(
                 num_extra_bytes,
             )),
+            4 => Ok(LazItemRecordBuilder::default_version_of::<Point4>(
+                num_extra_bytes,
+            )),
+            5 => Ok(LazItemRecordBuilder::default_version_of::<Point5>(
+                num_extra_bytes,
+            )),
             6 => Ok(LazItemRecordBuilder::default_version_of::<Point6>(
                 num_extra_bytes,
             )),
@@ -264,12 +323,21 @@ impl LazItemRecordBuilder {
             8 => Ok(LazItemRecordBuilder::default_version_of::<Point8>(
                 num_extra_bytes,
             )),
+            9 => Ok(LazItemRecordBuilder::default_version_of::<Point9>(
+                num_extra_bytes,
+            )),
+            10 => Ok(LazItemRecordBuilder::default_version_of::<Point10>(
+                num_extra_bytes,
+            )),
             _ => Err(LasZipError::UnsupportedPointFormat(point_format_id)),
         }
     }
 
     pub fn new() -> Self {
-        Self { items: vec![] }
+        Self {
+            items: vec![],
+            normalize: false,
+        }
     }
 
     pub fn add_item(&mut self, item_type: LazItemType) -> &mut Self {
@@ -277,8 +345,42 @@ impl LazItemRecordBuilder {
         self
     }
 
+    /// Makes [`Self::build`] put the items in the order mandated by the specification
+    /// (the point item first, then the GPS time, colors and wave packet, the bytes last)
+    /// instead of the order they were added in, other readers reject the items otherwise.
+    ///
+    /// Byte items are also merged into one.
+    ///
+    /// ```
+    /// use laz::{LazItemRecordBuilder, LazItemType};
+    ///
+    /// let items = LazItemRecordBuilder::new()
+    ///     .add_item(LazItemType::Byte(2))
+    ///     .add_item(LazItemType::RGB12)
+    ///     .add_item(LazItemType::Point10)
+    ///     .add_item(LazItemType::Byte(1))
+    ///     .with_normalized_order()
+    ///     .build();
+    /// let types = items.iter().map(|item| item.item_type()).collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     types,
+    ///     vec![LazItemType::Point10, LazItemType::RGB12, LazItemType::Byte(3)]
+    /// );
+    /// ```
+    pub fn with_normalized_order(&mut self) -> &mut Self {
+        self.normalize = true;
+        self
+    }
+
     pub fn build(&self) -> Vec<LazItem> {
-        self.items
+        let normalized;
+        let items = if self.normalize {
+            normalized = normalized_item_types(&self.items);
+            &normalized
+        } else {
+            &self.items
+        };
+        items
             .iter()
             .map(|item_type| {
                 let size = item_type.size();
@@ -293,11 +395,44 @@ impl LazItemRecordBuilder {
     }
 }
 
-fn read_laz_items_from<R: Read>(mut src: &mut R) -> crate::Result<Vec<LazItem>> {
+/// Returns the position of the `item_type` in the order of the specification,
+/// the items of a point record must have increasing ranks.
+pub(crate) fn spec_order_rank(item_type: LazItemType) -> u8 {
+    match item_type {
+        LazItemType::Point10 | LazItemType::Point14 => 0,
+        LazItemType::GpsTime => 1,
+        LazItemType::RGB12 | LazItemType::RGB14 | LazItemType::RGBNIR14 => 2,
+        LazItemType::WavePacket13 | LazItemType::WavePacket14 => 3,
+        LazItemType::Unknown { .. } => 4,
+        LazItemType::Byte(_) | LazItemType::Byte14(_) => 5,
+    }
+}
+
+/// Returns the `items` in the order of the specification, with the byte items merged.
+///
+/// Unknown items keep their relative order, between the known ones and the bytes.
+fn normalized_item_types(items: &[LazItemType]) -> Vec<LazItemType> {
+    let mut sorted = items.to_vec();
+    sorted.sort_by_key(|item_type| spec_order_rank(*item_type));
+    let mut normalized = Vec::<LazItemType>::with_capacity(sorted.len());
+    for item_type in sorted {
+        match (normalized.last_mut(), item_type) {
+            (Some(LazItemType::Byte(size)), LazItemType::Byte(more))
+            | (Some(LazItemType::Byte14(size)), LazItemType::Byte14(more)) => *size += more,
+            _ => normalized.push(item_type),
+        }
+    }
+    normalized
+}
+
+fn read_laz_items_from<R: Read>(
+    mut src: &mut R,
+    allow_unknown: bool,
+) -> crate::Result<Vec<LazItem>> {
     let num_items = src.read_u16::<LittleEndian>()?;
     let mut items = Vec::<LazItem>::with_capacity(num_items as usize);
     for _ in 0..num_items {
-        items.push(LazItem::read_from(&mut src)?)
+        items.push(LazItem::read_from(&mut src, allow_unknown)?)
     }
     Ok(items)
 }
@@ -356,7 +491,7 @@ impl Default for CompressorType {
 /// of the compression/decompression algorithm.
 ///
 /// To create one from scratch, see the [`LazVlrBuilder`]
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct LazVlr {
     // coded on u16
     pub(super) compressor: CompressorType,
@@ -374,8 +509,27 @@ pub struct LazVlr {
     offset_to_special_evlrs: i64,
 
     items: Vec<LazItem>,
+
+    /// Expected number of points, not part of the vlr data
+    points_hint: Option<u64>,
 }
 
+impl PartialEq for LazVlr {
+    fn eq(&self, other: &Self) -> bool {
+        // The points hint is not compared, as it is not part of the vlr data
+        self.compressor == other.compressor
+            && self.coder == other.coder
+            && self.version == other.version
+            && self.options == other.options
+            && self.chunk_size == other.chunk_size
+            && self.number_of_special_evlrs == other.number_of_special_evlrs
+            && self.offset_to_special_evlrs == other.offset_to_special_evlrs
+            && self.items == other.items
+    }
+}
+
+impl Eq for LazVlr {}
+
 impl LazVlr {
     /// The user id of the LasZip VLR header.
     pub const USER_ID: &'static str = "laszip encoded";
@@ -409,26 +563,47 @@ impl LazVlr {
             number_of_special_evlrs: -1,
             offset_to_special_evlrs: -1,
             items,
+            points_hint: None,
         }
     }
 
     /// Tries to read the Vlr information from the record_data source
-    pub fn read_from<R: Read>(mut src: R) -> crate::Result<Self> {
+    pub fn read_from<R: Read>(src: R) -> crate::Result<Self> {
+        Self::read_from_impl(src, false)
+    }
+
+    /// Tries to read the Vlr information from the record_data source,
+    /// without failing on item types this crate does not know about.
+    ///
+    /// Unknown items are read as [`LazItemType::Unknown`] and unknown coders are kept
+    /// (see [`Self::coder`]), this allows to inspect the vlr of files written by newer
+    /// LAZ implementations (e.g. with [`crate::lint`]), but their points cannot be
+    /// compressed nor decompressed.
+    pub fn read_from_lenient<R: Read>(src: R) -> crate::Result<Self> {
+        Self::read_from_impl(src, true)
+    }
+
+    fn read_from_impl<R: Read>(mut src: R, lenient: bool) -> crate::Result<Self> {
         let compressor_type = src.read_u16::<LittleEndian>()?;
         let compressor = match CompressorType::from_u16(compressor_type) {
             Some(c) => c,
             None => return Err(LasZipError::UnknownCompressorType(compressor_type)),
         };
+        let coder = src.read_u16::<LittleEndian>()?;
+        if coder != 0 && !lenient {
+            return Err(LasZipError::UnknownCoder(coder));
+        }
 
         Ok(Self {
             compressor,
-            coder: src.read_u16::<LittleEndian>()?,
+            coder,
             version: Version::read_from(&mut src)?,
             options: src.read_u32::<LittleEndian>()?,
             chunk_size: src.read_u32::<LittleEndian>()?,
             number_of_special_evlrs: src.read_i64::<LittleEndian>()?,
             offset_to_special_evlrs: src.read_i64::<LittleEndian>()?,
-            items: read_laz_items_from(&mut src)?,
+            items: read_laz_items_from(&mut src, lenient)?,
+            points_hint: None,
         })
     }
 
@@ -436,6 +611,58 @@ impl LazVlr {
         Self::read_from(buffer.as_ref())
     }
 
+    /// Same as [`Self::from_buffer`] but does not fail on unknown item types,
+    /// see [`Self::read_from_lenient`].
+    pub fn from_buffer_lenient<T: AsRef<[u8]>>(buffer: T) -> crate::Result<Self> {
+        Self::read_from_lenient(buffer.as_ref())
+    }
+
+    /// Reads the whole Vlr, that is the 54 bytes of the LAS VLR header
+    /// followed by the *record_data*.
+    ///
+    /// Returns an error if the user id and record id of the header
+    /// are not the ones of the LasZip VLR.
+    pub fn read_full_vlr_from<R: Read>(mut src: R) -> crate::Result<Self> {
+        let _reserved = src.read_u16::<LittleEndian>()?;
+        let mut user_id = [0u8; 16];
+        src.read_exact(&mut user_id)?;
+        let record_id = src.read_u16::<LittleEndian>()?;
+        let record_length = src.read_u16::<LittleEndian>()?;
+        let mut description = [0u8; 32];
+        src.read_exact(&mut description)?;
+
+        if user_id != padded_string::<16>(Self::USER_ID) || record_id != Self::RECORD_ID {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "the VLR is not the LasZip VLR",
+            )
+            .into());
+        }
+        let mut record_data = vec![0u8; usize::from(record_length)];
+        src.read_exact(&mut record_data)?;
+        Self::from_buffer(record_data)
+    }
+
+    /// Writes the whole Vlr, that is the 54 bytes of the LAS VLR header
+    /// followed by the *record_data*.
+    pub fn write_full_vlr_to<W: Write>(&self, mut dst: W) -> std::io::Result<()> {
+        let mut record_data = Vec::new();
+        self.write_to(&mut record_data)?;
+        let record_length = u16::try_from(record_data.len()).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the record data of the VLR is too big",
+            )
+        })?;
+
+        dst.write_u16::<LittleEndian>(0)?;
+        dst.write_all(&padded_string::<16>(Self::USER_ID))?;
+        dst.write_u16::<LittleEndian>(Self::RECORD_ID)?;
+        dst.write_u16::<LittleEndian>(record_length)?;
+        dst.write_all(&padded_string::<32>(Self::DESCRIPTION))?;
+        dst.write_all(&record_data)
+    }
+
     /// Writes the Vlr to the source.
     ///
     /// This **only** write the *record_data* the
@@ -452,6 +679,38 @@ impl LazVlr {
         Ok(())
     }
 
+    /// Returns whether the points of the chunks described by this vlr are encoded
+    /// the same way as the ones described by `other`.
+    ///
+    /// The items (type, size and version), the compressor and the coder are compared,
+    /// while the chunk size, the version of the library that wrote the vlr, the options
+    /// and the special EVLRs are ignored.
+    ///
+    /// Compatible vlrs mean that chunks of the two files can be merged without
+    /// re-encoding the points (if the chunk sizes differ, the merged chunks have to be
+    /// described as variable-size chunks), and that a record (de)compressor made for
+    /// one can be reused for the other.
+    pub fn compatible_with(&self, other: &LazVlr) -> bool {
+        self.compressor == other.compressor
+            && self.coder == other.coder
+            && self.items == other.items
+    }
+
+    /// Returns the offset and the number of the special EVLRs,
+    /// that hold the laszip data like the chunk table, `None` if they are not used.
+    ///
+    /// The offset is from the start of the file.
+    pub fn special_evlrs(&self) -> Option<(u64, u64)> {
+        if self.number_of_special_evlrs > 0 && self.offset_to_special_evlrs > 0 {
+            Some((
+                self.offset_to_special_evlrs as u64,
+                self.number_of_special_evlrs as u64,
+            ))
+        } else {
+            None
+        }
+    }
+
     #[inline]
     /// Returns whether the chunk size is variable.
     pub fn uses_variable_size_chunks(&self) -> bool {
@@ -466,6 +725,20 @@ impl LazVlr {
         self.chunk_size
     }
 
+    /// Returns how the compressed data is organized
+    #[inline]
+    pub fn compressor(&self) -> CompressorType {
+        self.compressor
+    }
+
+    /// Returns the entropy coder of the points, 0 is the arithmetic coder.
+    ///
+    /// The arithmetic coder is the only one LAZ defines and this crate implements,
+    /// vlrs with other coders are only read by [`Self::read_from_lenient`].
+    pub fn coder(&self) -> u16 {
+        self.coder
+    }
+
     /// Returns the items compressed by this VLR
     #[inline]
     pub fn items(&self) -> &Vec<LazItem> {
@@ -479,6 +752,163 @@ impl LazVlr {
         u64::from(self.items.iter().map(|item| item.size).sum::<u16>())
     }
 
+    /// Returns whether the items of this vlr describe points of the
+    /// given LAS `point_format_id` with `num_extra_bytes` extra bytes.
+    ///
+    /// This only looks at the types of the items, not their version.
+    pub fn is_compatible_with_point_format(
+        &self,
+        point_format_id: u8,
+        num_extra_bytes: u16,
+    ) -> bool {
+        use LazItemType::*;
+        let (mut expected, extra_bytes) = match point_format_id {
+            0 => (vec![Point10], Byte(num_extra_bytes)),
+            1 => (vec![Point10, GpsTime], Byte(num_extra_bytes)),
+            2 => (vec![Point10, RGB12], Byte(num_extra_bytes)),
+            3 => (vec![Point10, GpsTime, RGB12], Byte(num_extra_bytes)),
+            4 => (vec![Point10, GpsTime, WavePacket13], Byte(num_extra_bytes)),
+            5 => (
+                vec![Point10, GpsTime, RGB12, WavePacket13],
+                Byte(num_extra_bytes),
+            ),
+            6 => (vec![Point14], Byte14(num_extra_bytes)),
+            7 => (vec![Point14, RGB14], Byte14(num_extra_bytes)),
+            8 => (vec![Point14, RGBNIR14], Byte14(num_extra_bytes)),
+            9 => (vec![Point14, WavePacket14], Byte14(num_extra_bytes)),
+            10 => (
+                vec![Point14, RGBNIR14, WavePacket14],
+                Byte14(num_extra_bytes),
+            ),
+            _ => return false,
+        };
+        if num_extra_bytes > 0 {
+            expected.push(extra_bytes);
+        }
+
+        self.items.len() == expected.len()
+            && self
+                .items
+                .iter()
+                .zip(expected)
+                .all(|(item, expected)| item.item_type == expected && item.size == expected.size())
+    }
+
+    /// Returns the LAS point format id and the number of extra bytes
+    /// of the points described by the items of this vlr, if they describe one.
+    pub(crate) fn point_format(&self) -> Option<(u8, u16)> {
+        let num_extra_bytes = match self.items.last()?.item_type {
+            LazItemType::Byte(size) | LazItemType::Byte14(size) => size,
+            _ => 0,
+        };
+        (0..=10u8)
+            .find(|&id| self.is_compatible_with_point_format(id, num_extra_bytes))
+            .map(|id| (id, num_extra_bytes))
+    }
+
+    /// Returns a copy of this vlr where the items are compressed in layers
+    /// (item version 3), the chunk size is kept.
+    ///
+    /// LAZ only defines the layered compression for the items of the point formats 6 to 10,
+    /// so the items of the point formats 0 to 5 are replaced by the items of the extended
+    /// point format given by [`extended_point_format_of`], e.g. `Point10`, `GpsTime`
+    /// & `RGB12` become `Point14` & `RGB14`.
+    /// The points then have to be converted with [`upgrade_point`], which
+    /// [`reencode`](crate::laszip::reencode) does.
+    ///
+    /// Fails with [`LasZipError::UnsupportedLazItemVersion`] if the items do not
+    /// describe one of the LAS point formats (e.g. unknown items).
+    ///
+    /// [`extended_point_format_of`]: crate::laszip::extended_point_format_of
+    /// [`upgrade_point`]: crate::laszip::upgrade_point
+    pub fn to_layered(&self) -> crate::Result<Self> {
+        let (point_format_id, num_extra_bytes) = self.point_format().ok_or_else(|| {
+            let item_type = self
+                .items
+                .iter()
+                .find(|item| matches!(item.item_type, LazItemType::Unknown { .. }))
+                .or_else(|| self.items.first())
+                .map_or(LazItemType::Point14, |item| item.item_type);
+            LasZipError::UnsupportedLazItemVersion(item_type, 3)
+        })?;
+        let point_format_id =
+            super::extended_point_format_of(point_format_id).unwrap_or(point_format_id);
+        Ok(Self {
+            compressor: CompressorType::LayeredChunked,
+            items: LazItemRecordBuilder::default_for_point_format_id(
+                point_format_id,
+                num_extra_bytes,
+            )?,
+            ..self.clone()
+        })
+    }
+
+    /// Returns the expected number of points to be compressed,
+    /// given with [`LazVlrBuilder::with_points_hint`].
+    ///
+    /// The hint is not part of the vlr data, so it is `None` for vlrs that were read.
+    pub fn points_hint(&self) -> Option<u64> {
+        self.points_hint
+    }
+
+    /// Returns the expected number of chunks, if a points hint was given
+    /// and the chunks are fixed-size.
+    pub(crate) fn num_chunks_hint(&self) -> Option<usize> {
+        if self.uses_variable_size_chunks() {
+            return None;
+        }
+        self.points_hint
+            .map(|n| (n.div_ceil(u64::from(self.chunk_size.max(1)))) as usize)
+    }
+
+    /// Returns an estimation of the peak number of bytes a (de)compressor of the points
+    /// described by this vlr needs, when `parallelism` chunks are processed at the same time
+    /// (1 for the sequential [`LasZipCompressor`] and [`LasZipDecompressor`]).
+    ///
+    /// For each chunk in-flight, this counts a buffer of its points, one of its compressed
+    /// data (assumed to be at most as big), and the state of the codecs,
+    /// then the chunk table is added, when the number of chunks is known from
+    /// the [`points_hint`](Self::points_hint).
+    ///
+    /// The size of variable-size chunks is only bounded by the number of points,
+    /// so `None` is returned for them when there is no points hint.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point6>(0);
+    /// let vlr = laz::LazVlrBuilder::new(items).with_fixed_chunk_size(50_000).build();
+    /// let sequential = vlr.max_memory_usage(1).unwrap();
+    /// assert!(sequential > 2 * 50_000 * 30);
+    /// assert_eq!(vlr.max_memory_usage(4), Some(4 * sequential));
+    /// ```
+    ///
+    /// [`LasZipCompressor`]: crate::LasZipCompressor
+    /// [`LasZipDecompressor`]: crate::LasZipDecompressor
+    pub fn max_memory_usage(&self, parallelism: usize) -> Option<u64> {
+        let chunk_points = match (self.uses_variable_size_chunks(), self.points_hint) {
+            (false, Some(num_points)) => u64::from(self.chunk_size).min(num_points),
+            (false, None) => u64::from(self.chunk_size),
+            (true, num_points) => num_points?,
+        };
+        let chunk_bytes = chunk_points.saturating_mul(self.items_size());
+        let codecs_state = self
+            .items
+            .iter()
+            .map(LazItem::codec_state_size)
+            .sum::<u64>();
+        let table_bytes = self.num_chunks_hint().map_or(0, |num_chunks| {
+            super::ChunkTable::memory_size(num_chunks) as u64
+        });
+        Some(
+            chunk_bytes
+                .saturating_mul(2)
+                .saturating_add(codecs_state)
+                .saturating_mul(parallelism.max(1) as u64)
+                .saturating_add(table_bytes),
+        )
+    }
+
     /// returns how many bytes a decompressed chunk contains
     #[cfg(feature = "parallel")]
     #[inline]
@@ -508,18 +938,14 @@ impl LazVlr {
 /// # Ok(())
 /// # }
 /// ```
+#[derive(Default)]
 pub struct LazVlrBuilder {
     items: Vec<LazItem>,
-    chunk_size: u32,
-}
-
-impl Default for LazVlrBuilder {
-    fn default() -> Self {
-        Self {
-            items: vec![],
-            chunk_size: DEFAULT_CHUNK_SIZE as u32,
-        }
-    }
+    /// `None` means the default chunk size, or the one chosen from the points hint
+    chunk_size: Option<u32>,
+    points_hint: Option<u64>,
+    /// Whether the items are given their default version
+    default_versions: bool,
 }
 
 impl LazVlrBuilder {
@@ -546,18 +972,51 @@ impl LazVlrBuilder {
     }
 
     pub fn with_fixed_chunk_size(mut self, chunk_size: u32) -> Self {
-        self.chunk_size = chunk_size;
+        self.chunk_size = Some(chunk_size);
         self
     }
 
     pub fn with_variable_chunk_size(mut self) -> Self {
-        self.chunk_size = LazVlr::VARIABLE_CHUNK_SIZE;
+        self.chunk_size = Some(LazVlr::VARIABLE_CHUNK_SIZE);
         self
     }
 
-    pub fn build(self) -> LazVlr {
+    /// Uses the chunk size of the `profile`, and the default versions
+    /// of the items, see [`CompressionProfile`]
+    ///
+    /// [`CompressionProfile`]: super::CompressionProfile
+    pub fn with_profile(mut self, profile: super::CompressionProfile) -> Self {
+        self.default_versions = true;
+        self.with_fixed_chunk_size(profile.chunk_size())
+    }
+
+    /// Gives the number of points that are expected to be compressed,
+    /// so that the compressors can preallocate what they need.
+    ///
+    /// Unless a chunk size is given, the chunk size is chosen so that the points
+    /// are split in chunks of equal sizes, of at most 50 000 points.
+    pub fn with_points_hint(mut self, num_points: u64) -> Self {
+        self.points_hint = Some(num_points);
+        self
+    }
+
+    pub fn build(mut self) -> LazVlr {
+        if self.default_versions {
+            for item in &mut self.items {
+                item.version = item.item_type.default_version();
+            }
+        }
         let mut vlr = LazVlr::from_laz_items(self.items);
-        vlr.chunk_size = self.chunk_size;
+        vlr.chunk_size = match (self.chunk_size, self.points_hint) {
+            (Some(chunk_size), _) => chunk_size,
+            (None, Some(num_points)) if num_points > 0 => {
+                let max_chunk_size = DEFAULT_CHUNK_SIZE as u64;
+                let num_chunks = num_points.div_ceil(max_chunk_size);
+                num_points.div_ceil(num_chunks) as u32
+            }
+            (None, _) => DEFAULT_CHUNK_SIZE as u32,
+        };
+        vlr.points_hint = self.points_hint;
         vlr
     }
 
@@ -574,3 +1033,213 @@ impl LazVlrBuilder {
         Self::new(laz_items)
     }
 }
+
+/// Returns the bytes of the `string` padded with zeros
+fn padded_string<const N: usize>(string: &str) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes[..string.len()].copy_from_slice(string.as_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{LasZipCompressor, LasZipDecompressor};
+
+    #[test]
+    fn test_is_compatible_with_point_format() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(3, 2)
+            .unwrap()
+            .build();
+        assert!(vlr.is_compatible_with_point_format(3, 2));
+        assert!(!vlr.is_compatible_with_point_format(3, 0));
+        assert!(!vlr.is_compatible_with_point_format(2, 2));
+        assert!(!vlr.is_compatible_with_point_format(7, 2));
+
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(8, 0)
+            .unwrap()
+            .build();
+        assert!(vlr.is_compatible_with_point_format(8, 0));
+        assert!(!vlr.is_compatible_with_point_format(7, 0));
+        assert!(!vlr.is_compatible_with_point_format(8, 1));
+        assert!(!vlr.is_compatible_with_point_format(11, 0));
+
+        let vlr = LazVlr::from_laz_items(
+            LazItemRecordBuilder::new()
+                .add_item(LazItemType::Point14)
+                .add_item(LazItemType::WavePacket14)
+                .build(),
+        );
+        assert!(vlr.is_compatible_with_point_format(9, 0));
+    }
+
+    #[test]
+    fn test_points_hint() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items.clone())
+            .with_points_hint(120_000)
+            .build();
+        assert_eq!(vlr.chunk_size(), 40_000);
+        assert_eq!(vlr.points_hint(), Some(120_000));
+        assert_eq!(vlr.num_chunks_hint(), Some(3));
+
+        let vlr = LazVlrBuilder::new(items.clone())
+            .with_points_hint(1_000)
+            .build();
+        assert_eq!(vlr.chunk_size(), 1_000);
+
+        let vlr = LazVlrBuilder::new(items.clone())
+            .with_fixed_chunk_size(10)
+            .with_points_hint(1_000)
+            .build();
+        assert_eq!(vlr.chunk_size(), 10);
+        assert_eq!(vlr.num_chunks_hint(), Some(100));
+
+        let mut data = Cursor::new(Vec::<u8>::new());
+        vlr.write_to(&mut data).unwrap();
+        let read_vlr = LazVlr::from_buffer(data.get_ref()).unwrap();
+        assert_eq!(read_vlr.points_hint(), None);
+        assert_eq!(read_vlr, vlr);
+
+        let vlr = LazVlrBuilder::new(items).with_points_hint(0).build();
+        assert_eq!(vlr.chunk_size(), 50_000);
+    }
+
+    #[test]
+    fn test_unknown_coder() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(0);
+        let vlr = LazVlrBuilder::new(items).build();
+        assert_eq!(vlr.coder(), 0);
+        let mut data = Cursor::new(Vec::<u8>::new());
+        vlr.write_to(&mut data).unwrap();
+        let mut data = data.into_inner();
+        data[2] = 1;
+        assert!(matches!(
+            LazVlr::from_buffer(&data),
+            Err(crate::LasZipError::UnknownCoder(1))
+        ));
+
+        // Kept for inspection, but the points cannot be decompressed
+        let vlr = LazVlr::read_from_lenient(data.as_slice()).unwrap();
+        assert_eq!(vlr.coder(), 1);
+        let error = LasZipDecompressor::new(Cursor::new(Vec::<u8>::new()), vlr)
+            .err()
+            .unwrap();
+        assert!(matches!(error, crate::LasZipError::UnknownCoder(1)));
+    }
+
+    #[test]
+    fn test_normalized_item_order() {
+        let items = LazItemRecordBuilder::new()
+            .add_item(LazItemType::Byte14(1))
+            .add_item(LazItemType::RGBNIR14)
+            .add_item(LazItemType::Byte14(4))
+            .add_item(LazItemType::Point14)
+            .with_normalized_order()
+            .build();
+        assert_eq!(
+            items,
+            LazItemRecordBuilder::default_version_of::<crate::las::Point8>(5)
+        );
+
+        // Without it, the order is kept
+        let items = LazItemRecordBuilder::new()
+            .add_item(LazItemType::GpsTime)
+            .add_item(LazItemType::Point10)
+            .build();
+        assert_eq!(items[0].item_type(), LazItemType::GpsTime);
+    }
+
+    #[test]
+    fn test_compatible_with() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(3, 2)
+            .unwrap()
+            .build();
+        let other = LazVlrBuilder::default()
+            .with_point_format(3, 2)
+            .unwrap()
+            .with_variable_chunk_size()
+            .build();
+        assert_ne!(vlr, other);
+        assert!(vlr.compatible_with(&other));
+
+        let other = LazVlrBuilder::default()
+            .with_point_format(3, 1)
+            .unwrap()
+            .build();
+        assert!(!vlr.compatible_with(&other));
+
+        let items = LazItemRecordBuilder::new()
+            .add_item(LazItemType::Point10)
+            .add_item(LazItemType::GpsTime)
+            .add_item(LazItemType::RGB12)
+            .add_item(LazItemType::Byte(2))
+            .build();
+        let mut items_v1 = items.clone();
+        for item in &mut items_v1 {
+            item.version = 1;
+        }
+        assert!(vlr.compatible_with(&LazVlr::from_laz_items(items)));
+        assert!(!vlr.compatible_with(&LazVlr::from_laz_items(items_v1)));
+    }
+
+    #[test]
+    fn test_full_vlr() {
+        let vlr = LazVlrBuilder::default()
+            .with_point_format(7, 3)
+            .unwrap()
+            .build();
+        let mut data = Vec::new();
+        vlr.write_full_vlr_to(&mut data).unwrap();
+        assert_eq!(&data[2..16], LazVlr::USER_ID.as_bytes());
+        assert_eq!(data.len(), 54 + 34 + 6 * vlr.items().len());
+        assert_eq!(LazVlr::read_full_vlr_from(data.as_slice()).unwrap(), vlr);
+
+        data[18] = 0;
+        assert!(LazVlr::read_full_vlr_from(data.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_unknown_item_inspection() {
+        let vlr = super::LazVlr::from_laz_items(
+            LazItemRecordBuilder::new()
+                .add_item(LazItemType::Point10)
+                .add_item(LazItemType::Byte(3))
+                .build(),
+        );
+        let mut vlr_data = Vec::<u8>::new();
+        vlr.write_to(&mut vlr_data).unwrap();
+        // Change the type code of the second item (Byte) to something unknown
+        let type_code_pos = 34 + 6;
+        vlr_data[type_code_pos..type_code_pos + 2].copy_from_slice(&42u16.to_le_bytes());
+
+        assert!(matches!(
+            super::LazVlr::from_buffer(&vlr_data),
+            Err(crate::LasZipError::UnknownLazItem(42))
+        ));
+        let lenient_vlr = super::LazVlr::from_buffer_lenient(&vlr_data).unwrap();
+        assert_eq!(
+            lenient_vlr.items()[1].item_type(),
+            LazItemType::Unknown {
+                type_code: 42,
+                size: 3
+            }
+        );
+        assert_eq!(lenient_vlr.items_size(), vlr.items_size());
+
+        // The encoding of the unknown item is not known
+        let error = LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), lenient_vlr.clone())
+            .err()
+            .unwrap();
+        assert!(matches!(error, crate::LasZipError::UnknownLazItem(42)));
+        let error = LasZipDecompressor::new(Cursor::new(Vec::<u8>::new()), lenient_vlr)
+            .err()
+            .unwrap();
+        assert!(matches!(error, crate::LasZipError::UnknownLazItem(42)));
+    }
+}
@@ -106,16 +106,57 @@
 //!
 //! - [`par_compress_buffer`]
 //! - [`par_decompress_buffer`]
+//!
+//! # Async
+//!
+//! With the optional feature 'tokio', [`laszip::AsyncLasZipCompressor`] and
+//! [`laszip::AsyncLasZipDecompressor`] compress to and decompress from tokio's
+//! async IO traits.
+//!
+//! # Digest
+//!
+//! With the optional feature 'digest', [`LasZipCompressor::set_compute_digest`] computes
+//! the SHA-256 of the uncompressed points, to be stored in a [`laszip::DigestEvlr`].
+//!
+//! # Borrowing the source or destination
+//!
+//! All the compressors and decompressors accept a `&mut` reference to the file,
+//! so that it can be lent for the point data and used afterwards, e.g. to write EVLRs
+//! after [`LasZipCompressor::finish`].
+//!
+//! # Point formats
+//!
+//! The codecs of the point formats can be compiled out to reduce the size of the binary,
+//! they are enabled by default:
+//!
+//! - 'format-legacy': point formats 0 to 5 (LAZ items of version 1 & 2)
+//! - 'format-14': point formats 6 to 10 (LAZ items of version 3)
+//! - 'extra-bytes': the extra bytes of the enabled point formats
+//!
+//! Compressing or decompressing items whose codec is compiled out returns a
+//! [`LasZipError::UnsupportedLazItemVersion`] error.
+#![cfg_attr(
+    not(all(
+        feature = "format-legacy",
+        feature = "format-14",
+        feature = "extra-bytes"
+    )),
+    allow(dead_code, unused_imports)
+)]
 
-pub use errors::LasZipError;
+pub use errors::{LasZipError, LasZipWarning};
 #[cfg(feature = "parallel")]
 pub use laszip::parallel::{
-    par_compress_buffer, par_decompress_buffer, ParLasZipCompressor, ParLasZipDecompressor,
+    par_compress_buffer, par_decompress_buffer, par_decompress_read_at,
+    par_decompress_selected_chunks, par_transcode_las_to_laz, ParLasZipCompressor,
+    ParLasZipDecompressor,
 };
-pub use laszip::{compress_buffer, decompress_buffer};
+pub use laszip::{capabilities, check_support};
+pub use laszip::{compress_buffer, compress_buffer_into, decompress_buffer};
 pub use laszip::{
     LasZipCompressor, LasZipDecompressor, LazCompressor, LazDecompressor, LazItem,
-    LazItemRecordBuilder, LazItemType, LazVlr, LazVlrBuilder,
+    LazItemRecordBuilder, LazItemType, LazVlr, LazVlrBuilder, PointBuffer, PointBufferMut,
+    PointFilter, ReturnFilter,
 };
 
 pub(crate) mod compressors;
@@ -123,6 +164,9 @@ pub(crate) mod decoders;
 pub(crate) mod decompressors;
 pub(crate) mod encoders;
 pub mod laszip;
+pub mod lint;
+#[cfg(feature = "model-stats")]
+pub mod model_stats;
 pub(crate) mod models;
 pub mod packers;
 pub mod record;
@@ -130,6 +174,13 @@ pub mod record;
 mod byteslice;
 pub mod errors;
 pub mod las;
+pub mod spatial;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+#[cfg(feature = "timing")]
+pub mod timing;
+#[cfg(not(feature = "timing"))]
+pub(crate) mod timing;
 
 #[cfg(test)]
 mod test;
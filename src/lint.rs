@@ -0,0 +1,397 @@
+//! Checks of LAZ data against the specification
+//!
+//! Unlike the decompressors, which accept what they can decompress,
+//! the functions of this module report everything that other readers
+//! may reject or misread, so that data providers can fix their files.
+//!
+//! # Example
+//!
+//! ```
+//! # fn main() -> laz::Result<()> {
+//! use laz::lint::{lint_file, Severity};
+//!
+//! let mut laz_file = std::fs::File::open("tests/data/point10.laz")?;
+//! let diagnostics = lint_file(&mut laz_file)?;
+//! for diagnostic in &diagnostics {
+//!     eprintln!("{:?}: {}", diagnostic.severity(), diagnostic);
+//! }
+//! assert!(diagnostics.iter().all(|d| d.severity() != Severity::Error));
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::las::file::{point_format_id_compressed_to_uncompressd, QuickHeader, Vlr};
+use crate::laszip::{check_support, ChunkTable, LazItemType, LazVlr, Unsupported};
+use crate::LasZipError;
+
+/// How serious a [`Diagnostic`] is
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Readers should handle it, but it is unusual
+    Info,
+    /// Some readers may reject the data, or give wrong results
+    Warning,
+    /// The data does not follow the specification
+    Error,
+}
+
+/// An issue found in LAZ data
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Diagnostic {
+    /// The LAS header has no LASzip vlr
+    MissingLaszipVlr,
+    /// The LASzip vlr could not be read
+    LaszipVlrUnreadable(LasZipError),
+    /// The bit of the point format id telling that the points are compressed is not set
+    CompressionBitNotSet,
+    /// The items are not in the order of the specification
+    /// (the point first, then the GPS time, colors, wave packet and the bytes last)
+    ItemOrder,
+    /// The size of an item is not the one its type has
+    ItemSize {
+        item_type: LazItemType,
+        size: u16,
+        expected: u16,
+    },
+    /// The item type is unknown
+    UnknownItem { type_code: u16 },
+    /// This crate cannot decompress the version of the item
+    UnsupportedItem {
+        item_type: LazItemType,
+        version: u16,
+        reason: Unsupported,
+    },
+    /// The item uses the first version of the compression, that LASzip replaced
+    DeprecatedItemVersion {
+        item_type: LazItemType,
+        version: u16,
+    },
+    /// The items do not describe the point format of the header
+    PointFormatMismatch { point_format_id: u8 },
+    /// The size of the points given by the header is not the size of the items
+    PointSizeMismatch { header: u16, items: u64 },
+    /// The chunk table could not be read, readers cannot seek
+    ChunkTableUnreadable(LasZipError),
+    /// The chunk table does not describe the compressed data
+    ChunkTableMismatch { table_bytes: u64, stream_bytes: u64 },
+    /// The number of points of the header is not the one of the chunk table
+    PointCountMismatch { header: u64, chunk_table: u64 },
+    /// The number of fixed-size chunks cannot hold the number of points of the header
+    ChunkCountMismatch { header_points: u64, num_chunks: u64 },
+}
+
+impl Diagnostic {
+    /// Returns how serious the issue is
+    pub fn severity(&self) -> Severity {
+        match self {
+            Diagnostic::CompressionBitNotSet
+            | Diagnostic::ItemOrder
+            | Diagnostic::DeprecatedItemVersion { .. }
+            | Diagnostic::ChunkTableUnreadable(_) => Severity::Warning,
+            Diagnostic::UnknownItem { .. } => Severity::Info,
+            Diagnostic::MissingLaszipVlr
+            | Diagnostic::LaszipVlrUnreadable(_)
+            | Diagnostic::ItemSize { .. }
+            | Diagnostic::UnsupportedItem { .. }
+            | Diagnostic::PointFormatMismatch { .. }
+            | Diagnostic::PointSizeMismatch { .. }
+            | Diagnostic::ChunkTableMismatch { .. }
+            | Diagnostic::PointCountMismatch { .. }
+            | Diagnostic::ChunkCountMismatch { .. } => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::MissingLaszipVlr => write!(f, "The LASzip vlr is missing"),
+            Diagnostic::LaszipVlrUnreadable(e) => {
+                write!(f, "The LASzip vlr could not be read: {}", e)
+            }
+            Diagnostic::CompressionBitNotSet => write!(
+                f,
+                "The point format id does not have the bit of compressed points set"
+            ),
+            Diagnostic::ItemOrder => {
+                write!(f, "The items are not in the order of the specification")
+            }
+            Diagnostic::ItemSize {
+                item_type,
+                size,
+                expected,
+            } => write!(
+                f,
+                "Item {:?} has a size of {} bytes instead of {}",
+                item_type, size, expected
+            ),
+            Diagnostic::UnknownItem { type_code } => {
+                write!(f, "Item type {} is unknown", type_code)
+            }
+            Diagnostic::UnsupportedItem {
+                item_type,
+                version,
+                reason,
+            } => write!(
+                f,
+                "Item {:?} version {} is not supported: {}",
+                item_type, version, reason
+            ),
+            Diagnostic::DeprecatedItemVersion { item_type, version } => write!(
+                f,
+                "Item {:?} uses the deprecated compression version: {}",
+                item_type, version
+            ),
+            Diagnostic::PointFormatMismatch { point_format_id } => write!(
+                f,
+                "The items do not describe points of format {}",
+                point_format_id
+            ),
+            Diagnostic::PointSizeMismatch { header, items } => write!(
+                f,
+                "The header gives points of {} bytes, the items describe {} bytes",
+                header, items
+            ),
+            Diagnostic::ChunkTableUnreadable(e) => {
+                write!(f, "The chunk table could not be read: {}", e)
+            }
+            Diagnostic::ChunkTableMismatch {
+                table_bytes,
+                stream_bytes,
+            } => write!(
+                f,
+                "The chunk table describes {} bytes of chunks, the data has {} bytes",
+                table_bytes, stream_bytes
+            ),
+            Diagnostic::PointCountMismatch {
+                header,
+                chunk_table,
+            } => write!(
+                f,
+                "The header gives {} points, the chunk table {} points",
+                header, chunk_table
+            ),
+            Diagnostic::ChunkCountMismatch {
+                header_points,
+                num_chunks,
+            } => write!(
+                f,
+                "{} chunks cannot hold the {} points of the header",
+                num_chunks, header_points
+            ),
+        }
+    }
+}
+
+/// Checks the items and compressor of the `vlr`
+pub fn lint_vlr(vlr: &LazVlr) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let items = vlr.items();
+    let ranks = items
+        .iter()
+        .map(|item| crate::laszip::spec_order_rank(item.item_type()));
+    if ranks.clone().zip(ranks.skip(1)).any(|(a, b)| a > b) {
+        diagnostics.push(Diagnostic::ItemOrder);
+    }
+
+    for item in items {
+        let item_type = item.item_type();
+        if let LazItemType::Unknown { type_code, .. } = item_type {
+            diagnostics.push(Diagnostic::UnknownItem { type_code });
+        } else if item.size() != item_type.size() {
+            diagnostics.push(Diagnostic::ItemSize {
+                item_type,
+                size: item.size(),
+                expected: item_type.size(),
+            });
+        }
+        if item.version() == 1 {
+            diagnostics.push(Diagnostic::DeprecatedItemVersion {
+                item_type,
+                version: item.version(),
+            });
+        }
+        if let Err(reason) = check_support(item_type, item.version(), vlr.compressor()) {
+            diagnostics.push(Diagnostic::UnsupportedItem {
+                item_type,
+                version: item.version(),
+                reason,
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Checks a LAZ file: its header, its LASzip vlr and its chunk table.
+///
+/// The points are not decompressed. Errors are only returned when the header
+/// or the vlrs cannot be read, other issues are reported as diagnostics.
+pub fn lint_file<R: Read + Seek>(mut src: R) -> crate::Result<Vec<Diagnostic>> {
+    let header = QuickHeader::read_from(&mut src)?;
+    let mut laszip_vlr_data = None;
+    for _ in 0..header.num_vlrs {
+        let vlr = Vlr::read_from(&mut src)?;
+        if vlr.is_laszip_vlr() {
+            laszip_vlr_data = Some(vlr.data().to_vec());
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    if header.point_format_id & 0x80 == 0 {
+        diagnostics.push(Diagnostic::CompressionBitNotSet);
+    }
+    let vlr = match laszip_vlr_data.map(LazVlr::from_buffer_lenient) {
+        None => {
+            diagnostics.push(Diagnostic::MissingLaszipVlr);
+            return Ok(diagnostics);
+        }
+        Some(Err(e)) => {
+            diagnostics.push(Diagnostic::LaszipVlrUnreadable(e));
+            return Ok(diagnostics);
+        }
+        Some(Ok(vlr)) => vlr,
+    };
+    diagnostics.extend(lint_vlr(&vlr));
+
+    let point_format_id = point_format_id_compressed_to_uncompressd(header.point_format_id);
+    if u64::from(header.point_size) != vlr.items_size() {
+        diagnostics.push(Diagnostic::PointSizeMismatch {
+            header: header.point_size,
+            items: vlr.items_size(),
+        });
+    }
+    let num_extra_bytes = base_point_size(point_format_id)
+        .and_then(|base_size| header.point_size.checked_sub(base_size));
+    let matches_point_format = num_extra_bytes.is_some_and(|num_extra_bytes| {
+        vlr.is_compatible_with_point_format(point_format_id, num_extra_bytes)
+    });
+    if !matches_point_format {
+        diagnostics.push(Diagnostic::PointFormatMismatch { point_format_id });
+    }
+
+    let start_of_data = u64::from(header.offset_to_points);
+    src.seek(SeekFrom::Start(start_of_data))?;
+    let chunk_table = match ChunkTable::read_from(&mut src, &vlr) {
+        Ok(chunk_table) => chunk_table,
+        Err(e) => {
+            diagnostics.push(Diagnostic::ChunkTableUnreadable(e));
+            return Ok(diagnostics);
+        }
+    };
+    src.seek(SeekFrom::Start(start_of_data))?;
+    if let Err(LasZipError::ChunkTableMismatch {
+        table_bytes,
+        stream_bytes,
+    }) = chunk_table.check_against(&mut src)
+    {
+        diagnostics.push(Diagnostic::ChunkTableMismatch {
+            table_bytes,
+            stream_bytes,
+        });
+    }
+
+    let num_chunks = chunk_table.len() as u64;
+    if vlr.uses_variable_size_chunks() {
+        let table_points = chunk_table.num_points();
+        if table_points != header.num_points {
+            diagnostics.push(Diagnostic::PointCountMismatch {
+                header: header.num_points,
+                chunk_table: table_points,
+            });
+        }
+    } else if num_chunks
+        != header
+            .num_points
+            .div_ceil(u64::from(vlr.chunk_size()).max(1))
+    {
+        diagnostics.push(Diagnostic::ChunkCountMismatch {
+            header_points: header.num_points,
+            num_chunks,
+        });
+    }
+    Ok(diagnostics)
+}
+
+/// Returns the size of the points of the LAS `point_format_id`, without extra bytes
+fn base_point_size(point_format_id: u8) -> Option<u16> {
+    let size = match point_format_id {
+        0 => 20,
+        1 => 28,
+        2 => 26,
+        3 => 34,
+        4 => 57,
+        5 => 63,
+        6 => 30,
+        7 => 36,
+        8 => 38,
+        9 => 59,
+        10 => 67,
+        _ => return None,
+    };
+    Some(size)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+
+    use super::*;
+    use crate::laszip::LazItem;
+    use crate::{LazItemRecordBuilder, LazVlrBuilder};
+
+    #[test]
+    fn test_test_data_is_clean() {
+        for path in &[
+            "tests/data/point10.laz",
+            "tests/data/point-time.laz",
+            "tests/data/point-color.laz",
+            "tests/data/point-time-color.laz",
+            "tests/data/extra-bytes.laz",
+        ] {
+            let diagnostics = lint_file(File::open(path).unwrap()).unwrap();
+            assert!(
+                diagnostics
+                    .iter()
+                    .all(|diagnostic| diagnostic.severity() < Severity::Error),
+                "{}: {:?}",
+                path,
+                diagnostics
+            );
+        }
+    }
+
+    #[test]
+    fn test_lint_vlr() {
+        let items = LazItemRecordBuilder::new()
+            .add_item(LazItemType::RGB12)
+            .add_item(LazItemType::Point10)
+            .build();
+        let diagnostics = lint_vlr(&LazVlrBuilder::new(items).build());
+        assert!(matches!(diagnostics[..], [Diagnostic::ItemOrder]));
+
+        let mut items = LazItemRecordBuilder::version_1_of::<crate::las::Point0>(0);
+        items.push(LazItem {
+            item_type: LazItemType::GpsTime,
+            size: 4,
+            version: 2,
+        });
+        let diagnostics = lint_vlr(&LazVlrBuilder::new(items).build());
+        assert!(matches!(
+            diagnostics[..],
+            [
+                Diagnostic::DeprecatedItemVersion {
+                    item_type: LazItemType::Point10,
+                    version: 1
+                },
+                Diagnostic::ItemSize {
+                    item_type: LazItemType::GpsTime,
+                    size: 4,
+                    expected: 8
+                }
+            ]
+        ));
+    }
+}
@@ -0,0 +1,251 @@
+//! State of the entropy models of the arithmetic coder, at the end of each chunk
+//!
+//! They are only available when the `model-stats` feature is enabled,
+//! see [`LasZipDecompressor::set_model_stats`](crate::LasZipDecompressor::set_model_stats).
+//!
+//! Models that were never used in a chunk are not reported, and the codecs of
+//! [`LazItemType::Point14`] and of the items of version 1 do not report their models yet.
+use std::io::Write;
+
+use crate::laszip::LazItemType;
+use crate::models::{ArithmeticBitModel, ArithmeticModel, BM_LENGTH_SHIFT, DM_LENGTH_SHIFT};
+
+/// State of one model
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelState {
+    /// A model of symbols
+    Symbols {
+        /// Number of times each symbol was coded, since the counts were last halved
+        symbol_counts: Vec<u32>,
+        /// Probability of each symbol, as used to code the next symbol
+        probabilities: Vec<f64>,
+    },
+    /// A model of bits
+    Bit {
+        /// Number of 0 bits coded, since the counts were last halved
+        bit_0_count: u32,
+        /// Number of bits coded, since the counts were last halved
+        bit_count: u32,
+        /// Probability of a 0, as used to code the next bit
+        bit_0_probability: f64,
+    },
+}
+
+/// One model of a field codec
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelStats {
+    /// Name of the model, e.g. `"bits[3]"` or `"ic_dx.corrector[12]"`
+    pub name: String,
+    pub state: ModelState,
+}
+
+impl ModelStats {
+    /// Returns the stats of the `model`, or `None` if it was never used
+    pub(crate) fn of_model(name: String, model: &ArithmeticModel) -> Option<Self> {
+        let initial_cycle = (model.symbols + 6) >> 1;
+        let untouched = model.update_cycle == initial_cycle
+            && model.symbols_until_update == initial_cycle
+            && model.symbol_count.iter().all(|&count| count == 1);
+        if untouched {
+            return None;
+        }
+        let scale = f64::from(1u32 << DM_LENGTH_SHIFT);
+        let probabilities = model
+            .distribution
+            .iter()
+            .enumerate()
+            .map(|(symbol, &start)| {
+                let end = model
+                    .distribution
+                    .get(symbol + 1)
+                    .copied()
+                    .unwrap_or(1 << DM_LENGTH_SHIFT);
+                f64::from(end - start) / scale
+            })
+            .collect();
+        Some(Self {
+            name,
+            state: ModelState::Symbols {
+                symbol_counts: model.symbol_count.clone(),
+                probabilities,
+            },
+        })
+    }
+
+    /// Returns the stats of the `model`, or `None` if it was never used
+    pub(crate) fn of_bit_model(name: String, model: &ArithmeticBitModel) -> Option<Self> {
+        let initial = ArithmeticBitModel::default();
+        let untouched = model.bit_count == initial.bit_count
+            && model.bit_0_count == initial.bit_0_count
+            && model.bits_until_update == initial.bits_until_update
+            && model.update_cycle == initial.update_cycle;
+        if untouched {
+            return None;
+        }
+        Some(Self {
+            name,
+            state: ModelState::Bit {
+                bit_0_count: model.bit_0_count,
+                // The 0s are counted as they are coded, the bits at the next update
+                bit_count: model.bit_count + (model.update_cycle - model.bits_until_update),
+                bit_0_probability: f64::from(model.bit_0_prob) / f64::from(1u32 << BM_LENGTH_SHIFT),
+            },
+        })
+    }
+}
+
+/// Adds the stats of the `model` if it was used
+pub(crate) fn add_model_stats(stats: &mut Vec<ModelStats>, name: &str, model: &ArithmeticModel) {
+    stats.extend(ModelStats::of_model(name.to_string(), model));
+}
+
+/// Adds the stats of the `models` that were used, named `name[index]`
+pub(crate) fn add_models_stats<'a>(
+    stats: &mut Vec<ModelStats>,
+    name: &str,
+    models: impl IntoIterator<Item = &'a ArithmeticModel>,
+) {
+    for (i, model) in models.into_iter().enumerate() {
+        stats.extend(ModelStats::of_model(format!("{}[{}]", name, i), model));
+    }
+}
+
+/// Models of the codec of a field
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldModelStats {
+    pub item_type: LazItemType,
+    pub models: Vec<ModelStats>,
+}
+
+/// Models of the codecs of all the fields, once the last point of a chunk was decompressed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkModelStats {
+    pub chunk_index: usize,
+    /// In the order of the items
+    pub fields: Vec<FieldModelStats>,
+}
+
+/// Writes the `chunks` as CSV, with one line per symbol (or bit) of each model:
+///
+/// `chunk,field,item_type,model,symbol,count,probability`
+///
+/// For the models of bits, there are two lines, where the count of the symbol 1 is
+/// the number of bits minus the number of 0s.
+pub fn write_csv<W: Write>(chunks: &[ChunkModelStats], mut dst: W) -> std::io::Result<()> {
+    writeln!(dst, "chunk,field,item_type,model,symbol,count,probability")?;
+    for chunk in chunks {
+        for (field_index, field) in chunk.fields.iter().enumerate() {
+            let prefix = format!(
+                "{},{},\"{:?}\"",
+                chunk.chunk_index, field_index, field.item_type
+            );
+            for model in &field.models {
+                match &model.state {
+                    ModelState::Symbols {
+                        symbol_counts,
+                        probabilities,
+                    } => {
+                        for (symbol, (count, probability)) in
+                            symbol_counts.iter().zip(probabilities).enumerate()
+                        {
+                            writeln!(
+                                dst,
+                                "{},\"{}\",{},{},{}",
+                                prefix, model.name, symbol, count, probability
+                            )?;
+                        }
+                    }
+                    ModelState::Bit {
+                        bit_0_count,
+                        bit_count,
+                        bit_0_probability,
+                    } => {
+                        writeln!(
+                            dst,
+                            "{},\"{}\",0,{},{}",
+                            prefix, model.name, bit_0_count, bit_0_probability
+                        )?;
+                        writeln!(
+                            dst,
+                            "{},\"{}\",1,{},{}",
+                            prefix,
+                            model.name,
+                            bit_count - bit_0_count,
+                            1.0 - bit_0_probability
+                        )?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "model-stats"))]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils;
+    use crate::{LasZipDecompressor, LazItemRecordBuilder, LazVlrBuilder};
+
+    #[test]
+    fn test_model_stats() {
+        use crate::model_stats::ModelState;
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point0>(2);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = test_utils::points(25, 22);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = LasZipDecompressor::new(compressed, vlr).unwrap();
+        assert!(decompressor.model_stats().is_none());
+        decompressor.set_model_stats(true);
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        let stats = decompressor.model_stats().unwrap();
+        // The last chunk is not full
+        assert_eq!(
+            stats.iter().map(|s| s.chunk_index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        let fields = &stats[0].fields;
+        assert_eq!(fields[0].item_type, LazItemType::Point10);
+        for model in &fields[0].models {
+            if let ModelState::Bit {
+                bit_0_count,
+                bit_count,
+                ..
+            } = model.state
+            {
+                assert!(bit_0_count <= bit_count, "{}", model.name);
+            }
+        }
+        assert!(fields[0]
+            .models
+            .iter()
+            .any(|model| model.name.starts_with("ic_dx.")));
+
+        // Each extra byte has its own model
+        let bytes = &fields[1].models;
+        assert_eq!(bytes.len(), 2);
+        assert_eq!(bytes[0].name, "bytes[0]");
+        match &bytes[0].state {
+            ModelState::Symbols {
+                symbol_counts,
+                probabilities,
+            } => {
+                assert_eq!(symbol_counts.len(), 256);
+                assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            }
+            ModelState::Bit { .. } => panic!("expected a model of symbols"),
+        }
+
+        let mut csv = Vec::new();
+        crate::model_stats::write_csv(stats, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("chunk,field,item_type,model,symbol,count,probability\n"));
+        assert!(csv.contains("1,1,\"Byte(2)\",\"bytes[0]\",0,"));
+    }
+}
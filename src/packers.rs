@@ -33,6 +33,9 @@
 ///
 /// This trait allows to have something that seems a bit faster
 /// that using a std::io::Cursor + the byteorder crate.
+///
+/// Values are always packed in little-endian, as in LAS files,
+/// whatever the endianness of the host is.
 pub trait Packable {
     fn unpack_from(input: &[u8]) -> Self;
     fn pack_into(&self, output: &mut [u8]);
@@ -62,12 +65,7 @@ impl Packable for u32 {
 
     #[inline]
     unsafe fn unpack_from_unchecked(input: &[u8]) -> Self {
-        let b1 = *input.get_unchecked(0);
-        let b2 = *input.get_unchecked(1);
-        let b3 = *input.get_unchecked(2);
-        let b4 = *input.get_unchecked(3);
-
-        u32::from_le_bytes([b1, b2, b3, b4])
+        u32::from_le_bytes(*(input.as_ptr() as *const [u8; 4]))
     }
 
     #[inline]
@@ -78,6 +76,38 @@ impl Packable for u32 {
     }
 }
 
+impl Packable for u64 {
+    #[inline]
+    fn unpack_from(input: &[u8]) -> Self {
+        assert!(
+            input.len() >= 8,
+            "u64::unpack_from expected a slice of 8 bytes"
+        );
+        unsafe { Self::unpack_from_unchecked(input) }
+    }
+
+    #[inline]
+    fn pack_into(&self, output: &mut [u8]) {
+        assert!(
+            output.len() >= 8,
+            "u64::pack_into expected a slice of 8 bytes"
+        );
+        unsafe { self.pack_into_unchecked(output) }
+    }
+
+    #[inline]
+    unsafe fn unpack_from_unchecked(input: &[u8]) -> Self {
+        u64::from_le_bytes(*(input.as_ptr() as *const [u8; 8]))
+    }
+
+    #[inline]
+    unsafe fn pack_into_unchecked(&self, output: &mut [u8]) {
+        output
+            .get_unchecked_mut(..8)
+            .copy_from_slice(&self.to_le_bytes());
+    }
+}
+
 impl Packable for u16 {
     #[inline]
     fn unpack_from(input: &[u8]) -> Self {
@@ -92,17 +122,14 @@ impl Packable for u16 {
     fn pack_into(&self, output: &mut [u8]) {
         assert!(
             output.len() >= 2,
-            "u32::pack_into expected a slice of 4 bytes"
+            "u16::pack_into expected a slice of 2 bytes"
         );
         unsafe { self.pack_into_unchecked(output) }
     }
 
     #[inline]
     unsafe fn unpack_from_unchecked(input: &[u8]) -> Self {
-        let b1 = *input.get_unchecked(0);
-        let b2 = *input.get_unchecked(1);
-
-        u16::from_le_bytes([b1, b2])
+        u16::from_le_bytes(*(input.as_ptr() as *const [u8; 2]))
     }
 
     #[inline]
@@ -200,3 +227,125 @@ impl Packable for i8 {
         *output.get_unchecked_mut(0) = (*self) as u8;
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::Packable;
+    use crate::las::gps::GpsTime;
+    use crate::las::point0::LasPoint0;
+    use crate::las::point6::LasPoint6;
+    use crate::las::rgb::RGB;
+    use crate::las::{Point0, Point6};
+
+    fn check_round_trip<T: Packable + std::fmt::Debug + PartialEq>(bytes: &[u8], expected: T) {
+        let value = T::unpack_from(bytes);
+        assert_eq!(value, expected);
+        let mut packed = vec![0u8; bytes.len()];
+        value.pack_into(&mut packed);
+        assert_eq!(packed, bytes);
+    }
+
+    #[test]
+    fn test_primitives_are_little_endian() {
+        check_round_trip(
+            &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+            0x0807_0605_0403_0201u64,
+        );
+        check_round_trip(&[0x01, 0x02, 0x03, 0x04], 0x0403_0201u32);
+        check_round_trip(&[0xFE, 0xFF, 0xFF, 0xFF], -2i32);
+        check_round_trip(&[0x34, 0x12], 0x1234u16);
+        check_round_trip(&[0x9C, 0xFF], -100i16);
+        check_round_trip(&[0xFB], -5i8);
+        check_round_trip(&[0xAB], 0xABu8);
+    }
+
+    #[test]
+    fn test_gps_time_is_little_endian() {
+        // 1.5 is 0x3FF8_0000_0000_0000
+        let bytes = [0, 0, 0, 0, 0, 0, 0xF8, 0x3F];
+        check_round_trip(&bytes, GpsTime::from(1.5f64));
+        assert_eq!(f64::from(GpsTime::unpack_from(&bytes)), 1.5);
+    }
+
+    #[test]
+    fn test_rgb_is_little_endian() {
+        check_round_trip(
+            &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+            RGB {
+                red: 0x0201,
+                green: 0x0403,
+                blue: 0x0605,
+            },
+        );
+    }
+
+    #[test]
+    fn test_rgb_and_nir_have_the_las_layout() {
+        assert_eq!(std::mem::size_of::<RGB>(), RGB::SIZE);
+        assert_eq!(std::mem::align_of::<RGB>(), 2);
+        assert_eq!(
+            std::mem::size_of::<crate::las::nir::Nir>(),
+            crate::las::nir::Nir::SIZE
+        );
+    }
+
+    #[test]
+    fn test_point0_is_little_endian() {
+        let bytes = [
+            0x01, 0x02, 0x03, 0x04, // x
+            0xFE, 0xFF, 0xFF, 0xFF, // y
+            0x00, 0x01, 0x00, 0x00, // z
+            0x34, 0x12, // intensity
+            0x51, // return number 1, 2 returns, scan direction flag
+            0x02, // classification
+            0xFB, // scan angle rank
+            0x07, // user data
+            0xCD, 0xAB, // point source id
+        ];
+        let point = Point0::unpack_from(&bytes);
+        assert_eq!(point.x(), 0x0403_0201);
+        assert_eq!(point.y(), -2);
+        assert_eq!(point.z(), 256);
+        assert_eq!(point.intensity(), 0x1234);
+        assert_eq!(point.return_number(), 1);
+        assert_eq!(point.number_of_returns_of_given_pulse(), 2);
+        assert!(point.scan_direction_flag());
+        assert!(!point.edge_of_flight_line());
+        assert_eq!(point.classification(), 2);
+        assert_eq!(point.scan_angle_rank(), -5);
+        assert_eq!(point.user_data(), 7);
+        assert_eq!(point.point_source_id(), 0xABCD);
+        check_round_trip(&bytes, point);
+    }
+
+    #[test]
+    fn test_point6_is_little_endian() {
+        let bytes = [
+            0x01, 0x02, 0x03, 0x04, // x
+            0xFE, 0xFF, 0xFF, 0xFF, // y
+            0x00, 0x01, 0x00, 0x00, // z
+            0x34, 0x12, // intensity
+            0x21, // return number 1, 2 returns
+            0x40, // scan direction flag
+            0x02, // classification
+            0x07, // user data
+            0x9C, 0xFF, // scan angle
+            0xCD, 0xAB, // point source id
+            0, 0, 0, 0, 0, 0, 0xF8, 0x3F, // gps time
+        ];
+        let point = Point6::unpack_from(&bytes);
+        assert_eq!(point.x(), 0x0403_0201);
+        assert_eq!(point.y(), -2);
+        assert_eq!(point.z(), 256);
+        assert_eq!(point.intensity(), 0x1234);
+        assert_eq!(point.return_number(), 1);
+        assert_eq!(point.number_of_returns_of_given_pulse(), 2);
+        assert!(point.scan_direction_flag());
+        assert_eq!(point.classification(), 2);
+        assert_eq!(point.user_data(), 7);
+        assert_eq!(point.scan_angle_rank(), -100);
+        assert_eq!(point.point_source_id(), 0xABCD);
+        assert_eq!(point.gps_time(), 1.5);
+        check_round_trip(&bytes, point);
+    }
+}
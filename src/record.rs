@@ -1,4 +1,31 @@
 //! Everything about compressing & decompressing point records
+//!
+//! # Custom record layouts
+//!
+//! The [`RecordCompressor`] & [`RecordDecompressor`] traits are the extension point
+//! of the chunked machinery: implementing them allows to use a custom layout for
+//! the points inside of the chunks, while [`LasZipCompressor::with_record_compressor`]
+//! and [`LasZipDecompressor::with_record_decompressor`] take care of the chunks,
+//! the chunk table and seeking.
+//!
+//! They follow semantic versioning: new methods only come with a default implementation,
+//! and existing methods are not changed in minor releases.
+//!
+//! The chunked machinery relies on the following:
+//!
+//! - At the start of each chunk, [`reset`] is called, followed by `set_fields_from`
+//!   with the items of the vlr, which must then succeed.
+//!   So [`reset`] must forget the fields that were set.
+//! - A chunk ends when the compressor's [`done`] has been called, it must have
+//!   written all the data of the chunk to the stream.
+//! - The position of the stream (found using `get_mut`) is used to compute the
+//!   size of the chunks and to seek to them, so the data of a chunk must be
+//!   written, and read, between its start and its end.
+//!
+//! [`LasZipCompressor::with_record_compressor`]: crate::LasZipCompressor::with_record_compressor
+//! [`LasZipDecompressor::with_record_decompressor`]: crate::LasZipDecompressor::with_record_decompressor
+//! [`reset`]: RecordCompressor::reset
+//! [`done`]: RecordCompressor::done
 
 use std::io::{Read, Seek, Write};
 
@@ -8,7 +35,8 @@ use crate::byteslice::{ChunksIrregular, ChunksIrregularMut};
 use crate::decoders;
 use crate::encoders;
 use crate::las;
-use crate::laszip::{LazItem, LazItemType};
+use crate::laszip::{DecompressionSelection, LazItem, LazItemType};
+use crate::timing::FieldTimer;
 use crate::LasZipError;
 
 /***************************************************************************************************
@@ -41,6 +69,19 @@ pub trait FieldDecompressor<R: Read> {
         decoder: &mut decoders::ArithmeticDecoder<R>,
         buf: &mut [u8],
     ) -> std::io::Result<()>;
+
+    /// Returns a copy of this decompressor in its current state.
+    ///
+    /// Decompressors that cannot be copied return `None`, which
+    /// disables snapshots for the records they are part of.
+    fn box_clone(&self) -> Option<Box<dyn FieldDecompressor<R> + Send>> {
+        None
+    }
+
+    /// Adds the state of the models that were used since the start of the chunk
+    /// to the `stats`, codecs that do not support it add nothing.
+    #[cfg(feature = "model-stats")]
+    fn add_model_stats(&self, _stats: &mut Vec<crate::model_stats::ModelStats>) {}
 }
 
 /// Trait to be implemented by FieldCompressors that works with layers.
@@ -71,6 +112,17 @@ pub trait LayeredFieldDecompressor<R: Read> {
     fn read_layers_sizes(&mut self, src: &mut R) -> std::io::Result<()>;
     /// Read the layers from the `src`.
     fn read_layers(&mut self, src: &mut R) -> std::io::Result<()>;
+
+    /// Returns the number of bytes of the layers, as read by [`Self::read_layers_sizes`],
+    /// that is, the most that [`Self::read_layers`] will allocate.
+    fn layers_size(&self) -> usize {
+        0
+    }
+
+    /// Adds the state of the models that were used since the start of the chunk
+    /// to the `stats`, codecs that do not support it add nothing.
+    #[cfg(feature = "model-stats")]
+    fn add_model_stats(&self, _stats: &mut Vec<crate::model_stats::ModelStats>) {}
 }
 
 /// Trait describing the interface needed to _decompress_ a point record
@@ -89,6 +141,22 @@ pub trait RecordDecompressor<R> {
         0
     }
 
+    /// Sets the maximum number of bytes the decompressor may allocate
+    /// to hold the compressed data of a chunk, `None` means no limit.
+    ///
+    /// Decompressors that go past it must return [`LasZipError::MemoryLimitExceeded`]
+    /// (wrapped in the io error), the ones that do not buffer data ignore it.
+    ///
+    /// [`LasZipError::MemoryLimitExceeded`]: crate::LasZipError::MemoryLimitExceeded
+    fn set_memory_limit(&mut self, _limit: Option<usize>) {}
+
+    /// Sets the fields to decompress, it is used by the next call to [`set_fields_from`].
+    ///
+    /// Only the layered decompressors support it, the others decompress all the fields.
+    ///
+    /// [`set_fields_from`]: Self::set_fields_from
+    fn set_selection(&mut self, _selection: DecompressionSelection) {}
+
     /// Decompress the next point and pack the result in the `out` slice
     fn decompress_next(&mut self, out: &mut [u8]) -> std::io::Result<()>;
 
@@ -114,9 +182,42 @@ pub trait RecordDecompressor<R> {
         Ok(out.len())
     }
 
+    /// Takes a snapshot of the decompressor state.
+    ///
+    /// Restoring the snapshot with [`restore`] after having moved the stream
+    /// back to the position it had when the snapshot was taken allows resuming
+    /// decompression from this exact record.
+    ///
+    /// Returns `None` when the decompressor does not support snapshots.
+    ///
+    /// [`restore`]: Self::restore
+    fn snapshot(&self) -> Option<RecordDecompressorSnapshot<R>> {
+        None
+    }
+
+    /// Restores a state previously obtained with [`snapshot`].
+    ///
+    /// [`snapshot`]: Self::snapshot
+    fn restore(&mut self, _snapshot: &RecordDecompressorSnapshot<R>) -> bool {
+        false
+    }
+
     /// Resets the `RecordDecompressor` to its initial state
     fn reset(&mut self);
 
+    /// Returns the time spent decompressing each field, since the creation
+    /// of the decompressor (resets do not clear it)
+    #[cfg(feature = "timing")]
+    fn field_times(&self) -> &[std::time::Duration] {
+        &[]
+    }
+
+    /// Returns the state of the models of each field, see [`crate::model_stats`]
+    #[cfg(feature = "model-stats")]
+    fn model_stats(&self) -> Vec<Vec<crate::model_stats::ModelStats>> {
+        Vec::new()
+    }
+
     /// Returns a mutable reference to the owned stream
     fn get_mut(&mut self) -> &mut R;
 
@@ -134,6 +235,28 @@ pub trait RecordDecompressor<R> {
                     Record Decompressors implementations
 ***************************************************************************************************/
 
+/// State of a [`RecordDecompressor`] captured in the middle of a chunk.
+///
+/// Holds a full copy of the fields' entropy models, so this is
+/// not a cheap object (about 2MiB for a Point10 record).
+pub struct RecordDecompressorSnapshot<R> {
+    field_decompressors: Vec<Box<dyn FieldDecompressor<R> + Send>>,
+    decoder_state: (u32, u32),
+}
+
+impl<R: Read> RecordDecompressorSnapshot<R> {
+    fn clone_fields(&self) -> Vec<Box<dyn FieldDecompressor<R> + Send>> {
+        self.field_decompressors
+            .iter()
+            .map(|field| {
+                field
+                    .box_clone()
+                    .expect("Snapshot field decompressors are clonable")
+            })
+            .collect()
+    }
+}
+
 /// Decompress points stored sequentially
 ///
 /// This [`RecordDecompressor`] expected the data to be organized as follow;
@@ -148,6 +271,7 @@ pub struct SequentialPointRecordDecompressor<'a, R: Read> {
     is_first_decompression: bool,
     record_size: usize,
     fields_sizes: Vec<usize>,
+    timer: FieldTimer,
 }
 
 impl<'a, R: Read> SequentialPointRecordDecompressor<'a, R> {
@@ -160,6 +284,7 @@ impl<'a, R: Read> SequentialPointRecordDecompressor<'a, R> {
             is_first_decompression: true,
             record_size: 0,
             fields_sizes: vec![],
+            timer: FieldTimer::default(),
         }
     }
 
@@ -186,6 +311,7 @@ impl<'a, R: Read> RecordDecompressor<R> for SequentialPointRecordDecompressor<'a
     fn set_fields_from(&mut self, laz_items: &Vec<LazItem>) -> crate::Result<()> {
         for record_item in laz_items {
             match record_item.version {
+                #[cfg(feature = "format-legacy")]
                 1 => match record_item.item_type {
                     LazItemType::Point10 => {
                         self.add_field_decompressor(las::v1::LasPoint0Decompressor::default())
@@ -196,6 +322,10 @@ impl<'a, R: Read> RecordDecompressor<R> for SequentialPointRecordDecompressor<'a
                     LazItemType::RGB12 => {
                         self.add_field_decompressor(las::v1::LasRGBDecompressor::default())
                     }
+                    LazItemType::WavePacket13 => {
+                        self.add_field_decompressor(las::v1::LasWavepacketDecompressor::default())
+                    }
+                    #[cfg(feature = "extra-bytes")]
                     LazItemType::Byte(_) => self.add_field_decompressor(
                         las::v1::LasExtraByteDecompressor::new(record_item.size as usize),
                     ),
@@ -206,6 +336,7 @@ impl<'a, R: Read> RecordDecompressor<R> for SequentialPointRecordDecompressor<'a
                         ));
                     }
                 },
+                #[cfg(feature = "format-legacy")]
                 2 => match record_item.item_type {
                     LazItemType::Point10 => {
                         self.add_field_decompressor(las::v2::LasPoint0Decompressor::default())
@@ -216,6 +347,10 @@ impl<'a, R: Read> RecordDecompressor<R> for SequentialPointRecordDecompressor<'a
                     LazItemType::RGB12 => {
                         self.add_field_decompressor(las::v2::LasRGBDecompressor::default())
                     }
+                    LazItemType::WavePacket13 => {
+                        self.add_field_decompressor(las::v1::LasWavepacketDecompressor::default())
+                    }
+                    #[cfg(feature = "extra-bytes")]
                     LazItemType::Byte(_) => self.add_field_decompressor(
                         las::v2::LasExtraByteDecompressor::new(record_item.size as usize),
                     ),
@@ -250,23 +385,93 @@ impl<'a, R: Read> RecordDecompressor<R> for SequentialPointRecordDecompressor<'a
                     self.fields_sizes.iter().copied(),
                 ));
 
+        let timer = &mut self.timer;
         if self.is_first_decompression {
-            for (fields_decompressor, out_field_data) in decompressors_and_data {
-                fields_decompressor
-                    .decompress_first(&mut self.decoder.get_mut(), out_field_data)?;
+            for (i, (fields_decompressor, out_field_data)) in decompressors_and_data.enumerate() {
+                let src = self.decoder.get_mut();
+                timer.time(i, || {
+                    fields_decompressor.decompress_first(src, out_field_data)
+                })?;
             }
             self.is_first_decompression = false;
             // the decoder needs to be told that it should read the
             // init bytes after the first record has been read
             self.decoder.read_init_bytes()?;
         } else {
-            for (fields_decompressor, field_chunk) in decompressors_and_data {
-                fields_decompressor.decompress_with(&mut self.decoder, field_chunk)?;
+            let decoder = &mut self.decoder;
+            for (i, (fields_decompressor, field_chunk)) in decompressors_and_data.enumerate() {
+                timer.time(i, || {
+                    fields_decompressor.decompress_with(decoder, field_chunk)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn decompress_many(&mut self, out: &mut [u8]) -> std::io::Result<()> {
+        let mut points = out.chunks_exact_mut(self.record_size);
+        if self.is_first_decompression {
+            match points.next() {
+                Some(first_point) => self.decompress_next(first_point)?,
+                None => return Ok(()),
+            }
+        }
+
+        // Same as calling `decompress_next` for each point, but the fields
+        // and their sizes are only looked up once for all the points
+        let decoder = &mut self.decoder;
+        let timer = &mut self.timer;
+        if let [field_decompressor] = self.field_decompressors.as_mut_slice() {
+            for point in points {
+                timer.time(0, || field_decompressor.decompress_with(decoder, point))?;
+            }
+        } else {
+            let fields_sizes = &self.fields_sizes;
+            for point in points {
+                let mut rest = point;
+                for (i, (field_decompressor, &field_size)) in self
+                    .field_decompressors
+                    .iter_mut()
+                    .zip(fields_sizes)
+                    .enumerate()
+                {
+                    let (field_data, next) = std::mem::take(&mut rest).split_at_mut(field_size);
+                    timer.time(i, || {
+                        field_decompressor.decompress_with(decoder, field_data)
+                    })?;
+                    rest = next;
+                }
             }
         }
         Ok(())
     }
 
+    fn snapshot(&self) -> Option<RecordDecompressorSnapshot<R>> {
+        if self.is_first_decompression {
+            return None;
+        }
+        let field_decompressors = self
+            .field_decompressors
+            .iter()
+            .map(|field| field.box_clone())
+            .collect::<Option<Vec<_>>>()?;
+        Some(RecordDecompressorSnapshot {
+            field_decompressors,
+            decoder_state: self.decoder.state(),
+        })
+    }
+
+    fn restore(&mut self, snapshot: &RecordDecompressorSnapshot<R>) -> bool {
+        if snapshot.field_decompressors.len() != self.field_decompressors.len() {
+            return false;
+        }
+        self.field_decompressors.clear();
+        self.field_decompressors.extend(snapshot.clone_fields());
+        self.decoder.set_state(snapshot.decoder_state);
+        self.is_first_decompression = false;
+        true
+    }
+
     fn reset(&mut self) {
         self.decoder.reset();
         self.is_first_decompression = true;
@@ -275,6 +480,23 @@ impl<'a, R: Read> RecordDecompressor<R> for SequentialPointRecordDecompressor<'a
         self.fields_sizes.clear();
     }
 
+    #[cfg(feature = "timing")]
+    fn field_times(&self) -> &[std::time::Duration] {
+        self.timer.times()
+    }
+
+    #[cfg(feature = "model-stats")]
+    fn model_stats(&self) -> Vec<Vec<crate::model_stats::ModelStats>> {
+        self.field_decompressors
+            .iter()
+            .map(|field| {
+                let mut stats = Vec::new();
+                field.add_model_stats(&mut stats);
+                stats
+            })
+            .collect()
+    }
+
     fn get_mut(&mut self) -> &mut R {
         self.decoder.get_mut()
     }
@@ -314,7 +536,12 @@ pub struct LayeredPointRecordDecompressor<'a, R: Read + Seek> {
     is_first_decompression: bool,
     fields_sizes: Vec<usize>,
     record_size: usize,
+    /// Number of points of the current chunk, read with its first point
+    record_count: u64,
     context: usize,
+    timer: FieldTimer,
+    memory_limit: Option<usize>,
+    selection: DecompressionSelection,
 }
 
 impl<'a, R: Read + Seek> LayeredPointRecordDecompressor<'a, R> {
@@ -327,7 +554,11 @@ impl<'a, R: Read + Seek> LayeredPointRecordDecompressor<'a, R> {
             is_first_decompression: true,
             fields_sizes: vec![],
             record_size: 0,
+            record_count: 0,
             context: 0,
+            timer: FieldTimer::default(),
+            memory_limit: None,
+            selection: DecompressionSelection::all(),
         }
     }
 
@@ -347,20 +578,32 @@ impl<'a, R: Read + Seek> RecordDecompressor<R> for LayeredPointRecordDecompresso
     fn set_fields_from(&mut self, laz_items: &Vec<LazItem>) -> crate::Result<()> {
         for record_item in laz_items {
             match record_item.version {
+                #[cfg(feature = "format-14")]
                 3 => match record_item.item_type {
-                    LazItemType::Point14 => {
-                        self.add_field_decompressor(las::v3::LasPoint6Decompressor::default())
-                    }
-                    LazItemType::RGB14 => {
-                        self.add_field_decompressor(las::v3::LasRGBDecompressor::default())
-                    }
+                    LazItemType::Point14 => self.add_field_decompressor(
+                        las::v3::LasPoint6Decompressor::selective(self.selection),
+                    ),
+                    LazItemType::RGB14 => self.add_field_decompressor(
+                        las::v3::LasRGBDecompressor::selective(self.selection),
+                    ),
                     LazItemType::RGBNIR14 => {
-                        self.add_field_decompressor(las::v3::LasRGBDecompressor::default());
-                        self.add_field_decompressor(las::v3::LasNIRDecompressor::default());
+                        self.add_field_decompressor(las::v3::LasRGBDecompressor::selective(
+                            self.selection,
+                        ));
+                        self.add_field_decompressor(las::v3::LasNIRDecompressor::selective(
+                            self.selection,
+                        ));
                     }
-                    LazItemType::Byte14(count) => self.add_field_decompressor(
-                        las::v3::LasExtraByteDecompressor::new(count as usize),
+                    LazItemType::WavePacket14 => self.add_field_decompressor(
+                        las::v3::LasWavepacketDecompressor::selective(self.selection),
                     ),
+                    #[cfg(feature = "extra-bytes")]
+                    LazItemType::Byte14(count) => {
+                        self.add_field_decompressor(las::v3::LasExtraByteDecompressor::selective(
+                            count as usize,
+                            self.selection,
+                        ))
+                    }
                     _ => {
                         return Err(LasZipError::UnsupportedLazItemVersion(
                             record_item.item_type,
@@ -383,6 +626,18 @@ impl<'a, R: Read + Seek> RecordDecompressor<R> for LayeredPointRecordDecompresso
         self.record_size
     }
 
+    fn record_count(&self) -> u64 {
+        self.record_count
+    }
+
+    fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    fn set_selection(&mut self, selection: DecompressionSelection) {
+        self.selection = selection;
+    }
+
     fn decompress_next(&mut self, out: &mut [u8]) -> std::io::Result<()> {
         let decompressors_and_data =
             self.field_decompressors
@@ -392,27 +647,36 @@ impl<'a, R: Read + Seek> RecordDecompressor<R> for LayeredPointRecordDecompresso
                     self.fields_sizes.iter().copied(),
                 ));
 
+        let timer = &mut self.timer;
+        let context = &mut self.context;
         if self.is_first_decompression {
-            for (field_decompressor, out_field_data) in decompressors_and_data {
-                field_decompressor.init_first_point(
-                    &mut self.input,
-                    out_field_data,
-                    &mut self.context,
-                )?;
+            let input = &mut self.input;
+            for (i, (field_decompressor, out_field_data)) in decompressors_and_data.enumerate() {
+                timer.time(i, || {
+                    field_decompressor.init_first_point(input, out_field_data, context)
+                })?;
             }
 
-            let _count = self.input.read_u32::<LittleEndian>()?;
+            self.record_count = u64::from(self.input.read_u32::<LittleEndian>()?);
             for field in &mut self.field_decompressors {
                 field.read_layers_sizes(&mut self.input)?;
             }
-            for field in &mut self.field_decompressors {
-                field.read_layers(&mut self.input)?;
+            let layers_size = self.field_decompressors.iter().fold(0usize, |size, field| {
+                size.saturating_add(field.layers_size())
+            });
+            crate::errors::check_memory_limit(self.memory_limit, layers_size)
+                .map_err(crate::LasZipError::into_io_error)?;
+            for (i, field) in self.field_decompressors.iter_mut().enumerate() {
+                let input = &mut self.input;
+                timer.time(i, || field.read_layers(input))?;
             }
             self.is_first_decompression = false;
         } else {
-            self.context = 0;
-            for (field_decompressor, out_field_data) in decompressors_and_data {
-                field_decompressor.decompress_field_with(out_field_data, &mut self.context)?;
+            *context = 0;
+            for (i, (field_decompressor, out_field_data)) in decompressors_and_data.enumerate() {
+                timer.time(i, || {
+                    field_decompressor.decompress_field_with(out_field_data, context)
+                })?;
             }
         }
         Ok(())
@@ -422,9 +686,27 @@ impl<'a, R: Read + Seek> RecordDecompressor<R> for LayeredPointRecordDecompresso
         self.is_first_decompression = true;
         self.field_decompressors.clear();
         self.record_size = 0;
+        self.record_count = 0;
         self.fields_sizes.clear();
     }
 
+    #[cfg(feature = "timing")]
+    fn field_times(&self) -> &[std::time::Duration] {
+        self.timer.times()
+    }
+
+    #[cfg(feature = "model-stats")]
+    fn model_stats(&self) -> Vec<Vec<crate::model_stats::ModelStats>> {
+        self.field_decompressors
+            .iter()
+            .map(|field| {
+                let mut stats = Vec::new();
+                field.add_model_stats(&mut stats);
+                stats
+            })
+            .collect()
+    }
+
     fn get_mut(&mut self) -> &mut R {
         &mut self.input
     }
@@ -517,6 +799,13 @@ pub trait RecordCompressor<W> {
     /// Resets the compressor to its initial state
     fn reset(&mut self);
 
+    /// Returns the time spent compressing each field, since the creation
+    /// of the compressor (resets do not clear it)
+    #[cfg(feature = "timing")]
+    fn field_times(&self) -> &[std::time::Duration] {
+        &[]
+    }
+
     /// Returns a mutable reference to the owned stream
     fn get_mut(&mut self) -> &mut W;
 
@@ -539,6 +828,7 @@ pub struct SequentialPointRecordCompressor<'a, W: Write> {
     encoder: encoders::ArithmeticEncoder<W>,
     record_size: usize,
     fields_sizes: Vec<usize>,
+    timer: FieldTimer,
 }
 
 impl<'a, W: Write> SequentialPointRecordCompressor<'a, W> {
@@ -549,6 +839,7 @@ impl<'a, W: Write> SequentialPointRecordCompressor<'a, W> {
             encoder: encoders::ArithmeticEncoder::new(output),
             record_size: 0,
             fields_sizes: vec![],
+            timer: FieldTimer::default(),
         }
     }
 
@@ -573,6 +864,7 @@ impl<'a, W: Write> RecordCompressor<W> for SequentialPointRecordCompressor<'a, W
     fn set_fields_from(&mut self, laz_items: &Vec<LazItem>) -> crate::Result<()> {
         for record_item in laz_items {
             match record_item.version {
+                #[cfg(feature = "format-legacy")]
                 1 => match record_item.item_type {
                     LazItemType::Point10 => {
                         self.add_field_compressor(las::v1::LasPoint0Compressor::default())
@@ -583,6 +875,10 @@ impl<'a, W: Write> RecordCompressor<W> for SequentialPointRecordCompressor<'a, W
                     LazItemType::RGB12 => {
                         self.add_field_compressor(las::v1::LasRGBCompressor::default())
                     }
+                    LazItemType::WavePacket13 => {
+                        self.add_field_compressor(las::v1::LasWavepacketCompressor::default())
+                    }
+                    #[cfg(feature = "extra-bytes")]
                     LazItemType::Byte(_) => self.add_field_compressor(
                         las::v1::LasExtraByteCompressor::new(record_item.size as usize),
                     ),
@@ -593,6 +889,7 @@ impl<'a, W: Write> RecordCompressor<W> for SequentialPointRecordCompressor<'a, W
                         ));
                     }
                 },
+                #[cfg(feature = "format-legacy")]
                 2 => match record_item.item_type {
                     LazItemType::Point10 => {
                         self.add_field_compressor(las::v2::LasPoint0Compressor::default())
@@ -603,6 +900,10 @@ impl<'a, W: Write> RecordCompressor<W> for SequentialPointRecordCompressor<'a, W
                     LazItemType::RGB12 => {
                         self.add_field_compressor(las::v2::LasRGBCompressor::default())
                     }
+                    LazItemType::WavePacket13 => {
+                        self.add_field_compressor(las::v1::LasWavepacketCompressor::default())
+                    }
+                    #[cfg(feature = "extra-bytes")]
                     LazItemType::Byte(_) => self.add_field_compressor(
                         las::v2::LasExtraByteCompressor::new(record_item.size as usize),
                     ),
@@ -633,14 +934,17 @@ impl<'a, W: Write> RecordCompressor<W> for SequentialPointRecordCompressor<'a, W
             ChunksIrregular::new(input, self.fields_sizes.iter().copied()),
         );
 
+        let timer = &mut self.timer;
         if self.is_first_compression {
-            for (field_compressor, field_data) in field_compressors_and_data {
-                field_compressor.compress_first(self.encoder.get_mut(), field_data)?;
+            for (i, (field_compressor, field_data)) in field_compressors_and_data.enumerate() {
+                let dst = self.encoder.get_mut();
+                timer.time(i, || field_compressor.compress_first(dst, field_data))?;
             }
             self.is_first_compression = false;
         } else {
-            for (field_compressor, field_data) in field_compressors_and_data {
-                field_compressor.compress_with(&mut self.encoder, field_data)?;
+            let encoder = &mut self.encoder;
+            for (i, (field_compressor, field_data)) in field_compressors_and_data.enumerate() {
+                timer.time(i, || field_compressor.compress_with(encoder, field_data))?;
             }
         }
         Ok(())
@@ -658,6 +962,11 @@ impl<'a, W: Write> RecordCompressor<W> for SequentialPointRecordCompressor<'a, W
         self.record_size = 0;
     }
 
+    #[cfg(feature = "timing")]
+    fn field_times(&self) -> &[std::time::Duration] {
+        self.timer.times()
+    }
+
     fn get_mut(&mut self) -> &mut W {
         self.encoder.get_mut()
     }
@@ -686,6 +995,7 @@ pub struct LayeredPointRecordCompressor<'a, W: Write> {
     dst: W,
     record_size: usize,
     fields_sizes: Vec<usize>,
+    timer: FieldTimer,
 }
 
 impl<'a, W: Write> LayeredPointRecordCompressor<'a, W> {
@@ -696,6 +1006,7 @@ impl<'a, W: Write> LayeredPointRecordCompressor<'a, W> {
             point_count: 0,
             dst,
             fields_sizes: vec![],
+            timer: FieldTimer::default(),
         }
     }
 
@@ -711,6 +1022,7 @@ impl<'a, W: Write> RecordCompressor<W> for LayeredPointRecordCompressor<'a, W> {
     fn set_fields_from(&mut self, laz_items: &Vec<LazItem>) -> crate::Result<()> {
         for item in laz_items {
             match item.version {
+                #[cfg(feature = "format-14")]
                 3 => match item.item_type {
                     LazItemType::Point14 => {
                         self.add_field_compressor(las::v3::LasPoint6Compressor::default())
@@ -722,6 +1034,10 @@ impl<'a, W: Write> RecordCompressor<W> for LayeredPointRecordCompressor<'a, W> {
                         self.add_field_compressor(las::v3::LasRGBCompressor::default());
                         self.add_field_compressor(las::v3::LasNIRCompressor::default());
                     }
+                    LazItemType::WavePacket14 => {
+                        self.add_field_compressor(las::v3::LasWavepacketCompressor::default())
+                    }
+                    #[cfg(feature = "extra-bytes")]
                     LazItemType::Byte14(n) => {
                         self.add_field_compressor(las::v3::LasExtraByteCompressor::new(n as usize));
                     }
@@ -754,13 +1070,19 @@ impl<'a, W: Write> RecordCompressor<W> for LayeredPointRecordCompressor<'a, W> {
             self.fields_sizes.iter().copied(),
         ));
 
+        let timer = &mut self.timer;
         if self.point_count == 0 {
-            for (field_compressor, field_data) in compressors_and_data {
-                field_compressor.init_first_point(&mut self.dst, field_data, &mut context)?;
+            let dst = &mut self.dst;
+            for (i, (field_compressor, field_data)) in compressors_and_data.enumerate() {
+                timer.time(i, || {
+                    field_compressor.init_first_point(dst, field_data, &mut context)
+                })?;
             }
         } else {
-            for (field_compressor, field_data) in compressors_and_data {
-                field_compressor.compress_field_with(field_data, &mut context)?;
+            for (i, (field_compressor, field_data)) in compressors_and_data.enumerate() {
+                timer.time(i, || {
+                    field_compressor.compress_field_with(field_data, &mut context)
+                })?;
             }
         }
         self.point_count += 1;
@@ -773,8 +1095,9 @@ impl<'a, W: Write> RecordCompressor<W> for LayeredPointRecordCompressor<'a, W> {
             for compressor in &mut self.field_compressors {
                 compressor.write_layers_sizes(&mut self.dst)?;
             }
-            for compressor in &mut self.field_compressors {
-                compressor.write_layers(&mut self.dst)?;
+            for (i, compressor) in self.field_compressors.iter_mut().enumerate() {
+                let dst = &mut self.dst;
+                self.timer.time(i, || compressor.write_layers(dst))?;
             }
         }
         Ok(())
@@ -787,6 +1110,11 @@ impl<'a, W: Write> RecordCompressor<W> for LayeredPointRecordCompressor<'a, W> {
         self.field_compressors.clear();
     }
 
+    #[cfg(feature = "timing")]
+    fn field_times(&self) -> &[std::time::Duration] {
+        self.timer.times()
+    }
+
     fn get_mut(&mut self) -> &mut W {
         &mut self.dst
     }
@@ -0,0 +1,325 @@
+//! Spatial partitioning of points in octrees and quadtrees
+//!
+//! Points are assigned to the node, at a given depth, whose bounds contain them.
+//! Nodes at depth `d` split each axis of the root bounds in `2^d` cells of equal size,
+//! a point lying on the boundary between two nodes belongs to the upper one,
+//! except on the upper boundary of the root bounds.
+
+/// Maximum depth of a tree, so that the cells of each axis fit in an `u32`
+pub const MAX_DEPTH: u32 = 31;
+
+/// Axis-aligned bounding box
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bounds {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl Bounds {
+    pub fn new(min: [f64; 3], max: [f64; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns whether the point is inside the bounds, boundaries included
+    pub fn contains(&self, point: [f64; 3]) -> bool {
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
+    /// Returns whether the point is inside the bounds, on the x & y axes only
+    pub fn contains_2d(&self, point: [f64; 2]) -> bool {
+        (0..2).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
+    /// Returns whether the two bounds overlap, boundaries included
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    pub fn center(&self) -> [f64; 3] {
+        let mut center = [0.0; 3];
+        for (i, c) in center.iter_mut().enumerate() {
+            *c = (self.min[i] + self.max[i]) / 2.0;
+        }
+        center
+    }
+
+    /// Returns the smallest cube with the same minimum that contains the bounds,
+    /// so that the nodes of an octree are cubes.
+    pub fn to_cube(&self) -> Self {
+        let size = (0..3)
+            .map(|i| self.max[i] - self.min[i])
+            .fold(0.0f64, f64::max);
+        Self {
+            min: self.min,
+            max: [self.min[0] + size, self.min[1] + size, self.min[2] + size],
+        }
+    }
+}
+
+/// Key of a node in an octree or a quadtree
+///
+/// For quadtrees, `z` is always 0.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeKey {
+    pub depth: u32,
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+impl NodeKey {
+    /// The root node, that covers the whole bounds of the tree
+    pub const ROOT: NodeKey = NodeKey {
+        depth: 0,
+        x: 0,
+        y: 0,
+        z: 0,
+    };
+
+    pub fn new(depth: u32, x: u32, y: u32, z: u32) -> Self {
+        Self { depth, x, y, z }
+    }
+
+    /// Returns the parent node, `None` for the root
+    pub fn parent(&self) -> Option<NodeKey> {
+        if self.depth == 0 {
+            None
+        } else {
+            Some(NodeKey::new(
+                self.depth - 1,
+                self.x / 2,
+                self.y / 2,
+                self.z / 2,
+            ))
+        }
+    }
+
+    /// Returns the 8 children of the node in an octree
+    pub fn octree_children(&self) -> [NodeKey; 8] {
+        let mut children = [*self; 8];
+        for (i, child) in children.iter_mut().enumerate() {
+            let i = i as u32;
+            *child = NodeKey::new(
+                self.depth + 1,
+                self.x * 2 + (i & 1),
+                self.y * 2 + ((i >> 1) & 1),
+                self.z * 2 + ((i >> 2) & 1),
+            );
+        }
+        children
+    }
+
+    /// Returns the 4 children of the node in a quadtree
+    pub fn quadtree_children(&self) -> [NodeKey; 4] {
+        let mut children = [*self; 4];
+        for (i, child) in children.iter_mut().enumerate() {
+            let i = i as u32;
+            *child = NodeKey::new(
+                self.depth + 1,
+                self.x * 2 + (i & 1),
+                self.y * 2 + ((i >> 1) & 1),
+                0,
+            );
+        }
+        children
+    }
+
+    /// Returns whether this node is `other` or one of its descendants
+    pub fn is_in(&self, other: &NodeKey) -> bool {
+        if self.depth < other.depth {
+            return false;
+        }
+        let shift = self.depth - other.depth;
+        (self.x >> shift) == other.x && (self.y >> shift) == other.y && (self.z >> shift) == other.z
+    }
+
+    /// Returns the bounds of the node in a tree whose root has the `root` bounds
+    ///
+    /// For quadtrees, the z bounds are the ones of the `root`.
+    pub fn bounds(&self, root: &Bounds) -> Bounds {
+        let num_cells = f64::from(2u32.pow(self.depth.min(MAX_DEPTH)));
+        let cells = [self.x, self.y, self.z];
+        let mut bounds = *root;
+        for (i, &cell) in cells.iter().enumerate() {
+            let size = (root.max[i] - root.min[i]) / num_cells;
+            bounds.min[i] = root.min[i] + f64::from(cell) * size;
+            bounds.max[i] = root.min[i] + f64::from(cell + 1) * size;
+        }
+        bounds
+    }
+}
+
+/// Index of the cell that contains the `value`, among the `num_cells`
+/// cells splitting `min..=max`.
+fn cell_of(value: f64, min: f64, max: f64, num_cells: u32) -> Option<u32> {
+    if !(min <= value && value <= max) {
+        return None;
+    }
+    let size = max - min;
+    if size <= 0.0 {
+        return Some(0);
+    }
+    let cell = ((value - min) / size * f64::from(num_cells)) as u64;
+    Some(cell.min(u64::from(num_cells - 1)) as u32)
+}
+
+/// Assigns points to the nodes of an octree
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Octree {
+    bounds: Bounds,
+    max_depth: u32,
+}
+
+impl Octree {
+    /// Creates an octree with the `bounds` as root, the depth is clamped to [`MAX_DEPTH`]
+    ///
+    /// Use [`Bounds::to_cube`] to have cubic nodes.
+    pub fn new(bounds: Bounds, max_depth: u32) -> Self {
+        Self {
+            bounds,
+            max_depth: max_depth.min(MAX_DEPTH),
+        }
+    }
+
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Returns the node at `depth` (clamped to the max depth) that contains the point,
+    /// `None` if the point is outside the bounds of the tree.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use laz::spatial::{Bounds, NodeKey, Octree};
+    ///
+    /// let octree = Octree::new(Bounds::new([0.0; 3], [8.0; 3]), 3);
+    /// assert_eq!(octree.node_of([1.0, 5.0, 8.0], 1), Some(NodeKey::new(1, 0, 1, 1)));
+    /// assert_eq!(octree.node_of([9.0, 5.0, 8.0], 1), None);
+    /// ```
+    pub fn node_of(&self, point: [f64; 3], depth: u32) -> Option<NodeKey> {
+        let depth = depth.min(self.max_depth);
+        let num_cells = 2u32.pow(depth);
+        let b = &self.bounds;
+        Some(NodeKey::new(
+            depth,
+            cell_of(point[0], b.min[0], b.max[0], num_cells)?,
+            cell_of(point[1], b.min[1], b.max[1], num_cells)?,
+            cell_of(point[2], b.min[2], b.max[2], num_cells)?,
+        ))
+    }
+
+    /// Returns the node at the max depth that contains the point
+    pub fn leaf_of(&self, point: [f64; 3]) -> Option<NodeKey> {
+        self.node_of(point, self.max_depth)
+    }
+}
+
+/// Assigns points to the nodes of a quadtree, using their x & y coordinates
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quadtree {
+    bounds: Bounds,
+    max_depth: u32,
+}
+
+impl Quadtree {
+    /// Creates a quadtree with the `bounds` as root, the depth is clamped to [`MAX_DEPTH`]
+    ///
+    /// The z bounds are not used.
+    pub fn new(bounds: Bounds, max_depth: u32) -> Self {
+        Self {
+            bounds,
+            max_depth: max_depth.min(MAX_DEPTH),
+        }
+    }
+
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Returns the node at `depth` (clamped to the max depth) that contains the point,
+    /// `None` if the point is outside the bounds of the tree.
+    pub fn node_of(&self, point: [f64; 2], depth: u32) -> Option<NodeKey> {
+        let depth = depth.min(self.max_depth);
+        let num_cells = 2u32.pow(depth);
+        let b = &self.bounds;
+        Some(NodeKey::new(
+            depth,
+            cell_of(point[0], b.min[0], b.max[0], num_cells)?,
+            cell_of(point[1], b.min[1], b.max[1], num_cells)?,
+            0,
+        ))
+    }
+
+    /// Returns the node at the max depth that contains the point
+    pub fn leaf_of(&self, point: [f64; 2]) -> Option<NodeKey> {
+        self.node_of(point, self.max_depth)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_octree_node_of() {
+        let octree = Octree::new(Bounds::new([0.0; 3], [4.0; 3]), 2);
+        assert_eq!(octree.node_of([0.0, 0.0, 0.0], 0), Some(NodeKey::ROOT));
+        assert_eq!(
+            octree.leaf_of([0.5, 1.0, 3.9]),
+            Some(NodeKey::new(2, 0, 1, 3))
+        );
+        // The upper boundary belongs to the last node
+        assert_eq!(octree.leaf_of([4.0; 3]), Some(NodeKey::new(2, 3, 3, 3)));
+        assert_eq!(octree.leaf_of([-0.1, 0.0, 0.0]), None);
+        // Depth is clamped
+        assert_eq!(octree.node_of([4.0; 3], 10).unwrap().depth, 2);
+    }
+
+    #[test]
+    fn test_nodes_are_consistent() {
+        let root = Bounds::new([-10.0, 5.0, 0.0], [30.0, 45.0, 40.0]);
+        let octree = Octree::new(root, 6);
+        let point = [3.3, 21.7, 39.0];
+        let leaf = octree.leaf_of(point).unwrap();
+        assert!(leaf.bounds(&root).contains(point));
+
+        let mut node = leaf;
+        while let Some(parent) = node.parent() {
+            assert!(leaf.is_in(&parent));
+            assert!(parent.octree_children().contains(&node));
+            assert_eq!(octree.node_of(point, parent.depth), Some(parent));
+            assert!(parent.bounds(&root).contains(point));
+            node = parent;
+        }
+        assert_eq!(node, NodeKey::ROOT);
+        assert!(!NodeKey::ROOT.is_in(&leaf));
+    }
+
+    #[test]
+    fn test_quadtree() {
+        let quadtree = Quadtree::new(Bounds::new([0.0, 0.0, 0.0], [2.0, 4.0, 0.0]), 1);
+        assert_eq!(quadtree.leaf_of([1.5, 1.0]), Some(NodeKey::new(1, 1, 0, 0)));
+        assert_eq!(
+            NodeKey::ROOT.quadtree_children()[1],
+            NodeKey::new(1, 1, 0, 0)
+        );
+        let bounds = NodeKey::new(1, 1, 0, 0).bounds(quadtree.bounds());
+        assert_eq!(bounds.min, [1.0, 0.0, 0.0]);
+        assert_eq!(bounds.max, [2.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn test_to_cube() {
+        let cube = Bounds::new([0.0, 1.0, 2.0], [1.0, 5.0, 3.0]).to_cube();
+        assert_eq!(cube.max, [4.0, 5.0, 6.0]);
+    }
+}
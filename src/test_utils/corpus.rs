@@ -0,0 +1,336 @@
+//! Reference LAZ data, for integration tests of downstream crates
+//!
+//! The corpus has an entry for each point format & item version this crate
+//! can compress, with and without extra bytes. The points of an entry are
+//! generated by a [`PointGenerator`](super::PointGenerator) and compressed on demand,
+//! rather than bundled, to keep the package small.
+//!
+//! Each entry pins the SHA-256 of its points and of its compressed bytes,
+//! so a reader can check that it decodes the points exactly, and a writer that
+//! it encodes them as this crate does.
+//!
+//! The compressed bytes are produced by this crate, not by LASzip: the corpus
+//! catches changes of the encoding, and disagreements with this crate, but it is
+//! not a proof of compatibility with LASzip. No LASzip-produced file is bundled
+//! nor downloaded.
+//!
+//! # Example
+//!
+//! ```
+//! # fn main() -> laz::Result<()> {
+//! use laz::test_utils::corpus;
+//!
+//! for entry in corpus::entries() {
+//!     let compressed = entry.compressed()?;
+//!     let mut points = vec![0u8; entry.num_points * entry.laz_vlr().items_size() as usize];
+//!     // Here, the decompression of the crate to test
+//!     laz::decompress_buffer(&compressed, &mut points, entry.laz_vlr())?;
+//!     assert!(entry.has_points_digest(&points), "{}", entry.name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::io::Cursor;
+
+use super::PointGenerator;
+use crate::laszip::PointDigest;
+use crate::{LazItemRecordBuilder, LazVlr, LazVlrBuilder};
+
+/// Number of points of each entry
+const NUM_POINTS: usize = 2_500;
+/// Chunk size of each entry, the last chunk is not full
+const CHUNK_SIZE: u32 = 1_000;
+/// Seed of the points of each entry
+const SEED: u64 = 42;
+
+/// An entry of the corpus
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CorpusEntry {
+    /// Unique name, e.g. `"format-3-v2-extra-bytes"`
+    pub name: &'static str,
+    pub point_format_id: u8,
+    pub num_extra_bytes: u16,
+    /// Version of the compression of the items
+    pub version: u16,
+    pub num_points: usize,
+    /// SHA-256 of the uncompressed points, in hexadecimal
+    pub points_sha256: &'static str,
+    /// SHA-256 of the compressed points (chunk table included), in hexadecimal
+    pub compressed_sha256: &'static str,
+}
+
+impl CorpusEntry {
+    /// Returns the vlr the points are compressed with
+    pub fn laz_vlr(&self) -> LazVlr {
+        let mut items = LazItemRecordBuilder::default_for_point_format_id(
+            self.point_format_id,
+            self.num_extra_bytes,
+        )
+        .expect("The corpus only has supported point formats");
+        for item in &mut items {
+            item.version = self.version;
+        }
+        LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(CHUNK_SIZE)
+            .build()
+    }
+
+    /// Returns the uncompressed points
+    pub fn points(&self) -> Vec<u8> {
+        PointGenerator::new(self.point_format_id, self.num_extra_bytes, SEED)
+            .expect("The corpus only has supported point formats")
+            .generate(self.num_points)
+    }
+
+    /// Returns the compressed points, as they would be found in a LAZ file
+    /// after its header & vlrs: the offset to the chunk table, the chunks then the chunk table
+    pub fn compressed(&self) -> crate::Result<Vec<u8>> {
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        crate::compress_buffer(&mut compressed, &self.points(), self.laz_vlr())?;
+        Ok(compressed.into_inner())
+    }
+
+    /// Returns whether `points` are the points of the entry
+    pub fn has_points_digest(&self, points: &[u8]) -> bool {
+        sha256_hex(points) == self.points_sha256
+    }
+
+    /// Returns whether `compressed` are the compressed points of the entry
+    pub fn has_compressed_digest(&self, compressed: &[u8]) -> bool {
+        sha256_hex(compressed) == self.compressed_sha256
+    }
+}
+
+/// Returns all the entries of the corpus
+pub fn entries() -> &'static [CorpusEntry] {
+    &ENTRIES
+}
+
+/// Returns the entry named `name`
+pub fn entry(name: &str) -> Option<&'static CorpusEntry> {
+    ENTRIES.iter().find(|entry| entry.name == name)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut digest = PointDigest::new();
+    digest.update(bytes);
+    digest
+        .finish()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+macro_rules! corpus_entry {
+    ($name:literal, $format:literal, $extra_bytes:literal, $version:literal, $points:literal, $compressed:literal) => {
+        CorpusEntry {
+            name: $name,
+            point_format_id: $format,
+            num_extra_bytes: $extra_bytes,
+            version: $version,
+            num_points: NUM_POINTS,
+            points_sha256: $points,
+            compressed_sha256: $compressed,
+        }
+    };
+}
+
+const ENTRIES: [CorpusEntry; 22] = [
+    corpus_entry!(
+        "format-0-v1",
+        0,
+        0,
+        1,
+        "e6d26afcd3432889469e933d78635da980301bf0a8a10ffe1b07a9b24381742c",
+        "cb5be22e6cc8265ad53b452e1950ca5758a0511fccb7a773f18338831fc972ec"
+    ),
+    corpus_entry!(
+        "format-0-v1-extra-bytes",
+        0,
+        3,
+        1,
+        "37409a110281b426773a7b99e06b96c258068d1daa70fc57cb043a738a40b186",
+        "d677f85be33149e1396237690d9a8a391a500df74fb4c3332caa42bfde3df045"
+    ),
+    corpus_entry!(
+        "format-0-v2",
+        0,
+        0,
+        2,
+        "e6d26afcd3432889469e933d78635da980301bf0a8a10ffe1b07a9b24381742c",
+        "b35e6b03ce1bfd3fbe04772f828798fcee9e18e30bc95cc5c849282471db3907"
+    ),
+    corpus_entry!(
+        "format-0-v2-extra-bytes",
+        0,
+        3,
+        2,
+        "37409a110281b426773a7b99e06b96c258068d1daa70fc57cb043a738a40b186",
+        "237f6c7d2c847bff2833c25d3eecdb55adce49b3006964658353e404126396f3"
+    ),
+    corpus_entry!(
+        "format-1-v1",
+        1,
+        0,
+        1,
+        "a5c64879c4c0914a552c1f93c632122a024f608b96e991ed8b6fcff4d76b05b3",
+        "f92df731f2cb50d577a540105011e2007a3bd15b057079d92477286c8e658df8"
+    ),
+    corpus_entry!(
+        "format-1-v1-extra-bytes",
+        1,
+        3,
+        1,
+        "0d144e742087f07eea7cf835e317407456b3d84f8d21ecec06e8e54479330e67",
+        "73edd7f9ab7e6d5e2faa970c7440f82a741838da726f64425fb693b1d679d4f5"
+    ),
+    corpus_entry!(
+        "format-1-v2",
+        1,
+        0,
+        2,
+        "a5c64879c4c0914a552c1f93c632122a024f608b96e991ed8b6fcff4d76b05b3",
+        "f164baf556a882fe197c78665539d26165914be7a49bc04f298877a60e27d7cf"
+    ),
+    corpus_entry!(
+        "format-1-v2-extra-bytes",
+        1,
+        3,
+        2,
+        "0d144e742087f07eea7cf835e317407456b3d84f8d21ecec06e8e54479330e67",
+        "fd15afd50974969b55556f1a9f8fb73b62fdcf7b5df4e73c32318510a40eeb58"
+    ),
+    corpus_entry!(
+        "format-2-v1",
+        2,
+        0,
+        1,
+        "3d76b0369c11e34e7c24afd97521f305ed2647b4f197e78e402bd7a7404b5f61",
+        "c34ec8f7c467d7e7d881297db595ad1890f7979d0168a17db537a531f5329b8e"
+    ),
+    corpus_entry!(
+        "format-2-v1-extra-bytes",
+        2,
+        3,
+        1,
+        "7c30fd194e6dbf8119e81fdf10d3641062fd4cfed668059d03203a0c53da5bf1",
+        "c690c2b453936f41a905b711ec4157913e75ac21b21a94b3c0f5ccd7238a50c8"
+    ),
+    corpus_entry!(
+        "format-2-v2",
+        2,
+        0,
+        2,
+        "3d76b0369c11e34e7c24afd97521f305ed2647b4f197e78e402bd7a7404b5f61",
+        "9abcd085d9a15cdd3d8b1aa44bf5ab951c2ad4aeffad630bd844e543f86f1b9b"
+    ),
+    corpus_entry!(
+        "format-2-v2-extra-bytes",
+        2,
+        3,
+        2,
+        "7c30fd194e6dbf8119e81fdf10d3641062fd4cfed668059d03203a0c53da5bf1",
+        "1d732d5ecf88f3c88b5c6d5c09e06f9113b2620d6821703e9a04019a77e822c2"
+    ),
+    corpus_entry!(
+        "format-3-v1",
+        3,
+        0,
+        1,
+        "3167928ea1a321ce4f440ed4239223a38cc01268116c5f38a87aabb46e53536b",
+        "00afc9f518757078a447cf01499e7efd272fe32cd46a0b2230fe8fc21fdbe230"
+    ),
+    corpus_entry!(
+        "format-3-v1-extra-bytes",
+        3,
+        3,
+        1,
+        "38c1bc26e712fa356eb5bc27c9bb347d6bb32cc99a826efadaa5f5e7c5034fcf",
+        "5cf103ac3b9b70ca9188b22a9eefa64787c584bf3183debcec3930decb55d57e"
+    ),
+    corpus_entry!(
+        "format-3-v2",
+        3,
+        0,
+        2,
+        "3167928ea1a321ce4f440ed4239223a38cc01268116c5f38a87aabb46e53536b",
+        "09b77e6d3a99b40f0760ee2d5b83d37ee68c8461af5cf5b3914007e551748dbf"
+    ),
+    corpus_entry!(
+        "format-3-v2-extra-bytes",
+        3,
+        3,
+        2,
+        "38c1bc26e712fa356eb5bc27c9bb347d6bb32cc99a826efadaa5f5e7c5034fcf",
+        "4d75d919317f6f453049d6a56766546e20ec0eb2533ba6acb43e60609d1dee52"
+    ),
+    corpus_entry!(
+        "format-6-v3",
+        6,
+        0,
+        3,
+        "c634cee807163adf6618de7c6f25d61008410b079ea5399f2e80a5b1c643c373",
+        "7524b16300649d9a1a8fc08e016d3859b3031ab6365586e6757d9e57afe4c9a3"
+    ),
+    corpus_entry!(
+        "format-6-v3-extra-bytes",
+        6,
+        3,
+        3,
+        "dbb9fa7111f6a8f30b1c88897cf9d990268aea4f0559bc5eb4add3c3b3ebdb87",
+        "fb6eb515a8e5f28f2f11786387b289a930997bfbb933d3d2c32cb37e8203788c"
+    ),
+    corpus_entry!(
+        "format-7-v3",
+        7,
+        0,
+        3,
+        "3d7ad237d6a9f91188ad1f63e6d918d6ec43996683dd1a5d40b4295e3b199f7e",
+        "6d2b954cc01f488c3c3b0b2930880a7965b3e52f1e842f17b6f6ea8006dc93ef"
+    ),
+    corpus_entry!(
+        "format-7-v3-extra-bytes",
+        7,
+        3,
+        3,
+        "d3df3627b668388c35d2ddd6466cf5b4826381c94bf92eee88da4da9be40ce5c",
+        "13b16a590c212f12b0fba0e6ebed5f65c21a69b6158c992c15aa90a8f0fd7937"
+    ),
+    corpus_entry!(
+        "format-8-v3",
+        8,
+        0,
+        3,
+        "8255efc2bb0b7badd8b07d36b8c598ab85d4556c8f5cb5ab60b3ac0bfe91f118",
+        "c5411068f1b113ac389f3d3750f8c52b0fed8278ed4865dc8e683c7b8b86714b"
+    ),
+    corpus_entry!(
+        "format-8-v3-extra-bytes",
+        8,
+        3,
+        3,
+        "f3fa688952a91864ccb6331a68e33aab6c4741f8f573c14e6f6c2f76ab6b3280",
+        "e97501b1969fb49fcfc9db1f926334fcc223fd4870a86894d342105973841b76"
+    ),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_corpus_digests() {
+        for entry in entries() {
+            let points = entry.points();
+            assert!(entry.has_points_digest(&points), "{}", entry.name);
+            let compressed = entry.compressed().unwrap();
+            assert!(entry.has_compressed_digest(&compressed), "{}", entry.name);
+
+            let mut decompressed = vec![0u8; points.len()];
+            crate::decompress_buffer(&compressed, &mut decompressed, entry.laz_vlr()).unwrap();
+            assert!(entry.has_points_digest(&decompressed), "{}", entry.name);
+        }
+        assert!(entry("format-8-v3").is_some());
+        assert!(entry("format-4-v2").is_none());
+    }
+}
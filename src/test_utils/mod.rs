@@ -0,0 +1,344 @@
+//! Deterministic generation of points, for tests and benchmarks
+//!
+//! The points look like the ones of an airborne scan: the coordinates follow scan lines
+//! over a smooth terrain, pulses have up to 3 returns sharing a GPS time that increases,
+//! and the intensity & colors depend on the classification.
+//!
+//! The same seed always gives the same points, whatever the platform.
+//!
+//! # Example
+//!
+//! ```
+//! # fn main() -> laz::Result<()> {
+//! use laz::test_utils::PointGenerator;
+//!
+//! let mut generator = PointGenerator::new(3, 0, 42)?;
+//! let points = generator.generate(1_000);
+//! let vlr = generator.laz_vlr();
+//!
+//! let mut compressed = std::io::Cursor::new(Vec::<u8>::new());
+//! laz::compress_buffer(&mut compressed, &points, vlr.clone())?;
+//! let mut decompressed = vec![0u8; points.len()];
+//! laz::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr)?;
+//! assert_eq!(decompressed, points);
+//! # Ok(())
+//! # }
+//! ```
+use crate::las::wavepacket::LasWavepacket;
+use crate::packers::Packable;
+use crate::{LazItemRecordBuilder, LazVlr, LazVlrBuilder};
+
+#[cfg(feature = "digest")]
+pub mod corpus;
+
+/// Number of pulses in a scan line
+const PULSES_PER_LINE: u32 = 500;
+/// Number of scan lines in a flight line
+const LINES_PER_FLIGHT_LINE: u32 = 400;
+/// Spacing between pulses, in coordinate units
+const PULSE_SPACING: i32 = 25;
+/// Time between two pulses
+const PULSE_PERIOD: f64 = 1e-5;
+/// Size in bytes of the waveform of a pulse
+const WAVEFORM_PACKET_SIZE: u32 = 256;
+
+/// Generator of pseudo random numbers (SplitMix64)
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`
+    fn below(&mut self, n: u32) -> u32 {
+        (((self.next_u64() >> 32) * u64::from(n)) >> 32) as u32
+    }
+
+    /// Returns a number in `-amplitude..=amplitude`
+    fn noise(&mut self, amplitude: i32) -> i32 {
+        self.below(2 * amplitude as u32 + 1) as i32 - amplitude
+    }
+}
+
+/// Generates points of a point format, see the [module](self) documentation
+#[derive(Debug, Clone)]
+pub struct PointGenerator {
+    rng: Rng,
+    point_format_id: u8,
+    num_extra_bytes: u16,
+    point_size: usize,
+    /// Index of the next pulse
+    pulse: u64,
+    /// Returns of the current pulse left to generate
+    returns_left: u8,
+    number_of_returns: u8,
+    /// Height of the first return of the current pulse, above the ground
+    canopy_height: i32,
+    gps_time: f64,
+    /// Offset of the waveform of the current pulse
+    waveform_offset: u64,
+    extra_bytes: Vec<u8>,
+}
+
+impl PointGenerator {
+    /// Creates a generator of points of the `point_format_id`, followed by
+    /// `num_extra_bytes` extra bytes, the `seed` fully determines the points.
+    ///
+    /// Returns an error for the point formats that the crate cannot compress.
+    pub fn new(point_format_id: u8, num_extra_bytes: u16, seed: u64) -> crate::Result<Self> {
+        let items =
+            LazItemRecordBuilder::default_for_point_format_id(point_format_id, num_extra_bytes)?;
+        let point_size = items.iter().map(|item| usize::from(item.size())).sum();
+        Ok(Self {
+            rng: Rng(seed),
+            point_format_id,
+            num_extra_bytes,
+            point_size,
+            pulse: 0,
+            returns_left: 0,
+            number_of_returns: 0,
+            canopy_height: 0,
+            gps_time: 250_000.0 + f64::from(seed as u16),
+            waveform_offset: 0,
+            extra_bytes: vec![0u8; usize::from(num_extra_bytes)],
+        })
+    }
+
+    pub fn point_format_id(&self) -> u8 {
+        self.point_format_id
+    }
+
+    pub fn num_extra_bytes(&self) -> u16 {
+        self.num_extra_bytes
+    }
+
+    /// Size in bytes of a point
+    pub fn point_size(&self) -> usize {
+        self.point_size
+    }
+
+    /// Returns the vlr to compress the points with
+    pub fn laz_vlr(&self) -> LazVlr {
+        LazVlrBuilder::default()
+            .with_point_format(self.point_format_id, self.num_extra_bytes)
+            .unwrap()
+            .build()
+    }
+
+    /// Returns the next `num_points` points
+    pub fn generate(&mut self, num_points: usize) -> Vec<u8> {
+        let mut points = vec![0u8; num_points * self.point_size];
+        self.fill(&mut points);
+        points
+    }
+
+    /// Fills the `points` with the next points
+    ///
+    /// # Panics
+    ///
+    /// If the size of `points` is not a multiple of the point size
+    pub fn fill(&mut self, points: &mut [u8]) {
+        assert!(
+            points.len().is_multiple_of(self.point_size),
+            "the size of the buffer is not a multiple of the point size"
+        );
+        for point in points.chunks_exact_mut(self.point_size) {
+            self.next_point(point);
+        }
+    }
+
+    fn next_point(&mut self, point: &mut [u8]) {
+        if self.returns_left == 0 {
+            self.pulse += 1;
+            self.gps_time += PULSE_PERIOD;
+            self.number_of_returns = match self.rng.below(10) {
+                0..=5 => 1,
+                6..=8 => 2,
+                _ => 3,
+            };
+            self.returns_left = self.number_of_returns;
+            self.canopy_height = if self.number_of_returns > 1 {
+                500 + self.rng.below(1_500) as i32
+            } else {
+                0
+            };
+        }
+        let return_number = self.number_of_returns - self.returns_left + 1;
+        self.returns_left -= 1;
+
+        let pulse_in_line = (self.pulse % u64::from(PULSES_PER_LINE)) as u32;
+        let line = (self.pulse / u64::from(PULSES_PER_LINE)) as u32;
+        let flight_line = line / LINES_PER_FLIGHT_LINE;
+        // Scan lines go back & forth
+        let scan_direction = line.is_multiple_of(2);
+        let position_in_line = if scan_direction {
+            pulse_in_line
+        } else {
+            PULSES_PER_LINE - 1 - pulse_in_line
+        };
+
+        let x = position_in_line as i32 * PULSE_SPACING + self.rng.noise(5);
+        let y = line as i32 * PULSE_SPACING + self.rng.noise(5);
+        let ground = terrain_height(x, y);
+        // Returns go from the top of the canopy down to the ground
+        let height = if return_number == self.number_of_returns {
+            0
+        } else {
+            self.canopy_height / i32::from(return_number)
+        };
+        let z = ground + height + self.rng.noise(3);
+
+        let classification = if height == 0 {
+            2 // Ground
+        } else if self.canopy_height > 1_600 {
+            6 // Building
+        } else {
+            5 // High vegetation
+        };
+        let intensity = (match classification {
+            2 => 800,
+            6 => 1_500,
+            _ => 300,
+        } + self.rng.below(200)) as u16
+            / u16::from(return_number);
+        let edge_of_flight_line = pulse_in_line == PULSES_PER_LINE - 1;
+        let scan_angle =
+            (f64::from(position_in_line) / f64::from(PULSES_PER_LINE) * 40.0 - 20.0).round() as i16;
+        let point_source_id = flight_line as u16 + 1;
+        let user_data = 0u8;
+
+        point[0..4].copy_from_slice(&x.to_le_bytes());
+        point[4..8].copy_from_slice(&y.to_le_bytes());
+        point[8..12].copy_from_slice(&z.to_le_bytes());
+        point[12..14].copy_from_slice(&intensity.to_le_bytes());
+        let mut offset = if self.point_format_id < 6 {
+            point[14] = (return_number & 0x7)
+                | (self.number_of_returns & 0x7) << 3
+                | (scan_direction as u8) << 6
+                | (edge_of_flight_line as u8) << 7;
+            point[15] = classification;
+            point[16] = scan_angle as i8 as u8;
+            point[17] = user_data;
+            point[18..20].copy_from_slice(&point_source_id.to_le_bytes());
+            if matches!(self.point_format_id, 1 | 3 | 4 | 5) {
+                point[20..28].copy_from_slice(&self.gps_time.to_le_bytes());
+                28
+            } else {
+                20
+            }
+        } else {
+            let scanner_channel = (flight_line % 2) as u8;
+            point[14] = (return_number & 0xf) | (self.number_of_returns & 0xf) << 4;
+            point[15] = scanner_channel << 4
+                | (scan_direction as u8) << 6
+                | (edge_of_flight_line as u8) << 7;
+            point[16] = classification;
+            point[17] = user_data;
+            // Scan angle in 0.006 degrees
+            point[18..20].copy_from_slice(&((f64::from(scan_angle) / 0.006) as i16).to_le_bytes());
+            point[20..22].copy_from_slice(&point_source_id.to_le_bytes());
+            point[22..30].copy_from_slice(&self.gps_time.to_le_bytes());
+            30
+        };
+
+        if matches!(self.point_format_id, 2 | 3 | 5 | 7 | 8 | 10) {
+            let base: [u32; 3] = match classification {
+                2 => [30_000, 25_000, 15_000],
+                6 => [40_000, 40_000, 42_000],
+                _ => [12_000, 35_000, 10_000],
+            };
+            for (i, &value) in base.iter().enumerate() {
+                let value = (value + self.rng.below(4_000)) as u16;
+                point[offset + 2 * i..offset + 2 * i + 2].copy_from_slice(&value.to_le_bytes());
+            }
+            offset += 6;
+        }
+        if matches!(self.point_format_id, 8 | 10) {
+            let nir = if classification == 5 { 45_000 } else { 20_000 } + self.rng.below(4_000);
+            point[offset..offset + 2].copy_from_slice(&(nir as u16).to_le_bytes());
+            offset += 2;
+        }
+        if matches!(self.point_format_id, 4 | 5 | 9 | 10) {
+            // The waveforms of the pulses are stored one after the other
+            if return_number == 1 {
+                self.waveform_offset += u64::from(WAVEFORM_PACKET_SIZE);
+            }
+            let wavepacket = LasWavepacket {
+                descriptor_index: 1,
+                offset: self.waveform_offset,
+                packet_size: WAVEFORM_PACKET_SIZE,
+                // Picoseconds from the first sample, 2 * height / the speed of light (in cm/ps)
+                return_point: 1_000.0 + (self.canopy_height - height) as f32 / 0.015,
+                x_t: (f64::from(scan_angle).to_radians().sin() * 1e-4) as f32,
+                y_t: 0.0,
+                z_t: -1.5e-4,
+            };
+            wavepacket.pack_into(&mut point[offset..offset + LasWavepacket::SIZE]);
+            offset += LasWavepacket::SIZE;
+        }
+
+        // Extra bytes drift slowly, like measurements would
+        for byte in self.extra_bytes.iter_mut() {
+            *byte = byte.wrapping_add(self.rng.below(3) as u8);
+        }
+        point[offset..].copy_from_slice(&self.extra_bytes);
+    }
+}
+
+/// Height of the ground, rolling hills
+fn terrain_height(x: i32, y: i32) -> i32 {
+    let x = f64::from(x) / 2_000.0;
+    let y = f64::from(y) / 3_000.0;
+    (10_000.0 + 800.0 * x.sin() + 600.0 * (y + 0.5 * x).cos()) as i32
+}
+
+/// Returns `num_points` simple points of `point_size` bytes, for the tests of the crate:
+/// the x coordinate of the i-th point is i, and its other bytes are `i % 7`.
+#[cfg(test)]
+pub(crate) fn points(num_points: usize, point_size: usize) -> Vec<u8> {
+    (0..num_points)
+        .flat_map(|i| {
+            let mut point = vec![(i % 7) as u8; point_size];
+            point[..4].copy_from_slice(&(i as i32).to_le_bytes());
+            point
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generator_is_deterministic() {
+        let first = PointGenerator::new(3, 2, 7).unwrap().generate(100);
+        let second = PointGenerator::new(3, 2, 7).unwrap().generate(100);
+        let other = PointGenerator::new(3, 2, 8).unwrap().generate(100);
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert!(PointGenerator::new(11, 0, 7).is_err());
+    }
+
+    #[test]
+    fn test_generated_points_roundtrip() {
+        for &point_format_id in &[0u8, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10] {
+            let mut generator = PointGenerator::new(point_format_id, 3, 1).unwrap();
+            let points = generator.generate(3_000);
+            assert_eq!(points.len(), 3_000 * generator.point_size());
+            let vlr = generator.laz_vlr();
+
+            let mut compressed = std::io::Cursor::new(Vec::<u8>::new());
+            crate::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+            assert!(compressed.get_ref().len() < points.len() / 2);
+            let mut decompressed = vec![0u8; points.len()];
+            crate::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr).unwrap();
+            assert_eq!(decompressed, points, "point format {}", point_format_id);
+        }
+    }
+}
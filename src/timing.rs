@@ -0,0 +1,176 @@
+//! Time measurements of the compression & decompression, per field and per chunk
+//!
+//! They are only collected when the `timing` feature is enabled.
+#[cfg(feature = "timing")]
+use std::time::Duration;
+
+#[cfg(feature = "timing")]
+use crate::laszip::LazItemType;
+
+/// Time spent in the codecs of each field
+#[derive(Debug, Default, Clone)]
+pub(crate) struct FieldTimer {
+    #[cfg(feature = "timing")]
+    times: Vec<Duration>,
+}
+
+impl FieldTimer {
+    /// Runs `f`, adding the time it took to the time of the field at `index`
+    #[inline(always)]
+    #[cfg_attr(not(feature = "timing"), allow(unused_variables))]
+    pub(crate) fn time<T>(&mut self, index: usize, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "timing")]
+        {
+            let start = std::time::Instant::now();
+            let result = f();
+            if self.times.len() <= index {
+                self.times.resize(index + 1, Duration::ZERO);
+            }
+            self.times[index] += start.elapsed();
+            result
+        }
+        #[cfg(not(feature = "timing"))]
+        {
+            f()
+        }
+    }
+
+    /// Returns the time spent in each field
+    #[cfg(feature = "timing")]
+    pub(crate) fn times(&self) -> &[Duration] {
+        &self.times
+    }
+}
+
+/// Times spent compressing or decompressing, see
+/// [`LasZipCompressor::timing_report`](crate::LasZipCompressor::timing_report) and
+/// [`LasZipDecompressor::timing_report`](crate::LasZipDecompressor::timing_report).
+#[cfg(feature = "timing")]
+#[derive(Debug, Default, Clone)]
+pub struct TimingReport {
+    /// Time spent in the codec of each field, in the order of the items
+    pub fields: Vec<(LazItemType, Duration)>,
+    /// Time spent in the codecs, for each chunk that was finished
+    pub chunks: Vec<Duration>,
+}
+
+#[cfg(feature = "timing")]
+impl TimingReport {
+    /// Returns the time spent in all the codecs
+    pub fn total(&self) -> Duration {
+        self.fields.iter().map(|(_, time)| *time).sum()
+    }
+}
+
+/// Keeps track of the time of each chunk
+#[cfg(feature = "timing")]
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ChunkTimer {
+    chunks: Vec<Duration>,
+    /// Total time of the fields when the current chunk started
+    chunk_start: Duration,
+}
+
+#[cfg(feature = "timing")]
+impl ChunkTimer {
+    /// To be called when a chunk is finished, with the current times of the fields
+    pub(crate) fn finish_chunk(&mut self, field_times: &[Duration]) {
+        let total = field_times.iter().sum::<Duration>();
+        self.chunks.push(total.saturating_sub(self.chunk_start));
+        self.chunk_start = total;
+    }
+
+    pub(crate) fn report(
+        &self,
+        items: &[crate::LazItem],
+        field_times: &[Duration],
+    ) -> TimingReport {
+        let mut field_times = field_times.iter();
+        TimingReport {
+            fields: items
+                .iter()
+                .map(|item| {
+                    let time = field_times
+                        .by_ref()
+                        .take(num_field_codecs(item.item_type()))
+                        .sum();
+                    (item.item_type(), time)
+                })
+                .collect(),
+            chunks: self.chunks.clone(),
+        }
+    }
+}
+
+/// Returns the number of field codecs of the items of the `item_type`,
+/// the RGB and the NIR of `RGBNIR14` have their own codecs
+#[cfg(feature = "timing")]
+fn num_field_codecs(item_type: LazItemType) -> usize {
+    match item_type {
+        LazItemType::RGBNIR14 => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(all(test, feature = "timing"))]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils;
+    use crate::{
+        LasZipCompressor, LasZipDecompressor, LazItem, LazItemRecordBuilder, LazVlrBuilder,
+    };
+
+    #[test]
+    fn test_report_sums_the_field_times_of_each_item() {
+        let items = [
+            LazItem::new(LazItemType::Point14, 3),
+            LazItem::new(LazItemType::RGBNIR14, 3),
+            LazItem::new(LazItemType::Byte14(2), 3),
+        ];
+        let field_times = [1, 2, 3, 4].map(Duration::from_millis);
+        let report = ChunkTimer::default().report(&items, &field_times);
+        assert_eq!(
+            report.fields,
+            vec![
+                (LazItemType::Point14, Duration::from_millis(1)),
+                (LazItemType::RGBNIR14, Duration::from_millis(5)),
+                (LazItemType::Byte14(2), Duration::from_millis(4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timing_report() {
+        let items = LazItemRecordBuilder::default_version_of::<crate::las::Point3>(0);
+        let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+        let points = test_utils::points(35, 34);
+
+        let mut compressor =
+            LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+        compressor.compress_many(&points).unwrap();
+        compressor.done().unwrap();
+        let report = compressor.timing_report();
+        let item_types = report.fields.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+        assert_eq!(
+            item_types,
+            vec![
+                LazItemType::Point10,
+                LazItemType::GpsTime,
+                LazItemType::RGB12
+            ]
+        );
+        assert_eq!(report.chunks.len(), 4);
+        assert!(report.chunks.iter().sum::<std::time::Duration>() <= report.total());
+
+        let compressed = compressor.into_inner().into_inner();
+        let mut decompressor = LasZipDecompressor::new(Cursor::new(compressed), vlr).unwrap();
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        let report = decompressor.timing_report();
+        assert_eq!(report.fields.len(), 3);
+        // The last chunk is not known to be finished
+        assert_eq!(report.chunks.len(), 3);
+    }
+}
@@ -0,0 +1,119 @@
+//! The compressors & decompressors can borrow the file they read from or
+//! write to, so that it can still be used once the points are done, e.g. to write EVLRs
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use laz::laszip::{DowngradingLasZipCompressor, TypedLasZipCompressor};
+use laz::{LasZipCompressor, LasZipDecompressor, LazItemRecordBuilder, LazVlr, LazVlrBuilder};
+
+const HEADER: &[u8] = b"header";
+const TRAILER: &[u8] = b"evlrs";
+
+fn points(vlr: &LazVlr, num_points: usize) -> Vec<u8> {
+    let point_size = vlr.items_size() as usize;
+    let mut points = vec![0u8; num_points * point_size];
+    for (i, point) in points.chunks_exact_mut(point_size).enumerate() {
+        point[..4].copy_from_slice(&(i as i32).to_le_bytes());
+        // return number & number of returns
+        point[14] = if point_size == 20 { 0x9 } else { 0x11 };
+    }
+    points
+}
+
+fn file_with_header() -> Cursor<Vec<u8>> {
+    let mut file = Cursor::new(Vec::<u8>::new());
+    file.write_all(HEADER).unwrap();
+    file
+}
+
+/// Writes the trailer after the points and checks that the points decompress
+/// fine, using a borrowed file
+fn check_file(mut file: Cursor<Vec<u8>>, vlr: LazVlr, expected: &[u8]) {
+    file.write_all(TRAILER).unwrap();
+
+    file.seek(SeekFrom::Start(HEADER.len() as u64)).unwrap();
+    let mut decompressed = vec![0u8; expected.len()];
+    {
+        let mut decompressor = LasZipDecompressor::new(&mut file, vlr).unwrap();
+        decompressor.decompress_many(&mut decompressed).unwrap();
+    }
+    assert_eq!(decompressed, expected);
+
+    file.seek(SeekFrom::End(-(TRAILER.len() as i64))).unwrap();
+    let mut trailer = Vec::new();
+    file.read_to_end(&mut trailer).unwrap();
+    assert_eq!(trailer, TRAILER);
+}
+
+#[test]
+fn test_borrowed_compressor() {
+    let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+    let points = points(&vlr, 25);
+
+    let mut file = file_with_header();
+    let mut compressor = LasZipCompressor::new(&mut file, vlr.clone()).unwrap();
+    compressor.compress_many(&points).unwrap();
+    compressor.finish().unwrap();
+    check_file(file, vlr, &points);
+}
+
+#[test]
+fn test_borrowed_typed_compressor() {
+    let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    let mut file = file_with_header();
+    let mut compressor =
+        TypedLasZipCompressor::with_fixed_size_chunks(&mut file, items, 10).unwrap();
+    let vlr = compressor.vlr().clone();
+    let points = points(&vlr, 25);
+    compressor.compress_many(&points).unwrap();
+    compressor.done().unwrap();
+    check_file(file, vlr, &points);
+}
+
+#[test]
+fn test_borrowed_downgrading_compressor() {
+    let mut file = file_with_header();
+    let mut compressor = DowngradingLasZipCompressor::new(&mut file, 6, 0).unwrap();
+    let vlr = compressor.vlr().clone();
+    let extended_points = points(
+        &LazVlrBuilder::default()
+            .with_point_format(6, 0)
+            .unwrap()
+            .build(),
+        25,
+    );
+    compressor.compress_many(&extended_points).unwrap();
+    compressor.finish().unwrap();
+
+    let mut expected = vec![0u8; 25 * vlr.items_size() as usize];
+    for (extended, legacy) in extended_points
+        .chunks_exact(30)
+        .zip(expected.chunks_exact_mut(vlr.items_size() as usize))
+    {
+        laz::laszip::downgrade_point(6, extended, legacy);
+    }
+    check_file(file, vlr, &expected);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_borrowed_par_compressor_and_decompressor() {
+    let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+    let points = points(&vlr, 25);
+
+    let mut file = file_with_header();
+    let mut compressor = laz::ParLasZipCompressor::new(&mut file, vlr.clone()).unwrap();
+    compressor.compress_many(&points).unwrap();
+    compressor.finish().unwrap();
+
+    file.seek(SeekFrom::Start(HEADER.len() as u64)).unwrap();
+    let mut decompressed = vec![0u8; points.len()];
+    {
+        let mut decompressor = laz::ParLasZipDecompressor::new(&mut file, vlr.clone()).unwrap();
+        decompressor.decompress_many(&mut decompressed).unwrap();
+    }
+    assert_eq!(decompressed, points);
+    file.seek(SeekFrom::End(0)).unwrap();
+    check_file(file, vlr, &points);
+}
@@ -0,0 +1,136 @@
+//! A custom record layout, that stores the points uncompressed,
+//! plugged into the chunked compressor & decompressor
+use std::io::{Cursor, Read, Seek, Write};
+
+use laz::record::{RecordCompressor, RecordDecompressor};
+use laz::{LasZipCompressor, LasZipDecompressor, LazItem, LazItemRecordBuilder, LazVlrBuilder};
+
+fn size_of_items(laz_items: &[LazItem]) -> usize {
+    laz_items.iter().map(|item| item.size() as usize).sum()
+}
+
+struct RawRecordCompressor<W> {
+    output: W,
+    record_size: usize,
+}
+
+impl<W: Write> RecordCompressor<W> for RawRecordCompressor<W> {
+    fn set_fields_from(&mut self, laz_items: &Vec<LazItem>) -> laz::Result<()> {
+        self.record_size = size_of_items(laz_items);
+        Ok(())
+    }
+
+    fn record_size(&self) -> usize {
+        self.record_size
+    }
+
+    fn compress_next(&mut self, input: &[u8]) -> std::io::Result<()> {
+        self.output.write_all(&input[..self.record_size])
+    }
+
+    fn done(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.record_size = 0;
+    }
+
+    fn get_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+
+    fn get(&self) -> &W {
+        &self.output
+    }
+
+    fn into_inner(self) -> W {
+        self.output
+    }
+
+    fn box_into_inner(self: Box<Self>) -> W {
+        self.output
+    }
+}
+
+struct RawRecordDecompressor<R> {
+    input: R,
+    record_size: usize,
+}
+
+impl<R: Read + Seek> RecordDecompressor<R> for RawRecordDecompressor<R> {
+    fn set_fields_from(&mut self, laz_items: &Vec<LazItem>) -> laz::Result<()> {
+        self.record_size = size_of_items(laz_items);
+        Ok(())
+    }
+
+    fn record_size(&self) -> usize {
+        self.record_size
+    }
+
+    fn decompress_next(&mut self, out: &mut [u8]) -> std::io::Result<()> {
+        self.input.read_exact(&mut out[..self.record_size])
+    }
+
+    fn reset(&mut self) {
+        self.record_size = 0;
+    }
+
+    fn get_mut(&mut self) -> &mut R {
+        &mut self.input
+    }
+
+    fn get(&self) -> &R {
+        &self.input
+    }
+
+    fn into_inner(self) -> R {
+        self.input
+    }
+
+    fn box_into_inner(self: Box<Self>) -> R {
+        self.input
+    }
+}
+
+#[test]
+fn test_custom_record_layout() {
+    let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+    let point_size = vlr.items_size() as usize;
+    let points = (0..25 * point_size)
+        .map(|i| (i % 251) as u8)
+        .collect::<Vec<u8>>();
+
+    let mut record_compressor = RawRecordCompressor {
+        output: Cursor::new(Vec::<u8>::new()),
+        record_size: 0,
+    };
+    record_compressor.set_fields_from(vlr.items()).unwrap();
+    let mut compressor =
+        LasZipCompressor::with_record_compressor(Box::new(record_compressor), vlr.clone());
+    compressor.compress_many(&points).unwrap();
+    compressor.done().unwrap();
+    let chunk_table = compressor.chunk_table().clone();
+    assert_eq!(chunk_table.len(), 3);
+    assert_eq!(chunk_table[0].byte_count, 10 * point_size as u64);
+    let compressed = compressor.into_inner().into_inner();
+    assert_eq!(&compressed[8..8 + points.len()], points.as_slice());
+
+    let mut record_decompressor = RawRecordDecompressor {
+        input: Cursor::new(compressed),
+        record_size: 0,
+    };
+    record_decompressor.set_fields_from(vlr.items()).unwrap();
+    let mut decompressor =
+        LasZipDecompressor::with_record_decompressor(Box::new(record_decompressor), vlr).unwrap();
+    assert_eq!(decompressor.chunk_table().unwrap().len(), 3);
+    let mut decompressed = vec![0u8; points.len()];
+    decompressor.decompress_many(&mut decompressed).unwrap();
+    assert_eq!(decompressed, points);
+
+    decompressor.seek(13).unwrap();
+    let mut point = vec![0u8; point_size];
+    decompressor.decompress_one(&mut point).unwrap();
+    assert_eq!(point, &points[13 * point_size..14 * point_size]);
+}
@@ -0,0 +1,144 @@
+//! Differential tests against LASzip
+//!
+//! Points are round-tripped through both implementations, using the `laszip`
+//! command line tool, and the decompressed points are expected to be identical.
+//! If the `LASZIP_COMPARE_BYTES` environment variable is set, the compressed bytes
+//! are also expected to be identical.
+//!
+//! The tool is found using the `LASZIP_BIN` environment variable,
+//! or by looking for `laszip64` and `laszip` in the `PATH`.
+//!
+//! Run with `cargo test --features differential-testing --test differential`
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use laz::las::file::{read_header_and_vlrs, QuickHeader};
+use laz::{LasZipCompressor, LasZipDecompressor, LazVlr};
+
+fn laszip_command() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("LASZIP_BIN") {
+        return Some(PathBuf::from(path));
+    }
+    ["laszip64", "laszip"]
+        .iter()
+        .map(PathBuf::from)
+        .find(|cmd| Command::new(cmd).arg("-version").output().is_ok())
+}
+
+fn run_laszip(laszip: &Path, input: &Path, output: &Path) {
+    let status = Command::new(laszip)
+        .arg("-i")
+        .arg(input)
+        .arg("-o")
+        .arg(output)
+        .status()
+        .expect("Failed to run laszip");
+    assert!(status.success(), "laszip failed on {}", input.display());
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "laz-rs-differential-{}-{}",
+        std::process::id(),
+        name
+    ))
+}
+
+/// Returns the uncompressed points of a LAS file
+fn read_las_points(path: &Path) -> Vec<u8> {
+    let bytes = std::fs::read(path).unwrap();
+    let mut src = Cursor::new(&bytes);
+    let (header, _) = read_header_and_vlrs(&mut src).unwrap();
+    let start = header.offset_to_points as usize;
+    let end = start + header.num_points as usize * header.point_size as usize;
+    bytes[start..end].to_vec()
+}
+
+/// Returns the header, the vlr, the raw bytes of the file and
+/// the points, decompressed by laz-rs, of a LAZ file
+fn read_laz(path: &Path) -> (QuickHeader, LazVlr, Vec<u8>, Vec<u8>) {
+    let mut file = File::open(path).unwrap();
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).unwrap();
+    let mut src = Cursor::new(&bytes);
+    let (header, vlr) = read_header_and_vlrs(&mut src).unwrap();
+    let vlr = vlr.expect("LASzip did not write a laszip vlr");
+    src.seek(SeekFrom::Start(u64::from(header.offset_to_points)))
+        .unwrap();
+    let mut points = vec![0u8; header.num_points as usize * header.point_size as usize];
+    LasZipDecompressor::new(src, vlr.clone())
+        .unwrap()
+        .decompress_many(&mut points)
+        .unwrap();
+    (header, vlr, bytes, points)
+}
+
+fn compress_with_laz_rs(points: &[u8], vlr: &LazVlr) -> Vec<u8> {
+    let mut compressor = LasZipCompressor::new(Cursor::new(Vec::new()), vlr.clone()).unwrap();
+    compressor.compress_many(points).unwrap();
+    compressor.finish().unwrap().into_inner()
+}
+
+fn check_file(laszip: &Path, las_path: &Path) {
+    let name = las_path.file_stem().unwrap().to_string_lossy().into_owned();
+    let expected_points = read_las_points(las_path);
+
+    // LASzip -> laz-rs
+    let laszip_laz = temp_path(&format!("{}.laz", name));
+    run_laszip(laszip, las_path, &laszip_laz);
+    let (laz_header, vlr, laz_bytes, points) = read_laz(&laszip_laz);
+    assert!(
+        points == expected_points,
+        "{}: laz-rs decompressed points differ",
+        name
+    );
+
+    let compressed = compress_with_laz_rs(&expected_points, &vlr);
+    if std::env::var_os("LASZIP_COMPARE_BYTES").is_some() {
+        let start = laz_header.offset_to_points as usize;
+        let laszip_compressed = &laz_bytes[start..(start + compressed.len()).min(laz_bytes.len())];
+        assert!(
+            laszip_compressed == compressed.as_slice(),
+            "{}: compressed bytes differ",
+            name
+        );
+    }
+
+    // laz-rs -> LASzip, the header & vlrs written by LASzip are reused
+    let laz_rs_laz = temp_path(&format!("{}-laz-rs.laz", name));
+    let mut laz_rs_bytes = laz_bytes[..laz_header.offset_to_points as usize].to_vec();
+    laz_rs_bytes.extend_from_slice(&compressed);
+    std::fs::write(&laz_rs_laz, &laz_rs_bytes).unwrap();
+    let round_tripped_las = temp_path(&format!("{}-laz-rs.las", name));
+    run_laszip(laszip, &laz_rs_laz, &round_tripped_las);
+    let points = read_las_points(&round_tripped_las);
+    assert!(
+        points == expected_points,
+        "{}: LASzip decompressed points differ",
+        name
+    );
+
+    for path in &[laszip_laz, laz_rs_laz, round_tripped_las] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn test_differential_with_laszip() {
+    let laszip = match laszip_command() {
+        Some(laszip) => laszip,
+        None => {
+            eprintln!("laszip was not found, set LASZIP_BIN to run the differential tests");
+            return;
+        }
+    };
+
+    let mut num_files = 0;
+    for entry in glob::glob("tests/data/*.las").unwrap() {
+        check_file(&laszip, &entry.unwrap());
+        num_files += 1;
+    }
+    assert!(num_files > 0);
+}
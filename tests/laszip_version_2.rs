@@ -1,5 +1,5 @@
 use std::fs::File;
-use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
 
 use laz::{
     LasZipCompressor, LasZipDecompressor, LazItemRecordBuilder, LazItemType, LazVlr, LazVlrBuilder,
@@ -152,6 +152,19 @@ fn test_seek() {
     las_file.read_exact(&mut buf).unwrap();
     assert_eq!(&buf, &decompression_buf);
 
+    // the first point of a chunk
+    let point_idx = 100;
+    las_file
+        .seek(SeekFrom::Start(
+            las_header.offset_to_points as u64 + (point_idx * POINT_SIZE) as u64,
+        ))
+        .unwrap();
+    decompressor.seek(point_idx as u64).unwrap();
+
+    decompressor.decompress_one(&mut decompression_buf).unwrap();
+    las_file.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, &decompression_buf);
+
     // stream to a point that is beyond the number of points compressed
     // BUT the point index fall into the last chunk index
     let point_idx = las_header.num_points as u64 + 1;
@@ -255,3 +268,558 @@ fn test_parallel_seek() {
         .is_err());
     assert!(las_file.read_exact(&mut buf).is_err());
 }
+
+#[test]
+fn test_seek_with_snapshots() {
+    let (mut las_file, compressed_data_stream, mut vlr_data) = create_data_with_small_chunk_size();
+    las_file.seek(SeekFrom::Start(0)).unwrap();
+    let (las_header, _) = laz::las::file::read_header_and_vlrs(&mut las_file).unwrap();
+    let point_size = las_header.point_size as usize;
+
+    let mut expected_points = vec![0u8; las_header.num_points as usize * point_size];
+    las_file.read_exact(&mut expected_points).unwrap();
+
+    let mut decompressor = LasZipDecompressor::new(
+        compressed_data_stream,
+        LazVlr::read_from(&mut vlr_data).unwrap(),
+    )
+    .unwrap();
+    decompressor.set_snapshot_interval(7);
+
+    // Going through all the points takes the snapshots
+    let mut all_points = vec![0u8; expected_points.len()];
+    decompressor.decompress_many(&mut all_points).unwrap();
+    assert_eq!(all_points, expected_points);
+
+    let mut point = vec![0u8; point_size];
+    for &point_idx in &[496usize, 5, 7, 8, 14, 49, 363, 0, 1] {
+        decompressor.seek(point_idx as u64).unwrap();
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(
+            &point[..],
+            &expected_points[point_idx * point_size..(point_idx + 1) * point_size]
+        );
+        // The following point must also be correct
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(
+            &point[..],
+            &expected_points[(point_idx + 1) * point_size..(point_idx + 2) * point_size]
+        );
+    }
+}
+
+#[test]
+fn test_decompress_many_across_chunks() {
+    let (mut las_file, compressed_data_stream, mut vlr_data) = create_data_with_small_chunk_size();
+    las_file.seek(SeekFrom::Start(0)).unwrap();
+    let (las_header, _) = laz::las::file::read_header_and_vlrs(&mut las_file).unwrap();
+    let point_size = las_header.point_size as usize;
+
+    let mut expected_points = vec![0u8; las_header.num_points as usize * point_size];
+    las_file.read_exact(&mut expected_points).unwrap();
+
+    let mut decompressor = LasZipDecompressor::new(
+        compressed_data_stream,
+        LazVlr::read_from(&mut vlr_data).unwrap(),
+    )
+    .unwrap();
+
+    // Batch sizes that do not line up with the chunk size of 50
+    let mut all_points = vec![0u8; expected_points.len()];
+    let mut batch_sizes = [1usize, 37, 50, 73, 2].iter().cycle();
+    let mut points = &mut all_points[..];
+    while !points.is_empty() {
+        let num_bytes = (batch_sizes.next().unwrap() * point_size).min(points.len());
+        let (batch, rest) = points.split_at_mut(num_bytes);
+        decompressor.decompress_many(batch).unwrap();
+        points = rest;
+    }
+    assert_eq!(all_points, expected_points);
+}
+
+#[test]
+fn test_decompress_filtered() {
+    let mut las_file = BufReader::new(File::open("tests/data/point-time-color.las").unwrap());
+    let mut las_reader = laz::las::file::SimpleReader::new(&mut las_file).unwrap();
+    let filter = laz::PointFilter::new()
+        .with_classifications(&[1, 2])
+        .with_returns(laz::ReturnFilter::First);
+
+    let mut laz_file = File::open("tests/data/point-time-color.laz").unwrap();
+    let (laz_header, laz_vlr) = laz::las::file::read_header_and_vlrs(&mut laz_file).unwrap();
+    let laz_vlr = laz_vlr.unwrap();
+
+    let mut expected_points = Vec::<u8>::new();
+    while let Some(point) = las_reader.read_next() {
+        let point = point.unwrap();
+        if filter.matches(laz_vlr.items(), point) {
+            expected_points.extend_from_slice(point);
+        }
+    }
+    assert!(!expected_points.is_empty());
+
+    let mut decompressor = LasZipDecompressor::new(&mut laz_file, laz_vlr).unwrap();
+    let mut points = Vec::<u8>::new();
+    let num_kept = decompressor
+        .decompress_filtered(laz_header.num_points, &filter, &mut points)
+        .unwrap();
+    assert_eq!(
+        num_kept as usize * laz_header.point_size as usize,
+        points.len()
+    );
+    assert_eq!(points, expected_points);
+}
+
+#[test]
+fn test_decompress_every_nth() {
+    let (mut las_file, compressed_data_stream, mut vlr_data) = create_data_with_small_chunk_size();
+    las_file.seek(SeekFrom::Start(0)).unwrap();
+    let (las_header, _) = laz::las::file::read_header_and_vlrs(&mut las_file).unwrap();
+    let point_size = las_header.point_size as usize;
+    las_file
+        .seek(SeekFrom::Start(las_header.offset_to_points as u64))
+        .unwrap();
+    let mut all_points = vec![0u8; las_header.num_points as usize * point_size];
+    las_file.read_exact(&mut all_points).unwrap();
+
+    let vlr = LazVlr::read_from(&mut vlr_data).unwrap();
+    let compressed_data = compressed_data_stream.into_inner();
+
+    // the chunk size is 50, use values of n smaller than, equal to, and greater than it
+    for &n in &[1u64, 7, 50, 120] {
+        let mut decompressor =
+            LasZipDecompressor::new(Cursor::new(compressed_data.as_slice()), vlr.clone()).unwrap();
+
+        // Decimate in two calls to check that the second one starts where expected
+        let mut points = Vec::<u8>::new();
+        let first_count = decompressor
+            .decompress_every_nth(n, 500, &mut points)
+            .unwrap();
+        let second_count = decompressor
+            .decompress_every_nth(n, las_header.num_points - 500, &mut points)
+            .unwrap();
+
+        let expected_points = all_points
+            .chunks_exact(point_size)
+            .enumerate()
+            .filter(|(i, _)| {
+                let i = *i as u64;
+                if i < 500 {
+                    i % n == 0
+                } else {
+                    (i - 500) % n == 0
+                }
+            })
+            .flat_map(|(_, point)| point.iter().copied())
+            .collect::<Vec<u8>>();
+        assert_eq!(
+            (first_count + second_count) as usize * point_size,
+            points.len()
+        );
+        assert_eq!(points, expected_points, "n = {}", n);
+    }
+}
+
+fn check_reencode(
+    reencode: fn(
+        Cursor<Vec<u8>>,
+        LazVlr,
+        Cursor<Vec<u8>>,
+        LazVlr,
+        u64,
+    ) -> laz::Result<Cursor<Vec<u8>>>,
+) {
+    let (mut las_file, compressed_data_stream, mut vlr_data) = create_data_with_small_chunk_size();
+    las_file.seek(SeekFrom::Start(0)).unwrap();
+    let (las_header, _) = laz::las::file::read_header_and_vlrs(&mut las_file).unwrap();
+    las_file
+        .seek(SeekFrom::Start(las_header.offset_to_points as u64))
+        .unwrap();
+    let mut expected_points = vec![0u8; las_header.num_points as usize * 20];
+    las_file.read_exact(&mut expected_points).unwrap();
+    let vlr = LazVlr::read_from(&mut vlr_data).unwrap();
+
+    let items = vlr.items().clone();
+    let fixed_vlr = LazVlrBuilder::new(items.clone())
+        .with_fixed_chunk_size(128)
+        .build();
+    let variable_vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+    for dst_vlr in [fixed_vlr, variable_vlr] {
+        let reencoded = reencode(
+            compressed_data_stream.clone(),
+            vlr.clone(),
+            Cursor::new(Vec::<u8>::new()),
+            dst_vlr.clone(),
+            las_header.num_points,
+        )
+        .unwrap()
+        .into_inner();
+
+        if dst_vlr.uses_variable_size_chunks() {
+            // The chunks of the source are kept
+            let chunk_table =
+                laz::laszip::ChunkTable::read_from(Cursor::new(reencoded.as_slice()), &dst_vlr)
+                    .unwrap();
+            assert_eq!(chunk_table.len(), 22);
+            assert_eq!(chunk_table[21].point_count, 15);
+        }
+
+        let mut points = vec![0u8; expected_points.len()];
+        laz::decompress_buffer(&reencoded, &mut points, dst_vlr).unwrap();
+        assert_eq!(points, expected_points);
+    }
+
+    // LAZ has no layered compression for Point10, the points are upgraded to Point14
+    let layered_vlr = vlr.to_layered().unwrap();
+    assert!(layered_vlr.is_compatible_with_point_format(6, 0));
+    let reencoded = reencode(
+        compressed_data_stream,
+        vlr,
+        Cursor::new(Vec::<u8>::new()),
+        layered_vlr.clone(),
+        las_header.num_points,
+    )
+    .unwrap()
+    .into_inner();
+    let mut expected = vec![0u8; las_header.num_points as usize * 30];
+    for (src, dst) in expected_points
+        .chunks_exact(20)
+        .zip(expected.chunks_exact_mut(30))
+    {
+        laz::laszip::upgrade_point(0, src, dst);
+    }
+    let mut points = vec![0u8; expected.len()];
+    laz::decompress_buffer(&reencoded, &mut points, layered_vlr).unwrap();
+    assert_eq!(points, expected);
+}
+
+#[test]
+fn test_reencode() {
+    check_reencode(laz::laszip::reencode);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_par_reencode() {
+    check_reencode(laz::laszip::par_reencode);
+}
+
+#[test]
+fn test_warnings() {
+    let (mut las_file, compressed_data, vlr_data) = create_data_with_small_chunk_size();
+    let vlr = LazVlr::read_from(vlr_data).unwrap();
+    let mut compressed = compressed_data.into_inner();
+    let offset_to_chunk_table = compressed[..8].to_vec();
+
+    las_file.seek(SeekFrom::Start(0)).unwrap();
+    let (las_header, _) = laz::las::file::read_header_and_vlrs(&mut las_file).unwrap();
+    las_file
+        .seek(SeekFrom::Start(las_header.offset_to_points as u64))
+        .unwrap();
+    let mut expected = vec![0u8; 2 * las_header.point_size as usize];
+    las_file.read_exact(&mut expected).unwrap();
+
+    // Offset not updated, but written at the end
+    compressed[..8].copy_from_slice(&0i64.to_le_bytes());
+    compressed.extend_from_slice(&offset_to_chunk_table);
+    let mut warnings = Vec::new();
+    let decompressor =
+        LasZipDecompressor::new_with_warnings(Cursor::new(&compressed), vlr.clone(), |w| {
+            warnings.push(w)
+        })
+        .unwrap();
+    drop(decompressor);
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(
+        warnings[0],
+        laz::LasZipWarning::ChunkTableOffsetAtEnd
+    ));
+
+    // No offset at all, the points can still be read
+    let len = compressed.len();
+    compressed[len - 8..].copy_from_slice(&0i64.to_le_bytes());
+    let mut warnings = Vec::new();
+    let mut decompressor =
+        LasZipDecompressor::new_with_warnings(Cursor::new(&compressed), vlr, |w| warnings.push(w))
+            .unwrap();
+    let mut points = vec![0u8; expected.len()];
+    decompressor.decompress_many(&mut points).unwrap();
+    assert_eq!(points, expected);
+    drop(decompressor);
+    assert_eq!(warnings.len(), 1);
+    assert!(matches!(
+        warnings[0],
+        laz::LasZipWarning::ChunkTableUnreadable(_)
+    ));
+}
+
+#[test]
+fn test_resume_from_checkpoint() {
+    let mut las_file = File::open("tests/data/point10.las").unwrap();
+    let (las_header, _) = laz::las::file::read_header_and_vlrs(&mut las_file).unwrap();
+    las_file
+        .seek(SeekFrom::Start(las_header.offset_to_points as u64))
+        .unwrap();
+    let mut points = vec![0u8; las_header.num_points as usize * las_header.point_size as usize];
+    las_file.read_exact(&mut points).unwrap();
+    let point_size = las_header.point_size as usize;
+
+    let vlr = LazVlrBuilder::new(
+        LazItemRecordBuilder::new()
+            .add_item(LazItemType::Point10)
+            .build(),
+    )
+    .with_fixed_chunk_size(50)
+    .build();
+
+    let mut compressor = LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+    assert!(compressor.checkpoint().is_none());
+    compressor
+        .compress_many(&points[..420 * point_size])
+        .unwrap();
+    let mut saved = Vec::<u8>::new();
+    compressor
+        .checkpoint()
+        .unwrap()
+        .write_to(&mut saved)
+        .unwrap();
+    // The job continues a bit, then gets interrupted
+    compressor
+        .compress_many(&points[420 * point_size..500 * point_size])
+        .unwrap();
+    let mut output = compressor.into_inner().into_inner();
+
+    let checkpoint = laz::laszip::CompressionCheckpoint::read_from(saved.as_slice()).unwrap();
+    assert_eq!(checkpoint.num_points(), 400);
+    assert_eq!(checkpoint.chunk_table().len(), 8);
+    output.truncate(checkpoint.data_end() as usize);
+    let num_done = checkpoint.num_points() as usize;
+    let mut compressor =
+        LasZipCompressor::resume(Cursor::new(output), vlr.clone(), checkpoint).unwrap();
+    // The points compressed before the checkpoint would be missing from the digest
+    #[cfg(feature = "digest")]
+    compressor.set_compute_digest(true);
+    compressor
+        .compress_many(&points[num_done * point_size..])
+        .unwrap();
+    #[cfg(feature = "digest")]
+    assert_eq!(compressor.digest(), None);
+    compressor.done().unwrap();
+    let output = compressor.into_inner().into_inner();
+
+    let mut expected = Cursor::new(Vec::<u8>::new());
+    laz::compress_buffer(&mut expected, &points, vlr.clone()).unwrap();
+    assert_eq!(output, expected.into_inner());
+
+    let mut decompressed = vec![0u8; points.len()];
+    laz::decompress_buffer(&output, &mut decompressed, vlr).unwrap();
+    assert_eq!(decompressed, points);
+}
+
+/// Writer that fails once, when the `fail_at`-th write happens
+struct FlakyWriter {
+    inner: Cursor<Vec<u8>>,
+    num_writes: usize,
+    fail_at: usize,
+}
+
+impl std::io::Write for FlakyWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.num_writes += 1;
+        if self.num_writes == self.fail_at {
+            // Simulate a partial write before the failure
+            let n = buf.len() / 2;
+            self.inner.write_all(&buf[..n])?;
+            return Err(std::io::Error::other("transient error"));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Seek for FlakyWriter {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn test_retry_after_write_error() {
+    let mut las_file = File::open("tests/data/point10.las").unwrap();
+    let (las_header, _) = laz::las::file::read_header_and_vlrs(&mut las_file).unwrap();
+    las_file
+        .seek(SeekFrom::Start(las_header.offset_to_points as u64))
+        .unwrap();
+    let mut points = vec![0u8; las_header.num_points as usize * las_header.point_size as usize];
+    las_file.read_exact(&mut points).unwrap();
+    let point_size = las_header.point_size as usize;
+
+    let vlr = LazVlrBuilder::new(
+        LazItemRecordBuilder::new()
+            .add_item(LazItemType::Point10)
+            .build(),
+    )
+    .with_fixed_chunk_size(50)
+    .build();
+    let mut expected = Cursor::new(Vec::<u8>::new());
+    laz::compress_buffer(&mut expected, &points, vlr.clone()).unwrap();
+    let expected = expected.into_inner();
+
+    for fail_at in 1..15 {
+        let output = FlakyWriter {
+            inner: Cursor::new(Vec::new()),
+            num_writes: 0,
+            fail_at,
+        };
+        let mut compressor = LasZipCompressor::new(output, vlr.clone()).unwrap();
+        let mut num_errors = 0;
+        let mut next = 0;
+        while next < las_header.num_points as usize {
+            match compressor.compress_one(&points[next * point_size..(next + 1) * point_size]) {
+                Ok(()) => next += 1,
+                Err(_) => {
+                    num_errors += 1;
+                    next = compressor.rewind_current_chunk().unwrap() as usize;
+                }
+            }
+        }
+        while compressor.done().is_err() {
+            num_errors += 1;
+        }
+        assert_eq!(num_errors, 1);
+
+        let mut output = compressor.into_inner().inner.into_inner();
+        // The partial write of the chunk table may leave trailing data
+        output.truncate(expected.len());
+        assert_eq!(output, expected, "fail_at: {}", fail_at);
+    }
+}
+
+#[test]
+fn test_finish_then_write_trailer() {
+    use std::io::Write;
+
+    let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+    let points = (0..20u8).collect::<Vec<u8>>().repeat(35);
+
+    let mut compressor = LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+    compressor.compress_many(&points).unwrap();
+    let mut output = compressor.finish().unwrap();
+    output.write_all(b"trailer").unwrap();
+    let output = output.into_inner();
+    assert!(output.ends_with(b"trailer"));
+
+    let mut expected = Cursor::new(Vec::<u8>::new());
+    laz::compress_buffer(&mut expected, &points, vlr.clone()).unwrap();
+    assert_eq!(output[..output.len() - 7], expected.into_inner()[..]);
+
+    #[cfg(feature = "parallel")]
+    {
+        let mut compressor =
+            laz::ParLasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr).unwrap();
+        compressor.compress_many(&points).unwrap();
+        let mut par_output = compressor.finish().unwrap();
+        par_output.write_all(b"trailer").unwrap();
+        assert_eq!(par_output.into_inner(), output);
+    }
+}
+
+#[test]
+fn test_point_buffers() {
+    let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    let vlr = LazVlrBuilder::new(items).with_fixed_chunk_size(10).build();
+    let points = (0..20u8).collect::<Vec<u8>>().repeat(35);
+
+    assert!(laz::PointBuffer::new(&points[..30], &vlr).is_err());
+    let mut compressor = LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+    let wrong_size = laz::PointBuffer::with_point_size(&points[..40], 10).unwrap();
+    assert!(compressor.compress_points(wrong_size).is_err());
+    compressor
+        .compress_points(laz::PointBuffer::new(&points, &vlr).unwrap())
+        .unwrap();
+    let mut compressed = compressor.finish().unwrap();
+    compressed.seek(SeekFrom::Start(0)).unwrap();
+
+    let mut decompressed = vec![0u8; points.len()];
+    let mut decompressor = LasZipDecompressor::new(compressed, vlr.clone()).unwrap();
+    decompressor
+        .decompress_points(laz::PointBufferMut::new(&mut decompressed, &vlr).unwrap())
+        .unwrap();
+    assert_eq!(decompressed, points);
+}
+
+#[test]
+fn test_adaptive_chunking() {
+    let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+
+    // Smooth points followed by noise
+    let mut seed = 42u32;
+    let mut points = Vec::<u8>::with_capacity(60_000 * 20);
+    for i in 0..60_000u32 {
+        let mut point = [0u8; 20];
+        if i < 30_000 {
+            point[..4].copy_from_slice(&i.to_le_bytes());
+        } else {
+            for byte in point.iter_mut() {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                *byte = (seed >> 16) as u8;
+            }
+        }
+        points.extend_from_slice(&point);
+    }
+
+    let mut compressor = LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+    compressor.set_adaptive_chunking(Some(laz::laszip::AdaptiveChunking {
+        max_chunk_size: 100_000,
+        ..Default::default()
+    }));
+    compressor.compress_many(&points).unwrap();
+    let mut compressed = compressor.finish().unwrap();
+
+    compressed.seek(SeekFrom::Start(0)).unwrap();
+    let chunk_table = laz::laszip::ChunkTable::read_from(&mut compressed, &vlr).unwrap();
+    let first_chunk_size = chunk_table[0].point_count;
+    assert!(chunk_table.len() >= 2);
+    assert!(
+        first_chunk_size > 30_000 && first_chunk_size <= 40_000,
+        "{}",
+        first_chunk_size
+    );
+
+    let mut decompressed = vec![0u8; points.len()];
+    laz::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr).unwrap();
+    assert_eq!(decompressed, points);
+}
+
+#[test]
+fn test_chunk_deadline() {
+    let items = LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+    let vlr = LazVlrBuilder::new(items).with_variable_chunk_size().build();
+    let points = (0..20u8).collect::<Vec<u8>>().repeat(30);
+
+    let mut compressor = LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+    compressor.set_chunk_deadline(Some(std::time::Duration::from_secs(3600)));
+    compressor.compress_many(&points[..20 * 10]).unwrap();
+    assert!(!compressor.finish_chunk_if_deadline_passed().unwrap());
+    assert_eq!(compressor.chunk_table().len(), 0);
+
+    // Every point is its own chunk
+    compressor.set_chunk_deadline(Some(std::time::Duration::ZERO));
+    assert!(compressor.finish_chunk_if_deadline_passed().unwrap());
+    assert_eq!(compressor.chunk_table().len(), 1);
+    assert!(!compressor.finish_chunk_if_deadline_passed().unwrap());
+    compressor.compress_many(&points[20 * 10..]).unwrap();
+    assert_eq!(compressor.chunk_table().len(), 21);
+    assert_eq!(compressor.chunk_table()[0].point_count, 10);
+    assert_eq!(compressor.chunk_table()[20].point_count, 1);
+
+    let compressed = compressor.finish().unwrap();
+    let mut decompressed = vec![0u8; points.len()];
+    laz::decompress_buffer(compressed.get_ref(), &mut decompressed, vlr).unwrap();
+    assert_eq!(decompressed, points);
+}
@@ -91,4 +91,534 @@ mod tests {
             assert_eq!(point, expected_point);
         }
     }
+
+    #[test]
+    fn test_par_reuse_scratch_buffers() {
+        use laz::laszip::parallel::ScratchBuffers;
+        use std::io::Cursor;
+
+        let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+        let vlr = laz::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(100)
+            .build();
+        let points = (0..=255u8).cycle().take(20 * 1234).collect::<Vec<u8>>();
+
+        let mut buffers = ScratchBuffers::with_capacity(4096, 100 * 20);
+        for _ in 0..2 {
+            let mut compressor = laz::ParLasZipCompressor::with_scratch_buffers(
+                Cursor::new(Vec::<u8>::new()),
+                vlr.clone(),
+                buffers,
+            )
+            .unwrap();
+            compressor.compress_many(&points[..20 * 150]).unwrap();
+            compressor.compress_many(&points[20 * 150..]).unwrap();
+            compressor.done().unwrap();
+            let (output, compression_buffers) = compressor.into_parts();
+            assert!(compression_buffers.uncompressed_capacity() >= 100 * 20);
+
+            let mut decompressor = laz::ParLasZipDecompressor::with_scratch_buffers(
+                Cursor::new(output.into_inner()),
+                vlr.clone(),
+                compression_buffers,
+            )
+            .unwrap();
+            let mut decompressed = vec![0u8; points.len()];
+            decompressor
+                .decompress_many(&mut decompressed[..20 * 10])
+                .unwrap();
+            decompressor
+                .decompress_many(&mut decompressed[20 * 10..])
+                .unwrap();
+            assert_eq!(decompressed, points);
+            buffers = decompressor.into_parts().1;
+            assert!(buffers.compressed_capacity() >= 4096);
+        }
+    }
+
+    #[test]
+    fn test_par_compress_many_batches() {
+        use std::io::Cursor;
+
+        for items in [
+            laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0),
+            laz::LazItemRecordBuilder::default_version_of::<laz::las::Point6>(0),
+        ] {
+            let vlr = laz::LazVlrBuilder::new(items)
+                .with_fixed_chunk_size(100)
+                .build();
+            let point_size = vlr.items_size() as usize;
+            let points = (0..=255u8)
+                .cycle()
+                .take(point_size * 2345)
+                .collect::<Vec<u8>>();
+
+            let mut expected = Cursor::new(Vec::<u8>::new());
+            laz::par_compress_buffer(&mut expected, &points, &vlr).unwrap();
+
+            // The compressors of the threads are reused from one batch to the next
+            let mut compressor =
+                laz::ParLasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr).unwrap();
+            for batch in points.chunks(point_size * 250) {
+                compressor.compress_many(batch).unwrap();
+            }
+            let output = compressor.finish().unwrap();
+            assert_eq!(output.into_inner(), expected.into_inner());
+        }
+    }
+
+    #[test]
+    fn test_par_decompress_selected_chunks() {
+        use std::io::Cursor;
+
+        let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+        let vlr = laz::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(100)
+            .build();
+        let points = (0..=255u8).cycle().take(20 * 350).collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        laz::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        let compressed = compressed.into_inner();
+
+        let chunks =
+            laz::par_decompress_selected_chunks(&compressed, &vlr, &[3, 0, 3], None).unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0], (3, points[20 * 300..].to_vec()));
+        assert_eq!(chunks[1], (0, points[..20 * 100].to_vec()));
+        assert_eq!(chunks[2], chunks[0]);
+        assert!(laz::par_decompress_selected_chunks(&compressed, &vlr, &[4], None).is_err());
+
+        let mut decompressor =
+            laz::ParLasZipDecompressor::new(Cursor::new(compressed), vlr).unwrap();
+        let mut decompressed = vec![0u8; 20 * 150];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        let chunks = decompressor.decompress_selected_chunks(&[2, 1]).unwrap();
+        assert_eq!(chunks[0], (2, points[20 * 200..20 * 300].to_vec()));
+        assert_eq!(chunks[1], (1, points[20 * 100..20 * 200].to_vec()));
+        // The sequential decompression is not disturbed
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, &points[20 * 150..20 * 300]);
+    }
+
+    #[test]
+    fn test_par_decompress_selected_chunks_into() {
+        use std::io::Cursor;
+
+        let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+        let vlr = laz::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(100)
+            .build();
+        let points = (0..=255u8).cycle().take(20 * 350).collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        laz::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = laz::ParLasZipDecompressor::new(compressed, vlr).unwrap();
+        let mut decompressed = vec![0u8; 20 * 300];
+        let counts = decompressor
+            .decompress_selected_chunks_into(&[1, 3, 0], &mut decompressed)
+            .unwrap();
+        assert_eq!(counts, vec![100, 50, 100]);
+        assert_eq!(&decompressed[..20 * 100], &points[20 * 100..20 * 200]);
+        assert_eq!(&decompressed[20 * 100..20 * 150], &points[20 * 300..]);
+        assert_eq!(&decompressed[20 * 200..], &points[..20 * 100]);
+
+        let mut too_small = vec![0u8; 20 * 150];
+        assert!(matches!(
+            decompressor.decompress_selected_chunks_into(&[0, 1], &mut too_small),
+            Err(laz::LasZipError::OutputBufferTooSmall { required: 4000 })
+        ));
+    }
+
+    #[test]
+    fn test_par_decompress_scheduled_chunks() {
+        use std::io::Cursor;
+
+        let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+        let vlr = laz::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(100)
+            .build();
+        let points = (0..=255u8).cycle().take(20 * 350).collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        laz::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = laz::ParLasZipDecompressor::new(compressed, vlr).unwrap();
+        let mut decompressed = vec![0u8; 20 * 50];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        let mut chunks = Vec::new();
+        decompressor
+            .decompress_scheduled_chunks(&[3, 1, 0], |index, points| chunks.push((index, points)))
+            .unwrap();
+        chunks.sort();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0], (0, points[..20 * 100].to_vec()));
+        assert_eq!(chunks[1], (1, points[20 * 100..20 * 200].to_vec()));
+        assert_eq!(chunks[2], (3, points[20 * 300..].to_vec()));
+        assert!(decompressor
+            .decompress_scheduled_chunks(&[0, 4], |_, _| panic!("4 is checked first"))
+            .is_err());
+        // The sequential decompression is not disturbed
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, &points[20 * 50..20 * 100]);
+    }
+
+    #[test]
+    fn test_par_decompress_max_in_flight_chunks() {
+        use std::io::Cursor;
+
+        let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+        let vlr = laz::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(100)
+            .build();
+        let points = (0..=255u8).cycle().take(20 * 750).collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        laz::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = laz::ParLasZipDecompressor::new(compressed, vlr).unwrap();
+        decompressor.set_max_in_flight_chunks(Some(3));
+
+        let mut decompressed = vec![0u8; 20 * 50];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, &points[..20 * 50]);
+        let mut decompressed = vec![0u8; 20 * 700];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, &points[20 * 50..]);
+
+        let indices = [7, 0, 5, 1, 2];
+        let chunks = decompressor.decompress_selected_chunks(&indices).unwrap();
+        assert_eq!(
+            chunks.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            indices
+        );
+        let mut decompressed = vec![0u8; 20 * 500];
+        let counts = decompressor
+            .decompress_selected_chunks_into(&indices, &mut decompressed)
+            .unwrap();
+        assert_eq!(counts, vec![50, 100, 100, 100, 100]);
+        let mut scheduled = Vec::new();
+        decompressor
+            .decompress_scheduled_chunks(&indices, |index, points| scheduled.push((index, points)))
+            .unwrap();
+        scheduled.sort();
+
+        for (i, (&index, count)) in indices.iter().zip(counts).enumerate() {
+            let expected = &points[20 * 100 * index..(20 * 100 * (index + 1)).min(points.len())];
+            assert_eq!(chunks[i].1, expected);
+            // Each chunk has the room of its entry in the chunk table
+            let offset = 20 * 100 * i;
+            assert_eq!(&decompressed[offset..offset + 20 * count], expected);
+            let scheduled_points = &scheduled.iter().find(|(j, _)| *j == index).unwrap().1;
+            assert_eq!(scheduled_points, expected);
+        }
+    }
+
+    #[test]
+    fn test_par_decompress_one_chunk_in_flight() {
+        use std::io::Cursor;
+
+        let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+        let vlr = laz::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(10)
+            .build();
+        let points = (0..=255u8).cycle().take(20 * 2005).collect::<Vec<u8>>();
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        laz::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        compressed.set_position(0);
+
+        let mut decompressor = laz::ParLasZipDecompressor::new(compressed, vlr).unwrap();
+        decompressor.set_max_in_flight_chunks(Some(1));
+        let mut decompressed = vec![0u8; 20 * 2005];
+        // Start in the middle of a chunk, then go through the 200 chunks left at once
+        decompressor
+            .decompress_many(&mut decompressed[..20 * 5])
+            .unwrap();
+        decompressor
+            .decompress_many(&mut decompressed[20 * 5..])
+            .unwrap();
+        assert_eq!(decompressed, points);
+    }
+
+    #[test]
+    fn test_par_with_thread_pool() {
+        use std::io::Cursor;
+        use std::sync::Arc;
+
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(2)
+                .build()
+                .unwrap(),
+        );
+        let items = laz::LazItemRecordBuilder::default_version_of::<laz::las::Point0>(0);
+        let vlr = laz::LazVlrBuilder::new(items)
+            .with_fixed_chunk_size(100)
+            .build();
+        let points = (0..=255u8).cycle().take(20 * 750).collect::<Vec<u8>>();
+
+        let mut compressor = laz::ParLasZipCompressor::new(Cursor::new(Vec::new()), vlr.clone())
+            .unwrap()
+            .with_thread_pool(Arc::clone(&pool));
+        compressor.compress_many(&points).unwrap();
+        let compressed = compressor.finish().unwrap().into_inner();
+
+        let mut decompressed = vec![0u8; points.len()];
+        laz::par_decompress_read_at(&compressed, vlr.clone(), 0, &mut decompressed, Some(&pool))
+            .unwrap();
+        assert_eq!(decompressed, points);
+        let decompressor =
+            laz::laszip::read_at::ReadAtDecompressor::new(&compressed, vlr.clone(), 0)
+                .unwrap()
+                .with_thread_pool(Arc::clone(&pool));
+        let mut decompressed = vec![0u8; 20 * 200];
+        decompressor
+            .par_decompress_chunks(2..4, &mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, &points[20 * 200..20 * 400]);
+        let chunks =
+            laz::par_decompress_selected_chunks(&compressed, &vlr, &[7, 2], Some(&pool)).unwrap();
+        assert_eq!(chunks[0], (7, points[20 * 700..].to_vec()));
+        assert_eq!(chunks[1], (2, points[20 * 200..20 * 300].to_vec()));
+
+        let mut decompressor = laz::ParLasZipDecompressor::new(Cursor::new(compressed), vlr)
+            .unwrap()
+            .with_thread_pool(Arc::clone(&pool));
+        let mut decompressed = vec![0u8; points.len()];
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, points);
+
+        let mut num_chunks = 0;
+        decompressor
+            .decompress_scheduled_chunks(&[0, 3, 7], |_, _| {
+                assert!(pool.current_thread_index().is_some());
+                num_chunks += 1;
+            })
+            .unwrap();
+        assert_eq!(num_chunks, 3);
+    }
+
+    #[test]
+    fn test_par_transcode_las_to_laz() {
+        for las_path in glob::glob("tests/data/*.las").unwrap() {
+            let las_path = las_path.unwrap();
+            let las = std::fs::read(&las_path).unwrap();
+            let laz =
+                laz::par_transcode_las_to_laz(&las, std::io::Cursor::new(Vec::new()), 50).unwrap();
+
+            let mut expected = Vec::new();
+            laz::las::file::SimpleReader::new(std::io::Cursor::new(&las))
+                .unwrap()
+                .read_to_end(&mut expected)
+                .unwrap();
+            let mut reader = laz::las::file::SimpleReader::new(laz).unwrap();
+            let mut points = Vec::new();
+            reader.read_to_end(&mut points).unwrap();
+            assert_eq!(points, expected, "{}", las_path.display());
+        }
+    }
+
+    #[test]
+    fn test_par_decompress_read_at() {
+        use laz::laszip::read_at::ReadAtDecompressor;
+        use std::io::Cursor;
+
+        for vlr in [
+            laz::LazVlrBuilder::default()
+                .with_point_format(0, 0)
+                .unwrap()
+                .with_fixed_chunk_size(100)
+                .build(),
+            laz::LazVlrBuilder::default()
+                .with_point_format(0, 0)
+                .unwrap()
+                .with_variable_chunk_size()
+                .build(),
+        ] {
+            let points = (0..=255u8).cycle().take(20 * 350).collect::<Vec<u8>>();
+            let mut compressor =
+                laz::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+            if vlr.uses_variable_size_chunks() {
+                compressor.compress_chunks(points.chunks(20 * 100)).unwrap();
+            } else {
+                compressor.compress_many(&points).unwrap();
+            }
+            let compressed = compressor.finish().unwrap().into_inner();
+
+            let mut decompressed = vec![0u8; points.len()];
+            laz::par_decompress_read_at(&compressed, vlr.clone(), 0, &mut decompressed, None)
+                .unwrap();
+            assert_eq!(decompressed, points);
+
+            let decompressor = ReadAtDecompressor::new(&compressed, vlr.clone(), 0).unwrap();
+            let mut decompressed = vec![0u8; 20 * 150];
+            assert_eq!(
+                decompressor
+                    .par_decompress_chunks(2..4, &mut decompressed)
+                    .unwrap(),
+                150
+            );
+            assert_eq!(decompressed, &points[20 * 200..]);
+            assert!(decompressor
+                .par_decompress_chunks(2..4, &mut vec![0u8; 20 * 151])
+                .is_err());
+            assert!(decompressor
+                .par_decompress_chunks(3..5, &mut decompressed)
+                .is_err());
+
+            let mut decompressor =
+                laz::ParLasZipDecompressor::new_read_at(&compressed, vlr, 0).unwrap();
+            decompressor.set_max_in_flight_chunks(Some(2));
+            let mut decompressed = vec![0u8; points.len()];
+            decompressor
+                .decompress_many(&mut decompressed[..20 * 50])
+                .unwrap();
+            decompressor
+                .decompress_many(&mut decompressed[20 * 50..])
+                .unwrap();
+            assert_eq!(decompressed, points);
+            let chunks = decompressor.decompress_selected_chunks(&[3, 1]).unwrap();
+            assert_eq!(chunks[0], (3, points[20 * 300..].to_vec()));
+            assert_eq!(chunks[1], (1, points[20 * 100..20 * 200].to_vec()));
+
+            decompressor.seek(250).unwrap();
+            let mut decompressed = vec![0u8; 20 * 100];
+            decompressor.decompress_many(&mut decompressed).unwrap();
+            assert_eq!(decompressed, &points[20 * 250..]);
+        }
+    }
+
+    #[test]
+    fn test_par_seek_variable_size_chunks() {
+        use std::io::Cursor;
+
+        for point_format_id in [0u8, 6].iter() {
+            let vlr = laz::LazVlrBuilder::default()
+                .with_point_format(*point_format_id, 0)
+                .unwrap()
+                .with_variable_chunk_size()
+                .build();
+            let point_size = vlr.items_size() as usize;
+            let points = (0..=255u8)
+                .cycle()
+                .take(point_size * 217)
+                .collect::<Vec<u8>>();
+            // Chunks of 30, 100, 7 & 80 points
+            let chunks = [0, 30, 130, 137, 217]
+                .windows(2)
+                .map(|bounds| &points[point_size * bounds[0]..point_size * bounds[1]])
+                .collect::<Vec<&[u8]>>();
+            let mut compressor =
+                laz::LasZipCompressor::new(Cursor::new(Vec::<u8>::new()), vlr.clone()).unwrap();
+            compressor.compress_chunks(chunks).unwrap();
+            let compressed = compressor.finish().unwrap().into_inner();
+
+            let mut decompressor =
+                laz::ParLasZipDecompressor::new(Cursor::new(compressed), vlr).unwrap();
+            let mut decompressed = vec![0u8; point_size * 10];
+            for &index in [0usize, 29, 30, 131, 136, 137, 150, 207, 5].iter() {
+                decompressor.seek(index as u64).unwrap();
+                decompressor.decompress_many(&mut decompressed).unwrap();
+                assert_eq!(
+                    decompressed,
+                    &points[point_size * index..point_size * (index + 10)],
+                    "{} {}",
+                    point_format_id,
+                    index
+                );
+            }
+
+            decompressor.seek(217).unwrap();
+            assert!(decompressor.decompress_many(&mut decompressed).is_err());
+        }
+    }
+
+    #[test]
+    fn test_par_with_options() {
+        use laz::laszip::{DecompressionSelection, LazOpenOptions};
+        use std::io::Cursor;
+
+        let vlr = laz::LazVlrBuilder::default()
+            .with_point_format(7, 0)
+            .unwrap()
+            .with_fixed_chunk_size(100)
+            .build();
+        let mut points = vec![0u8; 36 * 250];
+        for (i, point) in points.chunks_exact_mut(36).enumerate() {
+            point[0..4].copy_from_slice(&(i as i32).to_le_bytes());
+            point[14] = 0x11;
+            point[30..36].copy_from_slice(&[i as u8, 1, 2, (i * 3) as u8, 4, 5]);
+        }
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        laz::compress_buffer(&mut compressed, &points, vlr.clone()).unwrap();
+        let compressed = compressed.into_inner();
+
+        let options = || {
+            LazOpenOptions::new()
+                .with_selection(DecompressionSelection::CHANNEL_RETURNS_XY)
+                .with_num_points(250)
+                .with_max_in_flight_chunks(1)
+        };
+        let mut expected = vec![0u8; points.len()];
+        laz::LasZipDecompressor::with_options(Cursor::new(&compressed), vlr.clone(), options())
+            .unwrap()
+            .decompress_many(&mut expected)
+            .unwrap();
+        assert_ne!(expected, points);
+        let mut decompressed = vec![0u8; points.len()];
+        let mut decompressor = laz::ParLasZipDecompressor::with_options(
+            Cursor::new(&compressed),
+            vlr.clone(),
+            options(),
+        )
+        .unwrap();
+        decompressor.decompress_many(&mut decompressed).unwrap();
+        assert_eq!(decompressed, expected);
+        decompressor.seek(249).unwrap();
+        decompressor
+            .decompress_many(&mut decompressed[..36])
+            .unwrap();
+        assert_eq!(decompressed[..36], expected[249 * 36..]);
+
+        let mut decompressed = vec![0u8; points.len()];
+        laz::ParLasZipDecompressor::new_read_at_with_options(
+            compressed.as_slice(),
+            vlr.clone(),
+            0,
+            options(),
+        )
+        .unwrap()
+        .decompress_many(&mut decompressed)
+        .unwrap();
+        assert_eq!(decompressed, expected);
+
+        let options = LazOpenOptions::new().with_memory_limit(16);
+        let result = laz::ParLasZipDecompressor::with_options(
+            Cursor::new(&compressed),
+            vlr.clone(),
+            options,
+        );
+        assert!(matches!(
+            result,
+            Err(laz::LasZipError::MemoryLimitExceeded { limit: 16, .. })
+        ));
+
+        let options = LazOpenOptions::new().with_lazy_chunk_table(true);
+        let result =
+            laz::ParLasZipDecompressor::with_options(Cursor::new(&compressed), vlr, options);
+        assert!(matches!(
+            result,
+            Err(laz::LasZipError::UnsupportedOption("lazy_chunk_table"))
+        ));
+
+        let items = laz::LazItemRecordBuilder::version_1_of::<laz::las::Point0>(0);
+        let vlr = laz::LazVlr::from_laz_items(items);
+        let mut compressed = Cursor::new(Vec::<u8>::new());
+        laz::compress_buffer(&mut compressed, &points[..20 * 10], vlr.clone()).unwrap();
+        compressed.set_position(0);
+        let mut num_warnings = 0;
+        let options = LazOpenOptions::new().with_warnings(|_| num_warnings += 1);
+        laz::ParLasZipDecompressor::with_options(compressed, vlr, options).unwrap();
+        assert_eq!(num_warnings, 1);
+    }
 }
@@ -154,3 +154,48 @@ fn test_variable_size_chunks_parallel_decompression() {
     decompressor.decompress_many(&mut points_out).unwrap();
     check_chunks(&points_out, &chunks);
 }
+
+/// Test seeking to points of chunks that do not have the same number of points
+#[test]
+fn test_variable_size_chunks_seek() {
+    let las_file = BufReader::new(File::open("tests/data/point-time-color.las").unwrap());
+    let mut las_reader = SimpleReader::new(las_file).unwrap();
+    let mut las_points_bytes = Vec::<u8>::new();
+    las_reader.read_to_end(&mut las_points_bytes).unwrap();
+
+    let point_size = las_reader.header.point_size as usize;
+    let chunk_sizes = [1, 2, 3, 4, 5, 6, 5, 4, 3, 2, 1];
+    let chunks = organize_as_variable_size_chunks(&las_points_bytes, point_size, &chunk_sizes);
+    let expected_points = chunks.concat();
+
+    let laz_vlr = LazVlrBuilder::default()
+        .with_point_format(las_reader.header.point_format_id, 0)
+        .unwrap()
+        .with_variable_chunk_size()
+        .build();
+    let mut compressed_output = Cursor::new(Vec::<u8>::new());
+    {
+        let mut compressor =
+            LasZipCompressor::new(&mut compressed_output, laz_vlr.clone()).unwrap();
+        compressor.compress_chunks(&chunks).unwrap();
+        compressor.done().unwrap();
+    }
+
+    compressed_output.set_position(0);
+    let mut decompressor = LasZipDecompressor::new(&mut compressed_output, laz_vlr).unwrap();
+    let mut point = vec![0u8; point_size];
+    let num_points = chunk_sizes.iter().sum::<usize>();
+    for point_idx in [0, 2, 5, 13, 14, 7, 35, 20, 1, num_points - 1] {
+        decompressor.seek(point_idx as u64).unwrap();
+        decompressor.decompress_one(&mut point).unwrap();
+        assert_eq!(
+            point.as_slice(),
+            &expected_points[point_idx * point_size..(point_idx + 1) * point_size],
+            "point {}",
+            point_idx
+        );
+    }
+
+    decompressor.seek(num_points as u64).unwrap();
+    assert!(decompressor.decompress_one(&mut point).is_err());
+}